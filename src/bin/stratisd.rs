@@ -25,21 +25,56 @@ extern crate enum_derive;
 #[cfg(test)]
 extern crate quickcheck;
 
+use std::io;
 use std::io::Write;
 use std::env;
 use std::error::Error;
-use std::rc::Rc;
-use std::cell::RefCell;
+use std::sync::Arc;
 use std::process::exit;
 
 use clap::{App, Arg};
-use log::LogLevelFilter;
-use env_logger::LogBuilder;
+use log::{Log, LogLevelFilter, LogMetadata, LogRecord};
+use env_logger::{LogBuilder, Logger};
 use dbus::WatchEvent;
 
-use libstratis::engine::{Engine, SimEngine, StratEngine};
+use libstratis::dbus_api::{DEFAULT_AUDIT_LOG_SIZE, DEFAULT_ERROR_LOG_SIZE, LogLevel};
+use libstratis::engine::{SIM_ENGINE_KIND, STRAT_ENGINE_KIND, create_engine};
 use libstratis::stratis::{StratisResult, StratisError, VERSION};
 
+/// Logs stratisd's own messages up to the maximum log level, which may be
+/// changed while stratisd runs, and passes all other messages to an
+/// env_logger.
+struct StratisLogger(Logger);
+
+impl StratisLogger {
+    fn is_own(target: &str) -> bool {
+        target.starts_with("stratisd") || target.starts_with("libstratis")
+    }
+}
+
+impl Log for StratisLogger {
+    fn enabled(&self, metadata: &LogMetadata) -> bool {
+        if StratisLogger::is_own(metadata.target()) {
+            metadata.level() <= log::max_log_level()
+        } else {
+            self.0.enabled(metadata)
+        }
+    }
+
+    fn log(&self, record: &LogRecord) {
+        if !StratisLogger::is_own(record.target()) {
+            return self.0.log(record);
+        }
+        if self.enabled(record.metadata()) {
+            let _ = writeln!(&mut io::stderr(),
+                             "{}:{}: {}",
+                             record.level(),
+                             record.location().module_path(),
+                             record.args());
+        }
+    }
+}
+
 /// Try to write the error from the program to stderr, vehemently.
 /// Return an error if stderr unavailable or writing was a failure.
 fn write_err(err: StratisError) -> StratisResult<()> {
@@ -68,6 +103,20 @@ fn run() -> StratisResult<()> {
         .arg(Arg::with_name("sim")
                  .long("sim")
                  .help("Use simulator engine"))
+        .arg(Arg::with_name("audit_log_size")
+                 .long("audit-log-size")
+                 .takes_value(true)
+                 .validator(|s| s.parse::<usize>().map(|_| ()).map_err(|e| e.to_string()))
+                 .help("Number of operations retained in the audit log"))
+        .arg(Arg::with_name("error_log_size")
+                 .long("error-log-size")
+                 .takes_value(true)
+                 .validator(|s| s.parse::<usize>().map(|_| ()).map_err(|e| e.to_string()))
+                 .help("Number of failed operations retained in the error log"))
+        .arg(Arg::with_name("service_name")
+                 .long("service-name")
+                 .takes_value(true)
+                 .help("D-Bus service name under which to register"))
         .get_matches();
 
     let mut builder = LogBuilder::new();
@@ -80,22 +129,38 @@ fn run() -> StratisResult<()> {
         }
     };
 
-    builder
-        .init()
-        .expect("This is the first and only initialization of the logger; it must succeed.");
-
-    let engine: Rc<RefCell<Engine>> = {
-        if matches.is_present("sim") {
-            info!("Using SimEngine");
-            Rc::new(RefCell::new(SimEngine::default()))
-        } else {
-            info!("Using StratEngine");
-            Rc::new(RefCell::new(try!(StratEngine::initialize())))
-        }
+    let logger = builder.build();
+    let mut max_level = None;
+    log::set_logger(|filter| {
+                        filter.set(logger.filter());
+                        max_level = Some(filter);
+                        Box::new(StratisLogger(logger))
+                    })
+            .expect("This is the first and only initialization of the logger; it must succeed.");
+
+    let engine_kind = if matches.is_present("sim") {
+        SIM_ENGINE_KIND
+    } else {
+        STRAT_ENGINE_KIND
     };
+    info!("Using {} engine", engine_kind);
+    let engine = try!(create_engine(engine_kind));
+
+    let audit_log_size = matches
+        .value_of("audit_log_size")
+        .map_or(DEFAULT_AUDIT_LOG_SIZE,
+                |s| s.parse().expect("validated by clap"));
+    let error_log_size = matches
+        .value_of("error_log_size")
+        .map_or(DEFAULT_ERROR_LOG_SIZE,
+                |s| s.parse().expect("validated by clap"));
 
     let (dbus_conn, mut tree, dbus_context) =
-        try!(libstratis::dbus_api::connect(Rc::clone(&engine)));
+        try!(libstratis::dbus_api::connect(Arc::clone(&engine),
+                                           audit_log_size,
+                                           error_log_size,
+                                           LogLevel::new(max_level),
+                                           matches.value_of("service_name")));
 
     // Get a list of fds to poll for
     let mut fds: Vec<_> = dbus_conn
@@ -121,8 +186,11 @@ fn run() -> StratisResult<()> {
             }
         }
 
-        // Ask the engine to check its pools
-        engine.borrow_mut().check()
+        // Ask the engine to check its pools, unless a panic while a
+        // handler had it locked has left it poisoned
+        if let Ok(mut engine) = engine.write() {
+            engine.check()
+        }
     }
 }
 
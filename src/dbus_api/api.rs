@@ -3,10 +3,12 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use std::borrow::Cow;
+use std::collections::{BTreeSet, HashSet};
 use std::fmt::Display;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::vec::Vec;
 use std::rc::Rc;
+use std::sync::{Arc, RwLock};
 use std::cell::RefCell;
 
 use dbus;
@@ -20,53 +22,437 @@ use dbus::arg::IterAppend;
 use dbus::tree::Access;
 use dbus::tree::EmitsChangedSignal;
 use dbus::tree::Factory;
+use dbus::tree::Interface;
+use dbus::tree::Method;
 use dbus::tree::MethodErr;
 use dbus::tree::MTFn;
 use dbus::tree::MethodResult;
 use dbus::tree::MethodInfo;
+use dbus::tree::ObjectPath;
 use dbus::tree::PropInfo;
 use dbus::tree::Tree;
 use dbus::ConnectionItem;
 
-use engine::{Engine, Redundancy};
-use stratis::VERSION;
+use time;
+use uuid::Uuid;
+
+use devicemapper::Sectors;
+use devicemapper::consts::SECTOR_SIZE;
+
+use engine::{AllocationPolicy, BlockDevCheck, BlockDevHealth, BlockDevState, BlockDevTier,
+             CacheMode, Compression, DEFAULT_EXTENT_SIZE, DEFAULT_MAX_FILESYSTEMS, DevUuid, Engine,
+             EngineDump, EngineError, EngineResult, ErrorEnum, MIN_DEV_SIZE, Pool, PoolState,
+             PoolDiff, PoolSpec, PoolTopology, Redundancy, Transaction, schedule_due};
+use stratis::{VERSION, build_profile, features, git_hash};
 
 use super::filesystem::create_dbus_filesystem;
-use super::pool::create_dbus_pool;
-use super::types::{DeferredAction, DbusContext, DbusErrorEnum, TData};
+use super::blockdev::create_dbus_blockdevs;
+use super::pool::{allocation_policy_from_code, compression_from_code, create_dbus_pool};
+use super::types::{DeferredAction, DbusContext, DbusErrorEnum, ErrorLog, LogLevel, TData};
 use super::util::STRATIS_BASE_PATH;
 use super::util::STRATIS_BASE_SERVICE;
 use super::util::code_to_message_items;
 use super::util::default_object_path;
+use super::util::echo_correlation_id;
+use super::util::engine_guard;
 use super::util::engine_to_dbus_err;
+use super::util::get_correlation_id;
 use super::util::get_next_arg;
+use super::util::list_return;
+use super::util::log_operation;
 use super::util::ok_message_items;
 use super::util::tuple_to_option;
 
 fn create_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
     let message: &Message = m.msg;
-    let mut iter = message.iter_init();
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 4));
+
+    let name: &str = try!(get_next_arg(&mut iter, 0));
+    let redundancy: (bool, u16) = try!(get_next_arg(&mut iter, 1));
+    let force: bool = try!(get_next_arg(&mut iter, 2));
+    let devs: Array<&str, _> = try!(get_next_arg(&mut iter, 3));
+
+    let blockdevs = devs.map(|x| Path::new(x)).collect::<Vec<&Path>>();
+
+    create_pool_common(m,
+                       correlation_id,
+                       name,
+                       None,
+                       tuple_to_option(redundancy),
+                       force,
+                       &blockdevs,
+                       &[],
+                       &[],
+                       None,
+                       None,
+                       None)
+}
+
+fn create_pool_with_cache(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 5));
+
+    let name: &str = try!(get_next_arg(&mut iter, 0));
+    let redundancy: (bool, u16) = try!(get_next_arg(&mut iter, 1));
+    let force: bool = try!(get_next_arg(&mut iter, 2));
+    let devs: Array<&str, _> = try!(get_next_arg(&mut iter, 3));
+    let cache_devs: Array<&str, _> = try!(get_next_arg(&mut iter, 4));
+
+    let blockdevs = devs.map(|x| Path::new(x)).collect::<Vec<&Path>>();
+    let cachedevs = cache_devs.map(|x| Path::new(x)).collect::<Vec<&Path>>();
+
+    create_pool_common(m,
+                       correlation_id,
+                       name,
+                       None,
+                       tuple_to_option(redundancy),
+                       force,
+                       &blockdevs,
+                       &cachedevs,
+                       &[],
+                       None,
+                       None,
+                       None)
+}
+
+fn create_pool_default_redundancy(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 4));
+
+    let name: &str = try!(get_next_arg(&mut iter, 0));
+    let force: bool = try!(get_next_arg(&mut iter, 1));
+    let devs: Array<&str, _> = try!(get_next_arg(&mut iter, 2));
+    let cache_devs: Array<&str, _> = try!(get_next_arg(&mut iter, 3));
+
+    let blockdevs = devs.map(|x| Path::new(x)).collect::<Vec<&Path>>();
+    let cachedevs = cache_devs.map(|x| Path::new(x)).collect::<Vec<&Path>>();
+
+    let redundancy = m.tree.get_data().default_redundancy.get();
+    create_pool_common(m,
+                       correlation_id,
+                       name,
+                       None,
+                       Some(redundancy),
+                       force,
+                       &blockdevs,
+                       &cachedevs,
+                       &[],
+                       None,
+                       None,
+                       None)
+}
+
+fn create_encrypted_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 6));
+
+    let name: &str = try!(get_next_arg(&mut iter, 0));
+    let redundancy: (bool, u16) = try!(get_next_arg(&mut iter, 1));
+    let force: bool = try!(get_next_arg(&mut iter, 2));
+    let devs: Array<&str, _> = try!(get_next_arg(&mut iter, 3));
+    let cache_devs: Array<&str, _> = try!(get_next_arg(&mut iter, 4));
+    let encryption: (bool, &str) = try!(get_next_arg(&mut iter, 5));
+
+    let blockdevs = devs.map(|x| Path::new(x)).collect::<Vec<&Path>>();
+    let cachedevs = cache_devs.map(|x| Path::new(x)).collect::<Vec<&Path>>();
+
+    create_pool_common(m,
+                       correlation_id,
+                       name,
+                       None,
+                       tuple_to_option(redundancy),
+                       force,
+                       &blockdevs,
+                       &cachedevs,
+                       &[],
+                       tuple_to_option(encryption),
+                       None,
+                       None)
+}
+
+fn create_compressed_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 6));
+
+    let name: &str = try!(get_next_arg(&mut iter, 0));
+    let redundancy: (bool, u16) = try!(get_next_arg(&mut iter, 1));
+    let force: bool = try!(get_next_arg(&mut iter, 2));
+    let devs: Array<&str, _> = try!(get_next_arg(&mut iter, 3));
+    let cache_devs: Array<&str, _> = try!(get_next_arg(&mut iter, 4));
+    let compression: u16 = try!(get_next_arg(&mut iter, 5));
+
+    let compression = match compression_from_code(compression) {
+        Some(compression) => compression,
+        None => {
+            let default_return =
+                MessageItem::Struct(vec![MessageItem::ObjectPath(default_object_path()),
+                                         MessageItem::Array(vec![], "s".into())]);
+            let error_message = format!("code {} does not correspond to any compression",
+                                        compression);
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::ERROR, error_message);
+            let msg = message.method_return().append3(default_return, rc, rs);
+            return Ok(vec![echo_correlation_id(msg, correlation_id)]);
+        }
+    };
+
+    let blockdevs = devs.map(|x| Path::new(x)).collect::<Vec<&Path>>();
+    let cachedevs = cache_devs.map(|x| Path::new(x)).collect::<Vec<&Path>>();
+
+    create_pool_common(m,
+                       correlation_id,
+                       name,
+                       None,
+                       tuple_to_option(redundancy),
+                       force,
+                       &blockdevs,
+                       &cachedevs,
+                       &[],
+                       None,
+                       Some(compression),
+                       None)
+}
+
+fn create_allocated_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 6));
 
     let name: &str = try!(get_next_arg(&mut iter, 0));
     let redundancy: (bool, u16) = try!(get_next_arg(&mut iter, 1));
     let force: bool = try!(get_next_arg(&mut iter, 2));
     let devs: Array<&str, _> = try!(get_next_arg(&mut iter, 3));
+    let cache_devs: Array<&str, _> = try!(get_next_arg(&mut iter, 4));
+    let policy: u16 = try!(get_next_arg(&mut iter, 5));
+
+    let policy = match allocation_policy_from_code(policy) {
+        Some(policy) => policy,
+        None => {
+            let default_return =
+                MessageItem::Struct(vec![MessageItem::ObjectPath(default_object_path()),
+                                         MessageItem::Array(vec![], "s".into())]);
+            let error_message = format!("code {} does not correspond to any allocation policy",
+                                        policy);
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::ERROR, error_message);
+            let msg = message.method_return().append3(default_return, rc, rs);
+            return Ok(vec![echo_correlation_id(msg, correlation_id)]);
+        }
+    };
+
+    let blockdevs = devs.map(|x| Path::new(x)).collect::<Vec<&Path>>();
+    let cachedevs = cache_devs.map(|x| Path::new(x)).collect::<Vec<&Path>>();
+
+    create_pool_common(m,
+                       correlation_id,
+                       name,
+                       None,
+                       tuple_to_option(redundancy),
+                       force,
+                       &blockdevs,
+                       &cachedevs,
+                       &[],
+                       None,
+                       None,
+                       Some(policy))
+}
+
+/// Separate devices, each tagged with the code of its BlockDevTier, into
+/// data devices, cache devices, and spares.
+/// Returns an error message if any code does not correspond to a tier.
+#[allow(type_complexity)]
+fn devices_by_tier<'a>(devices: &[(&'a str, u16)])
+                       -> Result<(Vec<&'a Path>, Vec<&'a Path>, Vec<&'a Path>), String> {
+    let mut blockdevs = Vec::new();
+    let mut cachedevs = Vec::new();
+    let mut sparedevs = Vec::new();
+    for &(dev, code) in devices {
+        match BlockDevTier::iter_variants().nth(code as usize) {
+            Some(BlockDevTier::Data) => blockdevs.push(Path::new(dev)),
+            Some(BlockDevTier::Cache) => cachedevs.push(Path::new(dev)),
+            Some(BlockDevTier::Spare) => sparedevs.push(Path::new(dev)),
+            None => {
+                return Err(format!("code {} for device {} does not correspond to any tier",
+                                   code,
+                                   dev))
+            }
+        }
+    }
+    Ok((blockdevs, cachedevs, sparedevs))
+}
+
+fn create_pool_with_tiers(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 4));
+
+    let name: &str = try!(get_next_arg(&mut iter, 0));
+    let redundancy: (bool, u16) = try!(get_next_arg(&mut iter, 1));
+    let force: bool = try!(get_next_arg(&mut iter, 2));
+    let devs: Array<(&str, u16), _> = try!(get_next_arg(&mut iter, 3));
+
+    let devs = devs.collect::<Vec<(&str, u16)>>();
+    let (blockdevs, cachedevs, sparedevs) = match devices_by_tier(&devs) {
+        Ok(devices) => devices,
+        Err(error_message) => {
+            let default_return =
+                MessageItem::Struct(vec![MessageItem::ObjectPath(default_object_path()),
+                                         MessageItem::Array(vec![], "s".into())]);
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::ERROR, error_message);
+            let msg = message.method_return().append3(default_return, rc, rs);
+            return Ok(vec![echo_correlation_id(msg, correlation_id)]);
+        }
+    };
+
+    create_pool_common(m,
+                       correlation_id,
+                       name,
+                       None,
+                       tuple_to_option(redundancy),
+                       force,
+                       &blockdevs,
+                       &cachedevs,
+                       &sparedevs,
+                       None,
+                       None,
+                       None)
+}
+
+fn create_pool_with_uuid(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 6));
+
+    let name: &str = try!(get_next_arg(&mut iter, 0));
+    let uuid: &str = try!(get_next_arg(&mut iter, 1));
+    let redundancy: (bool, u16) = try!(get_next_arg(&mut iter, 2));
+    let force: bool = try!(get_next_arg(&mut iter, 3));
+    let devs: Array<&str, _> = try!(get_next_arg(&mut iter, 4));
+    let cache_devs: Array<&str, _> = try!(get_next_arg(&mut iter, 5));
+
+    let pool_uuid = match Uuid::parse_str(uuid) {
+        Ok(pool_uuid) => pool_uuid,
+        Err(_) => {
+            let default_return =
+                MessageItem::Struct(vec![MessageItem::ObjectPath(default_object_path()),
+                                         MessageItem::Array(vec![], "s".into())]);
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::ERROR,
+                                                 format!("{} is not a valid UUID", uuid));
+            let msg = message.method_return().append3(default_return, rc, rs);
+            return Ok(vec![echo_correlation_id(msg, correlation_id)]);
+        }
+    };
 
     let blockdevs = devs.map(|x| Path::new(x)).collect::<Vec<&Path>>();
+    let cachedevs = cache_devs.map(|x| Path::new(x)).collect::<Vec<&Path>>();
+
+    create_pool_common(m,
+                       correlation_id,
+                       name,
+                       Some(pool_uuid),
+                       tuple_to_option(redundancy),
+                       force,
+                       &blockdevs,
+                       &cachedevs,
+                       &[],
+                       None,
+                       None,
+                       None)
+}
+
+/// Add the spares to a newly created pool and, if there is a key
+/// description, mark it as encrypted, and, if there is a compression,
+/// set it.
+fn complete_pool(pool: &mut Pool,
+                 sparedevs: &[&Path],
+                 force: bool,
+                 key_description: Option<&str>,
+                 compression: Option<Compression>,
+                 allocation_policy: Option<AllocationPolicy>)
+                 -> EngineResult<()> {
+    if !sparedevs.is_empty() {
+        try!(pool.add_spares(sparedevs, force));
+    }
+    if let Some(key_description) = key_description {
+        try!(pool.set_key_description(key_description));
+    }
+    if let Some(compression) = compression {
+        try!(pool.set_compression(compression));
+    }
+    if let Some(allocation_policy) = allocation_policy {
+        try!(pool.set_allocation_policy(allocation_policy));
+    }
+    Ok(())
+}
 
+/// Create a pool and its D-Bus object on behalf of one of the pool
+/// creation methods, once the method's arguments have been read. The pool
+/// is identified by uuid if given, otherwise by a UUID generated for it.
+/// If the spares can not be added, the pool can not be marked as
+/// encrypted, or its compression or allocation policy can not be set,
+/// the pool is destroyed again.
+fn create_pool_common(m: &MethodInfo<MTFn<TData>, TData>,
+                      correlation_id: Option<String>,
+                      name: &str,
+                      uuid: Option<Uuid>,
+                      redundancy: Option<u16>,
+                      force: bool,
+                      blockdevs: &[&Path],
+                      cachedevs: &[&Path],
+                      sparedevs: &[&Path],
+                      key_description: Option<&str>,
+                      compression: Option<Compression>,
+                      allocation_policy: Option<AllocationPolicy>)
+                      -> MethodResult {
+    let message: &Message = m.msg;
     let object_path = m.path.get_name();
     let dbus_context = m.tree.get_data();
-    let result = dbus_context
-        .engine
-        .borrow_mut()
-        .create_pool(name, &blockdevs, tuple_to_option(redundancy), force);
 
     let return_message = message.method_return();
+    let default_return =
+        MessageItem::Struct(vec![MessageItem::ObjectPath(default_object_path()),
+                                 MessageItem::Array(vec![], "s".into())]);
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; default_return; return_message);
+    let result = {
+        let created = match uuid {
+            Some(uuid) => {
+                engine
+                    .create_pool_with_uuid(uuid, name, blockdevs, cachedevs, redundancy, force)
+                    .map(|devnodes| (uuid, devnodes))
+            }
+            None => engine.create_pool(name, blockdevs, cachedevs, redundancy, force),
+        };
+        created.and_then(|(uuid, devnodes)| {
+            if sparedevs.is_empty() && key_description.is_none() &&
+               compression.is_none() && allocation_policy.is_none() {
+                return Ok((uuid, devnodes));
+            }
+            let completed = complete_pool(engine
+                                              .get_mut_pool(&uuid)
+                                              .expect("pool was just created"),
+                                          sparedevs,
+                                          force,
+                                          key_description,
+                                          compression,
+                                          allocation_policy);
+            match completed {
+                Ok(_) => Ok((uuid, devnodes)),
+                Err(err) => {
+                    let _ = engine.destroy_pool(&uuid);
+                    Err(err)
+                }
+            }
+        })
+    };
+    let operation = format!("CreatePool {}", name);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
 
     let msg = match result {
         Ok((uuid, devnodes)) => {
             let pool_object_path: dbus::Path =
                 create_dbus_pool(dbus_context, object_path.clone(), uuid);
+            if let Some(pool) = engine.get_pool(&uuid) {
+                create_dbus_blockdevs(dbus_context, &pool_object_path, pool);
+            }
             let paths = devnodes
                 .iter()
                 .map(|d| {
@@ -82,21 +468,26 @@ fn create_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
             return_message.append3(return_value, rc, rs)
         }
         Err(x) => {
-            let return_path = MessageItem::ObjectPath(default_object_path());
-            let return_list = MessageItem::Array(vec![], "s".into());
-            let return_value = MessageItem::Struct(vec![return_path, return_list]);
             let (rc, rs) = engine_to_dbus_err(&x);
             let (rc, rs) = code_to_message_items(rc, rs);
-            return_message.append3(return_value, rc, rs)
+            return_message.append3(default_return, rc, rs)
         }
     };
-    Ok(vec![msg])
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
 }
 
 fn destroy_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    destroy_pool_common(m, false)
+}
+
+fn force_destroy_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    destroy_pool_common(m, true)
+}
+
+fn destroy_pool_common(m: &MethodInfo<MTFn<TData>, TData>, force: bool) -> MethodResult {
 
     let message: &Message = m.msg;
-    let mut iter = message.iter_init();
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
 
     let object_path: dbus::Path<'static> = try!(get_next_arg(&mut iter, 0));
 
@@ -106,22 +497,26 @@ fn destroy_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
     let return_message = message.method_return();
 
     let pool_uuid = match m.tree.get(&object_path) {
-        Some(pool_path) => get_data!(pool_path; default_return; return_message).uuid,
+        Some(pool_path) => {
+            get_data!(pool_path; correlation_id; default_return; return_message).uuid
+        }
         None => {
             let (rc, rs) = ok_message_items();
-            return Ok(vec![return_message.append3(default_return, rc, rs)]);
+            let msg = return_message.append3(default_return, rc, rs);
+            return Ok(vec![echo_correlation_id(msg, correlation_id)]);
         }
     };
 
-    let msg = match dbus_context
-              .engine
-              .borrow_mut()
-              .destroy_pool(&pool_uuid) {
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let result = destroy_pool_objects(dbus_context, &mut *engine, object_path, pool_uuid, force);
+    let operation = format!("{}DestroyPool {}", if force { "Force" } else { "" }, pool_uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
         Ok(action) => {
-            dbus_context
-                .actions
-                .borrow_mut()
-                .push_remove(object_path);
             let (rc, rs) = ok_message_items();
             return_message.append3(MessageItem::Bool(action), rc, rs)
         }
@@ -131,150 +526,2786 @@ fn destroy_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
             return_message.append3(default_return, rc, rs)
         }
     };
-    Ok(vec![msg])
-}
-
-fn get_list_items<T, I>(i: &mut IterAppend, iter: I) -> Result<(), MethodErr>
-    where T: Display + Into<u16>,
-          I: Iterator<Item = T>
-{
-    let msg_vec = iter.map(|item| {
-                               MessageItem::Struct(vec![MessageItem::Str(format!("{}", item)),
-                                                        MessageItem::UInt16(item.into())])
-                           })
-        .collect::<Vec<MessageItem>>();
-    i.append(MessageItem::Array(msg_vec, Cow::Borrowed("(sq)")));
-    Ok(())
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
 }
 
-fn get_error_values(i: &mut IterAppend,
-                    _p: &PropInfo<MTFn<TData>, TData>)
-                    -> Result<(), MethodErr> {
-    get_list_items(i, DbusErrorEnum::iter_variants())
+/// Queue the removal of the objects, if any, of the things with uuids.
+fn remove_objects(dbus_context: &DbusContext, uuids: &[Uuid]) {
+    for uuid in uuids {
+        if let Some(path) = dbus_context.object_path_for(uuid) {
+            dbus_context.push_remove(path);
+        }
+    }
 }
 
-
-fn get_redundancy_values(i: &mut IterAppend,
-                         _p: &PropInfo<MTFn<TData>, TData>)
-                         -> Result<(), MethodErr> {
-    get_list_items(i, Redundancy::iter_variants())
+/// Destroy the pool with this UUID, and queue the removal of its object,
+/// object_path, together with the objects of its filesystems and blockdevs.
+/// A pool which has filesystems can not be destroyed unless force is true,
+/// in which case its filesystems are destroyed with it.
+fn destroy_pool_objects(dbus_context: &DbusContext,
+                        engine: &mut Engine,
+                        object_path: dbus::Path<'static>,
+                        uuid: Uuid,
+                        force: bool)
+                        -> EngineResult<bool> {
+    let (filesystems, blockdevs) = engine
+        .get_pool(&uuid)
+        .map(|pool| {
+                 (pool.filesystems()
+                      .iter()
+                      .map(|f| *f.uuid())
+                      .collect::<Vec<_>>(),
+                  pool.blockdevs()
+                      .iter()
+                      .map(|&(_, bd)| *bd.uuid())
+                      .collect::<Vec<_>>())
+             })
+        .unwrap_or_default();
+    let destroyed = try!(if force {
+                             engine.force_destroy_pool(&uuid)
+                         } else {
+                             engine.destroy_pool(&uuid)
+                         });
+    remove_objects(dbus_context, &filesystems);
+    remove_objects(dbus_context, &blockdevs);
+    dbus_context.push_remove(object_path);
+    Ok(destroyed)
 }
 
-fn get_version(i: &mut IterAppend, _p: &PropInfo<MTFn<TData>, TData>) -> Result<(), MethodErr> {
-    i.append(VERSION);
-    Ok(())
+/// Create the objects of pool, of its blockdevs, and of its filesystems,
+/// the pool's object under object_path. Returns the path of the pool's
+/// object.
+fn create_pool_objects(dbus_context: &DbusContext,
+                       object_path: &dbus::Path<'static>,
+                       pool: &Pool)
+                       -> dbus::Path<'static> {
+    let pool_path = create_dbus_pool(dbus_context, object_path.clone(), *pool.uuid());
+    create_dbus_blockdevs(dbus_context, &pool_path, pool);
+    for fs_uuid in pool.filesystems().iter().map(|f| *f.uuid()) {
+        create_dbus_filesystem(dbus_context, pool_path.clone(), fs_uuid);
+    }
+    pool_path
 }
 
-fn configure_simulator(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
-    let message = m.msg;
-    let mut iter = message.iter_init();
+fn soft_destroy_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
 
-    let denominator: u32 = try!(get_next_arg(&mut iter, 0));
+    let object_path: dbus::Path<'static> = try!(get_next_arg(&mut iter, 0));
 
     let dbus_context = m.tree.get_data();
-    let result = dbus_context
-        .engine
-        .borrow_mut()
-        .configure_simulator(denominator);
 
+    let default_return = MessageItem::Bool(false);
     let return_message = message.method_return();
 
+    let pool_uuid = match m.tree.get(&object_path) {
+        Some(pool_path) => {
+            get_data!(pool_path; correlation_id; default_return; return_message).uuid
+        }
+        None => {
+            let (rc, rs) = ok_message_items();
+            let msg = return_message.append3(default_return, rc, rs);
+            return Ok(vec![echo_correlation_id(msg, correlation_id)]);
+        }
+    };
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let result = soft_destroy_pool_objects(dbus_context, &mut *engine, object_path, pool_uuid);
+    let operation = format!("SoftDestroyPool {}", pool_uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
     let msg = match result {
-        Ok(_) => {
+        Ok(action) => {
             let (rc, rs) = ok_message_items();
-            return_message.append2(rc, rs)
+            return_message.append3(MessageItem::Bool(action), rc, rs)
         }
         Err(err) => {
             let (rc, rs) = engine_to_dbus_err(&err);
             let (rc, rs) = code_to_message_items(rc, rs);
-            return_message.append2(rc, rs)
+            return_message.append3(default_return, rc, rs)
         }
     };
-    Ok(vec![msg])
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
 }
 
-fn get_base_tree<'a>(dbus_context: DbusContext) -> (Tree<MTFn<TData>, TData>, dbus::Path<'a>) {
+/// Mark the pool with this UUID deleted, and, if it was marked, queue the
+/// removal of its object, object_path, together with the objects of its
+/// filesystems and blockdevs.
+fn soft_destroy_pool_objects(dbus_context: &DbusContext,
+                             engine: &mut Engine,
+                             object_path: dbus::Path<'static>,
+                             uuid: Uuid)
+                             -> EngineResult<bool> {
+    let (filesystems, blockdevs) = engine
+        .get_pool(&uuid)
+        .map(|pool| {
+                 (pool.filesystems()
+                      .iter()
+                      .map(|f| *f.uuid())
+                      .collect::<Vec<_>>(),
+                  pool.blockdevs()
+                      .iter()
+                      .map(|&(_, bd)| *bd.uuid())
+                      .collect::<Vec<_>>())
+             })
+        .unwrap_or_default();
+    let deleted = try!(engine.soft_destroy_pool(&uuid));
+    if deleted {
+        remove_objects(dbus_context, &filesystems);
+        remove_objects(dbus_context, &blockdevs);
+        dbus_context.push_remove(object_path);
+    }
+    Ok(deleted)
+}
 
-    let f = Factory::new_fn();
+/// The UUID of the pool marked deleted which is named name.
+/// Returns an error if there is no such pool.
+fn deleted_pool_uuid(engine: &Engine, name: &str) -> EngineResult<Uuid> {
+    engine
+        .deleted_pools()
+        .iter()
+        .find(|p| p.name() == name)
+        .map(|p| *p.uuid())
+        .ok_or_else(|| {
+                        EngineError::Engine(ErrorEnum::NotFound,
+                                            format!("no deleted pool named {}", name))
+                    })
+}
 
-    let base_tree = f.tree(dbus_context);
+/// Restore the pool marked deleted which is named name, and create its
+/// objects under object_path. Returns the path of the pool's object.
+fn restore_pool_objects(dbus_context: &DbusContext,
+                        engine: &mut Engine,
+                        object_path: &dbus::Path<'static>,
+                        name: &str)
+                        -> EngineResult<dbus::Path<'static>> {
+    let uuid = try!(deleted_pool_uuid(engine, name));
+    try!(engine.restore_pool(&uuid));
+    Ok(create_pool_objects(dbus_context,
+                           object_path,
+                           engine.get_pool(&uuid).expect("pool was just restored")))
+}
 
-    let create_pool_method = f.method("CreatePool", (), create_pool)
-        .in_arg(("name", "s"))
-        .in_arg(("redundancy", "(bq)"))
-        .in_arg(("force", "b"))
-        .in_arg(("devices", "as"))
-        .out_arg(("result", "(oas)"))
-        .out_arg(("return_code", "q"))
-        .out_arg(("return_string", "s"));
+fn restore_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
 
-    let destroy_pool_method = f.method("DestroyPool", (), destroy_pool)
-        .in_arg(("pool", "o"))
-        .out_arg(("action", "b"))
-        .out_arg(("return_code", "q"))
-        .out_arg(("return_string", "s"));
+    let name: &str = try!(get_next_arg(&mut iter, 0));
 
-    let configure_simulator_method = f.method("ConfigureSimulator", (), configure_simulator)
-        .in_arg(("denominator", "u"))
-        .out_arg(("return_code", "q"))
-        .out_arg(("return_string", "s"));
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::ObjectPath(default_object_path());
 
-    let redundancy_values_property =
-        f.property::<Array<(&str, u16), &Iterator<Item = (&str, u16)>>, _>("RedundancyValues", ())
-            .access(Access::Read)
-            .emits_changed(EmitsChangedSignal::Const)
-            .on_get(get_redundancy_values);
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
 
-    let error_values_property =
-        f.property::<Array<(&str, u16), &Iterator<Item = (&str, u16)>>, _>("ErrorValues", ())
-            .access(Access::Read)
-            .emits_changed(EmitsChangedSignal::Const)
-            .on_get(get_error_values);
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let result = restore_pool_objects(dbus_context, &mut *engine, object_path, name);
+    let operation = format!("RestorePool {}", name);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
 
-    let version_property = f.property::<&str, _>("Version", ())
-        .access(Access::Read)
-        .emits_changed(EmitsChangedSignal::Const)
-        .on_get(get_version);
+    let msg = match result {
+        Ok(pool_path) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::ObjectPath(pool_path), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
 
-    let interface_name = format!("{}.{}", STRATIS_BASE_SERVICE, "Manager");
+fn purge_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
 
-    let obj_path = f.object_path(STRATIS_BASE_PATH, None)
-        .introspectable()
-        .object_manager()
-        .add(f.interface(interface_name, ())
-                 .add_m(create_pool_method)
-                 .add_m(destroy_pool_method)
-                 .add_m(configure_simulator_method)
-                 .add_p(error_values_property)
-                 .add_p(redundancy_values_property)
-                 .add_p(version_property));
+    let name: &str = try!(get_next_arg(&mut iter, 0));
 
-    let path = obj_path.get_name().to_owned();
-    (base_tree.add(obj_path), path)
-}
+    let dbus_context = m.tree.get_data();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
 
-#[allow(type_complexity)]
-pub fn connect(engine: Rc<RefCell<Engine>>)
-               -> Result<(Connection, Tree<MTFn<TData>, TData>, DbusContext), dbus::Error> {
-    let c = try!(Connection::get_private(BusType::System));
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
 
-    let local_engine = Rc::clone(&engine);
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let result = deleted_pool_uuid(&*engine, name).and_then(|uuid| engine.destroy_pool(&uuid));
+    let operation = format!("PurgePool {}", name);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
 
-    let (tree, object_path) = get_base_tree(DbusContext::new(engine));
-    let dbus_context = tree.get_data().clone();
+    let msg = match result {
+        Ok(action) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(action), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
 
-    // This should never panic as create_dbus_pool() and
-    // create_dbus_filesystem() do not borrow the engine.
-    for pool in local_engine.borrow().pools() {
-        let pool_path = create_dbus_pool(&dbus_context, object_path.clone(), *pool.uuid());
-        for fs_uuid in pool.filesystems().iter().map(|f| *f.uuid()) {
-            create_dbus_filesystem(&dbus_context, pool_path.clone(), fs_uuid);
+/// Load the pools described by dump into the engine, replacing all those
+/// it has if replace is true, and create their objects under object_path.
+/// The removal of the objects of the pools replaced, and of their
+/// filesystems and blockdevs, is queued.
+/// Returns the object paths of the pools loaded.
+fn load_state_objects(dbus_context: &DbusContext,
+                      engine: &mut Engine,
+                      object_path: &dbus::Path<'static>,
+                      dump: &EngineDump,
+                      replace: bool)
+                      -> EngineResult<Vec<dbus::Path<'static>>> {
+    let mut replaced = Vec::new();
+    if replace {
+        for pool in engine.pools() {
+            replaced.push(*pool.uuid());
+            replaced.extend(pool.filesystems().iter().map(|f| *f.uuid()));
+            replaced.extend(pool.blockdevs().iter().map(|&(_, bd)| *bd.uuid()));
         }
     }
 
-    try!(tree.set_registered(&c, true));
-
-    try!(c.register_name(STRATIS_BASE_SERVICE, NameFlag::ReplaceExisting as u32));
+    let uuids = try!(engine.load_state(dump, replace));
+    for uuid in replaced {
+        if let Some(path) = dbus_context.object_path_for(&uuid) {
+            dbus_context.push_remove(path);
+        }
+    }
+    Ok(uuids
+           .iter()
+           .map(|uuid| {
+                    create_pool_objects(dbus_context,
+                                        object_path,
+                                        engine.get_pool(uuid).expect("pool was just loaded"))
+                })
+           .collect())
+}
+
+/// The number of objects known to dbus_context which represent pools,
+/// filesystems, non-cache blockdevs, and cache blockdevs, respectively.
+fn object_count(dbus_context: &DbusContext, engine: &Engine) -> (u16, u16, u16, u16) {
+    let has_object = |uuid: &Uuid| dbus_context.object_path_for(uuid).is_some();
+    let mut count = (0, 0, 0, 0);
+    for pool in engine.pools() {
+        if has_object(pool.uuid()) {
+            count.0 += 1;
+        }
+        count.1 += pool.filesystems()
+            .iter()
+            .filter(|f| has_object(f.uuid()))
+            .count() as u16;
+        for (tier, blockdev) in pool.blockdevs() {
+            if has_object(blockdev.uuid()) {
+                match tier {
+                    BlockDevTier::Cache => count.3 += 1,
+                    _ => count.2 += 1,
+                }
+            }
+        }
+    }
+    count
+}
+
+fn get_object_count(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+
+    let dbus_context = m.tree.get_data();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Struct(vec![MessageItem::UInt16(0),
+                                                  MessageItem::UInt16(0),
+                                                  MessageItem::UInt16(0),
+                                                  MessageItem::UInt16(0)]);
+    let engine = read_engine!(dbus_context; default_return; return_message);
+
+    let (pools, filesystems, blockdevs, caches) = object_count(dbus_context, &*engine);
+    let (rc, rs) = ok_message_items();
+    let counts = MessageItem::Struct(vec![MessageItem::UInt16(pools),
+                                          MessageItem::UInt16(filesystems),
+                                          MessageItem::UInt16(blockdevs),
+                                          MessageItem::UInt16(caches)]);
+    Ok(vec![return_message.append3(counts, rc, rs)])
+}
+
+/// The object paths known to dbus_context which do not correspond to any
+/// pool, filesystem, or blockdev in the engine, in order.
+fn orphaned_paths(dbus_context: &DbusContext, engine: &Engine) -> Vec<dbus::Path<'static>> {
+    let uuids = engine
+        .pools()
+        .iter()
+        .flat_map(|p| {
+                      p.filesystems()
+                          .into_iter()
+                          .map(|f| *f.uuid())
+                          .chain(p.blockdevs().into_iter().map(|(_, bd)| *bd.uuid()))
+                          .chain(Some(*p.uuid()))
+                  })
+        .collect::<HashSet<Uuid>>();
+    let mut paths = dbus_context
+        .object_paths
+        .borrow()
+        .iter()
+        .filter(|&(_, uuid)| !uuids.contains(uuid))
+        .map(|(path, _)| path.clone())
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
+
+/// Remove the orphaned object paths known to dbus_context from the tree.
+/// Returns the paths removed.
+fn remove_orphaned_paths(dbus_context: &DbusContext,
+                         engine: &Engine)
+                         -> Vec<dbus::Path<'static>> {
+    let paths = orphaned_paths(dbus_context, engine);
+    for path in &paths {
+        dbus_context.push_remove(path.clone());
+    }
+    paths
+}
+
+fn list_orphaned_paths(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+
+    let dbus_context = m.tree.get_data();
+    let default_return = MessageItem::Array(vec![], "o".into());
+    let engine = read_engine!(dbus_context; default_return; message.method_return());
+
+    let paths = orphaned_paths(dbus_context, &*engine)
+        .into_iter()
+        .map(MessageItem::ObjectPath)
+        .collect();
+
+    Ok(vec![list_return(message, paths, "o")])
+}
+
+fn prune_orphaned_paths(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, _) = try!(get_correlation_id(message, 0));
+
+    let dbus_context = m.tree.get_data();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Array(vec![], "o".into());
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let engine = read_engine!(dbus_context; correlation_id; default_return; return_message);
+    let paths = remove_orphaned_paths(dbus_context, &*engine)
+        .into_iter()
+        .map(MessageItem::ObjectPath)
+        .collect();
+    let result: EngineResult<()> = Ok(());
+    let operation = "PruneOrphanedPaths".to_owned();
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let (rc, rs) = ok_message_items();
+    let msg = return_message.append3(MessageItem::Array(paths, "o".into()), rc, rs);
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+/// The names of the methods of the service's interfaces on object, sorted.
+/// The dbus crate offers no way to walk the interfaces or methods of an
+/// object, so they are read from its Debug representation.
+fn object_method_names(object: &ObjectPath<MTFn<TData>, TData>) -> Vec<String> {
+    let repr = format!("{:?}", object);
+    let name_marker = "name: Member(\"";
+    let mut names = BTreeSet::new();
+    for iface in repr.split("Interface { name: Interface(\"").skip(1) {
+        if !iface.starts_with(STRATIS_BASE_SERVICE) {
+            continue;
+        }
+        for method in iface.split("Method { ").skip(1) {
+            if let Some(start) = method.find(name_marker) {
+                let name = &method[start + name_marker.len()..];
+                if let Some(end) = name.find('"') {
+                    names.insert(name[..end].to_string());
+                }
+            }
+        }
+    }
+    names.into_iter().collect()
+}
+
+fn list_methods(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+
+    let object = m.tree
+        .get(m.path.get_name())
+        .expect("implicit argument must be in tree");
+    let names = object_method_names(object)
+        .into_iter()
+        .map(MessageItem::Str)
+        .collect();
+
+    Ok(vec![list_return(message, names, "s")])
+}
+
+fn list_pending_operations(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+
+    let operations = m.tree
+        .get_data()
+        .pending
+        .borrow()
+        .iter()
+        .map(|op| {
+                 MessageItem::Struct(vec![MessageItem::Str(op.operation.clone()),
+                                          MessageItem::Str(op.target.clone()),
+                                          MessageItem::UInt64(op.started_at)])
+             })
+        .collect();
+
+    Ok(vec![list_return(message, operations, "(sst)")])
+}
+
+fn get_base_object_path(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+
+    let (rc, rs) = ok_message_items();
+    Ok(vec![message
+                .method_return()
+                .append3(MessageItem::ObjectPath(m.path.get_name().clone()), rc, rs)])
+}
+
+fn cancel_operation(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let op_id: &str = try!(get_next_arg(&mut iter, 0));
+
+    m.tree.get_data().cancel(op_id);
+
+    let (rc, rs) = ok_message_items();
+    Ok(vec![message.method_return().append2(rc, rs)])
+}
+
+fn pause_signals(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+
+    m.tree.get_data().signals.borrow_mut().pause();
+
+    let (rc, rs) = ok_message_items();
+    Ok(vec![message.method_return().append2(rc, rs)])
+}
+
+fn resume_signals(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+
+    m.tree.get_data().signals.borrow_mut().resume();
+
+    let (rc, rs) = ok_message_items();
+    Ok(vec![message.method_return().append2(rc, rs)])
+}
+
+/// Every pool, filesystem, and blockdev which has an object, given by its
+/// object path, kind, and name. Pools are in order of name, each followed
+/// by its filesystems in order of name, and then by its blockdevs in order
+/// of device node.
+fn inventory(dbus_context: &DbusContext,
+             engine: &Engine)
+             -> Vec<(dbus::Path<'static>, String, String)> {
+    let mut pools = engine.pools();
+    pools.sort_by(|a, b| a.name().cmp(b.name()));
+
+    let mut objects = Vec::new();
+    for pool in pools {
+        let mut members = vec![(*pool.uuid(), "pool", pool.name().to_owned())];
+
+        let mut filesystems = pool.filesystems();
+        filesystems.sort_by(|a, b| a.name().cmp(b.name()));
+        members.extend(filesystems
+                           .iter()
+                           .map(|f| (*f.uuid(), "filesystem", f.name().to_owned())));
+
+        let mut blockdevs = pool.blockdevs()
+            .iter()
+            .map(|&(_, bd)| (*bd.uuid(), "blockdev", format!("{}", bd.devnode().display())))
+            .collect::<Vec<_>>();
+        blockdevs.sort_by(|a, b| a.2.cmp(&b.2));
+        members.extend(blockdevs);
+
+        objects.extend(members
+                           .into_iter()
+                           .filter_map(|(uuid, kind, name)| {
+                                           dbus_context
+                                               .object_path_for(&uuid)
+                                               .map(|path| (path, kind.to_owned(), name))
+                                       }));
+    }
+    objects
+}
+
+fn request_state_sync(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+
+    let dbus_context = m.tree.get_data();
+    let engine = read_engine!(dbus_context; message.method_return());
+    let objects = inventory(dbus_context, &*engine);
+    dbus_context.signals.borrow_mut().state_sync(objects);
+
+    let (rc, rs) = ok_message_items();
+    Ok(vec![message.method_return().append2(rc, rs)])
+}
+
+fn get_list_items<T, I>(i: &mut IterAppend, iter: I) -> Result<(), MethodErr>
+    where T: Display + Into<u16>,
+          I: Iterator<Item = T>
+{
+    let msg_vec = iter.map(|item| {
+                               MessageItem::Struct(vec![MessageItem::Str(format!("{}", item)),
+                                                        MessageItem::UInt16(item.into())])
+                           })
+        .collect::<Vec<MessageItem>>();
+    i.append(MessageItem::Array(msg_vec, Cow::Borrowed("(sq)")));
+    Ok(())
+}
+
+fn get_error_values(i: &mut IterAppend,
+                    _p: &PropInfo<MTFn<TData>, TData>)
+                    -> Result<(), MethodErr> {
+    get_list_items(i, DbusErrorEnum::iter_variants())
+}
+
+
+fn get_redundancy_values(i: &mut IterAppend,
+                         _p: &PropInfo<MTFn<TData>, TData>)
+                         -> Result<(), MethodErr> {
+    get_list_items(i, Redundancy::iter_variants())
+}
+
+fn get_cache_mode_values(i: &mut IterAppend,
+                         _p: &PropInfo<MTFn<TData>, TData>)
+                         -> Result<(), MethodErr> {
+    get_list_items(i, CacheMode::iter_variants())
+}
+
+fn get_compression_values(i: &mut IterAppend,
+                          _p: &PropInfo<MTFn<TData>, TData>)
+                          -> Result<(), MethodErr> {
+    get_list_items(i, Compression::iter_variants())
+}
+
+fn get_allocation_policy_values(i: &mut IterAppend,
+                                _p: &PropInfo<MTFn<TData>, TData>)
+                                -> Result<(), MethodErr> {
+    get_list_items(i, AllocationPolicy::iter_variants())
+}
+
+fn get_blockdev_check_values(i: &mut IterAppend,
+                             _p: &PropInfo<MTFn<TData>, TData>)
+                             -> Result<(), MethodErr> {
+    get_list_items(i, BlockDevCheck::iter_variants())
+}
+
+fn get_blockdev_tier_values(i: &mut IterAppend,
+                            _p: &PropInfo<MTFn<TData>, TData>)
+                            -> Result<(), MethodErr> {
+    get_list_items(i, BlockDevTier::iter_variants())
+}
+
+fn get_pool_state_values(i: &mut IterAppend,
+                         _p: &PropInfo<MTFn<TData>, TData>)
+                         -> Result<(), MethodErr> {
+    get_list_items(i, PoolState::iter_variants())
+}
+
+fn get_default_redundancy(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let redundancy = m.tree.get_data().default_redundancy.get();
+
+    let (rc, rs) = ok_message_items();
+    Ok(vec![message
+                .method_return()
+                .append3(MessageItem::UInt16(redundancy), rc, rs)])
+}
+
+fn supports_feature(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let feature: &str = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let engine = read_engine!(dbus_context; false; message.method_return());
+    let supported = engine.supports(feature);
+
+    let (rc, rs) = ok_message_items();
+    Ok(vec![message
+                .method_return()
+                .append3(MessageItem::Bool(supported), rc, rs)])
+}
+
+/// The defaults which apply to a newly created pool: its redundancy, its
+/// extent size in bytes, the size in bytes of the smallest device which
+/// may be made one of its blockdevs, and the number of filesystems it
+/// may hold.
+fn defaults(dbus_context: &DbusContext) -> (u16, u64, u64, u32) {
+    (dbus_context.default_redundancy.get(),
+     *DEFAULT_EXTENT_SIZE * SECTOR_SIZE as u64,
+     *MIN_DEV_SIZE,
+     DEFAULT_MAX_FILESYSTEMS)
+}
+
+fn get_defaults(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let (redundancy, extent_bytes, min_dev_bytes, max_filesystems) =
+        defaults(m.tree.get_data());
+
+    let (rc, rs) = ok_message_items();
+    Ok(vec![message
+                .method_return()
+                .append3(MessageItem::Struct(vec![MessageItem::UInt16(redundancy),
+                                                  MessageItem::UInt64(extent_bytes),
+                                                  MessageItem::UInt64(min_dev_bytes),
+                                                  MessageItem::UInt32(max_filesystems)]),
+                         rc,
+                         rs)])
+}
+
+fn suggest_redundancy(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let device_count: u16 = try!(get_next_arg(&mut iter, 0));
+
+    let return_message = message.method_return();
+
+    let msg = match Redundancy::suggest(device_count as usize) {
+        Some(redundancy) => {
+            let suggestion = MessageItem::Struct(vec![MessageItem::UInt16(redundancy.into()),
+                                                      MessageItem::Str(redundancy.to_string())]);
+            let (rc, rs) = ok_message_items();
+            return_message.append3(suggestion, rc, rs)
+        }
+        None => {
+            let default_return = MessageItem::Struct(vec![MessageItem::UInt16(0),
+                                                          MessageItem::Str(String::new())]);
+            let error_message = format!("no redundancy is possible with {} devices",
+                                        device_count);
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::ERROR, error_message);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+fn set_default_redundancy(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let redundancy: u16 = try!(get_next_arg(&mut iter, 0));
+
+    let return_message = message.method_return();
+
+    let msg = match m.tree.get_data().set_default_redundancy(redundancy) {
+        Ok(()) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append2(rc, rs)
+        }
+        Err(error_message) => {
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::ERROR, error_message);
+            return_message.append2(rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+fn get_log_level(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let level = m.tree.get_data().log_level.get();
+
+    let (rc, rs) = ok_message_items();
+    Ok(vec![message
+                .method_return()
+                .append3(MessageItem::Str(level), rc, rs)])
+}
+
+fn set_log_level(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let level: &str = try!(get_next_arg(&mut iter, 0));
+
+    let return_message = message.method_return();
+
+    let msg = match m.tree.get_data().log_level.set(level) {
+        Ok(_) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append2(rc, rs)
+        }
+        Err(error_message) => {
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::ERROR, error_message);
+            return_message.append2(rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+fn get_service_name(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let service_name = m.tree.get_data().service_name.as_str().to_owned();
+
+    let (rc, rs) = ok_message_items();
+    Ok(vec![message
+                .method_return()
+                .append3(MessageItem::Str(service_name), rc, rs)])
+}
+
+fn get_version(i: &mut IterAppend, _p: &PropInfo<MTFn<TData>, TData>) -> Result<(), MethodErr> {
+    i.append(VERSION);
+    Ok(())
+}
+
+/// The version, git commit, build profile, and features of stratisd, as
+/// returned by GetBuildInfo.
+fn build_info() -> MessageItem {
+    let features = features()
+        .into_iter()
+        .map(|f| MessageItem::Str(f.into()))
+        .collect();
+    MessageItem::Struct(vec![MessageItem::Str(VERSION.into()),
+                             MessageItem::Str(git_hash().into()),
+                             MessageItem::Str(build_profile().into()),
+                             MessageItem::Array(features, "s".into())])
+}
+
+fn get_build_info(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+
+    let (rc, rs) = ok_message_items();
+    Ok(vec![message.method_return().append3(build_info(), rc, rs)])
+}
+
+fn configure_simulator(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let denominator: u32 = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let return_message = message.method_return();
+
+    let result = write_engine!(dbus_context; return_message).configure_simulator(denominator);
+
+    let msg = match result {
+        Ok(_) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append2(rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append2(rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+fn get_audit_log(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let count: u32 = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let audit_log = dbus_context.audit_log.borrow();
+
+    let entries = audit_log
+        .last(count as usize)
+        .iter()
+        .map(|entry| {
+                 MessageItem::Struct(vec![MessageItem::UInt64(entry.timestamp),
+                                          MessageItem::Str(entry.operation.clone()),
+                                          MessageItem::Str(entry.result.clone())])
+             })
+        .collect();
+
+    Ok(vec![list_return(message, entries, "(tss)")])
+}
+
+fn get_error_history(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let count: u32 = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let error_log = dbus_context.error_log.borrow();
+
+    let entries = error_log
+        .last(count as usize)
+        .iter()
+        .map(|entry| {
+                 MessageItem::Struct(vec![MessageItem::Str(entry.operation.clone()),
+                                          MessageItem::Str(entry.target.clone()),
+                                          MessageItem::Str(entry.error.clone()),
+                                          MessageItem::UInt64(entry.timestamp)])
+             })
+        .collect();
+
+    Ok(vec![list_return(message, entries, "(ssst)")])
+}
+
+/// The engine's unacknowledged alerts, each given by its id, severity,
+/// message, and the time it was raised in RFC 3339 format.
+fn alert_list(engine: &Engine) -> Vec<(u64, u16, String, String)> {
+    engine
+        .alerts()
+        .into_iter()
+        .map(|alert| {
+                 let raised = time::at_utc(time::Timespec::new(alert.timestamp as i64, 0));
+                 (alert.id, alert.severity.into(), alert.message, raised.rfc3339().to_string())
+             })
+        .collect()
+}
+
+fn list_alerts(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+
+    let dbus_context = m.tree.get_data();
+    let default_return = MessageItem::Array(vec![], "(tqss)".into());
+    let engine = read_engine!(dbus_context; default_return; message.method_return());
+
+    let alerts = alert_list(&*engine)
+        .into_iter()
+        .map(|(id, severity, alert_message, raised)| {
+                 MessageItem::Struct(vec![MessageItem::UInt64(id),
+                                          MessageItem::UInt16(severity),
+                                          MessageItem::Str(alert_message),
+                                          MessageItem::Str(raised)])
+             })
+        .collect();
+
+    Ok(vec![list_return(message, alerts, "(tqss)")])
+}
+
+fn acknowledge_alert(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
+
+    let alert_id: u64 = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let return_message = message.method_return();
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; ; return_message);
+
+    let result = write_engine!(dbus_context; correlation_id; ; return_message)
+        .acknowledge_alert(alert_id);
+    let operation = format!("AcknowledgeAlert {}", alert_id);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(_) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append2(rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append2(rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+fn check_pool_devices(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let redundancy: u16 = try!(get_next_arg(&mut iter, 0));
+    let devs: Array<&str, _> = try!(get_next_arg(&mut iter, 1));
+
+    let blockdevs = devs.map(|x| Path::new(x)).collect::<Vec<&Path>>();
+
+    let dbus_context = m.tree.get_data();
+    let return_message = message.method_return();
+    let return_sig = "(sqs)";
+    let default_return = MessageItem::Struct(vec![MessageItem::Bool(false),
+                                                  MessageItem::Array(vec![], return_sig.into())]);
+
+    let result = read_engine!(dbus_context; default_return.clone(); return_message)
+        .check_blockdevs(&blockdevs, Some(redundancy));
+
+    let msg = match result {
+        Ok((ok, checks)) => {
+            let checks = checks
+                .into_iter()
+                .map(|(path, check, reason)| {
+                         MessageItem::Struct(vec![MessageItem::Str(format!("{}",
+                                                                           path.display())),
+                                                  MessageItem::UInt16(check.into()),
+                                                  MessageItem::Str(reason)])
+                     })
+                .collect();
+            let return_value = MessageItem::Struct(vec![MessageItem::Bool(ok),
+                                                        MessageItem::Array(checks,
+                                                                           return_sig.into())]);
+            let (rc, rs) = ok_message_items();
+            return_message.append3(return_value, rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+fn dump_state(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let redact: bool = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let return_message = message.method_return();
+    let engine = read_engine!(dbus_context; ""; return_message);
+
+    let result = EngineDump::new(&*engine, redact).to_json();
+
+    let msg = match result {
+        Ok(state) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Str(state), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(MessageItem::Str(String::new()), rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+fn load_state(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 2));
+
+    let json: &str = try!(get_next_arg(&mut iter, 0));
+    let replace: bool = try!(get_next_arg(&mut iter, 1));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Array(vec![], "o".into());
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let result = EngineDump::from_json(json).and_then(|dump| {
+        load_state_objects(dbus_context, &mut *engine, object_path, &dump, replace)
+    });
+    let operation = format!("LoadState replace={}", replace);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(paths) => {
+            let paths = paths.into_iter().map(MessageItem::ObjectPath).collect();
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Array(paths, "o".into()), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+/// Rescan the engine's devices, and create objects, under object_path, for
+/// the pools set up as a result.
+/// Returns the object paths of the pools set up.
+fn rescan_objects(dbus_context: &DbusContext,
+                  engine: &mut Engine,
+                  object_path: &dbus::Path<'static>)
+                  -> EngineResult<Vec<dbus::Path<'static>>> {
+    let uuids = try!(engine.rescan());
+    Ok(uuids
+           .iter()
+           .map(|uuid| {
+                    create_pool_objects(dbus_context,
+                                        object_path,
+                                        engine.get_pool(uuid).expect("pool was just set up"))
+                })
+           .collect())
+}
+
+fn rescan_devices(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, _) = try!(get_correlation_id(message, 0));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Array(vec![], "o".into());
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let result = rescan_objects(dbus_context, &mut *engine, object_path);
+    let operation = "RescanDevices".to_owned();
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(paths) => {
+            let paths = paths.into_iter().map(MessageItem::ObjectPath).collect();
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Array(paths, "o".into()), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+/// Execute transaction, and, if it succeeds, bring the objects known to
+/// dbus_context into line with the engine: the removal of the objects of
+/// pools, filesystems, and blockdevs which no longer exist is queued, and
+/// objects are created, under object_path, for those which have none.
+/// Returns the index and error of the failing step, if any.
+fn execute_transaction_objects(dbus_context: &DbusContext,
+                               engine: &mut Engine,
+                               object_path: &dbus::Path<'static>,
+                               transaction: &Transaction)
+                               -> EngineResult<Option<(usize, EngineError)>> {
+    if let Some(failure) = try!(transaction.execute(engine)) {
+        return Ok(Some(failure));
+    }
+    remove_orphaned_paths(dbus_context, engine);
+    for pool in engine.pools() {
+        let pool_path = create_dbus_pool(dbus_context, object_path.clone(), *pool.uuid());
+        create_dbus_blockdevs(dbus_context, &pool_path, pool);
+        for fs_uuid in pool.filesystems().iter().map(|f| *f.uuid()) {
+            if dbus_context.object_path_for(&fs_uuid).is_none() {
+                create_dbus_filesystem(dbus_context, pool_path.clone(), fs_uuid);
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn execute_transaction(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
+
+    let json: &str = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Struct(vec![MessageItem::Bool(false),
+                                                  MessageItem::UInt32(0)]);
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let (failed_step, result) = match Transaction::from_json(json).and_then(|t| {
+        execute_transaction_objects(dbus_context, &mut *engine, object_path, &t)
+    }) {
+            Ok(Some((index, err))) => (Some(index), Err(err)),
+            Ok(None) => (None, Ok(())),
+            Err(err) => (None, Err(err)),
+        };
+    let operation = match failed_step {
+        Some(index) => format!("ExecuteTransaction failed_step={}", index),
+        None => "ExecuteTransaction".into(),
+    };
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let failed_step = match failed_step {
+        Some(index) => {
+            MessageItem::Struct(vec![MessageItem::Bool(true), MessageItem::UInt32(index as u32)])
+        }
+        None => default_return,
+    };
+    let msg = match result {
+        Ok(_) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(failed_step, rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(failed_step, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+/// The names of the pools known to the engine which begin with prefix,
+/// in sorted order.
+fn pool_names(engine: &Engine, prefix: &str) -> Vec<String> {
+    let mut names = engine
+        .pools()
+        .iter()
+        .map(|p| p.name())
+        .filter(|n| n.starts_with(prefix))
+        .map(|n| n.to_owned())
+        .collect::<Vec<String>>();
+    names.sort();
+    names
+}
+
+fn list_pools(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let prefix: &str = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let default_return = MessageItem::Array(vec![], "s".into());
+    let engine = read_engine!(dbus_context; default_return; message.method_return());
+    let names = pool_names(&*engine, prefix)
+        .into_iter()
+        .map(MessageItem::Str)
+        .collect();
+
+    Ok(vec![list_return(message, names, "s")])
+}
+
+/// The name, UUID, and state of every pool, in order of name.
+fn pools_full(engine: &Engine) -> Vec<(String, Uuid, PoolState)> {
+    let mut pools = engine
+        .pools()
+        .iter()
+        .map(|p| (p.name().to_owned(), *p.uuid(), p.state()))
+        .collect::<Vec<_>>();
+    pools.sort_by(|a, b| a.0.cmp(&b.0));
+    pools
+}
+
+fn list_pools_full(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+
+    let dbus_context = m.tree.get_data();
+    let default_return = MessageItem::Array(vec![], "(ssq)".into());
+    let engine = read_engine!(dbus_context; default_return; message.method_return());
+    let pools = pools_full(&*engine)
+        .into_iter()
+        .map(|(name, uuid, state)| {
+                 MessageItem::Struct(vec![MessageItem::Str(name),
+                                          MessageItem::Str(format!("{}", uuid.simple())),
+                                          MessageItem::UInt16(state.into())])
+             })
+        .collect();
+
+    Ok(vec![list_return(message, pools, "(ssq)")])
+}
+
+/// The name and UUID of every pool marked deleted, in order of name.
+fn deleted_pools(engine: &Engine) -> Vec<(String, Uuid)> {
+    let mut pools = engine
+        .deleted_pools()
+        .iter()
+        .map(|p| (p.name().to_owned(), *p.uuid()))
+        .collect::<Vec<_>>();
+    pools.sort_by(|a, b| a.0.cmp(&b.0));
+    pools
+}
+
+fn list_deleted_pools(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+
+    let dbus_context = m.tree.get_data();
+    let default_return = MessageItem::Array(vec![], "(ss)".into());
+    let engine = read_engine!(dbus_context; default_return; message.method_return());
+    let pools = deleted_pools(&*engine)
+        .into_iter()
+        .map(|(name, uuid)| {
+                 MessageItem::Struct(vec![MessageItem::Str(name),
+                                          MessageItem::Str(format!("{}", uuid.simple()))])
+             })
+        .collect();
+
+    Ok(vec![list_return(message, pools, "(ss)")])
+}
+
+/// The name and object path of each pool named in names which has an
+/// object, in the order of names. Names of unknown pools are omitted.
+fn pool_object_paths(dbus_context: &DbusContext,
+                     engine: &Engine,
+                     names: &[&str])
+                     -> Vec<(String, dbus::Path<'static>)> {
+    let pools = engine.pools();
+    names
+        .iter()
+        .filter_map(|name| {
+                        pools
+                            .iter()
+                            .find(|p| p.name() == *name)
+                            .and_then(|p| dbus_context.object_path_for(p.uuid()))
+                            .map(|path| (name.to_string(), path))
+                    })
+        .collect()
+}
+
+fn get_pool_object_paths(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let names: Array<&str, _> = try!(get_next_arg(&mut iter, 0));
+    let names = names.collect::<Vec<&str>>();
+
+    let dbus_context = m.tree.get_data();
+    let default_return = MessageItem::Array(vec![], "(so)".into());
+    let engine = read_engine!(dbus_context; default_return; message.method_return());
+    let paths = pool_object_paths(dbus_context, &*engine, &names)
+        .into_iter()
+        .map(|(name, path)| {
+                 MessageItem::Struct(vec![MessageItem::Str(name), MessageItem::ObjectPath(path)])
+             })
+        .collect();
+
+    Ok(vec![list_return(message, paths, "(so)")])
+}
+
+/// The pool name, filesystem name, and filesystem type of every filesystem
+/// whose type matches fs_type, ignoring case.
+fn filesystems_by_type(engine: &Engine, fs_type: &str) -> Vec<(String, String, String)> {
+    engine
+        .pools()
+        .iter()
+        .flat_map(|p| {
+                      p.filesystems()
+                          .into_iter()
+                          .filter(|f| f.fs_type().eq_ignore_ascii_case(fs_type))
+                          .map(move |f| {
+                                   (p.name().to_owned(),
+                                    f.name().to_owned(),
+                                    f.fs_type().to_owned())
+                               })
+                  })
+        .collect()
+}
+
+/// The pool and filesystem names of every filesystem whose snapshot
+/// schedule is due at the UNIX time now, in order. Malformed schedules are
+/// never due.
+fn due_snapshots(engine: &Engine, now: u64) -> Vec<(String, String)> {
+    let mut due = engine
+        .pools()
+        .iter()
+        .flat_map(|p| {
+                      p.filesystems()
+                          .into_iter()
+                          .filter(|f| {
+                                      f.snapshot_schedule()
+                                          .map_or(false, |s| schedule_due(s, now).unwrap_or(false))
+                                  })
+                          .map(move |f| (p.name().to_owned(), f.name().to_owned()))
+                  })
+        .collect::<Vec<_>>();
+    due.sort();
+    due
+}
+
+/// The name and state of every pool whose state is not Good, in order of
+/// name.
+fn unhealthy_pools(engine: &Engine) -> Vec<(String, PoolState)> {
+    let mut pools = engine
+        .pools()
+        .iter()
+        .filter(|p| p.state() != PoolState::Good)
+        .map(|p| (p.name().to_owned(), p.state()))
+        .collect::<Vec<_>>();
+    pools.sort_by(|a, b| a.0.cmp(&b.0));
+    pools
+}
+
+/// The names of the pools tagged with tag, in order.
+fn pools_by_tag(engine: &Engine, tag: &str) -> Vec<String> {
+    let mut pools = engine
+        .pools()
+        .iter()
+        .filter(|p| p.tags().contains(&tag))
+        .map(|p| p.name().to_owned())
+        .collect::<Vec<_>>();
+    pools.sort();
+    pools
+}
+
+/// The state of pool, an explanation of it, and the number of its data
+/// blockdevs which are missing because they have failed.
+fn state_detail(pool: &Pool) -> (PoolState, String, u16) {
+    let blockdevs = pool.blockdevs()
+        .into_iter()
+        .filter(|&(tier, _)| tier == BlockDevTier::Data)
+        .map(|(_, bd)| bd)
+        .collect::<Vec<_>>();
+    let missing = blockdevs
+        .iter()
+        .filter(|bd| bd.state() == BlockDevState::Failed)
+        .count();
+    let critical = blockdevs
+        .iter()
+        .find(|bd| bd.health() == BlockDevHealth::Critical);
+
+    let redundancy = pool.redundancy();
+    let detail = if missing > redundancy.tolerance() {
+        format!("{} of {} devices missing, exceeds {} tolerance of {}",
+                missing,
+                blockdevs.len(),
+                redundancy,
+                redundancy.tolerance())
+    } else if missing > 0 {
+        format!("{} of {} devices missing", missing, blockdevs.len())
+    } else if let Some(bd) = critical {
+        format!("device {} is in critical health", bd.devnode().display())
+    } else {
+        format!("all {} devices present", blockdevs.len())
+    };
+    let read_only = blockdevs.iter().filter(|bd| bd.read_only()).count();
+    let detail = if read_only > 0 {
+        format!("{}; {} of {} devices read-only", detail, read_only, blockdevs.len())
+    } else {
+        detail
+    };
+    (pool.state(), detail, missing as u16)
+}
+
+fn get_pool_state_detail(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let name: &str = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Struct(vec![MessageItem::UInt16(0),
+                                                  MessageItem::Str("".into()),
+                                                  MessageItem::UInt16(0)]);
+    let engine = read_engine!(dbus_context; default_return; return_message);
+
+    let msg = match engine.pools().into_iter().find(|p| p.name() == name) {
+        Some(pool) => {
+            let (state, detail, missing) = state_detail(pool);
+            let return_value = MessageItem::Struct(vec![MessageItem::UInt16(state.into()),
+                                                        MessageItem::Str(detail),
+                                                        MessageItem::UInt16(missing)]);
+            let (rc, rs) = ok_message_items();
+            return_message.append3(return_value, rc, rs)
+        }
+        None => {
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::NOTFOUND,
+                                                 format!("no pool named {}", name));
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+/// The number of further blockdevs pool may lose without losing data:
+/// the tolerance of its redundancy, plus its spares, less the data
+/// blockdevs which have already failed.
+fn fault_tolerance(pool: &Pool) -> u16 {
+    let blockdevs = pool.blockdevs();
+    let failed = blockdevs
+        .iter()
+        .filter(|&&(tier, bd)| tier == BlockDevTier::Data && bd.state() == BlockDevState::Failed)
+        .count();
+    let spares = blockdevs
+        .iter()
+        .filter(|&&(tier, _)| tier == BlockDevTier::Spare)
+        .count();
+    (pool.redundancy().tolerance() + spares).saturating_sub(failed) as u16
+}
+
+fn get_fault_tolerance(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let name: &str = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let return_message = message.method_return();
+    let engine = read_engine!(dbus_context; MessageItem::UInt16(0); return_message);
+
+    let msg = match engine.pools().into_iter().find(|p| p.name() == name) {
+        Some(pool) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::UInt16(fault_tolerance(pool)), rc, rs)
+        }
+        None => {
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::NOTFOUND,
+                                                 format!("no pool named {}", name));
+            return_message.append3(MessageItem::UInt16(0), rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+fn get_allocatable_space(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let name: &str = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let return_message = message.method_return();
+    let engine = read_engine!(dbus_context; MessageItem::UInt64(0); return_message);
+
+    let msg = match engine.pools().into_iter().find(|p| p.name() == name) {
+        Some(pool) => {
+            match pool.allocatable_space() {
+                Ok(space) => {
+                    let (rc, rs) = ok_message_items();
+                    return_message.append3(MessageItem::UInt64(*space * SECTOR_SIZE as u64),
+                                           rc,
+                                           rs)
+                }
+                Err(err) => {
+                    let (rc, rs) = engine_to_dbus_err(&err);
+                    let (rc, rs) = code_to_message_items(rc, rs);
+                    return_message.append3(MessageItem::UInt64(0), rc, rs)
+                }
+            }
+        }
+        None => {
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::NOTFOUND,
+                                                 format!("no pool named {}", name));
+            return_message.append3(MessageItem::UInt64(0), rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+fn get_pool_snapshot_usage(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let name: &str = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let return_message = message.method_return();
+    let engine = read_engine!(dbus_context; 0u16, 0u64; return_message);
+
+    let result = match engine.pools().into_iter().find(|p| p.name() == name) {
+        Some(pool) => pool.snapshot_usage(),
+        None => {
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::NOTFOUND,
+                                                 format!("no pool named {}", name));
+            return Ok(vec![return_message.append2(0u16, 0u64).append2(rc, rs)]);
+        }
+    };
+
+    let msg = match result {
+        Ok((count, used)) => {
+            let (rc, rs) = ok_message_items();
+            return_message
+                .append2(count as u16, *used * SECTOR_SIZE as u64)
+                .append2(rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append2(0u16, 0u64).append2(rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+fn get_pool_topology(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let name: &str = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Str("".into());
+    let engine = read_engine!(dbus_context; default_return; return_message);
+
+    let result = match engine.pools().into_iter().find(|p| p.name() == name) {
+        Some(pool) => PoolTopology::new(pool).to_json(),
+        None => {
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::NOTFOUND,
+                                                 format!("no pool named {}", name));
+            return Ok(vec![return_message.append3(default_return, rc, rs)]);
+        }
+    };
+
+    let msg = match result {
+        Ok(json) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Str(json), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+fn diff_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let name: &str = try!(get_next_arg(&mut iter, 0));
+    let desired: &str = try!(get_next_arg(&mut iter, 1));
+
+    let dbus_context = m.tree.get_data();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Str("".into());
+    let engine = read_engine!(dbus_context; default_return; return_message);
+
+    let result = match engine.pools().into_iter().find(|p| p.name() == name) {
+        Some(pool) => {
+            PoolSpec::from_json(desired).and_then(|spec| PoolDiff::new(pool, &spec).to_json())
+        }
+        None => {
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::NOTFOUND,
+                                                 format!("no pool named {}", name));
+            return Ok(vec![return_message.append3(default_return, rc, rs)]);
+        }
+    };
+
+    let msg = match result {
+        Ok(json) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Str(json), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+fn get_pool_fragmentation(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let name: &str = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let return_message = message.method_return();
+    let default_return = MessageItem::UInt16(0);
+    let engine = read_engine!(dbus_context; default_return; return_message);
+
+    let result = match engine.pools().into_iter().find(|p| p.name() == name) {
+        Some(pool) => pool.fragmentation(),
+        None => {
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::NOTFOUND,
+                                                 format!("no pool named {}", name));
+            return Ok(vec![return_message.append3(default_return, rc, rs)]);
+        }
+    };
+
+    let msg = match result {
+        Ok(percent) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::UInt16(percent), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+/// What destroying pool would free: the number of its filesystems, the
+/// bytes it uses, and the device nodes of its devices, in order.
+fn destroy_preview(pool: &Pool) -> EngineResult<(u16, u64, Vec<PathBuf>)> {
+    let used = try!(pool.total_physical_used());
+    let mut devnodes = pool.blockdevs()
+        .into_iter()
+        .map(|(_, bd)| bd.devnode())
+        .collect::<Vec<_>>();
+    devnodes.sort();
+    Ok((pool.filesystems().len() as u16, *used * SECTOR_SIZE as u64, devnodes))
+}
+
+fn preview_destroy_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let name: &str = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Struct(vec![MessageItem::UInt16(0),
+                                                  MessageItem::UInt64(0),
+                                                  MessageItem::Array(vec![], "s".into())]);
+    let engine = read_engine!(dbus_context; default_return; return_message);
+
+    let result = match engine.pools().into_iter().find(|p| p.name() == name) {
+        Some(pool) => destroy_preview(pool),
+        None => {
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::NOTFOUND,
+                                                 format!("no pool named {}", name));
+            return Ok(vec![return_message.append3(default_return, rc, rs)]);
+        }
+    };
+
+    let msg = match result {
+        Ok((filesystems, used, devnodes)) => {
+            let paths = devnodes
+                .iter()
+                .map(|d| MessageItem::Str(format!("{}", d.display())))
+                .collect();
+            let return_value = MessageItem::Struct(vec![MessageItem::UInt16(filesystems),
+                                                        MessageItem::UInt64(used),
+                                                        MessageItem::Array(paths, "s".into())]);
+            let (rc, rs) = ok_message_items();
+            return_message.append3(return_value, rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+/// The UUID of the snapshot named snapshot_name in the pool named pool_name.
+/// Returns an error if there is no such filesystem, or if it is not a
+/// snapshot, i.e., has no origin.
+fn snapshot_uuid(engine: &Engine, pool_name: &str, snapshot_name: &str) -> EngineResult<Uuid> {
+    let pool = try!(engine
+                        .pools()
+                        .into_iter()
+                        .find(|p| p.name() == pool_name)
+                        .ok_or_else(|| {
+                                        EngineError::Engine(ErrorEnum::NotFound,
+                                                            format!("no pool named {}", pool_name))
+                                    }));
+    let filesystem = try!(pool.filesystems()
+                              .into_iter()
+                              .find(|f| f.name() == snapshot_name)
+                              .ok_or_else(|| {
+                                              EngineError::Engine(ErrorEnum::NotFound,
+                                                                  format!("no snapshot named {}",
+                                                                          snapshot_name))
+                                          }));
+    if filesystem.origin().is_none() {
+        let message = format!("filesystem {} is not a snapshot", snapshot_name);
+        return Err(EngineError::Engine(ErrorEnum::Error, message));
+    }
+    Ok(*filesystem.uuid())
+}
+
+fn get_snapshot_object_path(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let pool_name: &str = try!(get_next_arg(&mut iter, 0));
+    let snapshot_name: &str = try!(get_next_arg(&mut iter, 1));
+
+    let dbus_context = m.tree.get_data();
+    let return_message = message.method_return();
+    let default_return = MessageItem::ObjectPath(default_object_path());
+    let engine = read_engine!(dbus_context; default_return.clone(); return_message);
+
+    let result = snapshot_uuid(&*engine, pool_name, snapshot_name)
+        .and_then(|uuid| {
+            dbus_context
+                .object_path_for(&uuid)
+                .ok_or_else(|| {
+                                EngineError::Engine(ErrorEnum::NotFound,
+                                                    format!("no object for snapshot {}",
+                                                            snapshot_name))
+                            })
+        });
+
+    let msg = match result {
+        Ok(path) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::ObjectPath(path), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+/// The names of the filesystems which have data on the device at devnode,
+/// in order, and whether the device could be removed from pool.
+/// Returns an error if the device does not belong to pool.
+fn remove_preview(pool: &Pool, devnode: &Path) -> EngineResult<(Vec<String>, bool)> {
+    let uuid = try!(pool.blockdevs()
+                        .iter()
+                        .find(|&&(_, bd)| bd.devnode() == devnode)
+                        .map(|&(_, bd)| *bd.uuid())
+                        .ok_or_else(|| {
+                                        EngineError::Engine(ErrorEnum::NotFound,
+                                                            format!("{}", devnode.display()))
+                                    }));
+    let names = try!(blockdev_filesystem_names(pool, &uuid));
+    Ok((names, try!(pool.can_remove_blockdev(&uuid))))
+}
+
+/// The names of the filesystems of pool which have data on the blockdev
+/// with uuid, in order.
+fn blockdev_filesystem_names(pool: &Pool, uuid: &DevUuid) -> EngineResult<Vec<String>> {
+    let mut names = try!(pool.blockdev_filesystems(uuid))
+        .iter()
+        .filter_map(|fs_uuid| pool.get_filesystem(fs_uuid))
+        .map(|f| f.name().to_owned())
+        .collect::<Vec<_>>();
+    names.sort();
+    Ok(names)
+}
+
+/// The space used and free on the device of pool at devnode, and the
+/// names of the filesystems which have data on it, in order.
+/// Returns an error if the device does not belong to pool.
+fn device_allocation(pool: &Pool,
+                     devnode: &Path)
+                     -> EngineResult<(Sectors, Sectors, Vec<String>)> {
+    let blockdevs = pool.blockdevs();
+    let bd = try!(blockdevs
+                      .iter()
+                      .find(|&&(_, bd)| bd.devnode() == devnode)
+                      .map(|&(_, bd)| bd)
+                      .ok_or_else(|| {
+                                      EngineError::Engine(ErrorEnum::NotFound,
+                                                          format!("{}", devnode.display()))
+                                  }));
+    let names = try!(blockdev_filesystem_names(pool, bd.uuid()));
+    let (size, used) = (bd.size(), bd.used());
+    Ok((used, if used < size { size - used } else { Sectors(0) }, names))
+}
+
+fn get_device_allocation(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let name: &str = try!(get_next_arg(&mut iter, 0));
+    let devnode: &str = try!(get_next_arg(&mut iter, 1));
+
+    let dbus_context = m.tree.get_data();
+    let return_message = message.method_return();
+    let default_filesystems = MessageItem::Array(vec![], "s".into());
+    let engine = read_engine!(dbus_context; 0u64, 0u64, default_filesystems; return_message);
+
+    let result = match engine.pools().into_iter().find(|p| p.name() == name) {
+        Some(pool) => device_allocation(pool, Path::new(devnode)),
+        None => {
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::NOTFOUND,
+                                                 format!("no pool named {}", name));
+            return Ok(vec![return_message
+                               .append3(0u64, 0u64, default_filesystems)
+                               .append2(rc, rs)]);
+        }
+    };
+
+    let msg = match result {
+        Ok((used, free, filesystems)) => {
+            let filesystems = filesystems.into_iter().map(MessageItem::Str).collect();
+            let (rc, rs) = ok_message_items();
+            return_message
+                .append3(*used * SECTOR_SIZE as u64,
+                         *free * SECTOR_SIZE as u64,
+                         MessageItem::Array(filesystems, "s".into()))
+                .append2(rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message
+                .append3(0u64, 0u64, default_filesystems)
+                .append2(rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+fn preview_remove_dev(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let name: &str = try!(get_next_arg(&mut iter, 0));
+    let devnode: &str = try!(get_next_arg(&mut iter, 1));
+
+    let dbus_context = m.tree.get_data();
+    let return_message = message.method_return();
+    let default_filesystems = MessageItem::Array(vec![], "s".into());
+    let engine = read_engine!(dbus_context; default_filesystems, false; return_message);
+
+    let result = match engine.pools().into_iter().find(|p| p.name() == name) {
+        Some(pool) => remove_preview(pool, Path::new(devnode)),
+        None => {
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::NOTFOUND,
+                                                 format!("no pool named {}", name));
+            return Ok(vec![return_message
+                               .append2(default_filesystems, MessageItem::Bool(false))
+                               .append2(rc, rs)]);
+        }
+    };
+
+    let msg = match result {
+        Ok((filesystems, possible)) => {
+            let filesystems = filesystems.into_iter().map(MessageItem::Str).collect();
+            let (rc, rs) = ok_message_items();
+            return_message
+                .append2(MessageItem::Array(filesystems, "s".into()),
+                         MessageItem::Bool(possible))
+                .append2(rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message
+                .append2(default_filesystems, MessageItem::Bool(false))
+                .append2(rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+/// Every device in every pool: the name of the pool, the device node, the
+/// UUID, the tier, and the size of the device in bytes, in order of pool
+/// name and then of device node.
+fn all_devices(engine: &Engine) -> Vec<(String, PathBuf, DevUuid, BlockDevTier, u64)> {
+    let mut devices = engine
+        .pools()
+        .iter()
+        .flat_map(|pool| {
+                      pool.blockdevs()
+                          .into_iter()
+                          .map(|(tier, bd)| {
+                                   (pool.name().to_owned(),
+                                    bd.devnode(),
+                                    *bd.uuid(),
+                                    tier,
+                                    *bd.size() * SECTOR_SIZE as u64)
+                               })
+                          .collect::<Vec<_>>()
+                  })
+        .collect::<Vec<_>>();
+    devices.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+    devices
+}
+
+fn list_all_devices(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+
+    let dbus_context = m.tree.get_data();
+    let default_return = MessageItem::Array(vec![], "(sssqt)".into());
+    let engine = read_engine!(dbus_context; default_return; message.method_return());
+    let devices = all_devices(&*engine)
+        .into_iter()
+        .map(|(pool_name, devnode, uuid, tier, size)| {
+                 MessageItem::Struct(vec![MessageItem::Str(pool_name),
+                                          MessageItem::Str(format!("{}", devnode.display())),
+                                          MessageItem::Str(format!("{}", uuid.simple())),
+                                          MessageItem::UInt16(tier.into()),
+                                          MessageItem::UInt64(size)])
+             })
+        .collect();
+
+    Ok(vec![list_return(message, devices, "(sssqt)")])
+}
+
+/// Metrics describing the engine and each of its pools, as key=value
+/// lines. Pool keys are prefixed by "pool.<uuid>.", pools are in order of
+/// UUID, and sizes are in bytes, saturating at the largest u64. A pool
+/// metric which can not be read is left out, and counted by "metric_errors".
+fn metrics(engine: &Engine) -> Vec<String> {
+    let mut pools = engine.pools();
+    pools.sort_by_key(|p| *p.uuid());
+
+    let degraded = pools
+        .iter()
+        .filter(|p| p.state() != PoolState::Good)
+        .count();
+    let mut metrics = vec![format!("pools={}", pools.len()),
+                           format!("pools_degraded={}", degraded)];
+
+    let mut errors = 0;
+    for pool in pools {
+        let prefix = format!("pool.{}.", pool.uuid().simple());
+        let io_stats = pool.io_stats();
+        metrics.push(format!("{}state={}", prefix, u16::from(pool.state())));
+        metrics.push(format!("{}filesystems={}", prefix, pool.filesystems().len()));
+        metrics.push(format!("{}total_physical_size_bytes={}",
+                             prefix,
+                             pool.total_physical_size().saturating_mul(SECTOR_SIZE as u64)));
+        match pool.total_physical_used() {
+            Ok(used) => {
+                metrics.push(format!("{}total_physical_used_bytes={}",
+                                     prefix,
+                                     used.saturating_mul(SECTOR_SIZE as u64)))
+            }
+            Err(_) => errors += 1,
+        }
+        metrics.push(format!("{}io.bytes_read={}", prefix, io_stats.bytes_read));
+        metrics.push(format!("{}io.bytes_written={}", prefix, io_stats.bytes_written));
+        metrics.push(format!("{}io.ops={}", prefix, io_stats.io_ops));
+    }
+    metrics.push(format!("metric_errors={}", errors));
+    metrics
+}
+
+fn get_metrics(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+
+    let dbus_context = m.tree.get_data();
+    let default_return = MessageItem::Array(vec![], "s".into());
+    let engine = read_engine!(dbus_context; default_return; message.method_return());
+    let metrics = metrics(&*engine)
+        .into_iter()
+        .map(MessageItem::Str)
+        .collect();
+
+    Ok(vec![list_return(message, metrics, "s")])
+}
+
+fn check_consistency(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+
+    let dbus_context = m.tree.get_data();
+    let default_return = MessageItem::Array(vec![], "s".into());
+    let issues = read_engine!(dbus_context; default_return; message.method_return())
+        .check_consistency()
+        .into_iter()
+        .map(MessageItem::Str)
+        .collect();
+
+    Ok(vec![list_return(message, issues, "s")])
+}
+
+fn list_unhealthy_pools(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+
+    let dbus_context = m.tree.get_data();
+    let default_return = MessageItem::Array(vec![], "(sq)".into());
+    let engine = read_engine!(dbus_context; default_return; message.method_return());
+    let pools = unhealthy_pools(&*engine)
+        .into_iter()
+        .map(|(name, state)| {
+                 MessageItem::Struct(vec![MessageItem::Str(name),
+                                          MessageItem::UInt16(state.into())])
+             })
+        .collect();
+
+    Ok(vec![list_return(message, pools, "(sq)")])
+}
+
+fn list_pools_by_tag(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let tag: &str = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let default_return = MessageItem::Array(vec![], "s".into());
+    let engine = read_engine!(dbus_context; default_return; message.method_return());
+    let pools = pools_by_tag(&*engine, tag)
+        .into_iter()
+        .map(MessageItem::Str)
+        .collect();
+
+    Ok(vec![list_return(message, pools, "s")])
+}
+
+fn evaluate_schedules(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let now: u64 = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let default_return = MessageItem::Array(vec![], "(ss)".into());
+    let engine = read_engine!(dbus_context; default_return; message.method_return());
+    let due = due_snapshots(&*engine, now)
+        .into_iter()
+        .map(|(pool, filesystem)| {
+                 MessageItem::Struct(vec![MessageItem::Str(pool), MessageItem::Str(filesystem)])
+             })
+        .collect();
+
+    Ok(vec![list_return(message, due, "(ss)")])
+}
+
+fn list_filesystems_by_type(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let fs_type: &str = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let default_return = MessageItem::Array(vec![], "(sss)".into());
+    let engine = read_engine!(dbus_context; default_return; message.method_return());
+    let filesystems = filesystems_by_type(&*engine, fs_type)
+        .into_iter()
+        .map(|(pool_name, name, fs_type)| {
+                 MessageItem::Struct(vec![MessageItem::Str(pool_name),
+                                          MessageItem::Str(name),
+                                          MessageItem::Str(fs_type)])
+             })
+        .collect();
+
+    Ok(vec![list_return(message, filesystems, "(sss)")])
+}
+
+/// The device node of the blockdev with this UUID, and the name of the
+/// pool to which it belongs, if any pool has it.
+fn blockdev_location(engine: &Engine, uuid: &DevUuid) -> Option<(PathBuf, String)> {
+    engine
+        .pools()
+        .iter()
+        .filter_map(|p| p.get_blockdev_path(uuid).map(|d| (d, p.name().to_owned())))
+        .next()
+}
+
+fn get_device_path(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message = m.msg;
+    let mut iter = message.iter_init();
+
+    let uuid: &str = try!(get_next_arg(&mut iter, 0));
+
+    let return_message = message.method_return();
+
+    let dev_uuid = match Uuid::parse_str(uuid) {
+        Ok(dev_uuid) => dev_uuid,
+        Err(_) => {
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::ERROR,
+                                                 format!("{} is not a valid UUID", uuid));
+            let msg = return_message.append2("", "").append2(rc, rs);
+            return Ok(vec![msg]);
+        }
+    };
+
+    let dbus_context = m.tree.get_data();
+    let engine = read_engine!(dbus_context; "", ""; return_message);
+    let found = blockdev_location(&*engine, &dev_uuid);
+
+    let msg = match found {
+        Some((devnode, pool_name)) => {
+            let (rc, rs) = ok_message_items();
+            return_message
+                .append2(devnode.to_string_lossy().into_owned(), pool_name)
+                .append2(rc, rs)
+        }
+        None => {
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::NOTFOUND,
+                                                 format!("no blockdev with UUID {}", uuid));
+            return_message.append2("", "").append2(rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+/// The methods of the base object: those of the original Manager interface,
+/// and those added by the r1 revision.
+fn get_manager_methods(f: &Factory<MTFn<TData>, TData>)
+                       -> (Vec<Arc<Method<MTFn<TData>, TData>>>,
+                           Vec<Arc<Method<MTFn<TData>, TData>>>) {
+    let create_pool_method = f.method("CreatePool", (), create_pool)
+        .in_arg(("name", "s"))
+        .in_arg(("redundancy", "(bq)"))
+        .in_arg(("force", "b"))
+        .in_arg(("devices", "as"))
+        .out_arg(("result", "(oas)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let destroy_pool_method = f.method("DestroyPool", (), destroy_pool)
+        .in_arg(("pool", "o"))
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let configure_simulator_method = f.method("ConfigureSimulator", (), configure_simulator)
+        .in_arg(("denominator", "u"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_audit_log_method = f.method("GetAuditLog", (), get_audit_log)
+        .in_arg(("count", "u"))
+        .out_arg(("entries", "a(tss)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let list_pools_method = f.method("ListPools", (), list_pools)
+        .in_arg(("prefix", "s"))
+        .out_arg(("names", "as"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_device_path_method = f.method("GetDevicePath", (), get_device_path)
+        .in_arg(("uuid", "s"))
+        .out_arg(("path", "s"))
+        .out_arg(("pool_name", "s"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let create_pool_default_redundancy_method =
+        f.method("CreatePoolDefaultRedundancy",
+                 (),
+                 create_pool_default_redundancy)
+            .in_arg(("name", "s"))
+            .in_arg(("force", "b"))
+            .in_arg(("devices", "as"))
+            .in_arg(("cache_devices", "as"))
+            .out_arg(("result", "(oas)"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let create_pool_with_cache_method =
+        f.method("CreatePoolWithCache", (), create_pool_with_cache)
+            .in_arg(("name", "s"))
+            .in_arg(("redundancy", "(bq)"))
+            .in_arg(("force", "b"))
+            .in_arg(("devices", "as"))
+            .in_arg(("cache_devices", "as"))
+            .out_arg(("result", "(oas)"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let create_pool_with_tiers_method =
+        f.method("CreatePoolWithTiers", (), create_pool_with_tiers)
+            .in_arg(("name", "s"))
+            .in_arg(("redundancy", "(bq)"))
+            .in_arg(("force", "b"))
+            .in_arg(("devices", "a(sq)"))
+            .out_arg(("result", "(oas)"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let create_pool_with_uuid_method =
+        f.method("CreatePoolWithUuid", (), create_pool_with_uuid)
+            .in_arg(("name", "s"))
+            .in_arg(("uuid", "s"))
+            .in_arg(("redundancy", "(bq)"))
+            .in_arg(("force", "b"))
+            .in_arg(("devices", "as"))
+            .in_arg(("cache_devices", "as"))
+            .out_arg(("result", "(oas)"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let get_default_redundancy_method =
+        f.method("GetDefaultRedundancy", (), get_default_redundancy)
+            .out_arg(("redundancy", "q"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let set_default_redundancy_method =
+        f.method("SetDefaultRedundancy", (), set_default_redundancy)
+            .in_arg(("redundancy", "q"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let get_defaults_method = f.method("GetDefaults", (), get_defaults)
+        .out_arg(("defaults", "(qttu)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let suggest_redundancy_method = f.method("SuggestRedundancy", (), suggest_redundancy)
+        .in_arg(("device_count", "q"))
+        .out_arg(("redundancy", "(qs)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let list_filesystems_by_type_method =
+        f.method("ListFilesystemsByType", (), list_filesystems_by_type)
+            .in_arg(("fs_type", "s"))
+            .out_arg(("filesystems", "a(sss)"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let list_unhealthy_pools_method =
+        f.method("ListUnhealthyPools", (), list_unhealthy_pools)
+            .out_arg(("pools", "a(sq)"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let check_pool_devices_method = f.method("CheckPoolDevices", (), check_pool_devices)
+        .in_arg(("redundancy", "q"))
+        .in_arg(("devices", "as"))
+        .out_arg(("result", "(ba(sqs))"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let list_orphaned_paths_method = f.method("ListOrphanedPaths", (), list_orphaned_paths)
+        .out_arg(("paths", "ao"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let prune_orphaned_paths_method = f.method("PruneOrphanedPaths", (), prune_orphaned_paths)
+        .out_arg(("paths", "ao"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let dump_state_method = f.method("DumpState", (), dump_state)
+        .in_arg(("redact", "b"))
+        .out_arg(("state", "s"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let list_methods_method = f.method("ListMethods", (), list_methods)
+        .out_arg(("names", "as"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_pool_state_detail_method =
+        f.method("GetPoolStateDetail", (), get_pool_state_detail)
+            .in_arg(("pool_name", "s"))
+            .out_arg(("detail", "(qsq)"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let list_alerts_method = f.method("ListAlerts", (), list_alerts)
+        .out_arg(("alerts", "a(tqss)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let acknowledge_alert_method = f.method("AcknowledgeAlert", (), acknowledge_alert)
+        .in_arg(("alert_id", "t"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_fault_tolerance_method = f.method("GetFaultTolerance", (), get_fault_tolerance)
+        .in_arg(("pool_name", "s"))
+        .out_arg(("tolerable_failures", "q"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_pool_topology_method = f.method("GetPoolTopology", (), get_pool_topology)
+        .in_arg(("pool_name", "s"))
+        .out_arg(("topology", "s"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_pool_fragmentation_method =
+        f.method("GetPoolFragmentation", (), get_pool_fragmentation)
+            .in_arg(("pool_name", "s"))
+            .out_arg(("fragmentation_percent", "q"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let list_all_devices_method = f.method("ListAllDevices", (), list_all_devices)
+        .out_arg(("devices", "a(sssqt)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_build_info_method = f.method("GetBuildInfo", (), get_build_info)
+        .out_arg(("build_info", "(sssas)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let list_pending_operations_method =
+        f.method("ListPendingOperations", (), list_pending_operations)
+            .out_arg(("operations", "a(sst)"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let get_base_object_path_method =
+        f.method("GetBaseObjectPath", (), get_base_object_path)
+            .out_arg(("object_path", "o"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let get_metrics_method = f.method("GetMetrics", (), get_metrics)
+        .out_arg(("metrics", "as"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_pool_object_paths_method =
+        f.method("GetPoolObjectPaths", (), get_pool_object_paths)
+            .in_arg(("pool_names", "as"))
+            .out_arg(("object_paths", "a(so)"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let check_consistency_method = f.method("CheckConsistency", (), check_consistency)
+        .out_arg(("issues", "as"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let cancel_operation_method = f.method("CancelOperation", (), cancel_operation)
+        .in_arg(("op_id", "s"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let pause_signals_method = f.method("PauseSignals", (), pause_signals)
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let resume_signals_method = f.method("ResumeSignals", (), resume_signals)
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let request_state_sync_method = f.method("RequestStateSync", (), request_state_sync)
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let create_encrypted_pool_method =
+        f.method("CreateEncryptedPool", (), create_encrypted_pool)
+            .in_arg(("name", "s"))
+            .in_arg(("redundancy", "(bq)"))
+            .in_arg(("force", "b"))
+            .in_arg(("devices", "as"))
+            .in_arg(("cache_devices", "as"))
+            .in_arg(("encryption", "(bs)"))
+            .out_arg(("result", "(oas)"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let create_compressed_pool_method =
+        f.method("CreateCompressedPool", (), create_compressed_pool)
+            .in_arg(("name", "s"))
+            .in_arg(("redundancy", "(bq)"))
+            .in_arg(("force", "b"))
+            .in_arg(("devices", "as"))
+            .in_arg(("cache_devices", "as"))
+            .in_arg(("compression", "q"))
+            .out_arg(("result", "(oas)"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let create_allocated_pool_method =
+        f.method("CreatePoolWithAllocationPolicy", (), create_allocated_pool)
+            .in_arg(("name", "s"))
+            .in_arg(("redundancy", "(bq)"))
+            .in_arg(("force", "b"))
+            .in_arg(("devices", "as"))
+            .in_arg(("cache_devices", "as"))
+            .in_arg(("allocation_policy", "q"))
+            .out_arg(("result", "(oas)"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let preview_destroy_pool_method = f.method("PreviewDestroyPool", (), preview_destroy_pool)
+        .in_arg(("name", "s"))
+        .out_arg(("preview", "(qtas)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let list_pools_by_tag_method = f.method("ListPoolsByTag", (), list_pools_by_tag)
+        .in_arg(("tag", "s"))
+        .out_arg(("pools", "as"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_log_level_method = f.method("GetLogLevel", (), get_log_level)
+        .out_arg(("level", "s"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let set_log_level_method = f.method("SetLogLevel", (), set_log_level)
+        .in_arg(("level", "s"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_service_name_method = f.method("GetServiceName", (), get_service_name)
+        .out_arg(("service_name", "s"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let load_state_method = f.method("LoadState", (), load_state)
+        .in_arg(("state", "s"))
+        .in_arg(("replace", "b"))
+        .out_arg(("pools", "ao"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let rescan_devices_method = f.method("RescanDevices", (), rescan_devices)
+        .out_arg(("pools", "ao"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let evaluate_schedules_method = f.method("EvaluateSchedules", (), evaluate_schedules)
+        .in_arg(("now_unix", "t"))
+        .out_arg(("due", "a(ss)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let supports_feature_method = f.method("SupportsFeature", (), supports_feature)
+        .in_arg(("feature", "s"))
+        .out_arg(("supported", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let force_destroy_pool_method = f.method("ForceDestroyPool", (), force_destroy_pool)
+        .in_arg(("pool", "o"))
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_allocatable_space_method =
+        f.method("GetAllocatableSpace", (), get_allocatable_space)
+            .in_arg(("pool_name", "s"))
+            .out_arg(("bytes", "t"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let get_error_history_method = f.method("GetErrorHistory", (), get_error_history)
+        .in_arg(("count", "u"))
+        .out_arg(("errors", "a(ssst)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let preview_remove_dev_method = f.method("PreviewRemoveDev", (), preview_remove_dev)
+        .in_arg(("pool_name", "s"))
+        .in_arg(("dev_path", "s"))
+        .out_arg(("filesystems", "as"))
+        .out_arg(("possible", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_snapshot_object_path_method =
+        f.method("GetSnapshotObjectPath", (), get_snapshot_object_path)
+            .in_arg(("pool_name", "s"))
+            .in_arg(("snapshot_name", "s"))
+            .out_arg(("object_path", "o"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let diff_pool_method = f.method("DiffPool", (), diff_pool)
+        .in_arg(("pool_name", "s"))
+        .in_arg(("desired", "s"))
+        .out_arg(("diff", "s"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_pool_snapshot_usage_method =
+        f.method("GetPoolSnapshotUsage", (), get_pool_snapshot_usage)
+            .in_arg(("pool_name", "s"))
+            .out_arg(("snapshot_count", "q"))
+            .out_arg(("snapshot_used_bytes", "t"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let get_device_allocation_method =
+        f.method("GetDeviceAllocation", (), get_device_allocation)
+            .in_arg(("pool_name", "s"))
+            .in_arg(("dev_path", "s"))
+            .out_arg(("used_bytes", "t"))
+            .out_arg(("free_bytes", "t"))
+            .out_arg(("filesystems", "as"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let soft_destroy_pool_method = f.method("SoftDestroyPool", (), soft_destroy_pool)
+        .in_arg(("pool", "o"))
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let restore_pool_method = f.method("RestorePool", (), restore_pool)
+        .in_arg(("pool_name", "s"))
+        .out_arg(("object_path", "o"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let purge_pool_method = f.method("PurgePool", (), purge_pool)
+        .in_arg(("pool_name", "s"))
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let list_deleted_pools_method = f.method("ListDeletedPools", (), list_deleted_pools)
+        .out_arg(("pools", "a(ss)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let execute_transaction_method = f.method("ExecuteTransaction", (), execute_transaction)
+        .in_arg(("steps", "s"))
+        .out_arg(("failed_step", "(bu)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let list_pools_full_method = f.method("ListPoolsFull", (), list_pools_full)
+        .out_arg(("pools", "a(ssq)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_object_count_method = f.method("GetObjectCount", (), get_object_count)
+        .out_arg(("counts", "(qqqq)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let methods = vec![create_pool_method,
+                       destroy_pool_method,
+                       configure_simulator_method,
+                       get_audit_log_method,
+                       list_pools_method,
+                       get_device_path_method]
+            .into_iter()
+            .map(Arc::new)
+            .collect::<Vec<_>>();
+
+    let r1_methods = vec![create_pool_default_redundancy_method,
+                          get_default_redundancy_method,
+                          set_default_redundancy_method,
+                          list_filesystems_by_type_method,
+                          list_unhealthy_pools_method,
+                          dump_state_method,
+                          check_pool_devices_method,
+                          list_orphaned_paths_method,
+                          prune_orphaned_paths_method,
+                          create_pool_with_tiers_method,
+                          list_methods_method,
+                          pause_signals_method,
+                          resume_signals_method,
+                          request_state_sync_method,
+                          get_base_object_path_method,
+                          suggest_redundancy_method,
+                          cancel_operation_method,
+                          get_metrics_method,
+                          check_consistency_method,
+                          get_pool_object_paths_method,
+                          get_object_count_method,
+                          create_encrypted_pool_method,
+                          get_defaults_method,
+                          list_pending_operations_method,
+                          get_pool_state_detail_method,
+                          list_all_devices_method,
+                          get_build_info_method,
+                          create_compressed_pool_method,
+                          preview_destroy_pool_method,
+                          list_pools_by_tag_method,
+                          get_log_level_method,
+                          set_log_level_method,
+                          get_service_name_method,
+                          load_state_method,
+                          list_pools_full_method,
+                          get_fault_tolerance_method,
+                          list_alerts_method,
+                          acknowledge_alert_method,
+                          create_allocated_pool_method,
+                          get_pool_topology_method,
+                          get_pool_fragmentation_method,
+                          execute_transaction_method,
+                          evaluate_schedules_method,
+                          supports_feature_method,
+                          force_destroy_pool_method,
+                          get_allocatable_space_method,
+                          get_error_history_method,
+                          preview_remove_dev_method,
+                          rescan_devices_method,
+                          get_snapshot_object_path_method,
+                          diff_pool_method,
+                          get_pool_snapshot_usage_method,
+                          create_pool_with_uuid_method,
+                          get_device_allocation_method,
+                          soft_destroy_pool_method,
+                          restore_pool_method,
+                          purge_pool_method,
+                          list_deleted_pools_method,
+                          create_pool_with_cache_method]
+            .into_iter()
+            .map(Arc::new)
+            .collect::<Vec<_>>();
+
+    (methods, r1_methods)
+}
+
+/// The interfaces of the base object, oldest first.
+///
+/// Each interface is versioned by a revision suffix; the original Manager
+/// interface has none. Changes to an interface are additive only: methods
+/// and properties may be added to the newest revision, but a published
+/// method or property is never removed or changed. Each revision contains
+/// everything in the revisions that precede it, so a client may use the
+/// newest revision it knows about, and old clients continue to use the
+/// interfaces they were written against.
+fn get_manager_interfaces(f: &Factory<MTFn<TData>, TData>) -> Vec<Interface<MTFn<TData>, TData>> {
+    let (methods, r1_methods) = get_manager_methods(f);
+
+    let redundancy_values_property =
+        f.property::<Array<(&str, u16), &Iterator<Item = (&str, u16)>>, _>("RedundancyValues", ())
+            .access(Access::Read)
+            .emits_changed(EmitsChangedSignal::Const)
+            .on_get(get_redundancy_values);
+
+    let error_values_property =
+        f.property::<Array<(&str, u16), &Iterator<Item = (&str, u16)>>, _>("ErrorValues", ())
+            .access(Access::Read)
+            .emits_changed(EmitsChangedSignal::Const)
+            .on_get(get_error_values);
+
+    let pool_state_values_property =
+        f.property::<Array<(&str, u16), &Iterator<Item = (&str, u16)>>, _>("PoolStateValues", ())
+            .access(Access::Read)
+            .emits_changed(EmitsChangedSignal::Const)
+            .on_get(get_pool_state_values);
+
+    let blockdev_check_values_property =
+        f.property::<Array<(&str, u16), &Iterator<Item = (&str, u16)>>, _>("BlockDevCheckValues",
+                                                                           ())
+            .access(Access::Read)
+            .emits_changed(EmitsChangedSignal::Const)
+            .on_get(get_blockdev_check_values);
+
+    let blockdev_tier_values_property =
+        f.property::<Array<(&str, u16), &Iterator<Item = (&str, u16)>>, _>("BlockDevTierValues",
+                                                                           ())
+            .access(Access::Read)
+            .emits_changed(EmitsChangedSignal::Const)
+            .on_get(get_blockdev_tier_values);
+
+    let cache_mode_values_property =
+        f.property::<Array<(&str, u16), &Iterator<Item = (&str, u16)>>, _>("CacheModeValues", ())
+            .access(Access::Read)
+            .emits_changed(EmitsChangedSignal::Const)
+            .on_get(get_cache_mode_values);
+
+    let compression_values_property =
+        f.property::<Array<(&str, u16), &Iterator<Item = (&str, u16)>>, _>("CompressionValues",
+                                                                           ())
+            .access(Access::Read)
+            .emits_changed(EmitsChangedSignal::Const)
+            .on_get(get_compression_values);
+
+    let allocation_policy_values_property =
+        f.property::<Array<(&str, u16), &Iterator<Item = (&str, u16)>>,
+                     _>("AllocationPolicyValues", ())
+            .access(Access::Read)
+            .emits_changed(EmitsChangedSignal::Const)
+            .on_get(get_allocation_policy_values);
+
+    let object_added_signal = f.signal("ObjectAdded", ())
+        .sarg::<dbus::Path, _>("object");
+
+    let object_removed_signal = f.signal("ObjectRemoved", ())
+        .sarg::<dbus::Path, _>("object");
+
+    let bulk_change_signal = f.signal("BulkChange", ())
+        .sarg::<Vec<dbus::Path>, _>("added")
+        .sarg::<Vec<dbus::Path>, _>("removed");
+
+    let state_sync_signal = f.signal("StateSync", ())
+        .sarg::<Vec<(dbus::Path, &str, &str)>, _>("objects");
+
+    let version_property = f.property::<&str, _>("Version", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::Const)
+        .on_get(get_version);
+
+    let properties = vec![error_values_property,
+                          pool_state_values_property,
+                          redundancy_values_property,
+                          version_property]
+            .into_iter()
+            .map(Arc::new)
+            .collect::<Vec<_>>();
+
+    let manager_interface = f.interface(format!("{}.{}", STRATIS_BASE_SERVICE, "Manager"), ());
+    let manager_interface = methods
+        .iter()
+        .fold(manager_interface, |i, m| i.add_m(m.clone()));
+    let manager_interface = properties
+        .iter()
+        .fold(manager_interface, |i, p| i.add_p(p.clone()));
+
+    let r1_interface = f.interface(format!("{}.{}", STRATIS_BASE_SERVICE, "Manager.r1"), ());
+    let r1_interface = methods
+        .iter()
+        .fold(r1_interface, |i, m| i.add_m(m.clone()));
+    let r1_interface = properties
+        .iter()
+        .fold(r1_interface, |i, p| i.add_p(p.clone()));
+    let r1_interface = r1_methods
+        .iter()
+        .fold(r1_interface, |i, m| i.add_m(m.clone()))
+        .add_p(cache_mode_values_property)
+        .add_p(compression_values_property)
+        .add_p(allocation_policy_values_property)
+        .add_p(blockdev_check_values_property)
+        .add_p(blockdev_tier_values_property)
+        .add_s(object_added_signal)
+        .add_s(object_removed_signal)
+        .add_s(bulk_change_signal)
+        .add_s(state_sync_signal);
+
+    vec![manager_interface, r1_interface]
+}
+
+fn get_base_tree<'a>(dbus_context: DbusContext) -> (Tree<MTFn<TData>, TData>, dbus::Path<'a>) {
+
+    let f = Factory::new_fn();
+
+    let base_tree = f.tree(dbus_context);
+
+    let obj_path = get_manager_interfaces(&f)
+        .into_iter()
+        .fold(f.object_path(STRATIS_BASE_PATH, None)
+                  .introspectable()
+                  .object_manager(),
+              |p, i| p.add(i));
+
+    let path = obj_path.get_name().to_owned();
+    (base_tree.add(obj_path), path)
+}
+
+/// The context in which stratisd serves D-Bus requests, registered under
+/// service_name, or under STRATIS_BASE_SERVICE if service_name is None.
+fn daemon_context(engine: Arc<RwLock<Engine>>,
+                  audit_log_size: usize,
+                  error_log_size: usize,
+                  log_level: LogLevel,
+                  service_name: Option<&str>)
+                  -> DbusContext {
+    let mut dbus_context = DbusContext::new(engine, audit_log_size);
+    dbus_context.error_log = Rc::new(RefCell::new(ErrorLog::new(error_log_size)));
+    dbus_context.log_level = Rc::new(log_level);
+    if let Some(service_name) = service_name {
+        dbus_context.service_name = Rc::new(service_name.into());
+    }
+    dbus_context
+}
+
+#[allow(type_complexity)]
+pub fn connect(engine: Arc<RwLock<Engine>>,
+               audit_log_size: usize,
+               error_log_size: usize,
+               log_level: LogLevel,
+               service_name: Option<&str>)
+               -> Result<(Connection, Tree<MTFn<TData>, TData>, DbusContext), dbus::Error> {
+    let c = try!(Connection::get_private(BusType::System));
+
+    let local_engine = Arc::clone(&engine);
+
+    let (tree, object_path) =
+        get_base_tree(daemon_context(engine,
+                                     audit_log_size,
+                                     error_log_size,
+                                     log_level,
+                                     service_name));
+    let dbus_context = tree.get_data().clone();
+
+    // This should never panic as create_dbus_pool(),
+    // create_dbus_blockdevs(), and create_dbus_filesystem() do not lock the engine.
+    for pool in local_engine.read().expect("no handler has run yet").pools() {
+        create_pool_objects(&dbus_context, &object_path, pool);
+    }
+
+    try!(tree.set_registered(&c, true));
+
+    try!(c.register_name(&dbus_context.service_name, NameFlag::ReplaceExisting as u32));
 
     Ok((c, tree, dbus_context))
 }
@@ -306,7 +3337,1079 @@ pub fn handle(c: &Connection,
                 }
             }
         }
+
+        for signal in dbus_context.signals.borrow_mut().drain() {
+            let _ = c.send(signal);
+        }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, RwLock};
+
+    use dbus;
+    use dbus::MessageItem;
+    use dbus::tree::{Factory, MethodResult};
+
+    use uuid::Uuid;
+
+    use devicemapper::Sectors;
+    use devicemapper::consts::SECTOR_SIZE;
+
+    use engine::{AlertSeverity, BlockDevCheck, BlockDevTier, DEFAULT_MAX_FILESYSTEMS, Engine,
+                 EngineDump, EngineError, ErrorEnum, IEC, PoolState, Redundancy, SimEngine};
+
+    use super::super::blockdev::create_dbus_blockdevs;
+    use super::super::filesystem::create_dbus_filesystem;
+    use super::super::pool::create_dbus_pool;
+    use super::super::types::{DbusContext, DbusErrorEnum, DeferredAction, LogLevel};
+    use super::super::util::{STRATIS_BASE_PATH, STRATIS_BASE_SERVICE, default_object_path};
+    use super::super::util::{MAX_ARRAY_SIZE, bounded_array, echo_correlation_id, engine_guard,
+                             test_reply};
+    use super::{alert_list, all_devices, blockdev_location, build_info, create_pool_objects,
+                daemon_context, defaults, destroy_pool_objects, due_snapshots, destroy_preview,
+                device_allocation, devices_by_tier, fault_tolerance, filesystems_by_type,
+                get_base_tree, get_manager_interfaces, get_manager_methods, inventory,
+                load_state_objects, metrics, object_count, object_method_names, orphaned_paths,
+                pool_names, pool_object_paths, pools_by_tag, remove_preview, pools_full,
+                remove_orphaned_paths, snapshot_uuid, state_detail, unhealthy_pools};
+
+    /// The reply of a handler which finds the engine lock poisoned.
+    fn poisoned_reply(engine: &RwLock<SimEngine>, op_id: Option<String>) -> MethodResult {
+        let _engine = lock_engine!(engine.read(); op_id; MessageItem::Bool(false); test_reply());
+        Ok(vec![])
+    }
+
+    #[test]
+    /// The build info gives the crate's version and the profile it was
+    /// built with
+    fn build_info_fields() {
+        let fields = match build_info() {
+            MessageItem::Struct(fields) => fields,
+            _ => panic!("build info is a struct"),
+        };
+        assert_eq!(fields.len(), 4);
+        assert_eq!(fields[0], MessageItem::Str(env!("CARGO_PKG_VERSION").into()));
+        let profile = if cfg!(debug_assertions) {
+            "debug"
+        } else {
+            "release"
+        };
+        assert_eq!(fields[2], MessageItem::Str(profile.into()));
+        assert_eq!(fields[3].type_sig(), "as");
+    }
+
+    #[test]
+    /// A lock on the engine poisoned by a panic in another handler yields
+    /// an internal error rather than a panic, echoing the correlation ID.
+    fn engine_lock_poisoned() {
+        let engine = Arc::new(RwLock::new(SimEngine::default()));
+        let writer = engine.clone();
+        assert!(::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| {
+            let _engine = writer.write().unwrap();
+            panic!("handler failed while holding the engine");
+        }))
+                        .is_err());
+
+        assert!(match engine_guard(engine.read()) {
+                    Err((rc, _)) => {
+                        rc == dbus::MessageItem::UInt16(DbusErrorEnum::INTERNAL_ERROR.into())
+                    }
+                    Ok(_) => false,
+                });
+
+        let reply = poisoned_reply(&engine, Some("id".into())).unwrap();
+        let items = reply[0].get_items();
+        assert_eq!(items.len(), 4);
+        assert_eq!(items[3], MessageItem::Str("id".into()));
+        assert_eq!(poisoned_reply(&engine, None).unwrap()[0].get_items().len(), 3);
+    }
+
+    #[test]
+    /// Every read method is well-behaved on an engine which has no pools:
+    /// lists are empty and lookups find nothing
+    fn empty_engine_reads() {
+        let engine = Arc::new(RwLock::new(SimEngine::default()));
+        let dbus_context = DbusContext::new(engine.clone(), 0);
+        {
+            let engine = engine.read().unwrap();
+            assert!(pool_names(&*engine, "").is_empty());
+            assert!(filesystems_by_type(&*engine, "xfs").is_empty());
+            assert!(unhealthy_pools(&*engine).is_empty());
+            assert!(metrics(&*engine) ==
+                    vec!["pools=0".to_string(),
+                         "pools_degraded=0".into(),
+                         "metric_errors=0".into()]);
+            assert!(engine.check_consistency().is_empty());
+            assert!(blockdev_location(&*engine, &Uuid::new_v4()).is_none());
+            let (ok, checks) = engine.check_blockdevs(&[], None).unwrap();
+            assert!(!ok && checks.is_empty());
+            assert!(EngineDump::new(&*engine, false).to_json().is_ok());
+        }
+        assert!(pool_object_paths(&dbus_context,
+                                  &*dbus_context.engine.read().unwrap(),
+                                  &["a"]).is_empty());
+        assert!(orphaned_paths(&dbus_context, &*dbus_context.engine.read().unwrap()).is_empty());
+        assert!(remove_orphaned_paths(&dbus_context,
+                                      &*dbus_context.engine.read().unwrap()).is_empty());
+        assert!(dbus_context.audit_log.borrow().last(10).is_empty());
+        assert!(bounded_array(vec![], "s", MAX_ARRAY_SIZE).is_ok());
+    }
+
+    #[test]
+    /// Only pools whose names begin with the prefix are listed, in order
+    fn pool_names_prefix() {
+        let mut engine = SimEngine::default();
+        for name in &["db-1", "app-b", "app-a"] {
+            engine.create_pool(name, &[], &[], None, false).unwrap();
+        }
+        assert!(pool_names(&engine, "app-") == vec!["app-a", "app-b"]);
+        assert!(pool_names(&engine, "").len() == 3);
+        assert!(pool_names(&engine, "web-").is_empty());
+    }
+
+    #[test]
+    /// The base object offers the original Manager interface and its revision
+    fn manager_interfaces_versioned() {
+        let f = Factory::new_fn();
+        let names = get_manager_interfaces(&f)
+            .iter()
+            .map(|i| i.get_name().to_string())
+            .collect::<Vec<String>>();
+        assert!(names ==
+                vec![format!("{}.Manager", STRATIS_BASE_SERVICE),
+                     format!("{}.Manager.r1", STRATIS_BASE_SERVICE)]);
+    }
+
+    #[test]
+    /// The methods of the original Manager interface keep the signatures
+    /// with which they were published
+    fn manager_signatures_pinned() {
+        let f = Factory::new_fn();
+        let published = |name: &'static str,
+                         in_args: &[(&'static str, &'static str)],
+                         out_args: &[(&'static str, &'static str)]| {
+            let method = f.method(name, (), |_| Ok(vec![]))
+                .in_args(in_args.iter().cloned())
+                .out_args(out_args.iter().cloned());
+            format!("{:?}", method)
+        };
+        let return_args = [("return_code", "q"), ("return_string", "s")];
+
+        // CreatePool: s(bq)bas -> (oas)qs
+        let create_pool = published("CreatePool",
+                                    &[("name", "s"),
+                                      ("redundancy", "(bq)"),
+                                      ("force", "b"),
+                                      ("devices", "as")],
+                                    &[("result", "(oas)"), return_args[0], return_args[1]]);
+        // DestroyPool: o -> bqs
+        let destroy_pool = published("DestroyPool",
+                                     &[("pool", "o")],
+                                     &[("action", "b"), return_args[0], return_args[1]]);
+        // ConfigureSimulator: u -> qs
+        let configure_simulator =
+            published("ConfigureSimulator", &[("denominator", "u")], &return_args);
+
+        let (methods, _) = get_manager_methods(&f);
+        let signature = |name: &str| {
+            methods
+                .iter()
+                .find(|m| &**m.get_name() == name)
+                .map(|m| format!("{:?}", m))
+        };
+        assert_eq!(signature("CreatePool"), Some(create_pool));
+        assert_eq!(signature("DestroyPool"), Some(destroy_pool));
+        assert_eq!(signature("ConfigureSimulator"), Some(configure_simulator));
+    }
+
+    #[test]
+    /// The method list of the base object is sorted, and includes the
+    /// original and r1 methods, but not those of the standard interfaces
+    fn manager_method_names_listed() {
+        let dbus_context = DbusContext::new(Arc::new(RwLock::new(SimEngine::default())), 0);
+        let (tree, path) = get_base_tree(dbus_context);
+        let names = object_method_names(tree.get(&path).unwrap());
+
+        let f = Factory::new_fn();
+        let (methods, r1_methods) = get_manager_methods(&f);
+        let mut expected = methods
+            .iter()
+            .chain(r1_methods.iter())
+            .map(|m| m.get_name().to_string())
+            .collect::<Vec<_>>();
+        expected.sort();
+        expected.dedup();
+        assert!(names == expected);
+        assert!(names.iter().all(|n| n != "Introspect"));
+    }
+
+    #[test]
+    /// The base object, which answers GetBaseObjectPath, is served at
+    /// the base path
+    fn base_object_path() {
+        let dbus_context = DbusContext::new(Arc::new(RwLock::new(SimEngine::default())), 0);
+        let (tree, path) = get_base_tree(dbus_context);
+        assert!(path == dbus::Path::new(STRATIS_BASE_PATH).unwrap());
+        assert!(tree.get(&path).is_some());
+    }
+
+    #[test]
+    /// A pool created with the default redundancy gets the default, which
+    /// may only be set to a code which corresponds to a redundancy
+    fn create_pool_default_redundancy() {
+        let dbus_context = DbusContext::new(Arc::new(RwLock::new(SimEngine::default())), 0);
+        let none = u16::from(Redundancy::NONE);
+        assert!(dbus_context.set_default_redundancy(none).is_ok());
+        let invalid = Redundancy::iter_variants().count() as u16;
+        assert!(dbus_context.set_default_redundancy(invalid).is_err());
+        assert_eq!(dbus_context.default_redundancy.get(), none);
+
+        let redundancy = dbus_context.default_redundancy.get();
+        let (uuid, _) = dbus_context
+            .engine
+            .write()
+            .unwrap()
+            .create_pool("name", &[], &[], Some(redundancy), false)
+            .unwrap();
+        assert!(dbus_context
+                    .engine
+                    .read()
+                    .unwrap()
+                    .get_pool(&uuid)
+                    .unwrap()
+                    .redundancy() == Redundancy::NONE);
+    }
+
+    #[test]
+    /// Filesystems are listed across pools by exact, case-insensitive type
+    fn filesystems_by_type_matches() {
+        let mut engine = SimEngine::default();
+        for pool_name in &["p1", "p2"] {
+            let (uuid, _) = engine.create_pool(pool_name, &[], &[], None, false).unwrap();
+            let pool = engine.get_mut_pool(&uuid).unwrap();
+            let fs_uuid = pool.create_filesystems(&[("fs1", None), ("fs2", None)])
+                .unwrap()
+                .iter()
+                .find(|x| x.0 == "fs2")
+                .unwrap()
+                .1;
+            pool.get_mut_filesystem(&fs_uuid)
+                .unwrap()
+                .set_fs_type("ext4")
+                .unwrap();
+        }
+
+        let mut ext4 = filesystems_by_type(&engine, "EXT4");
+        ext4.sort();
+        assert!(ext4 ==
+                vec![("p1".into(), "fs2".into(), "ext4".into()),
+                     ("p2".into(), "fs2".into(), "ext4".into())]);
+        assert!(filesystems_by_type(&engine, "xfs").len() == 2);
+        assert!(filesystems_by_type(&engine, "ext").is_empty());
+    }
+
+    #[test]
+    /// Only filesystems whose schedules are due at the given time are
+    /// listed
+    fn due_snapshots_evaluated() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine.create_pool("pool", &[], &[], None, false).unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        let filesystems = pool.create_filesystems(&[("hourly", None), ("daily", None)])
+            .unwrap();
+        for &(name, fs_uuid) in &filesystems {
+            let schedule = if name == "hourly" { "0 * * * *" } else { "0 3 * * *" };
+            pool.get_mut_filesystem(&fs_uuid)
+                .unwrap()
+                .set_snapshot_schedule(schedule)
+                .unwrap();
+        }
+
+        // Thursday, 2017-06-01 12:00:00 UTC
+        let now = 1_496_318_400;
+        assert!(due_snapshots(&engine, now) == vec![("pool".into(), "hourly".into())]);
+        assert!(due_snapshots(&engine, now + 60).is_empty());
+    }
+
+    #[test]
+    /// Known pools are resolved to their object paths, unknown pools are
+    /// omitted
+    fn pool_object_paths_known() {
+        let engine = Arc::new(RwLock::new(SimEngine::default()));
+        let dbus_context = DbusContext::new(engine.clone(), 0);
+        let mut paths = vec![];
+        for name in &["a", "b"] {
+            let (uuid, _) = engine
+                .write()
+                .unwrap()
+                .create_pool(name, &[], &[], None, false)
+                .unwrap();
+            paths.push(create_dbus_pool(&dbus_context, default_object_path(), uuid));
+        }
+
+        assert!(pool_object_paths(&dbus_context,
+                                  &*dbus_context.engine.read().unwrap(),
+                                  &["b", "x", "a"]) ==
+                vec![("b".into(), paths[1].clone()), ("a".into(), paths[0].clone())]);
+        assert!(pool_object_paths(&dbus_context,
+                                  &*dbus_context.engine.read().unwrap(),
+                                  &["x"]).is_empty());
+    }
+
+    #[test]
+    /// The detail of a pool's state counts its missing devices, and says
+    /// when there are more than its redundancy tolerates
+    fn state_detail_offline() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("name", &[Path::new("/s/a"), Path::new("/s/b")], &[], None, false)
+            .unwrap();
+        assert!(state_detail(engine.get_pool(&uuid).unwrap()) ==
+                (PoolState::Good, "all 2 devices present".into(), 0));
+
+        for path in &["/s/a", "/s/b"] {
+            engine
+                .get_mut_pool(&uuid)
+                .unwrap()
+                .mark_blockdev_failed(Path::new(path))
+                .unwrap();
+        }
+        assert!(state_detail(engine.get_pool(&uuid).unwrap()) ==
+                (PoolState::Offline,
+                 "2 of 2 devices missing, exceeds NONE tolerance of 0".into(),
+                 2));
+    }
+
+    #[test]
+    /// Failing a device raises an alert for the device and another for the
+    /// pool it degrades; each is listed until it is acknowledged
+    fn alerts_listed_until_acknowledged() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("name", &[Path::new("/s/a"), Path::new("/s/b")], &[], None, false)
+            .unwrap();
+        assert!(alert_list(&engine).is_empty());
+
+        engine
+            .get_mut_pool(&uuid)
+            .unwrap()
+            .mark_blockdev_failed(Path::new("/s/a"))
+            .unwrap();
+        let alerts = alert_list(&engine);
+        assert!(alerts
+                    .iter()
+                    .map(|&(_, severity, ref message, _)| (severity, message.as_str()))
+                    .collect::<Vec<_>>() ==
+                vec![(AlertSeverity::Critical.into(), "device /s/a of pool name failed"),
+                     (AlertSeverity::Warning.into(), "pool name is degraded")]);
+
+        engine.acknowledge_alert(alerts[0].0).unwrap();
+        assert!(alert_list(&engine) == alerts[1..].to_vec());
+        assert!(engine.acknowledge_alert(alerts[0].0).is_err());
+    }
+
+    #[test]
+    /// A spare adds to the number of blockdevs a pool may lose, a failed
+    /// blockdev takes from it.
+    fn fault_tolerance_counts_spares_and_failures() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("name", &[Path::new("/s/a"), Path::new("/s/b")], &[], None, false)
+            .unwrap();
+        assert!(fault_tolerance(engine.get_pool(&uuid).unwrap()) == 0);
+
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        pool.add_spares(&[Path::new("/s/c")], false).unwrap();
+        assert!(fault_tolerance(pool) == 1);
+
+        pool.mark_blockdev_failed(Path::new("/s/a")).unwrap();
+        assert!(fault_tolerance(pool) == 0);
+
+        pool.mark_blockdev_failed(Path::new("/s/b")).unwrap();
+        assert!(fault_tolerance(pool) == 0);
+    }
+
+    #[test]
+    /// A read-only data device is noted in the pool's state detail.
+    fn state_detail_read_only() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("name", &[Path::new("/s/a"), Path::new("/s/b")], &[], None, false)
+            .unwrap();
+        engine
+            .get_mut_pool(&uuid)
+            .unwrap()
+            .set_blockdev_read_only(Path::new("/s/a"), true)
+            .unwrap();
+        assert!(state_detail(engine.get_pool(&uuid).unwrap()) ==
+                (PoolState::Good, "all 2 devices present; 1 of 2 devices read-only".into(), 0));
+    }
+
+    #[test]
+    /// A snapshot is resolved by name, but a filesystem which is not a
+    /// snapshot is rejected
+    fn snapshot_resolved() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("name", &[Path::new("/s/a")], &[], None, false)
+            .unwrap();
+        let fs_uuid = engine
+            .get_mut_pool(&uuid)
+            .unwrap()
+            .create_filesystems(&[("origin", None)])
+            .unwrap()[0]
+            .1;
+        let snap_uuid = engine
+            .create_snapshot(&uuid, &fs_uuid, &uuid, "snap")
+            .unwrap();
+
+        assert!(snapshot_uuid(&engine, "name", "snap").unwrap() == snap_uuid);
+        assert!(match snapshot_uuid(&engine, "name", "origin") {
+                    Err(EngineError::Engine(ErrorEnum::Error, _)) => true,
+                    _ => false,
+                });
+        assert!(match snapshot_uuid(&engine, "name", "absent") {
+                    Err(EngineError::Engine(ErrorEnum::NotFound, _)) => true,
+                    _ => false,
+                });
+        assert!(match snapshot_uuid(&engine, "absent", "snap") {
+                    Err(EngineError::Engine(ErrorEnum::NotFound, _)) => true,
+                    _ => false,
+                });
+    }
+
+    #[test]
+    /// The allocation of a device reflects the filesystems created on it
+    fn device_allocation_reflects_filesystems() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("name", &[Path::new("/s/b"), Path::new("/s/a")], &[], None, false)
+            .unwrap();
+        engine
+            .get_mut_pool(&uuid)
+            .unwrap()
+            .create_filesystems(&[("y", None), ("x", None)])
+            .unwrap();
+        let pool = engine.get_pool(&uuid).unwrap();
+        let size = pool.blockdevs()
+            .iter()
+            .find(|&&(_, bd)| bd.devnode() == Path::new("/s/a"))
+            .map(|&(_, bd)| bd.size())
+            .unwrap();
+
+        let (used, free, names) = device_allocation(pool, Path::new("/s/a")).unwrap();
+        assert!(used > Sectors(0));
+        assert!(used + free == size);
+        assert!(names == vec!["x".to_owned(), "y".to_owned()]);
+
+        let (used, _, names) = device_allocation(pool, Path::new("/s/b")).unwrap();
+        assert!(used == Sectors(0));
+        assert!(names.is_empty());
+
+        assert!(match device_allocation(pool, Path::new("/s/z")) {
+                    Err(EngineError::Engine(ErrorEnum::NotFound, _)) => true,
+                    _ => false,
+                });
+    }
+
+    #[test]
+    /// The preview of a device's removal names the filesystems with data
+    /// on it, and tells whether the pool could do without it
+    fn remove_preview_affected() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("name", &[Path::new("/s/b"), Path::new("/s/a")], &[], None, false)
+            .unwrap();
+        engine
+            .get_mut_pool(&uuid)
+            .unwrap()
+            .create_filesystems(&[("y", None), ("x", None)])
+            .unwrap();
+        let pool = engine.get_pool(&uuid).unwrap();
+        assert!(remove_preview(pool, Path::new("/s/a")).unwrap() ==
+                (vec!["x".into(), "y".into()], true));
+        assert!(remove_preview(pool, Path::new("/s/b")).unwrap() == (vec![], true));
+        assert!(remove_preview(pool, Path::new("/s/z")).is_err());
+
+        let (uuid, _) = engine
+            .create_pool("solo", &[Path::new("/s/c")], &[], None, false)
+            .unwrap();
+        engine
+            .get_mut_pool(&uuid)
+            .unwrap()
+            .create_filesystems(&[("z", None)])
+            .unwrap();
+        let pool = engine.get_pool(&uuid).unwrap();
+        assert!(remove_preview(pool, Path::new("/s/c")).unwrap() ==
+                (vec!["z".into()], false));
+    }
+
+    #[test]
+    /// The preview of destroying a pool counts its filesystems and the
+    /// space they use, and lists all its devices, without changing it
+    fn destroy_preview_matches_pool() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("name", &[Path::new("/s/b"), Path::new("/s/a")], &[], None, false)
+            .unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        pool.add_spares(&[Path::new("/s/c")], false).unwrap();
+        pool.create_filesystems(&[("fs1", None), ("fs2", None)])
+            .unwrap();
+        let used = *pool.total_physical_used().unwrap() * SECTOR_SIZE as u64;
+
+        assert!(destroy_preview(pool).unwrap() ==
+                (2,
+                 used,
+                 vec![PathBuf::from("/s/a"), PathBuf::from("/s/b"), PathBuf::from("/s/c")]));
+        assert!(pool.filesystems().len() == 2);
+        assert!(pool.blockdevs().len() == 3);
+    }
+
+    #[test]
+    /// Each pool is listed once, in order of name, with the UUID and state
+    /// by which it is known to the engine
+    fn pools_full_listed() {
+        let mut engine = SimEngine::default();
+        assert!(pools_full(&engine).is_empty());
+        let (b_uuid, _) = engine
+            .create_pool("b", &[Path::new("/s/b")], &[], None, false)
+            .unwrap();
+        engine
+            .create_pool("a", &[Path::new("/s/a")], &[], None, false)
+            .unwrap();
+        engine
+            .get_mut_pool(&b_uuid)
+            .unwrap()
+            .mark_blockdev_failed(Path::new("/s/b"))
+            .unwrap();
+
+        let pools = pools_full(&engine);
+        assert!(pools.iter().map(|p| p.0.as_str()).collect::<Vec<_>>() == vec!["a", "b"]);
+        for &(ref name, uuid, state) in &pools {
+            let pool = engine.get_pool(&uuid).unwrap();
+            assert!(pool.name() == name);
+            assert!(state_detail(pool).0 == state);
+        }
+        assert!(pools[1].2 == PoolState::Offline);
+    }
+
+    #[test]
+    /// Only the pools carrying a tag are listed for it, in order of name
+    fn pools_listed_by_tag() {
+        let mut engine = SimEngine::default();
+        for name in &["c", "b", "a"] {
+            let (uuid, _) = engine.create_pool(name, &[], &[], None, false).unwrap();
+            let pool = engine.get_mut_pool(&uuid).unwrap();
+            pool.add_tag("all").unwrap();
+            if *name != "b" {
+                pool.add_tag("some").unwrap();
+            }
+        }
+        assert!(pools_by_tag(&engine, "all") == vec!["a", "b", "c"]);
+        assert!(pools_by_tag(&engine, "some") == vec!["a", "c"]);
+        assert!(pools_by_tag(&engine, "none").is_empty());
+
+        let uuid = *engine.pools().into_iter().find(|p| p.name() == "a").unwrap().uuid();
+        engine.get_mut_pool(&uuid).unwrap().remove_tag("some").unwrap();
+        assert!(pools_by_tag(&engine, "some") == vec!["c"]);
+    }
+
+    #[test]
+    /// Every device of every pool is listed once, with its pool and tier
+    fn all_devices_listed() {
+        let mut engine = SimEngine::default();
+        assert!(all_devices(&engine).is_empty());
+
+        let (uuid, _) = engine
+            .create_pool("a", &[Path::new("/s/a1")], &[Path::new("/s/a2")], None, false)
+            .unwrap();
+        engine
+            .get_mut_pool(&uuid)
+            .unwrap()
+            .add_spares(&[Path::new("/s/a3")], false)
+            .unwrap();
+        engine
+            .create_pool("b", &[Path::new("/s/b1"), Path::new("/s/b2")], &[], None, false)
+            .unwrap();
+
+        let devices = all_devices(&engine)
+            .into_iter()
+            .map(|(pool_name, devnode, _, tier, _)| (pool_name, devnode, tier))
+            .collect::<Vec<_>>();
+        assert!(devices ==
+                vec![("a".into(), PathBuf::from("/s/a1"), BlockDevTier::Data),
+                     ("a".into(), PathBuf::from("/s/a2"), BlockDevTier::Cache),
+                     ("a".into(), PathBuf::from("/s/a3"), BlockDevTier::Spare),
+                     ("b".into(), PathBuf::from("/s/b1"), BlockDevTier::Data),
+                     ("b".into(), PathBuf::from("/s/b2"), BlockDevTier::Data)]);
+    }
+
+    #[test]
+    /// Only pools which are not Good are listed as unhealthy
+    fn unhealthy_pools_degraded() {
+        let mut engine = SimEngine::default();
+        engine
+            .create_pool("healthy", &[Path::new("/s/a")], &[], None, false)
+            .unwrap();
+        assert!(unhealthy_pools(&engine).is_empty());
+
+        let (uuid, _) = engine
+            .create_pool("degraded",
+                         &[Path::new("/s/b"), Path::new("/s/c")],
+                         &[],
+                         None,
+                         false)
+            .unwrap();
+        engine
+            .get_mut_pool(&uuid)
+            .unwrap()
+            .mark_blockdev_failed(Path::new("/s/b"))
+            .unwrap();
+        assert!(unhealthy_pools(&engine) == vec![("degraded".into(), PoolState::Degraded)]);
+    }
+
+    #[test]
+    /// Loading a dump into an empty daemon reproduces the inventory of the
+    /// daemon dumped; loading it again conflicts unless it replaces what
+    /// was loaded before
+    fn load_state_reproduces_inventory() {
+        let kinds_and_names = |dbus_context: &DbusContext| {
+            inventory(dbus_context, &*dbus_context.engine.read().unwrap())
+                .into_iter()
+                .map(|(_, kind, name)| (kind, name))
+                .collect::<Vec<_>>()
+        };
+
+        let source = DbusContext::new(Arc::new(RwLock::new(SimEngine::default())), 0);
+        {
+            let mut engine = source.engine.write().unwrap();
+            let (uuid, _) = engine
+                .create_pool("pool", &[Path::new("/s/a")], &[Path::new("/s/c")], None, false)
+                .unwrap();
+            let pool = engine.get_mut_pool(&uuid).unwrap();
+            pool.create_filesystems(&[("fs", None)]).unwrap();
+            pool.add_tag("tag").unwrap();
+            engine
+                .create_pool("other", &[Path::new("/s/b")], &[], None, false)
+                .unwrap();
+            for pool in engine.pools() {
+                create_pool_objects(&source, &default_object_path(), pool);
+            }
+        }
+        let json = EngineDump::new(&*source.engine.read().unwrap(), false)
+            .to_json()
+            .unwrap();
+
+        let target = DbusContext::new(Arc::new(RwLock::new(SimEngine::default())), 0);
+        let dump = EngineDump::from_json(&json).unwrap();
+        assert!(load_state_objects(&target,
+                                   &mut *target.engine.write().unwrap(),
+                                   &default_object_path(), &dump, false)
+                    .unwrap()
+                    .len() == 2);
+        assert!(kinds_and_names(&target) == kinds_and_names(&source));
+        assert!(EngineDump::new(&*target.engine.read().unwrap(), false) == dump);
+
+        assert!(load_state_objects(&target,
+                                   &mut *target.engine.write().unwrap(),
+                                   &default_object_path(), &dump, false).is_err());
+        assert!(load_state_objects(&target,
+                                   &mut *target.engine.write().unwrap(),
+                                   &default_object_path(), &dump, true).is_ok());
+        assert!(kinds_and_names(&target) == kinds_and_names(&source));
+        assert!(object_count(&target, &*target.engine.read().unwrap()) ==
+                object_count(&source, &*source.engine.read().unwrap()));
+    }
+
+    #[test]
+    /// A pool's object path is derived from its UUID, so a pool loaded
+    /// from a dump has the object path it had when it was dumped
+    fn pool_object_path_stable() {
+        let source = DbusContext::new(Arc::new(RwLock::new(SimEngine::default())), 0);
+        let path = {
+            let mut engine = source.engine.write().unwrap();
+            let (uuid, _) = engine
+                .create_pool("pool", &[Path::new("/s/a")], &[], None, false)
+                .unwrap();
+            create_pool_objects(&source, &default_object_path(), engine.get_pool(&uuid).unwrap())
+        };
+        let json = EngineDump::new(&*source.engine.read().unwrap(), false)
+            .to_json()
+            .unwrap();
+
+        let target = DbusContext::new(Arc::new(RwLock::new(SimEngine::default())), 0);
+        target.get_next_id();
+        let dump = EngineDump::from_json(&json).unwrap();
+        assert!(load_state_objects(&target,
+                                   &mut *target.engine.write().unwrap(),
+                                   &default_object_path(), &dump, false).unwrap() ==
+                vec![path]);
+    }
+
+    #[test]
+    /// Destroying a pool's filesystems and then the pool removes every
+    /// object, so the object count returns to what it was before the pool
+    /// was created
+    fn object_count_restored() {
+        let dbus_context = DbusContext::new(Arc::new(RwLock::new(SimEngine::default())), 0);
+        assert!(object_count(&dbus_context,
+                             &*dbus_context.engine.read().unwrap()) == (0, 0, 0, 0));
+
+        let (uuid, _) = dbus_context
+            .engine
+            .write()
+            .unwrap()
+            .create_pool("name", &[Path::new("/s/a")], &[Path::new("/s/c")], None, false)
+            .unwrap();
+        let pool_path = create_dbus_pool(&dbus_context, default_object_path(), uuid);
+        let fs_uuids = dbus_context
+            .engine
+            .write()
+            .unwrap()
+            .get_mut_pool(&uuid)
+            .unwrap()
+            .create_filesystems(&[("a", None), ("b", None)])
+            .unwrap()
+            .into_iter()
+            .map(|(_, fs_uuid)| fs_uuid)
+            .collect::<Vec<_>>();
+        for fs_uuid in &fs_uuids {
+            create_dbus_filesystem(&dbus_context, pool_path.clone(), *fs_uuid);
+        }
+        create_dbus_blockdevs(&dbus_context,
+                              &pool_path,
+                              dbus_context.engine.read().unwrap().get_pool(&uuid).unwrap());
+        assert!(object_count(&dbus_context,
+                             &*dbus_context.engine.read().unwrap()) == (1, 2, 1, 1));
+
+        dbus_context
+            .engine
+            .write()
+            .unwrap()
+            .get_mut_pool(&uuid)
+            .unwrap()
+            .destroy_filesystems(&fs_uuids.iter().collect::<Vec<_>>())
+            .unwrap();
+        for fs_uuid in &fs_uuids {
+            let path = dbus_context.object_path_for(fs_uuid).unwrap();
+            dbus_context.push_remove(path);
+        }
+        assert!(object_count(&dbus_context,
+                             &*dbus_context.engine.read().unwrap()) == (1, 0, 1, 1));
+
+        assert!(destroy_pool_objects(&dbus_context,
+                                     &mut *dbus_context.engine.write().unwrap(),
+                                     pool_path, uuid, false).unwrap());
+        assert!(object_count(&dbus_context,
+                             &*dbus_context.engine.read().unwrap()) == (0, 0, 0, 0));
+        assert!(dbus_context.object_paths.borrow().is_empty());
+    }
+
+    #[test]
+    /// Destroying a pool which still has filesystems by force removes the
+    /// objects of its filesystems and blockdevs along with its own, leaving
+    /// no object paths behind
+    fn force_destroy_removes_objects() {
+        let dbus_context = DbusContext::new(Arc::new(RwLock::new(SimEngine::default())), 0);
+        let (uuid, _) = dbus_context
+            .engine
+            .write()
+            .unwrap()
+            .create_pool("name", &[Path::new("/s/a")], &[Path::new("/s/c")], None, false)
+            .unwrap();
+        let pool_path = create_pool_objects(&dbus_context,
+                                            &default_object_path(),
+                                            dbus_context
+                                                .engine
+                                                .read()
+                                                .unwrap()
+                                                .get_pool(&uuid)
+                                                .unwrap());
+        let fs_uuids = dbus_context
+            .engine
+            .write()
+            .unwrap()
+            .get_mut_pool(&uuid)
+            .unwrap()
+            .create_filesystems(&[("a", None), ("b", None)])
+            .unwrap()
+            .into_iter()
+            .map(|(_, fs_uuid)| fs_uuid)
+            .collect::<Vec<_>>();
+        for fs_uuid in &fs_uuids {
+            create_dbus_filesystem(&dbus_context, pool_path.clone(), *fs_uuid);
+        }
+        assert!(object_count(&dbus_context,
+                             &*dbus_context.engine.read().unwrap()) == (1, 2, 1, 1));
+
+        assert!(destroy_pool_objects(&dbus_context,
+                                     &mut *dbus_context.engine.write().unwrap(),
+                                     pool_path.clone(), uuid, false).is_err());
+        assert!(object_count(&dbus_context,
+                             &*dbus_context.engine.read().unwrap()) == (1, 2, 1, 1));
+
+        assert!(destroy_pool_objects(&dbus_context,
+                                     &mut *dbus_context.engine.write().unwrap(),
+                                     pool_path, uuid, true).unwrap());
+        assert!(object_count(&dbus_context,
+                             &*dbus_context.engine.read().unwrap()) == (0, 0, 0, 0));
+        assert!(dbus_context.object_paths.borrow().is_empty());
+    }
+
+    #[test]
+    /// Destroying a pool by force on which a snapshot in another pool
+    /// depends fails without destroying any of its filesystems
+    fn force_destroy_dependent_keeps_filesystems() {
+        let dbus_context = DbusContext::new(Arc::new(RwLock::new(SimEngine::default())), 0);
+        let (uuid, snapshot_pool_uuid) = {
+            let mut engine = dbus_context.engine.write().unwrap();
+            let (uuid, _) = engine.create_pool("origin", &[], &[], None, false).unwrap();
+            let (snapshot_pool_uuid, _) =
+                engine.create_pool("snapshots", &[], &[], None, false).unwrap();
+            (uuid, snapshot_pool_uuid)
+        };
+        let pool_path = create_pool_objects(&dbus_context,
+                                            &default_object_path(),
+                                            dbus_context
+                                                .engine
+                                                .read()
+                                                .unwrap()
+                                                .get_pool(&uuid)
+                                                .unwrap());
+        let fs_uuid = dbus_context
+            .engine
+            .write()
+            .unwrap()
+            .get_mut_pool(&uuid)
+            .unwrap()
+            .create_filesystems(&[("fs", None)])
+            .unwrap()[0]
+            .1;
+        dbus_context
+            .engine
+            .write()
+            .unwrap()
+            .create_snapshot(&uuid, &fs_uuid, &snapshot_pool_uuid, "snap")
+            .unwrap();
+
+        assert!(match destroy_pool_objects(&dbus_context,
+                                           &mut *dbus_context.engine.write().unwrap(),
+                                           pool_path,
+                                           uuid,
+                                           true) {
+                    Err(EngineError::Engine(ErrorEnum::Busy, msg)) => msg.contains("snap"),
+                    _ => false,
+                });
+        assert!(dbus_context
+                    .engine
+                    .read()
+                    .unwrap()
+                    .get_pool(&uuid)
+                    .unwrap()
+                    .filesystems()
+                    .len() == 1);
+    }
+
+    #[test]
+    /// The daemon is registered under the service name it is given, or
+    /// under the base service if it is given none
+    fn daemon_service_name() {
+        let engine = Arc::new(RwLock::new(SimEngine::default()));
+        let dbus_context = daemon_context(engine.clone(), 0, 0, LogLevel::new(None), None);
+        assert_eq!(*dbus_context.service_name, STRATIS_BASE_SERVICE);
+
+        let dbus_context =
+            daemon_context(engine, 0, 0, LogLevel::new(None), Some("org.storage.stratis1.test"));
+        assert_eq!(*dbus_context.service_name, "org.storage.stratis1.test");
+        let (tree, _) = get_base_tree(dbus_context);
+        assert_eq!(*tree.get_data().service_name, "org.storage.stratis1.test");
+    }
+
+    #[test]
+    /// The defaults are the configured default redundancy and the engine's
+    /// constants, in bytes where they are sizes
+    fn defaults_configured() {
+        let dbus_context = DbusContext::new(Arc::new(RwLock::new(SimEngine::default())), 0);
+        assert!(defaults(&dbus_context) ==
+                (Redundancy::NONE.into(), IEC::Mi, IEC::Gi, DEFAULT_MAX_FILESYSTEMS));
+
+        dbus_context.default_redundancy.set(7);
+        assert!(defaults(&dbus_context).0 == 7);
+    }
+
+    #[test]
+    /// An object path whose object the engine does not know is an orphan
+    fn orphaned_paths_pruned() {
+        let dbus_context = DbusContext::new(Arc::new(RwLock::new(SimEngine::default())), 0);
+        let (uuid, _) = dbus_context
+            .engine
+            .write()
+            .unwrap()
+            .create_pool("name", &[], &[], None, false)
+            .unwrap();
+        create_dbus_pool(&dbus_context,
+                         dbus::Path::new(STRATIS_BASE_PATH).unwrap(),
+                         uuid);
+        assert!(orphaned_paths(&dbus_context, &*dbus_context.engine.read().unwrap()).is_empty());
+
+        let orphan = dbus::Path::new(format!("{}/{}", STRATIS_BASE_PATH, 99)).unwrap();
+        dbus_context
+            .object_paths
+            .borrow_mut()
+            .insert(orphan.clone(), Uuid::new_v4());
+        assert!(orphaned_paths(&dbus_context,
+                               &*dbus_context.engine.read().unwrap()) == vec![orphan.clone()]);
+
+        assert!(remove_orphaned_paths(&dbus_context, &*dbus_context.engine.read().unwrap()) ==
+                vec![orphan.clone()]);
+        assert!(orphaned_paths(&dbus_context, &*dbus_context.engine.read().unwrap()).is_empty());
+        assert!(dbus_context.object_paths.borrow().len() == 1);
+        assert!(match dbus_context.actions.borrow_mut().drain().last() {
+                    Some(DeferredAction::Remove(path)) => path == orphan,
+                    _ => false,
+                });
+    }
+
+    #[test]
+    /// While signals are paused, the creation of several pools is
+    /// announced by a single summary signal when they are resumed.
+    fn paused_signals_summarized() {
+        let dbus_context = DbusContext::new(Arc::new(RwLock::new(SimEngine::default())), 0);
+        let create_pools = |names: &[&str]| {
+            for name in names {
+                let (uuid, _) = dbus_context
+                    .engine
+                    .write()
+                    .unwrap()
+                    .create_pool(name, &[], &[], None, false)
+                    .unwrap();
+                create_dbus_pool(&dbus_context,
+                                 dbus::Path::new(STRATIS_BASE_PATH).unwrap(),
+                                 uuid);
+            }
+        };
+
+        create_pools(&["a", "b", "c"]);
+        let signals = dbus_context
+            .signals
+            .borrow_mut()
+            .drain()
+            .collect::<Vec<_>>();
+        assert!(signals.len() == 3);
+        assert!(signals
+                    .iter()
+                    .all(|s| s.member().unwrap() == "ObjectAdded".into()));
+
+        dbus_context.signals.borrow_mut().pause();
+        create_pools(&["d", "e", "f"]);
+        assert!(dbus_context.signals.borrow_mut().drain().next().is_none());
+
+        dbus_context.signals.borrow_mut().resume();
+        let signals = dbus_context
+            .signals
+            .borrow_mut()
+            .drain()
+            .collect::<Vec<_>>();
+        assert!(signals.len() == 1);
+        assert!(signals[0].member().unwrap() == "BulkChange".into());
+        assert!(match signals[0].get_items()[0] {
+                    dbus::MessageItem::Array(ref added, _) => added.len() == 3,
+                    _ => false,
+                });
+    }
+
+    #[test]
+    /// Metrics count the pools and describe each of them
+    fn metrics_after_create_pool() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine.create_pool("name", &[], &[], None, false).unwrap();
+        engine
+            .get_mut_pool(&uuid)
+            .unwrap()
+            .create_filesystems(&[("fs", None)])
+            .unwrap();
+
+        let metrics = metrics(&engine);
+        let prefix = format!("pool.{}.", uuid.simple());
+        assert!(metrics.contains(&"pools=1".into()));
+        assert!(metrics.contains(&"pools_degraded=0".into()));
+        assert!(metrics.contains(&format!("{}filesystems=1", prefix)));
+        assert!(metrics.contains(&format!("{}state=0", prefix)));
+        assert!(metrics.contains(&"metric_errors=0".into()));
+
+        let pool = engine.get_pool(&uuid).unwrap();
+        let size = pool.total_physical_size().saturating_mul(SECTOR_SIZE as u64);
+        let used = pool.total_physical_used().unwrap().saturating_mul(SECTOR_SIZE as u64);
+        assert!(metrics.contains(&format!("{}total_physical_size_bytes={}", prefix, size)));
+        assert!(metrics.contains(&format!("{}total_physical_used_bytes={}", prefix, used)));
+    }
+
+    #[test]
+    /// Each device goes to the collection for its tier
+    fn devices_by_tier_split() {
+        let data = u16::from(BlockDevTier::Data);
+        let cache = u16::from(BlockDevTier::Cache);
+        let spare = u16::from(BlockDevTier::Spare);
+        let (blockdevs, cachedevs, sparedevs) =
+            devices_by_tier(&[("/s/a", data), ("/s/c", cache), ("/s/s", spare), ("/s/b", data)])
+                .unwrap();
+        assert!(blockdevs == vec![Path::new("/s/a"), Path::new("/s/b")]);
+        assert!(cachedevs == vec![Path::new("/s/c")]);
+        assert!(sparedevs == vec![Path::new("/s/s")]);
+        assert!(devices_by_tier(&[("/s/a", data), ("/s/j", 7)]).is_err());
+
+        let mut engine = SimEngine::default();
+        let (_, mut devnodes) = engine
+            .create_pool("name", &blockdevs, &cachedevs, None, false)
+            .unwrap();
+        devnodes.sort();
+        assert!(devnodes == vec![PathBuf::from("/s/a"), PathBuf::from("/s/b")]);
+        let (_, checks) = engine
+            .check_blockdevs(&[Path::new("/s/c")], None)
+            .unwrap();
+        assert!(checks[0].1 == BlockDevCheck::InUse);
+    }
+
+    #[test]
+    /// A state sync announces every existing object, even while other
+    /// signals are paused
+    fn state_sync_describes_pools() {
+        let engine = Arc::new(RwLock::new(SimEngine::default()));
+        let dbus_context = DbusContext::new(engine.clone(), 0);
+        let (uuid, _) = engine
+            .write()
+            .unwrap()
+            .create_pool("pool", &[Path::new("/s/a")], &[], None, false)
+            .unwrap();
+        let pool_path = create_dbus_pool(&dbus_context, default_object_path(), uuid);
+        create_dbus_blockdevs(&dbus_context,
+                              &pool_path,
+                              engine.read().unwrap().get_pool(&uuid).unwrap());
+        dbus_context.signals.borrow_mut().drain();
+
+        dbus_context.signals.borrow_mut().pause();
+        let objects = inventory(&dbus_context, &*dbus_context.engine.read().unwrap());
+        assert!(objects.len() == 2);
+        assert!(objects[0] == (pool_path, "pool".into(), "pool".into()));
+        assert!(objects[1].1 == "blockdev" && objects[1].2 == "/s/a");
+
+        dbus_context.signals.borrow_mut().state_sync(objects);
+        let signals = dbus_context
+            .signals
+            .borrow_mut()
+            .drain()
+            .collect::<Vec<_>>();
+        assert!(signals.len() == 1);
+        assert!(signals[0].member().unwrap() == "StateSync".into());
+        assert!(match signals[0].get_items()[0] {
+                    dbus::MessageItem::Array(ref objects, _) => objects.len() == 2,
+                    _ => false,
+                });
+    }
+}
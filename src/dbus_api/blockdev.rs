@@ -0,0 +1,356 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::path::Path;
+
+use dbus;
+use dbus::Message;
+use dbus::MessageItem;
+use dbus::arg::IterAppend;
+use dbus::tree::Access;
+use dbus::tree::EmitsChangedSignal;
+use dbus::tree::Factory;
+use dbus::tree::MTFn;
+use dbus::tree::MethodErr;
+use dbus::tree::MethodInfo;
+use dbus::tree::MethodResult;
+use dbus::tree::PropInfo;
+
+use uuid::Uuid;
+
+use engine::{BlockDevTier, Dev, DevUuid, EngineResult, Pool};
+
+use super::types::{DbusContext, DbusErrorEnum, OPContext, TData};
+
+use super::util::STRATIS_BASE_PATH;
+use super::util::STRATIS_BASE_SERVICE;
+use super::util::code_to_message_items;
+use super::util::echo_correlation_id;
+use super::util::engine_guard;
+use super::util::engine_to_dbus_err;
+use super::util::get_correlation_id;
+use super::util::get_parent;
+use super::util::get_uuid;
+use super::util::log_operation;
+use super::util::ok_message_items;
+
+/// Create the D-Bus object for the blockdev with this UUID.
+/// If the blockdev already has an object, the path of the existing object
+/// is returned.
+pub fn create_dbus_blockdev<'a>(dbus_context: &DbusContext,
+                                parent: dbus::Path<'static>,
+                                uuid: Uuid)
+                                -> dbus::Path<'a> {
+    if let Some(object_path) = dbus_context.object_path_for(&uuid) {
+        return object_path;
+    }
+
+    let f = Factory::new_fn();
+
+    let remove_method = f.method("Remove", (), remove_blockdev)
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let devnode_property = f.property::<&str, _>("Devnode", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::Const)
+        .on_get(get_blockdev_devnode);
+
+    let state_property = f.property::<u16, _>("State", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_blockdev_state);
+
+    let read_only_property = f.property::<bool, _>("ReadOnly", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_blockdev_read_only);
+
+    let tier_property = f.property::<u16, _>("Tier", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_blockdev_tier);
+
+    let capacity_property = f.property::<&str, _>("Capacity", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_blockdev_capacity);
+
+    let used_property = f.property::<&str, _>("Used", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_blockdev_used);
+
+    let pool_property = f.property::<&dbus::Path, _>("Pool", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::Const)
+        .on_get(get_parent);
+
+    let uuid_property = f.property::<&str, _>("Uuid", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::Const)
+        .on_get(get_uuid);
+
+    let object_name = format!("{}/{}",
+                              STRATIS_BASE_PATH,
+                              dbus_context.get_next_id().to_string());
+
+    let interface_name = format!("{}.{}", STRATIS_BASE_SERVICE, "blockdev");
+
+    let object_path = f.object_path(object_name, Some(OPContext::new(parent, uuid)))
+        .introspectable()
+        .add(f.interface(interface_name, ())
+                 .add_m(remove_method)
+                 .add_p(capacity_property)
+                 .add_p(devnode_property)
+                 .add_p(pool_property)
+                 .add_p(read_only_property)
+                 .add_p(state_property)
+                 .add_p(tier_property)
+                 .add_p(used_property)
+                 .add_p(uuid_property));
+
+    let path = object_path.get_name().to_owned();
+    dbus_context.push_add(object_path);
+    path
+}
+
+/// Create D-Bus objects for those blockdevs of pool, the object of which
+/// is parent, that do not already have one.
+pub fn create_dbus_blockdevs(dbus_context: &DbusContext,
+                             parent: &dbus::Path<'static>,
+                             pool: &Pool) {
+    for (_, blockdev) in pool.blockdevs() {
+        create_dbus_blockdev(dbus_context, parent.clone(), *blockdev.uuid());
+    }
+}
+
+/// The UUID of the blockdev of pool with this device node, if any.
+pub fn blockdev_uuid(pool: &Pool, devnode: &Path) -> Option<DevUuid> {
+    pool.blockdevs()
+        .into_iter()
+        .find(|&(_, bd)| bd.devnode() == devnode)
+        .map(|(_, bd)| *bd.uuid())
+}
+
+/// Queue the removal of the object of the blockdev with this UUID, if
+/// pool no longer has the blockdev.
+pub fn remove_departed_blockdev(dbus_context: &DbusContext, pool: &Pool, uuid: Option<DevUuid>) {
+    if let Some(uuid) = uuid {
+        if pool.blockdevs().iter().all(|&(_, bd)| *bd.uuid() != uuid) {
+            if let Some(object_path) = dbus_context.object_path_for(&uuid) {
+                dbus_context.push_remove(object_path);
+            }
+        }
+    }
+}
+
+/// Remove the blockdev with this UUID from pool, and queue the removal of
+/// its object, object_path.
+/// Returns true if the blockdev was removed.
+fn remove_blockdev_object(dbus_context: &DbusContext,
+                          pool: &mut Pool,
+                          object_path: &dbus::Path<'static>,
+                          uuid: Uuid)
+                          -> EngineResult<bool> {
+    let removed = try!(pool.remove_blockdev(&uuid));
+    if removed {
+        dbus_context.push_remove(object_path.clone());
+    }
+    Ok(removed)
+}
+
+fn remove_blockdev(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, _) = try!(get_correlation_id(message, 0));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let blockdev_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let blockdev_data = get_data!(blockdev_path; correlation_id; default_return; return_message);
+
+    let pool_path = get_parent!(m; blockdev_data; correlation_id; default_return; return_message);
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
+
+    let result = remove_blockdev_object(dbus_context, pool, object_path, blockdev_data.uuid);
+    let operation = format!("Remove {}", blockdev_data.uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(action) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(action), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+/// Get a blockdev property and place it on the D-Bus. The property is
+/// found by means of the getter method which takes the tier of the
+/// blockdev and a reference to it, and obtains the property from them.
+fn get_blockdev_property<F>(i: &mut IterAppend,
+                            p: &PropInfo<MTFn<TData>, TData>,
+                            getter: F)
+                            -> Result<(), MethodErr>
+    where F: Fn(BlockDevTier, &Dev) -> Result<MessageItem, MethodErr>
+{
+    let dbus_context = p.tree.get_data();
+    let object_path = p.path.get_name();
+
+    let blockdev_path = p.tree
+        .get(object_path)
+        .expect("tree must contain implicit argument");
+
+    let blockdev_data = try!(blockdev_path
+                                 .get_data()
+                                 .as_ref()
+                                 .ok_or_else(|| {
+                                                 MethodErr::failed(&format!("no data for object \
+                                                                             path {}",
+                                                                            object_path))
+                                             }));
+
+    let pool_path = try!(p.tree
+                             .get(&blockdev_data.parent)
+                             .ok_or_else(|| {
+                                             MethodErr::failed(&format!("no path for parent \
+                                                                         object path {}",
+                                                                        &blockdev_data.parent))
+                                         }));
+
+    let pool_uuid = try!(pool_path
+                             .get_data()
+                             .as_ref()
+                             .ok_or_else(|| {
+                                             MethodErr::failed(&format!("no data for object \
+                                                                         path {}",
+                                                                        object_path))
+                                         }))
+            .uuid;
+
+    let engine = try!(dbus_context
+                          .engine
+                          .read()
+                          .map_err(|_| MethodErr::failed(&"engine lock poisoned")));
+    let pool = try!(engine
+                        .get_pool(&pool_uuid)
+                        .ok_or_else(|| {
+                                        MethodErr::failed(&format!("no pool corresponding to \
+                                                                    uuid {}",
+                                                                   &pool_uuid))
+                                    }));
+    let blockdev_uuid = &blockdev_data.uuid;
+    let (tier, blockdev) = try!(pool.blockdevs()
+                                    .into_iter()
+                                    .find(|&(_, bd)| bd.uuid() == blockdev_uuid)
+                                    .ok_or_else(|| {
+                                                    MethodErr::failed(&format!("no blockdev with \
+                                                                                uuid {}",
+                                                                               blockdev_uuid))
+                                                }));
+    i.append(try!(getter(tier, blockdev)));
+    Ok(())
+}
+
+fn get_blockdev_devnode(i: &mut IterAppend,
+                        p: &PropInfo<MTFn<TData>, TData>)
+                        -> Result<(), MethodErr> {
+    get_blockdev_property(i, p, |_, bd| {
+        Ok(MessageItem::Str(format!("{}", bd.devnode().display())))
+    })
+}
+
+fn get_blockdev_state(i: &mut IterAppend,
+                      p: &PropInfo<MTFn<TData>, TData>)
+                      -> Result<(), MethodErr> {
+    get_blockdev_property(i, p, |_, bd| Ok(MessageItem::UInt16(bd.state().into())))
+}
+
+fn get_blockdev_read_only(i: &mut IterAppend,
+                          p: &PropInfo<MTFn<TData>, TData>)
+                          -> Result<(), MethodErr> {
+    get_blockdev_property(i, p, |_, bd| Ok(MessageItem::Bool(bd.read_only())))
+}
+
+fn get_blockdev_tier(i: &mut IterAppend,
+                     p: &PropInfo<MTFn<TData>, TData>)
+                     -> Result<(), MethodErr> {
+    get_blockdev_property(i, p, |tier, _| Ok(MessageItem::UInt16(tier.into())))
+}
+
+fn get_blockdev_capacity(i: &mut IterAppend,
+                         p: &PropInfo<MTFn<TData>, TData>)
+                         -> Result<(), MethodErr> {
+    get_blockdev_property(i, p, |_, bd| Ok(MessageItem::Str(format!("{}", *bd.size()))))
+}
+
+fn get_blockdev_used(i: &mut IterAppend,
+                     p: &PropInfo<MTFn<TData>, TData>)
+                     -> Result<(), MethodErr> {
+    get_blockdev_property(i, p, |_, bd| Ok(MessageItem::Str(format!("{}", *bd.used()))))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::sync::{Arc, RwLock};
+
+    use engine::{Engine, SimEngine};
+
+    use super::super::types::{DbusContext, DeferredAction};
+    use super::super::util::default_object_path;
+    use super::{create_dbus_blockdevs, remove_blockdev_object};
+
+    #[test]
+    /// Each blockdev gets an object, by way of which it can be removed,
+    /// unless it is the last data device
+    fn remove_by_object() {
+        let engine = Arc::new(RwLock::new(SimEngine::default()));
+        let (pool_uuid, _) = engine
+            .write()
+            .unwrap()
+            .create_pool("pool", &[Path::new("/s/a"), Path::new("/s/b")], &[], None, false)
+            .unwrap();
+        let dbus_context = DbusContext::new(engine.clone(), 0);
+        create_dbus_blockdevs(&dbus_context,
+                              &default_object_path(),
+                              engine.read().unwrap().get_pool(&pool_uuid).unwrap());
+        assert!(dbus_context.object_paths.borrow().len() == 2);
+
+        let mut engine = engine.write().unwrap();
+        let pool = engine.get_mut_pool(&pool_uuid).unwrap();
+        let uuids = pool.blockdevs()
+            .iter()
+            .map(|&(_, bd)| *bd.uuid())
+            .collect::<Vec<_>>();
+        let path = dbus_context.object_path_for(&uuids[0]).unwrap();
+        assert!(remove_blockdev_object(&dbus_context, pool, &path, uuids[0]).unwrap());
+        assert!(pool.blockdevs().len() == 1);
+        assert!(match dbus_context.actions.borrow_mut().drain().last() {
+                    Some(DeferredAction::Remove(removed)) => removed == path,
+                    _ => false,
+                });
+
+        let path = dbus_context.object_path_for(&uuids[1]).unwrap();
+        assert!(remove_blockdev_object(&dbus_context, pool, &path, uuids[1]).is_err());
+        assert!(pool.blockdevs().len() == 1);
+    }
+}
@@ -2,6 +2,8 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::path::Path;
+
 use dbus;
 use dbus::Message;
 use dbus::MessageItem;
@@ -17,7 +19,7 @@ use dbus::tree::PropInfo;
 
 use uuid::Uuid;
 
-use engine::RenameAction;
+use engine::{EngineError, EngineResult, ErrorEnum, Pool, RenameAction};
 
 use super::super::engine::Filesystem;
 
@@ -26,8 +28,13 @@ use super::types::{DbusContext, DbusErrorEnum, OPContext, TData};
 use super::util::STRATIS_BASE_PATH;
 use super::util::STRATIS_BASE_SERVICE;
 use super::util::code_to_message_items;
+use super::util::default_object_path;
+use super::util::echo_correlation_id;
+use super::util::engine_guard;
 use super::util::engine_to_dbus_err;
+use super::util::get_correlation_id;
 use super::util::get_next_arg;
+use super::util::log_operation;
 use super::util::get_parent;
 use super::util::get_uuid;
 use super::util::ok_message_items;
@@ -45,6 +52,42 @@ pub fn create_dbus_filesystem<'a>(dbus_context: &DbusContext,
         .out_arg(("return_code", "q"))
         .out_arg(("return_string", "s"));
 
+    let destroy_method = f.method("Destroy", (), destroy_filesystem)
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let create_snapshot_method = f.method("CreateSnapshot", (), create_snapshot)
+        .in_arg(("name", "s"))
+        .out_arg(("result", "o"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let set_snapshot_schedule_method =
+        f.method("SetSnapshotSchedule", (), set_snapshot_schedule)
+            .in_arg(("schedule", "s"))
+            .out_arg(("action", "b"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let set_io_limits_method = f.method("SetIoLimits", (), set_io_limits)
+        .in_arg(("read_iops", "t"))
+        .in_arg(("write_iops", "t"))
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let set_mountpoint_method = f.method("SetMountpoint", (), set_mountpoint)
+        .in_arg(("mountpoint", "s"))
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_properties_method = f.method("GetProperties", (), get_properties)
+        .out_arg(("properties", "(ssssss)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
     let devnode_property = f.property::<&str, _>("Devnode", ())
         .access(Access::Read)
         .emits_changed(EmitsChangedSignal::Const)
@@ -55,6 +98,36 @@ pub fn create_dbus_filesystem<'a>(dbus_context: &DbusContext,
         .emits_changed(EmitsChangedSignal::False)
         .on_get(get_filesystem_name);
 
+    let type_property = f.property::<&str, _>("Type", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_filesystem_type);
+
+    let mountpoint_property = f.property::<&str, _>("Mountpoint", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_filesystem_mountpoint);
+
+    let size_property = f.property::<&str, _>("Size", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_filesystem_size);
+
+    let snapshot_schedule_property = f.property::<&str, _>("SnapshotSchedule", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_filesystem_snapshot_schedule);
+
+    let read_iops_limit_property = f.property::<u64, _>("ReadIopsLimit", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_filesystem_read_iops_limit);
+
+    let write_iops_limit_property = f.property::<u64, _>("WriteIopsLimit", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_filesystem_write_iops_limit);
+
     let pool_property = f.property::<&dbus::Path, _>("Pool", ())
         .access(Access::Read)
         .emits_changed(EmitsChangedSignal::Const)
@@ -75,19 +148,31 @@ pub fn create_dbus_filesystem<'a>(dbus_context: &DbusContext,
         .introspectable()
         .add(f.interface(interface_name, ())
                  .add_m(rename_method)
+                 .add_m(destroy_method)
+                 .add_m(create_snapshot_method)
+                 .add_m(set_snapshot_schedule_method)
+                 .add_m(set_io_limits_method)
+                 .add_m(set_mountpoint_method)
+                 .add_m(get_properties_method)
                  .add_p(devnode_property)
+                 .add_p(mountpoint_property)
                  .add_p(name_property)
                  .add_p(pool_property)
-                 .add_p(uuid_property));
+                 .add_p(read_iops_limit_property)
+                 .add_p(size_property)
+                 .add_p(snapshot_schedule_property)
+                 .add_p(type_property)
+                 .add_p(uuid_property)
+                 .add_p(write_iops_limit_property));
 
     let path = object_path.get_name().to_owned();
-    dbus_context.actions.borrow_mut().push_add(object_path);
+    dbus_context.push_add(object_path);
     path
 }
 
 fn rename_filesystem(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
     let message: &Message = m.msg;
-    let mut iter = message.iter_init();
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
 
     let new_name: &str = try!(get_next_arg(&mut iter, 0));
 
@@ -99,15 +184,24 @@ fn rename_filesystem(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
     let filesystem_path = m.tree
         .get(object_path)
         .expect("implicit argument must be in tree");
-    let filesystem_data = get_data!(filesystem_path; default_return; return_message);
+    let filesystem_data =
+        get_data!(filesystem_path; correlation_id; default_return; return_message);
 
-    let pool_path = get_parent!(m; filesystem_data; default_return; return_message);
-    let pool_uuid = &get_data!(pool_path; default_return; return_message).uuid;
+    let pool_path =
+        get_parent!(m; filesystem_data; correlation_id; default_return; return_message);
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
 
-    let mut engine = dbus_context.engine.borrow_mut();
-    let pool = get_mut_pool!(engine; pool_uuid; default_return; return_message);
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
 
-    let msg = match pool.rename_filesystem(&filesystem_data.uuid, new_name) {
+    let result = pool.rename_filesystem(&filesystem_data.uuid, new_name);
+    let operation = format!("SetName {}", filesystem_data.uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
         Ok(RenameAction::NoSource) => {
             let error_message = format!("pool {} doesn't know about filesystem {}",
                                         pool_uuid,
@@ -130,9 +224,324 @@ fn rename_filesystem(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
         }
     };
 
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+fn set_snapshot_schedule(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
+
+    let schedule: &str = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let filesystem_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let filesystem_data =
+        get_data!(filesystem_path; correlation_id; default_return; return_message);
+
+    let pool_path =
+        get_parent!(m; filesystem_data; correlation_id; default_return; return_message);
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
+
+    let result = match pool.get_mut_filesystem(&filesystem_data.uuid) {
+        Some(filesystem) => filesystem.set_snapshot_schedule(schedule),
+        None => {
+            Err(EngineError::Engine(ErrorEnum::NotFound, filesystem_data.uuid.to_string()))
+        }
+    };
+    let operation = format!("SetSnapshotSchedule {}", filesystem_data.uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(action) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(action), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+fn set_io_limits(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 2));
+
+    let read_iops: u64 = try!(get_next_arg(&mut iter, 0));
+    let write_iops: u64 = try!(get_next_arg(&mut iter, 1));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let filesystem_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let filesystem_data =
+        get_data!(filesystem_path; correlation_id; default_return; return_message);
+
+    let pool_path =
+        get_parent!(m; filesystem_data; correlation_id; default_return; return_message);
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
+
+    let result = match pool.get_mut_filesystem(&filesystem_data.uuid) {
+        Some(filesystem) => filesystem.set_io_limits(read_iops, write_iops),
+        None => {
+            Err(EngineError::Engine(ErrorEnum::NotFound, filesystem_data.uuid.to_string()))
+        }
+    };
+    let operation = format!("SetIoLimits {}", filesystem_data.uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(action) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(action), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+fn set_mountpoint(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
+
+    let mountpoint: &str = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let filesystem_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let filesystem_data =
+        get_data!(filesystem_path; correlation_id; default_return; return_message);
+
+    let pool_path =
+        get_parent!(m; filesystem_data; correlation_id; default_return; return_message);
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
+
+    let result = match pool.get_mut_filesystem(&filesystem_data.uuid) {
+        Some(filesystem) => filesystem.set_mountpoint(Path::new(mountpoint)),
+        None => {
+            Err(EngineError::Engine(ErrorEnum::NotFound, filesystem_data.uuid.to_string()))
+        }
+    };
+    let operation = format!("SetMountpoint {}", filesystem_data.uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(action) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(action), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+/// The name, UUID, device node, type, size in sectors, and mount point
+/// of filesystem. The device node and mount point are empty if there is
+/// none.
+fn filesystem_properties(filesystem: &Filesystem) -> Vec<String> {
+    let devnode = filesystem
+        .devnode()
+        .map(|d| format!("{}", d.display()))
+        .unwrap_or_default();
+    let mountpoint = filesystem
+        .mountpoint()
+        .map(|p| format!("{}", p.display()))
+        .unwrap_or_default();
+    vec![filesystem.name().to_owned(),
+         format!("{}", filesystem.uuid().simple()),
+         devnode,
+         filesystem.fs_type().to_owned(),
+         format!("{}", *filesystem.size()),
+         mountpoint]
+}
+
+fn get_properties(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Struct(vec![MessageItem::Str("".into()); 6]);
+
+    let filesystem_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let filesystem_data = get_data!(filesystem_path; default_return; return_message);
+
+    let pool_path = get_parent!(m; filesystem_data; default_return; return_message);
+    let pool_uuid = &get_data!(pool_path; default_return; return_message).uuid;
+
+    let engine = read_engine!(dbus_context; default_return; return_message);
+    let pool = get_pool!(engine; pool_uuid; default_return; return_message);
+
+    let msg = match pool.get_filesystem(&filesystem_data.uuid) {
+        Some(filesystem) => {
+            let properties = filesystem_properties(filesystem)
+                .into_iter()
+                .map(MessageItem::Str)
+                .collect();
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Struct(properties), rc, rs)
+        }
+        None => {
+            let message = format!("pool {} has no filesystem {}",
+                                  pool_uuid,
+                                  filesystem_data.uuid);
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::NOTFOUND, message);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
     Ok(vec![msg])
 }
 
+/// Destroy the filesystem with this UUID, which belongs to pool, and queue
+/// the removal of its object, object_path.
+/// Returns true if the filesystem was destroyed.
+fn destroy_filesystem_object(dbus_context: &DbusContext,
+                             pool: &mut Pool,
+                             object_path: &dbus::Path<'static>,
+                             uuid: Uuid)
+                             -> EngineResult<bool> {
+    let destroyed = try!(pool.destroy_filesystems(&[&uuid]));
+    if destroyed.contains(&&uuid) {
+        dbus_context.push_remove(object_path.clone());
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+fn destroy_filesystem(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, _) = try!(get_correlation_id(message, 0));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let filesystem_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let filesystem_data =
+        get_data!(filesystem_path; correlation_id; default_return; return_message);
+
+    let pool_path =
+        get_parent!(m; filesystem_data; correlation_id; default_return; return_message);
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
+
+    let result = destroy_filesystem_object(dbus_context, pool, object_path, filesystem_data.uuid);
+    let operation = format!("Destroy {}", filesystem_data.uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(action) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(action), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+fn create_snapshot(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
+
+    let snapshot_name: &str = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::ObjectPath(default_object_path());
+
+    let filesystem_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let filesystem_data =
+        get_data!(filesystem_path; correlation_id; default_return; return_message);
+
+    let pool_path =
+        get_parent!(m; filesystem_data; correlation_id; default_return; return_message);
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let result = write_engine!(dbus_context; correlation_id; default_return; return_message)
+        .create_snapshot(pool_uuid, &filesystem_data.uuid, pool_uuid, snapshot_name);
+    let operation = format!("CreateSnapshot {}", filesystem_data.uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(uuid) => {
+            let snapshot_path: dbus::Path =
+                create_dbus_filesystem(dbus_context, pool_path.get_name().clone(), uuid);
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::ObjectPath(snapshot_path), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
 /// Get a filesystem property and place it on the D-Bus. The property is
 /// found by means of the getter method which takes a reference to a
 /// Filesystem and obtains the property from the filesystem.
@@ -173,7 +582,10 @@ fn get_filesystem_property<F>(i: &mut IterAppend,
                                     }))
             .uuid;
 
-    let engine = dbus_context.engine.borrow();
+    let engine = try!(dbus_context
+                          .engine
+                          .read()
+                          .map_err(|_| MethodErr::failed(&"engine lock poisoned")));
     let pool = try!(engine
                  .get_pool(&pool_uuid)
                  .ok_or_else(|| {
@@ -211,3 +623,114 @@ fn get_filesystem_name(i: &mut IterAppend,
                        -> Result<(), MethodErr> {
     get_filesystem_property(i, p, |f| Ok(MessageItem::Str(f.name().to_owned())))
 }
+
+fn get_filesystem_type(i: &mut IterAppend,
+                       p: &PropInfo<MTFn<TData>, TData>)
+                       -> Result<(), MethodErr> {
+    get_filesystem_property(i, p, |f| Ok(MessageItem::Str(f.fs_type().to_owned())))
+}
+
+fn get_filesystem_snapshot_schedule(i: &mut IterAppend,
+                                    p: &PropInfo<MTFn<TData>, TData>)
+                                    -> Result<(), MethodErr> {
+    get_filesystem_property(i, p, |f| {
+        Ok(MessageItem::Str(f.snapshot_schedule().unwrap_or("").to_owned()))
+    })
+}
+
+fn get_filesystem_read_iops_limit(i: &mut IterAppend,
+                                  p: &PropInfo<MTFn<TData>, TData>)
+                                  -> Result<(), MethodErr> {
+    get_filesystem_property(i, p, |f| Ok(MessageItem::UInt64(f.io_limits().0)))
+}
+
+fn get_filesystem_write_iops_limit(i: &mut IterAppend,
+                                   p: &PropInfo<MTFn<TData>, TData>)
+                                   -> Result<(), MethodErr> {
+    get_filesystem_property(i, p, |f| Ok(MessageItem::UInt64(f.io_limits().1)))
+}
+
+fn get_filesystem_mountpoint(i: &mut IterAppend,
+                             p: &PropInfo<MTFn<TData>, TData>)
+                             -> Result<(), MethodErr> {
+    get_filesystem_property(i, p, |f| {
+        Ok(MessageItem::Str(f.mountpoint()
+                                .map(|p| format!("{}", p.display()))
+                                .unwrap_or_default()))
+    })
+}
+
+fn get_filesystem_size(i: &mut IterAppend,
+                       p: &PropInfo<MTFn<TData>, TData>)
+                       -> Result<(), MethodErr> {
+    get_filesystem_property(i, p, |f| Ok(MessageItem::Str(format!("{}", *f.size()))))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::sync::{Arc, RwLock};
+
+    use engine::{Engine, EngineError, ErrorEnum, SimEngine};
+
+    use super::super::types::{DbusContext, DeferredAction};
+    use super::super::util::default_object_path;
+    use super::{create_dbus_filesystem, destroy_filesystem_object, filesystem_properties};
+
+    #[test]
+    /// Destroying a filesystem by way of its object removes the filesystem
+    /// and its object
+    fn destroy_by_object() {
+        let engine = Arc::new(RwLock::new(SimEngine::default()));
+        let (pool_uuid, _) = engine
+            .write()
+            .unwrap()
+            .create_pool("pool", &[], &[], None, false)
+            .unwrap();
+        let fs_uuid = engine
+            .write()
+            .unwrap()
+            .get_mut_pool(&pool_uuid)
+            .unwrap()
+            .create_filesystems(&[("fs", None)])
+            .unwrap()[0]
+            .1;
+        let dbus_context = DbusContext::new(engine.clone(), 0);
+        let path = create_dbus_filesystem(&dbus_context, default_object_path(), fs_uuid);
+
+        let mut engine = engine.write().unwrap();
+        let pool = engine.get_mut_pool(&pool_uuid).unwrap();
+        assert!(destroy_filesystem_object(&dbus_context, pool, &path, fs_uuid).unwrap());
+        assert!(pool.filesystems().is_empty());
+        assert!(dbus_context.object_paths.borrow().is_empty());
+        assert!(match dbus_context.actions.borrow_mut().drain().last() {
+                    Some(DeferredAction::Remove(removed)) => removed == path,
+                    _ => false,
+                });
+    }
+
+    #[test]
+    /// A mount point set on a filesystem is among its properties; a
+    /// relative one is refused
+    fn set_mountpoint_properties() {
+        let mut engine = SimEngine::default();
+        let (pool_uuid, _) = engine
+            .create_pool("pool", &[Path::new("/s/a")], &[], None, false)
+            .unwrap();
+        let pool = engine.get_mut_pool(&pool_uuid).unwrap();
+        let fs_uuid = pool.create_filesystems(&[("fs", None)]).unwrap()[0].1;
+        let filesystem = pool.get_mut_filesystem(&fs_uuid).unwrap();
+
+        assert!(filesystem.set_mountpoint(Path::new("/srv/fs")).unwrap());
+        assert!(!filesystem.set_mountpoint(Path::new("/srv/fs")).unwrap());
+        assert!(match filesystem.set_mountpoint(Path::new("srv")) {
+                    Err(EngineError::Engine(ErrorEnum::Invalid, _)) => true,
+                    _ => false,
+                });
+
+        let properties = filesystem_properties(filesystem);
+        assert_eq!(properties[0], "fs");
+        assert_eq!(properties[1], format!("{}", fs_uuid.simple()));
+        assert_eq!(properties[5], "/srv/fs");
+    }
+}
@@ -4,45 +4,152 @@
 
 
 /// Macro for early return with Ok dbus message on failure to get data
-/// associated with object path.
+/// associated with object path. Given the correlation ID of a mutating
+/// method, op_id, the message echoes it.
 macro_rules! get_data {
     ( $path:ident; $default:expr; $message:expr ) => {
+        get_data!($path; None; $default; $message)
+    };
+    ( $path:ident; $op_id:expr; $default:expr; $message:expr ) => {
         if let Some(ref data) = *$path.get_data() {
             data
         } else {
             let message = format!("no data for object path {}", $path.get_name());
             let (rc, rs) = code_to_message_items(DbusErrorEnum::INTERNAL_ERROR, message);
-            return Ok(vec![$message.append3($default, rc, rs)]);
+            let reply = $message.append3($default, rc, rs);
+            return Ok(vec![echo_correlation_id(reply, $op_id.clone())]);
         }
-    }
+    };
 }
 
 
 /// Macro for early return with Ok dbus message on failure to get parent
-/// object path from tree.
+/// object path from tree; see get_data! for op_id.
 macro_rules! get_parent {
     ( $m:ident; $data:ident; $default:expr; $message:expr ) => {
+        get_parent!($m; $data; None; $default; $message)
+    };
+    ( $m:ident; $data:ident; $op_id:expr; $default:expr; $message:expr ) => {
         if let Some(parent) = $m.tree.get(&$data.parent) {
             parent
         } else {
             let message = format!("no path for object path {}", $data.parent);
             let (rc, rs) = code_to_message_items(DbusErrorEnum::INTERNAL_ERROR, message);
-            return Ok(vec![$message.append3($default, rc, rs)]);
+            let reply = $message.append3($default, rc, rs);
+            return Ok(vec![echo_correlation_id(reply, $op_id.clone())]);
         }
-    }
+    };
+}
+
+
+/// Macro for early return with Ok dbus message on failure to get pool; see
+/// get_data! for op_id.
+macro_rules! get_pool {
+    ( $engine:ident; $uuid:ident; $default:expr; $message:expr ) => {
+        get_pool!($engine; $uuid; None; $default; $message)
+    };
+    ( $engine:ident; $uuid:ident; $op_id:expr; $default:expr; $message:expr ) => {
+        if let Some(pool) = $engine.get_pool($uuid) {
+            pool
+        } else {
+            let message = format!("engine does not know about pool with uuid {}",
+                                  $uuid);
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::INTERNAL_ERROR, message);
+            let reply = $message.append3($default, rc, rs);
+            return Ok(vec![echo_correlation_id(reply, $op_id.clone())]);
+        }
+    };
 }
 
 
-/// Macro for early return with Ok dbus message on failure to get mutable pool.
+/// Macro for early return with Ok dbus message on failure to get mutable pool;
+/// see get_data! for op_id.
 macro_rules! get_mut_pool {
     ( $engine:ident; $uuid:ident; $default:expr; $message:expr ) => {
+        get_mut_pool!($engine; $uuid; None; $default; $message)
+    };
+    ( $engine:ident; $uuid:ident; $op_id:expr; $default:expr; $message:expr ) => {
         if let Some(pool) = $engine.get_mut_pool($uuid) {
             pool
         } else {
             let message = format!("engine does not know about pool with uuid {}",
                                   $uuid);
             let (rc, rs) = code_to_message_items(DbusErrorEnum::INTERNAL_ERROR, message);
-            return Ok(vec![$message.append3($default, rc, rs)]);
+            let reply = $message.append3($default, rc, rs);
+            return Ok(vec![echo_correlation_id(reply, $op_id.clone())]);
+        }
+    };
+}
+
+
+/// Macro for early return with Ok dbus message if a mutating operation is
+/// already in progress, or if the client has cancelled this operation by
+/// means of its correlation ID. Evaluates to a guard which keeps the engine
+/// marked busy until it is dropped. The defaults are the values of the
+/// reply, if any, which precede the return code and string.
+macro_rules! begin_operation {
+    ( $m:ident; $context:ident; $op_id:ident; $($default:expr),*; $message:expr ) => {
+        {
+            let operation = $m.method.get_name().to_string();
+            let target = $m.path.get_name().to_string();
+            let op_id = $op_id.as_ref().map(|id| id.as_str());
+            match $context.begin_requested_operation(op_id, &operation, &target) {
+                Ok(guard) => guard,
+                Err((code, message)) => {
+                    let (rc, rs) = code_to_message_items(code, message);
+                    let mut reply = $message;
+                    reply.append_items(&[$(MessageItem::from($default),)* rc, rs]);
+                    return Ok(vec![echo_correlation_id(reply, $op_id.clone())]);
+                }
+            }
+        }
+    }
+}
+
+
+/// Macro for early return with Ok dbus message on failure to take lock, a
+/// lock on the engine, which happens only if a handler panicked while it
+/// held the write lock. The defaults are the values of the reply, if any,
+/// which precede the return code and string; the reply echoes op_id.
+macro_rules! lock_engine {
+    ( $lock:expr; $op_id:expr; $($default:expr),*; $message:expr ) => {
+        match engine_guard($lock) {
+            Ok(engine) => engine,
+            Err((rc, rs)) => {
+                let mut reply = $message;
+                reply.append_items(&[$(MessageItem::from($default),)* rc, rs]);
+                return Ok(vec![echo_correlation_id(reply, $op_id.clone())]);
+            }
         }
     }
 }
+
+
+/// Macro for early return with Ok dbus message on failure to lock the
+/// engine for reading; see lock_engine!, and get_data! for op_id.
+macro_rules! read_engine {
+    ( $context:ident; $message:expr ) => {
+        lock_engine!($context.engine.read(); None; ; $message)
+    };
+    ( $context:ident; $($default:expr),+; $message:expr ) => {
+        lock_engine!($context.engine.read(); None; $($default),+; $message)
+    };
+    ( $context:ident; $op_id:ident; $($default:expr),*; $message:expr ) => {
+        lock_engine!($context.engine.read(); $op_id; $($default),*; $message)
+    };
+}
+
+
+/// Macro for early return with Ok dbus message on failure to lock the
+/// engine for writing; see lock_engine!, and get_data! for op_id.
+macro_rules! write_engine {
+    ( $context:ident; $message:expr ) => {
+        lock_engine!($context.engine.write(); None; ; $message)
+    };
+    ( $context:ident; $($default:expr),+; $message:expr ) => {
+        lock_engine!($context.engine.write(); None; $($default),+; $message)
+    };
+    ( $context:ident; $op_id:ident; $($default:expr),*; $message:expr ) => {
+        lock_engine!($context.engine.write(); $op_id; $($default),*; $message)
+    };
+}
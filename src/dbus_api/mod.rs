@@ -6,9 +6,11 @@
 mod macros;
 
 mod api;
+mod blockdev;
 mod filesystem;
 mod pool;
 mod types;
 mod util;
 
 pub use self::api::{connect, handle};
+pub use self::types::{DEFAULT_AUDIT_LOG_SIZE, DEFAULT_ERROR_LOG_SIZE, LogLevel};
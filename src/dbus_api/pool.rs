@@ -3,7 +3,7 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::vec::Vec;
 
 use dbus;
@@ -23,24 +23,53 @@ use dbus::tree::PropInfo;
 use uuid::Uuid;
 
 use devicemapper::Sectors;
+use devicemapper::consts::SECTOR_SIZE;
 
-use engine::{Pool, RenameAction};
+use engine::{AllocationPolicy, BlockDevTier, CacheMode, Compression, EngineError, EngineResult,
+             ErrorEnum, Pool, Redundancy, RenameAction, format_bytes};
 
+use super::blockdev::{blockdev_uuid, create_dbus_blockdevs, remove_departed_blockdev};
 use super::filesystem::create_dbus_filesystem;
 use super::types::{DbusContext, DbusErrorEnum, OPContext, TData};
 
 use super::util::STRATIS_BASE_PATH;
 use super::util::STRATIS_BASE_SERVICE;
 use super::util::code_to_message_items;
+use super::util::default_object_path;
+use super::util::echo_correlation_id;
+use super::util::engine_guard;
 use super::util::engine_to_dbus_err;
+use super::util::get_correlation_id;
 use super::util::get_next_arg;
+use super::util::log_operation;
 use super::util::get_uuid;
 use super::util::ok_message_items;
+use super::util::uuid_to_path_element;
 
 
+/// Create filesystems with the given names in pool, and an object for each
+/// under pool_path. Returns the object path and name of each filesystem.
+fn create_filesystem_objects<'a>(dbus_context: &DbusContext,
+                                 pool: &mut Pool,
+                                 pool_path: &dbus::Path<'static>,
+                                 names: &[&'a str])
+                                 -> EngineResult<Vec<(dbus::Path<'static>, &'a str)>> {
+    let specs = names
+        .iter()
+        .map(|&x| (x, None))
+        .collect::<Vec<(&str, Option<Sectors>)>>();
+    let infos = try!(pool.create_filesystems(&specs));
+    Ok(infos
+           .into_iter()
+           .map(|(name, uuid)| {
+                    (create_dbus_filesystem(dbus_context, pool_path.clone(), uuid), name)
+                })
+           .collect())
+}
+
 fn create_filesystems(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
     let message: &Message = m.msg;
-    let mut iter = message.iter_init();
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
 
     let filesystems: Array<&str, _> = try!(get_next_arg(&mut iter, 0));
     let dbus_context = m.tree.get_data();
@@ -53,25 +82,21 @@ fn create_filesystems(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
     let pool_path = m.tree
         .get(object_path)
         .expect("implicit argument must be in tree");
-    let pool_uuid = &get_data!(pool_path; default_return; return_message).uuid;
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
 
-    let mut engine = dbus_context.engine.borrow_mut();
-    let pool = get_mut_pool!(engine; pool_uuid; default_return; return_message);
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
 
-    let result =
-        pool.create_filesystems(&filesystems
-                                     .map(|x| (x, None))
-                                     .collect::<Vec<(&str, Option<Sectors>)>>());
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
 
-    let msg = match result {
-        Ok(ref infos) => {
-            let mut return_value = Vec::new();
-            for &(name, uuid) in infos {
-                let fs_object_path: dbus::Path =
-                    create_dbus_filesystem(dbus_context, object_path.clone(), uuid);
-                return_value.push((fs_object_path, name));
-            }
+    let names = filesystems.collect::<Vec<&str>>();
+    let result = create_filesystem_objects(dbus_context, pool, object_path, &names);
+    let operation = format!("CreateFilesystems {}", pool_uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
 
+    let msg = match result {
+        Ok(ref return_value) => {
             let return_value = return_value
                 .iter()
                 .map(|x| {
@@ -89,13 +114,68 @@ fn create_filesystems(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
             return_message.append3(default_return, rc, rs)
         }
     };
-    Ok(vec![msg])
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+
+}
+
+fn clone_filesystem(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 2));
+
+    let filesystem: dbus::Path<'static> = try!(get_next_arg(&mut iter, 0));
+    let new_name: &str = try!(get_next_arg(&mut iter, 1));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::ObjectPath(default_object_path());
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let filesystem_uuid = match m.tree.get(&filesystem) {
+        Some(filesystem_path) => {
+            get_data!(filesystem_path; correlation_id; default_return; return_message).uuid
+        }
+        None => {
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::NOTFOUND,
+                                                 format!("no filesystem {}", filesystem));
+            let msg = return_message.append3(default_return, rc, rs);
+            return Ok(vec![echo_correlation_id(msg, correlation_id)]);
+        }
+    };
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
+
+    let result = pool.clone_filesystem(&filesystem_uuid, new_name);
+    let operation = format!("CloneFilesystem {}", filesystem_uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
 
+    let msg = match result {
+        Ok(uuid) => {
+            let fs_object_path: dbus::Path =
+                create_dbus_filesystem(dbus_context, object_path.clone(), uuid);
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::ObjectPath(fs_object_path), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
 }
 
 fn destroy_filesystems(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
     let message: &Message = m.msg;
-    let mut iter = message.iter_init();
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
 
     let filesystems: Array<dbus::Path<'static>, _> = try!(get_next_arg(&mut iter, 0));
 
@@ -108,20 +188,26 @@ fn destroy_filesystems(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
     let pool_path = m.tree
         .get(object_path)
         .expect("implicit argument must be in tree");
-    let pool_uuid = &get_data!(pool_path; default_return; return_message).uuid;
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
 
-    let mut engine = dbus_context.engine.borrow_mut();
-    let pool = get_mut_pool!(engine; pool_uuid; default_return; return_message);
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
 
     let mut filesystem_map: HashMap<Uuid, dbus::Path<'static>> = HashMap::new();
     for op in filesystems {
         if let Some(filesystem_path) = m.tree.get(&op) {
-            let filesystem_uuid = get_data!(filesystem_path; default_return; return_message).uuid;
+            let filesystem_uuid =
+                get_data!(filesystem_path; correlation_id; default_return; return_message).uuid;
             filesystem_map.insert(filesystem_uuid, op);
         }
     }
 
     let result = pool.destroy_filesystems(&filesystem_map.keys().collect::<Vec<&Uuid>>());
+    let operation = format!("DestroyFilesystems {}", pool_uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
     let msg = match result {
         Ok(ref uuids) => {
             for uuid in uuids {
@@ -129,7 +215,7 @@ fn destroy_filesystems(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
                     .get(uuid)
                     .expect("'uuids' is a subset of filesystem_map.keys()")
                     .clone();
-                dbus_context.actions.borrow_mut().push_remove(op);
+                dbus_context.push_remove(op);
             }
 
             let return_value = uuids
@@ -145,12 +231,102 @@ fn destroy_filesystems(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
             return_message.append3(default_return, rc, rs)
         }
     };
-    Ok(vec![msg])
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
 }
 
 fn add_devs(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    add_devs_common(m, "AddDevs", |pool, devs, force| pool.add_blockdevs(devs, force))
+}
+
+fn add_spares(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    add_devs_common(m, "AddSpares", |pool, devs, force| pool.add_spares(devs, force))
+}
+
+/// Add the devices at paths to pool as blockdevs one at a time, so that
+/// the failure of one does not prevent the others from being added.
+/// Returns, for each path in order, the result of adding it. A device
+/// which already belongs to the pool, or which is given more than once,
+/// is not added again.
+fn add_blockdevs_each(pool: &mut Pool,
+                      paths: &[&Path],
+                      force: bool)
+                      -> Vec<(PathBuf, EngineResult<()>)> {
+    let mut results: Vec<(PathBuf, EngineResult<()>)> = Vec::new();
+    for path in paths {
+        let duplicate = results.iter().any(|&(ref p, _)| p == path) ||
+                        pool.spares().iter().any(|p| p == path) ||
+                        pool.blockdevs()
+                            .iter()
+                            .any(|&(_, bd)| bd.devnode() == *path);
+        let result = if duplicate {
+            let message = format!("device {} already belongs to pool {}",
+                                  path.display(),
+                                  pool.name());
+            Err(EngineError::Engine(ErrorEnum::AlreadyExists, message))
+        } else {
+            pool.add_blockdevs(&[path], force).map(|_| ())
+        };
+        results.push((path.to_path_buf(), result));
+    }
+    results
+}
+
+fn add_devs_each(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
     let message: &Message = m.msg;
-    let mut iter = message.iter_init();
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 2));
+
+    let force: bool = try!(get_next_arg(&mut iter, 0));
+    let devs: Array<&str, _> = try!(get_next_arg(&mut iter, 1));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let return_sig = "(sqs)";
+    let default_return = MessageItem::Array(vec![], return_sig.into());
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
+
+    let blockdevs = devs.map(|x| Path::new(x)).collect::<Vec<&Path>>();
+
+    let results = add_blockdevs_each(pool, &blockdevs, force);
+    let result: EngineResult<()> = Ok(());
+    let operation = format!("AddDevsEach {}", pool_uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    create_dbus_blockdevs(dbus_context, object_path, pool);
+    let results = results
+        .into_iter()
+        .map(|(path, result)| {
+            let (rc, rs) = match result {
+                Ok(()) => (DbusErrorEnum::OK, String::new()),
+                Err(err) => engine_to_dbus_err(&err),
+            };
+            MessageItem::Struct(vec![MessageItem::Str(format!("{}", path.display())),
+                                     MessageItem::UInt16(rc.into()),
+                                     MessageItem::Str(rs)])
+        })
+        .collect();
+    let (rc, rs) = ok_message_items();
+    let msg = return_message.append3(MessageItem::Array(results, return_sig.into()), rc, rs);
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+/// Add devices to a pool on behalf of the method named method, by means
+/// of add, which adds the devices to the pool in some role.
+fn add_devs_common<F>(m: &MethodInfo<MTFn<TData>, TData>, method: &str, add: F) -> MethodResult
+    where F: Fn(&mut Pool, &[&Path], bool) -> EngineResult<Vec<PathBuf>>
+{
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 2));
 
     let force: bool = try!(get_next_arg(&mut iter, 0));
     let devs: Array<&str, _> = try!(get_next_arg(&mut iter, 1));
@@ -164,15 +340,23 @@ fn add_devs(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
     let pool_path = m.tree
         .get(object_path)
         .expect("implicit argument must be in tree");
-    let pool_uuid = &get_data!(pool_path; default_return; return_message).uuid;
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
 
-    let mut engine = dbus_context.engine.borrow_mut();
-    let pool = get_mut_pool!(engine; pool_uuid; default_return; return_message);
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
 
     let blockdevs = devs.map(|x| Path::new(x)).collect::<Vec<&Path>>();
 
-    let msg = match pool.add_blockdevs(&blockdevs, force) {
+    let result = add(pool, &blockdevs, force);
+    let operation = format!("{} {}", method, pool_uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
         Ok(devnodes) => {
+            create_dbus_blockdevs(dbus_context, object_path, pool);
             let paths = devnodes
                 .iter()
                 .map(|d| {
@@ -190,14 +374,26 @@ fn add_devs(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
             return_message.append3(default_return, rc, rs)
         }
     };
-    Ok(vec![msg])
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
 }
 
-fn rename_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+/// Mark the device dev of pool failed, and remove its object if a spare
+/// took its place.
+fn mark_device_failed_object(dbus_context: &DbusContext,
+                             pool: &mut Pool,
+                             dev: &Path)
+                             -> EngineResult<bool> {
+    let failed_uuid = blockdev_uuid(pool, dev);
+    let action = try!(pool.mark_blockdev_failed(dev));
+    remove_departed_blockdev(dbus_context, pool, failed_uuid);
+    Ok(action)
+}
+
+fn mark_device_failed(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
     let message: &Message = m.msg;
-    let mut iter = message.iter_init();
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
 
-    let new_name: &str = try!(get_next_arg(&mut iter, 0));
+    let dev: &str = try!(get_next_arg(&mut iter, 0));
 
     let dbus_context = m.tree.get_data();
     let object_path = m.path.get_name();
@@ -207,24 +403,22 @@ fn rename_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
     let pool_path = m.tree
         .get(object_path)
         .expect("implicit argument must be in tree");
-    let pool_uuid = get_data!(pool_path; default_return; return_message).uuid;
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
 
-    let msg = match dbus_context
-              .engine
-              .borrow_mut()
-              .rename_pool(&pool_uuid, new_name) {
-        Ok(RenameAction::NoSource) => {
-            let error_message = format!("engine doesn't know about pool {}", &pool_uuid);
-            let (rc, rs) = code_to_message_items(DbusErrorEnum::INTERNAL_ERROR, error_message);
-            return_message.append3(default_return, rc, rs)
-        }
-        Ok(RenameAction::Identity) => {
-            let (rc, rs) = ok_message_items();
-            return_message.append3(MessageItem::Bool(false), rc, rs)
-        }
-        Ok(RenameAction::Renamed) => {
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
+
+    let result = mark_device_failed_object(dbus_context, pool, Path::new(dev));
+    let operation = format!("MarkDeviceFailed {}", pool_uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(action) => {
             let (rc, rs) = ok_message_items();
-            return_message.append3(MessageItem::Bool(true), rc, rs)
+            return_message.append3(MessageItem::Bool(action), rc, rs)
         }
         Err(err) => {
             let (rc, rs) = engine_to_dbus_err(&err);
@@ -232,129 +426,1739 @@ fn rename_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
             return_message.append3(default_return, rc, rs)
         }
     };
-    Ok(vec![msg])
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
 }
 
-/// Get a pool property and place it on the D-Bus. The property is
-/// found by means of the getter method which takes a reference to a
-/// Pool and obtains the property from the pool.
-fn get_pool_property<F>(i: &mut IterAppend,
-                        p: &PropInfo<MTFn<TData>, TData>,
-                        getter: F)
-                        -> Result<(), MethodErr>
-    where F: Fn(&Pool) -> Result<MessageItem, MethodErr>
-{
-    let dbus_context = p.tree.get_data();
-    let object_path = p.path.get_name();
-    let pool_path = p.tree
+fn set_device_read_only(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 2));
+
+    let dev: &str = try!(get_next_arg(&mut iter, 0));
+    let read_only: bool = try!(get_next_arg(&mut iter, 1));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let pool_path = m.tree
         .get(object_path)
         .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
 
-    let pool_uuid = try!(pool_path
-                        .get_data()
-                        .as_ref()
-                        .ok_or_else(|| {
-                                        MethodErr::failed(&format!("no data for object path {}",
-                                                                   object_path))
-                                    }))
-            .uuid;
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
 
-    let engine = dbus_context.engine.borrow();
-    let pool = try!(engine
-                 .get_pool(&pool_uuid)
-                 .ok_or_else(|| {
-                                 MethodErr::failed(&format!("no pool corresponding to uuid {}",
-                                                            &pool_uuid))
-                             }));
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
 
-    i.append(try!(getter(pool)));
-    Ok(())
-}
+    let result = pool.set_blockdev_read_only(Path::new(dev), read_only);
+    let operation = format!("SetDeviceReadOnly {} {}", pool_uuid, read_only);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
 
-fn get_pool_name(i: &mut IterAppend, p: &PropInfo<MTFn<TData>, TData>) -> Result<(), MethodErr> {
-    get_pool_property(i, p, |p| Ok(MessageItem::Str(p.name().to_owned())))
+    let msg = match result {
+        Ok(action) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(action), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
 }
 
-fn get_pool_total_physical_used(i: &mut IterAppend,
-                                p: &PropInfo<MTFn<TData>, TData>)
-                                -> Result<(), MethodErr> {
-    fn get_used(pool: &Pool) -> Result<MessageItem, MethodErr> {
-        let err_func = |_| {
-            MethodErr::failed(&format!("no total physical size computed for pool with uuid {}",
-                                       pool.uuid()))
-        };
+fn set_metadata_size(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
 
-        try!(pool.total_physical_used()
-                 .map(|u| Ok(MessageItem::Str(format!("{}", *u))))
-                 .map_err(err_func))
-    }
+    let size: u64 = try!(get_next_arg(&mut iter, 0));
 
-    get_pool_property(i, p, get_used)
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
+
+    let result = pool.set_metadata_size(Sectors(size));
+    let operation = format!("SetMetadataSize {}", pool_uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(action) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(action), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
 }
 
-fn get_pool_total_physical_size(i: &mut IterAppend,
-                                p: &PropInfo<MTFn<TData>, TData>)
-                                -> Result<(), MethodErr> {
-    get_pool_property(i,
-                      p,
-                      |p| Ok(MessageItem::Str(format!("{}", *p.total_physical_size()))))
+/// Replace old_dev in pool by new_dev, removing the object of old_dev and
+/// creating one under pool_path for new_dev.
+fn replace_device_objects(dbus_context: &DbusContext,
+                          pool: &mut Pool,
+                          pool_path: &dbus::Path<'static>,
+                          old_dev: &Path,
+                          new_dev: &Path,
+                          force: bool)
+                          -> EngineResult<PathBuf> {
+    let old_uuid = blockdev_uuid(pool, old_dev);
+    let devnode = try!(pool.replace_blockdev(old_dev, new_dev, force));
+    remove_departed_blockdev(dbus_context, pool, old_uuid);
+    create_dbus_blockdevs(dbus_context, pool_path, pool);
+    Ok(devnode)
 }
 
-pub fn create_dbus_pool<'a>(dbus_context: &DbusContext,
-                            parent: dbus::Path<'static>,
-                            uuid: Uuid)
-                            -> dbus::Path<'a> {
+fn replace_device(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 3));
 
-    let f = Factory::new_fn();
+    let force: bool = try!(get_next_arg(&mut iter, 0));
+    let old_dev: &str = try!(get_next_arg(&mut iter, 1));
+    let new_dev: &str = try!(get_next_arg(&mut iter, 2));
 
-    let create_filesystems_method = f.method("CreateFilesystems", (), create_filesystems)
-        .in_arg(("specs", "as"))
-        .out_arg(("filesystems", "a(os)"))
-        .out_arg(("return_code", "q"))
-        .out_arg(("return_string", "s"));
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Str("".into());
 
-    let destroy_filesystems_method = f.method("DestroyFilesystems", (), destroy_filesystems)
-        .in_arg(("filesystems", "ao"))
-        .out_arg(("results", "as"))
-        .out_arg(("return_code", "q"))
-        .out_arg(("return_string", "s"));
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
 
-    let add_devs_method = f.method("AddDevs", (), add_devs)
-        .in_arg(("force", "b"))
-        .in_arg(("devices", "as"))
-        .out_arg(("results", "as"))
-        .out_arg(("return_code", "q"))
-        .out_arg(("return_string", "s"));
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
 
-    let rename_method = f.method("SetName", (), rename_pool)
-        .in_arg(("name", "s"))
-        .out_arg(("action", "b"))
-        .out_arg(("return_code", "q"))
-        .out_arg(("return_string", "s"));
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
 
-    let name_property = f.property::<&str, _>("Name", ())
-        .access(Access::Read)
-        .emits_changed(EmitsChangedSignal::False)
-        .on_get(get_pool_name);
+    let result = replace_device_objects(dbus_context,
+                                        pool,
+                                        object_path,
+                                        Path::new(old_dev),
+                                        Path::new(new_dev),
+                                        force);
+    let operation = format!("ReplaceDevice {}", pool_uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
 
-    let total_physical_size_property = f.property::<&str, _>("TotalPhysicalSize", ())
-        .access(Access::Read)
-        .emits_changed(EmitsChangedSignal::False)
-        .on_get(get_pool_total_physical_size);
+    let msg = match result {
+        Ok(devnode) => {
+            let devnode = devnode
+                .to_str()
+                .expect("'devnode' originated in the 'new_device' D-Bus argument.")
+                .into();
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Str(devnode), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
 
-    let total_physical_used_property = f.property::<&str, _>("TotalPhysicalUsed", ())
-        .access(Access::Read)
-        .emits_changed(EmitsChangedSignal::False)
-        .on_get(get_pool_total_physical_used);
+fn compact_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, _) = try!(get_correlation_id(message, 0));
 
-    let uuid_property = f.property::<&str, _>("Uuid", ())
-        .access(Access::Read)
-        .emits_changed(EmitsChangedSignal::Const)
-        .on_get(get_uuid);
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
 
-    let object_name = format!("{}/{}",
-                              STRATIS_BASE_PATH,
-                              dbus_context.get_next_id().to_string());
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
+
+    let result = pool.compact();
+    let operation = format!("Compact {}", pool_uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(action) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(action), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+fn rebalance_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, _) = try!(get_correlation_id(message, 0));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
+
+    let result = pool.rebalance();
+    let operation = format!("Rebalance {}", pool_uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(action) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(action), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+fn set_read_only(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
+
+    let read_only: bool = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
+
+    let result = pool.set_read_only(read_only);
+    let operation = format!("SetReadOnly {}", pool_uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(action) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(action), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+fn import_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    set_imported_common(m, "Import", true)
+}
+
+fn adopt_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    set_imported_common(m, "Adopt", false)
+}
+
+/// Mark a pool as imported from another host, or as adopted by this one,
+/// on behalf of the method named method.
+fn set_imported_common(m: &MethodInfo<MTFn<TData>, TData>,
+                       method: &str,
+                       imported: bool)
+                       -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, _) = try!(get_correlation_id(message, 0));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
+
+    let result = pool.set_imported(imported);
+    let operation = format!("{} {}", method, pool_uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(action) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(action), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+fn add_tag(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    change_tag_common(m, "AddTag", true)
+}
+
+fn remove_tag(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    change_tag_common(m, "RemoveTag", false)
+}
+
+/// Tag a pool, or remove a tag from it, on behalf of the method named
+/// method.
+fn change_tag_common(m: &MethodInfo<MTFn<TData>, TData>, method: &str, add: bool) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
+
+    let tag: &str = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
+
+    let result = if add {
+        pool.add_tag(tag)
+    } else {
+        pool.remove_tag(tag)
+    };
+    let operation = format!("{} {} {}", method, pool_uuid, tag);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(action) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(action), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+fn set_description(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
+
+    let description: &str = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
+
+    let result = pool.set_description(description);
+    let operation = format!("SetDescription {}", pool_uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(action) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(action), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+fn set_max_filesystems(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
+
+    let max: u32 = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
+
+    let result = pool.set_max_filesystems(max);
+    let operation = format!("SetMaxFilesystems {}", pool_uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(action) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(action), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+fn set_max_snapshots(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
+
+    let max: u32 = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
+
+    let result = pool.set_max_snapshots(max);
+    let operation = format!("SetMaxSnapshots {}", pool_uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(action) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(action), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+fn set_reserved_free(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
+
+    let size: u64 = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
+
+    let result = pool.set_reserved_free(Sectors(size));
+    let operation = format!("SetReservedFree {}", pool_uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(action) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(action), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+fn set_usage_alarm(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
+
+    let percent: u16 = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
+
+    let result = pool.set_usage_alarm(percent);
+    let operation = format!("SetUsageAlarm {}", pool_uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(action) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(action), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+/// The cache mode which corresponds to code, if any.
+fn cache_mode_from_code(code: u16) -> Option<CacheMode> {
+    CacheMode::iter_variants().nth(code as usize)
+}
+
+fn set_cache_mode(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
+
+    let mode: u16 = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let mode = match cache_mode_from_code(mode) {
+        Some(mode) => mode,
+        None => {
+            let error_message = format!("code {} does not correspond to any cache mode", mode);
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::ERROR, error_message);
+            let msg = return_message.append3(default_return, rc, rs);
+            return Ok(vec![echo_correlation_id(msg, correlation_id)]);
+        }
+    };
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
+
+    let result = pool.set_cache_mode(mode);
+    let operation = format!("SetCacheMode {}", pool_uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(action) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(action), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+fn set_cache_enabled(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
+
+    let enabled: bool = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
+
+    let result = pool.set_cache_enabled(enabled);
+    let operation = format!("SetCacheEnabled {} {}", pool_uuid, enabled);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(action) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(action), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+/// The compression which corresponds to code, if any.
+pub fn compression_from_code(code: u16) -> Option<Compression> {
+    Compression::iter_variants().nth(code as usize)
+}
+
+fn set_compression(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
+
+    let compression: u16 = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let compression = match compression_from_code(compression) {
+        Some(compression) => compression,
+        None => {
+            let error_message = format!("code {} does not correspond to any compression",
+                                        compression);
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::ERROR, error_message);
+            let msg = return_message.append3(default_return, rc, rs);
+            return Ok(vec![echo_correlation_id(msg, correlation_id)]);
+        }
+    };
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
+
+    let result = pool.set_compression(compression);
+    let operation = format!("SetCompression {}", pool_uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(action) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(action), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+/// The allocation policy which corresponds to code, if any.
+pub fn allocation_policy_from_code(code: u16) -> Option<AllocationPolicy> {
+    AllocationPolicy::iter_variants().nth(code as usize)
+}
+
+fn set_allocation_policy(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
+
+    let policy: u16 = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let policy = match allocation_policy_from_code(policy) {
+        Some(policy) => policy,
+        None => {
+            let error_message = format!("code {} does not correspond to any allocation policy",
+                                        policy);
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::ERROR, error_message);
+            let msg = return_message.append3(default_return, rc, rs);
+            return Ok(vec![echo_correlation_id(msg, correlation_id)]);
+        }
+    };
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
+
+    let result = pool.set_allocation_policy(policy);
+    let operation = format!("SetAllocationPolicy {}", pool_uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(action) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(action), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+fn reshape_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
+
+    let code: u16 = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let redundancy = match Redundancy::iter_variants().nth(code as usize) {
+        Some(redundancy) => redundancy,
+        None => {
+            let error_message = format!("code {} does not correspond to any redundancy", code);
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::ERROR, error_message);
+            let msg = return_message.append3(default_return, rc, rs);
+            return Ok(vec![echo_correlation_id(msg, correlation_id)]);
+        }
+    };
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let mut engine = write_engine!(dbus_context; correlation_id; default_return; return_message);
+    let pool = get_mut_pool!(engine; pool_uuid; correlation_id; default_return; return_message);
+
+    let result = pool.reshape(redundancy);
+    let operation = format!("Reshape {}", pool_uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(action) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(action), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+fn get_extent_size(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::UInt64(0);
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; default_return; return_message).uuid;
+
+    let engine = read_engine!(dbus_context; default_return; return_message);
+    let pool = get_pool!(engine; pool_uuid; default_return; return_message);
+
+    let extent_bytes = *pool.extent_size() * SECTOR_SIZE as u64;
+    let (rc, rs) = ok_message_items();
+    Ok(vec![return_message.append3(MessageItem::UInt64(extent_bytes), rc, rs)])
+}
+
+/// The sizes of the pool's physical space, the part of it in use, and its
+/// metadata reservation, each with its name, in bytes and as people read
+/// them.
+fn capacity(pool: &Pool) -> EngineResult<Vec<(&'static str, u64, String)>> {
+    let sizes = [("total_physical_size", pool.total_physical_size()),
+                 ("total_physical_used", try!(pool.total_physical_used())),
+                 ("metadata_size", pool.metadata_size())];
+    Ok(sizes
+           .iter()
+           .map(|&(name, size)| {
+                    let bytes = *size * SECTOR_SIZE as u64;
+                    (name, bytes, format_bytes(bytes))
+                })
+           .collect())
+}
+
+fn get_capacity(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let return_sig = "(sts)";
+    let default_return = MessageItem::Array(vec![], return_sig.into());
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; default_return; return_message).uuid;
+
+    let engine = read_engine!(dbus_context; default_return; return_message);
+    let pool = get_pool!(engine; pool_uuid; default_return; return_message);
+
+    let msg = match capacity(pool) {
+        Ok(sizes) => {
+            let sizes = sizes
+                .into_iter()
+                .map(|(name, bytes, human)| {
+                         MessageItem::Struct(vec![MessageItem::Str(name.into()),
+                                                  MessageItem::UInt64(bytes),
+                                                  MessageItem::Str(human)])
+                     })
+                .collect();
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Array(sizes, return_sig.into()), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+/// The UUID, name, size, and amount used of each of the pool's filesystems,
+/// sorted by sort_by, which is "name", "size", or "used"; if it is empty,
+/// by name. Filesystems of the same size or amount used are in order of
+/// name. If descending is true, the whole order is reversed.
+fn filesystem_list(pool: &Pool,
+                   sort_by: &str,
+                   descending: bool)
+                   -> EngineResult<Vec<(Uuid, String, Sectors, Sectors)>> {
+    let mut filesystems = Vec::new();
+    for filesystem in pool.filesystems() {
+        filesystems.push((*filesystem.uuid(),
+                          filesystem.name().to_owned(),
+                          filesystem.size(),
+                          try!(filesystem.used())));
+    }
+    filesystems.sort_by(|a, b| a.1.cmp(&b.1));
+    match sort_by {
+        "" | "name" => {}
+        "size" => filesystems.sort_by_key(|f| f.2),
+        "used" => filesystems.sort_by_key(|f| f.3),
+        _ => {
+            let message = format!("can not sort filesystems by \"{}\"", sort_by);
+            return Err(EngineError::Engine(ErrorEnum::Invalid, message));
+        }
+    }
+    if descending {
+        filesystems.reverse();
+    }
+    Ok(filesystems)
+}
+
+fn list_filesystems(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let mut iter = message.iter_init();
+
+    let sort_by: &str = try!(get_next_arg(&mut iter, 0));
+    let descending: bool = try!(get_next_arg(&mut iter, 1));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let return_sig = "(ostt)";
+    let default_return = MessageItem::Array(vec![], return_sig.into());
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; default_return; return_message).uuid;
+
+    let engine = read_engine!(dbus_context; default_return; return_message);
+    let pool = get_pool!(engine; pool_uuid; default_return; return_message);
+
+    let msg = match filesystem_list(pool, sort_by, descending) {
+        Ok(filesystems) => {
+            let filesystems = filesystems
+                .into_iter()
+                .map(|(uuid, name, size, used)| {
+                         let path = dbus_context
+                             .object_path_for(&uuid)
+                             .unwrap_or_else(default_object_path);
+                         MessageItem::Struct(vec![MessageItem::ObjectPath(path),
+                                                  MessageItem::Str(name),
+                                                  MessageItem::UInt64(*size *
+                                                                      SECTOR_SIZE as u64),
+                                                  MessageItem::UInt64(*used *
+                                                                      SECTOR_SIZE as u64)])
+                     })
+                .collect();
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Array(filesystems, return_sig.into()), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+fn get_pool_created_at(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::UInt64(0);
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; default_return; return_message).uuid;
+
+    let engine = read_engine!(dbus_context; default_return; return_message);
+    let pool = get_pool!(engine; pool_uuid; default_return; return_message);
+
+    let (rc, rs) = ok_message_items();
+    Ok(vec![return_message.append3(MessageItem::UInt64(pool.created_at()), rc, rs)])
+}
+
+fn get_space_shortfall(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::UInt64(0);
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; default_return; return_message).uuid;
+
+    let engine = read_engine!(dbus_context; default_return; return_message);
+    let pool = get_pool!(engine; pool_uuid; default_return; return_message);
+
+    let msg = match pool.space_shortfall() {
+        Ok(shortfall) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::UInt64(*shortfall * SECTOR_SIZE as u64), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+fn get_io_stats(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Struct(vec![MessageItem::UInt64(0),
+                                                  MessageItem::UInt64(0),
+                                                  MessageItem::UInt64(0)]);
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; default_return; return_message).uuid;
+
+    let engine = read_engine!(dbus_context; default_return; return_message);
+    let pool = get_pool!(engine; pool_uuid; default_return; return_message);
+
+    let stats = pool.io_stats();
+    let (rc, rs) = ok_message_items();
+    let return_value = MessageItem::Struct(vec![MessageItem::UInt64(stats.bytes_read),
+                                                MessageItem::UInt64(stats.bytes_written),
+                                                MessageItem::UInt64(stats.io_ops)]);
+    Ok(vec![return_message.append3(return_value, rc, rs)])
+}
+
+/// The number of the pool's cache devices, their total size in bytes,
+/// and how the cache handles I/O: "Passthrough" if the cache is disabled,
+/// otherwise the cache mode.
+fn cache_stats(pool: &Pool) -> (u16, u64, String) {
+    let cache_devs = pool.blockdevs()
+        .into_iter()
+        .filter(|&(tier, _)| tier == BlockDevTier::Cache)
+        .map(|(_, bd)| bd)
+        .collect::<Vec<_>>();
+    let size = cache_devs.iter().map(|bd| *bd.size()).sum::<u64>();
+    let mode = if pool.cache_enabled() {
+        pool.cache_mode().to_string()
+    } else {
+        "Passthrough".into()
+    };
+    (cache_devs.len() as u16, size * SECTOR_SIZE as u64, mode)
+}
+
+fn get_cache_stats(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Struct(vec![MessageItem::UInt16(0),
+                                                  MessageItem::UInt64(0),
+                                                  MessageItem::Str("".into())]);
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; default_return; return_message).uuid;
+
+    let engine = read_engine!(dbus_context; default_return; return_message);
+    let pool = get_pool!(engine; pool_uuid; default_return; return_message);
+
+    let (devices, size, mode) = cache_stats(pool);
+    let (rc, rs) = ok_message_items();
+    let return_value = MessageItem::Struct(vec![MessageItem::UInt16(devices),
+                                                MessageItem::UInt64(size),
+                                                MessageItem::Str(mode)]);
+    Ok(vec![return_message.append3(return_value, rc, rs)])
+}
+
+fn get_device_health(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let mut iter = message.iter_init();
+
+    let dev: &str = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Struct(vec![MessageItem::UInt16(0),
+                                                  MessageItem::UInt16(0)]);
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; default_return; return_message).uuid;
+
+    let engine = read_engine!(dbus_context; default_return; return_message);
+    let pool = get_pool!(engine; pool_uuid; default_return; return_message);
+
+    let msg = match pool.blockdevs()
+              .into_iter()
+              .find(|&(_, bd)| bd.devnode() == Path::new(dev)) {
+        Some((_, bd)) => {
+            let (rc, rs) = ok_message_items();
+            let return_value = MessageItem::Struct(vec![MessageItem::UInt16(bd.wear_percent()),
+                                                        MessageItem::UInt16(bd.health()
+                                                                                .into())]);
+            return_message.append3(return_value, rc, rs)
+        }
+        None => {
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::NOTFOUND,
+                                                 format!("no device {} in pool", dev));
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+fn rename_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (correlation_id, mut iter) = try!(get_correlation_id(message, 1));
+
+    let new_name: &str = try!(get_next_arg(&mut iter, 0));
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = get_data!(pool_path; correlation_id; default_return; return_message).uuid;
+
+    let _guard = begin_operation!(m; dbus_context; correlation_id; default_return; return_message);
+
+    let result = write_engine!(dbus_context; correlation_id; default_return; return_message)
+        .rename_pool(&pool_uuid, new_name);
+    let operation = format!("SetName {}", pool_uuid);
+    log_operation(&correlation_id, &operation, &result);
+    dbus_context.record_operation(operation, &result);
+
+    let msg = match result {
+        Ok(RenameAction::NoSource) => {
+            let error_message = format!("engine doesn't know about pool {}", &pool_uuid);
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::INTERNAL_ERROR, error_message);
+            return_message.append3(default_return, rc, rs)
+        }
+        Ok(RenameAction::Identity) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(false), rc, rs)
+        }
+        Ok(RenameAction::Renamed) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(true), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![echo_correlation_id(msg, correlation_id)])
+}
+
+/// Get a pool property and place it on the D-Bus. The property is
+/// found by means of the getter method which takes a reference to a
+/// Pool and obtains the property from the pool.
+fn get_pool_property<F>(i: &mut IterAppend,
+                        p: &PropInfo<MTFn<TData>, TData>,
+                        getter: F)
+                        -> Result<(), MethodErr>
+    where F: Fn(&Pool) -> Result<MessageItem, MethodErr>
+{
+    let dbus_context = p.tree.get_data();
+    let object_path = p.path.get_name();
+    let pool_path = p.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+
+    let pool_uuid = try!(pool_path
+                        .get_data()
+                        .as_ref()
+                        .ok_or_else(|| {
+                                        MethodErr::failed(&format!("no data for object path {}",
+                                                                   object_path))
+                                    }))
+            .uuid;
+
+    let engine = try!(dbus_context
+                          .engine
+                          .read()
+                          .map_err(|_| MethodErr::failed(&"engine lock poisoned")));
+    let pool = try!(engine
+                 .get_pool(&pool_uuid)
+                 .ok_or_else(|| {
+                                 MethodErr::failed(&format!("no pool corresponding to uuid {}",
+                                                            &pool_uuid))
+                             }));
+
+    i.append(try!(getter(pool)));
+    Ok(())
+}
+
+fn get_pool_name(i: &mut IterAppend, p: &PropInfo<MTFn<TData>, TData>) -> Result<(), MethodErr> {
+    get_pool_property(i, p, |p| Ok(MessageItem::Str(p.name().to_owned())))
+}
+
+fn get_pool_total_physical_used(i: &mut IterAppend,
+                                p: &PropInfo<MTFn<TData>, TData>)
+                                -> Result<(), MethodErr> {
+    fn get_used(pool: &Pool) -> Result<MessageItem, MethodErr> {
+        let err_func = |_| {
+            MethodErr::failed(&format!("no total physical size computed for pool with uuid {}",
+                                       pool.uuid()))
+        };
+
+        try!(pool.total_physical_used()
+                 .map(|u| Ok(MessageItem::Str(format!("{}", *u))))
+                 .map_err(err_func))
+    }
+
+    get_pool_property(i, p, get_used)
+}
+
+fn get_pool_total_physical_size(i: &mut IterAppend,
+                                p: &PropInfo<MTFn<TData>, TData>)
+                                -> Result<(), MethodErr> {
+    get_pool_property(i,
+                      p,
+                      |p| Ok(MessageItem::Str(format!("{}", *p.total_physical_size()))))
+}
+
+fn get_pool_metadata_size(i: &mut IterAppend,
+                          p: &PropInfo<MTFn<TData>, TData>)
+                          -> Result<(), MethodErr> {
+    get_pool_property(i,
+                      p,
+                      |p| Ok(MessageItem::Str(format!("{}", *p.metadata_size()))))
+}
+
+fn get_pool_cache_mode(i: &mut IterAppend,
+                       p: &PropInfo<MTFn<TData>, TData>)
+                       -> Result<(), MethodErr> {
+    get_pool_property(i, p, |p| Ok(MessageItem::UInt16(p.cache_mode().into())))
+}
+
+fn get_pool_cache_enabled(i: &mut IterAppend,
+                          p: &PropInfo<MTFn<TData>, TData>)
+                          -> Result<(), MethodErr> {
+    get_pool_property(i, p, |p| Ok(MessageItem::Bool(p.cache_enabled())))
+}
+
+fn get_pool_compression(i: &mut IterAppend,
+                        p: &PropInfo<MTFn<TData>, TData>)
+                        -> Result<(), MethodErr> {
+    get_pool_property(i, p, |p| Ok(MessageItem::UInt16(p.compression().into())))
+}
+
+fn get_pool_allocation_policy(i: &mut IterAppend,
+                              p: &PropInfo<MTFn<TData>, TData>)
+                              -> Result<(), MethodErr> {
+    get_pool_property(i, p, |p| Ok(MessageItem::UInt16(p.allocation_policy().into())))
+}
+
+fn get_pool_description(i: &mut IterAppend,
+                        p: &PropInfo<MTFn<TData>, TData>)
+                        -> Result<(), MethodErr> {
+    get_pool_property(i, p, |p| Ok(MessageItem::Str(p.description().to_owned())))
+}
+
+fn get_pool_tags(i: &mut IterAppend,
+                 p: &PropInfo<MTFn<TData>, TData>)
+                 -> Result<(), MethodErr> {
+    get_pool_property(i, p, |p| {
+        Ok(MessageItem::Array(p.tags()
+                                  .into_iter()
+                                  .map(|t| MessageItem::Str(t.into()))
+                                  .collect(),
+                              "s".into()))
+    })
+}
+
+fn get_pool_spares(i: &mut IterAppend,
+                   p: &PropInfo<MTFn<TData>, TData>)
+                   -> Result<(), MethodErr> {
+    get_pool_property(i, p, |p| {
+        let mut spares = p.spares()
+            .iter()
+            .map(|s| format!("{}", s.display()))
+            .collect::<Vec<_>>();
+        spares.sort();
+        Ok(MessageItem::Array(spares.into_iter().map(MessageItem::Str).collect(),
+                              "s".into()))
+    })
+}
+
+fn get_pool_created_at_property(i: &mut IterAppend,
+                                p: &PropInfo<MTFn<TData>, TData>)
+                                -> Result<(), MethodErr> {
+    get_pool_property(i, p, |p| Ok(MessageItem::UInt64(p.created_at())))
+}
+
+fn get_pool_max_filesystems(i: &mut IterAppend,
+                            p: &PropInfo<MTFn<TData>, TData>)
+                            -> Result<(), MethodErr> {
+    get_pool_property(i, p, |p| Ok(MessageItem::UInt32(p.max_filesystems())))
+}
+
+fn get_pool_max_snapshots(i: &mut IterAppend,
+                          p: &PropInfo<MTFn<TData>, TData>)
+                          -> Result<(), MethodErr> {
+    get_pool_property(i, p, |p| Ok(MessageItem::UInt32(p.max_snapshots())))
+}
+
+fn get_pool_reserved_free(i: &mut IterAppend,
+                          p: &PropInfo<MTFn<TData>, TData>)
+                          -> Result<(), MethodErr> {
+    get_pool_property(i, p, |p| Ok(MessageItem::UInt64(*p.reserved_free())))
+}
+
+fn get_pool_usage_alarm(i: &mut IterAppend,
+                        p: &PropInfo<MTFn<TData>, TData>)
+                        -> Result<(), MethodErr> {
+    get_pool_property(i, p, |p| Ok(MessageItem::UInt16(p.usage_alarm())))
+}
+
+fn get_pool_encrypted(i: &mut IterAppend,
+                      p: &PropInfo<MTFn<TData>, TData>)
+                      -> Result<(), MethodErr> {
+    get_pool_property(i, p, |p| Ok(MessageItem::Bool(p.key_description().is_some())))
+}
+
+fn get_pool_key_description(i: &mut IterAppend,
+                            p: &PropInfo<MTFn<TData>, TData>)
+                            -> Result<(), MethodErr> {
+    get_pool_property(i, p, |p| {
+        Ok(MessageItem::Str(p.key_description().unwrap_or("").to_owned()))
+    })
+}
+
+fn get_pool_imported(i: &mut IterAppend,
+                     p: &PropInfo<MTFn<TData>, TData>)
+                     -> Result<(), MethodErr> {
+    get_pool_property(i, p, |p| Ok(MessageItem::Bool(p.imported())))
+}
+
+fn get_pool_read_only(i: &mut IterAppend,
+                      p: &PropInfo<MTFn<TData>, TData>)
+                      -> Result<(), MethodErr> {
+    get_pool_property(i, p, |p| Ok(MessageItem::Bool(p.read_only())))
+}
+
+fn get_pool_redundancy(i: &mut IterAppend,
+                       p: &PropInfo<MTFn<TData>, TData>)
+                       -> Result<(), MethodErr> {
+    get_pool_property(i, p, |p| Ok(MessageItem::UInt16(p.redundancy().into())))
+}
+
+fn get_pool_state(i: &mut IterAppend, p: &PropInfo<MTFn<TData>, TData>) -> Result<(), MethodErr> {
+    get_pool_property(i, p, |p| Ok(MessageItem::UInt16(p.state().into())))
+}
+
+/// Create the D-Bus object for the pool with this UUID.
+/// If the pool already has an object, as when a creation is retried, no
+/// new object is made and the path of the existing object is returned.
+pub fn create_dbus_pool<'a>(dbus_context: &DbusContext,
+                            parent: dbus::Path<'static>,
+                            uuid: Uuid)
+                            -> dbus::Path<'a> {
+    if let Some(object_path) = dbus_context.object_path_for(&uuid) {
+        return object_path;
+    }
+
+    let f = Factory::new_fn();
+
+    let create_filesystems_method = f.method("CreateFilesystems", (), create_filesystems)
+        .in_arg(("specs", "as"))
+        .out_arg(("filesystems", "a(os)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let destroy_filesystems_method = f.method("DestroyFilesystems", (), destroy_filesystems)
+        .in_arg(("filesystems", "ao"))
+        .out_arg(("results", "as"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let clone_filesystem_method = f.method("CloneFilesystem", (), clone_filesystem)
+        .in_arg(("filesystem", "o"))
+        .in_arg(("name", "s"))
+        .out_arg(("result", "o"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let add_devs_method = f.method("AddDevs", (), add_devs)
+        .in_arg(("force", "b"))
+        .in_arg(("devices", "as"))
+        .out_arg(("results", "as"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let add_spares_method = f.method("AddSpares", (), add_spares)
+        .in_arg(("force", "b"))
+        .in_arg(("devices", "as"))
+        .out_arg(("results", "as"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let add_devs_each_method = f.method("AddDevsEach", (), add_devs_each)
+        .in_arg(("force", "b"))
+        .in_arg(("devices", "as"))
+        .out_arg(("results", "a(sqs)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let mark_device_failed_method = f.method("MarkDeviceFailed", (), mark_device_failed)
+        .in_arg(("device", "s"))
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let set_device_read_only_method = f.method("SetDeviceReadOnly", (), set_device_read_only)
+        .in_arg(("device", "s"))
+        .in_arg(("read_only", "b"))
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let replace_device_method = f.method("ReplaceDevice", (), replace_device)
+        .in_arg(("force", "b"))
+        .in_arg(("old_device", "s"))
+        .in_arg(("new_device", "s"))
+        .out_arg(("device", "s"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let set_metadata_size_method = f.method("SetMetadataSize", (), set_metadata_size)
+        .in_arg(("size", "t"))
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let set_read_only_method = f.method("SetReadOnly", (), set_read_only)
+        .in_arg(("read_only", "b"))
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let import_method = f.method("Import", (), import_pool)
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let adopt_method = f.method("Adopt", (), adopt_pool)
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let set_cache_mode_method = f.method("SetCacheMode", (), set_cache_mode)
+        .in_arg(("mode", "q"))
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let set_cache_enabled_method = f.method("SetCacheEnabled", (), set_cache_enabled)
+        .in_arg(("enabled", "b"))
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let set_allocation_policy_method =
+        f.method("SetAllocationPolicy", (), set_allocation_policy)
+            .in_arg(("policy", "q"))
+            .out_arg(("action", "b"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let set_compression_method = f.method("SetCompression", (), set_compression)
+        .in_arg(("compression", "q"))
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let add_tag_method = f.method("AddTag", (), add_tag)
+        .in_arg(("tag", "s"))
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let remove_tag_method = f.method("RemoveTag", (), remove_tag)
+        .in_arg(("tag", "s"))
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let set_description_method = f.method("SetDescription", (), set_description)
+        .in_arg(("description", "s"))
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_extent_size_method = f.method("GetExtentSize", (), get_extent_size)
+        .out_arg(("extent_bytes", "t"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let reshape_method = f.method("Reshape", (), reshape_pool)
+        .in_arg(("redundancy", "q"))
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let set_max_filesystems_method = f.method("SetMaxFilesystems", (), set_max_filesystems)
+        .in_arg(("max", "u"))
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let set_max_snapshots_method = f.method("SetMaxSnapshots", (), set_max_snapshots)
+        .in_arg(("max", "u"))
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let set_reserved_free_method = f.method("SetReservedFree", (), set_reserved_free)
+        .in_arg(("size", "t"))
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let set_usage_alarm_method = f.method("SetUsageAlarm", (), set_usage_alarm)
+        .in_arg(("percent", "q"))
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let compact_method = f.method("Compact", (), compact_pool)
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let rebalance_method = f.method("Rebalance", (), rebalance_pool)
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_capacity_method = f.method("GetCapacity", (), get_capacity)
+        .out_arg(("sizes", "a(sts)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let list_filesystems_method = f.method("ListFilesystems", (), list_filesystems)
+        .in_arg(("sort_by", "s"))
+        .in_arg(("descending", "b"))
+        .out_arg(("filesystems", "a(ostt)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_pool_created_at_method = f.method("GetPoolCreatedAt", (), get_pool_created_at)
+        .out_arg(("created_at", "t"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_space_shortfall_method = f.method("GetSpaceShortfall", (), get_space_shortfall)
+        .out_arg(("shortfall_bytes", "t"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_io_stats_method = f.method("GetIoStats", (), get_io_stats)
+        .out_arg(("stats", "(ttt)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_cache_stats_method = f.method("GetCacheStats", (), get_cache_stats)
+        .out_arg(("stats", "(qts)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_device_health_method = f.method("GetDeviceHealth", (), get_device_health)
+        .in_arg(("device", "s"))
+        .out_arg(("health", "(qq)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let rename_method = f.method("SetName", (), rename_pool)
+        .in_arg(("name", "s"))
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let metadata_size_property = f.property::<&str, _>("MetadataSize", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_pool_metadata_size);
+
+    let name_property = f.property::<&str, _>("Name", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_pool_name);
+
+    let cache_mode_property = f.property::<u16, _>("CacheMode", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_pool_cache_mode);
+
+    let cache_enabled_property = f.property::<bool, _>("CacheEnabled", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_pool_cache_enabled);
+
+    let allocation_policy_property = f.property::<u16, _>("AllocationPolicy", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_pool_allocation_policy);
+
+    let compression_property = f.property::<u16, _>("Compression", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_pool_compression);
+
+    let description_property = f.property::<&str, _>("Description", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_pool_description);
+
+    let tags_property = f.property::<Array<&str, &Iterator<Item = &str>>, _>("Tags", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_pool_tags);
+
+    let spares_property = f.property::<Array<&str, &Iterator<Item = &str>>, _>("Spares", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_pool_spares);
+
+    let created_at_property = f.property::<u64, _>("CreatedAt", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::Const)
+        .on_get(get_pool_created_at_property);
+
+    let max_filesystems_property = f.property::<u32, _>("MaxFilesystems", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_pool_max_filesystems);
+
+    let max_snapshots_property = f.property::<u32, _>("MaxSnapshots", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_pool_max_snapshots);
+
+    let reserved_free_property = f.property::<u64, _>("ReservedFree", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_pool_reserved_free);
+
+    let usage_alarm_property = f.property::<u16, _>("UsageAlarm", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_pool_usage_alarm);
+
+    let encrypted_property = f.property::<bool, _>("Encrypted", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::Const)
+        .on_get(get_pool_encrypted);
+
+    let key_description_property = f.property::<&str, _>("KeyDescription", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::Const)
+        .on_get(get_pool_key_description);
+
+    let imported_property = f.property::<bool, _>("Imported", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_pool_imported);
+
+    let read_only_property = f.property::<bool, _>("ReadOnly", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_pool_read_only);
+
+    let redundancy_property = f.property::<u16, _>("Redundancy", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_pool_redundancy);
+
+    let state_property = f.property::<u16, _>("State", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_pool_state);
+
+    let total_physical_size_property = f.property::<&str, _>("TotalPhysicalSize", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_pool_total_physical_size);
+
+    let total_physical_used_property = f.property::<&str, _>("TotalPhysicalUsed", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_pool_total_physical_used);
+
+    let uuid_property = f.property::<&str, _>("Uuid", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::Const)
+        .on_get(get_uuid);
+
+    let object_name = format!("{}/{}", STRATIS_BASE_PATH, uuid_to_path_element(&uuid));
 
     let interface_name = format!("{}.{}", STRATIS_BASE_SERVICE, "pool");
 
@@ -363,14 +2167,387 @@ pub fn create_dbus_pool<'a>(dbus_context: &DbusContext,
         .add(f.interface(interface_name, ())
                  .add_m(create_filesystems_method)
                  .add_m(destroy_filesystems_method)
+                 .add_m(clone_filesystem_method)
                  .add_m(add_devs_method)
+                 .add_m(add_devs_each_method)
+                 .add_m(add_spares_method)
+                 .add_m(mark_device_failed_method)
+                 .add_m(set_device_read_only_method)
+                 .add_m(replace_device_method)
+                 .add_m(set_metadata_size_method)
+                 .add_m(set_read_only_method)
+                 .add_m(import_method)
+                 .add_m(adopt_method)
+                 .add_m(set_cache_mode_method)
+                 .add_m(set_cache_enabled_method)
+                 .add_m(set_compression_method)
+                 .add_m(set_allocation_policy_method)
+                 .add_m(add_tag_method)
+                 .add_m(remove_tag_method)
+                 .add_m(set_description_method)
+                 .add_m(get_extent_size_method)
+                 .add_m(get_space_shortfall_method)
+                 .add_m(get_pool_created_at_method)
+                 .add_m(get_capacity_method)
+                 .add_m(list_filesystems_method)
+                 .add_m(compact_method)
+                 .add_m(rebalance_method)
+                 .add_m(set_max_filesystems_method)
+                 .add_m(set_max_snapshots_method)
+                 .add_m(reshape_method)
+                 .add_m(set_reserved_free_method)
+                 .add_m(set_usage_alarm_method)
+                 .add_m(get_io_stats_method)
+                 .add_m(get_cache_stats_method)
+                 .add_m(get_device_health_method)
                  .add_m(rename_method)
+                 .add_p(metadata_size_property)
                  .add_p(name_property)
+                 .add_p(cache_mode_property)
+                 .add_p(cache_enabled_property)
+                 .add_p(compression_property)
+                 .add_p(allocation_policy_property)
+                 .add_p(tags_property)
+                 .add_p(description_property)
+                 .add_p(created_at_property)
+                 .add_p(max_filesystems_property)
+                 .add_p(max_snapshots_property)
+                 .add_p(reserved_free_property)
+                 .add_p(usage_alarm_property)
+                 .add_p(encrypted_property)
+                 .add_p(key_description_property)
+                 .add_p(read_only_property)
+                 .add_p(imported_property)
+                 .add_p(spares_property)
+                 .add_p(redundancy_property)
+                 .add_p(state_property)
                  .add_p(total_physical_size_property)
                  .add_p(total_physical_used_property)
                  .add_p(uuid_property));
 
     let path = object_path.get_name().to_owned();
-    dbus_context.actions.borrow_mut().push_add(object_path);
+    dbus_context.push_add(object_path);
     path
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, RwLock};
+
+    use dbus;
+    use dbus::Message;
+    use dbus::arg::IterAppend;
+    use dbus::tree::{Factory, MTFn, PropInfo, Tree};
+
+    use devicemapper::Sectors;
+
+    use engine::{AllocationPolicy, CacheMode, Compression, Engine, EngineError, ErrorEnum,
+                 PoolUuid, SimEngine};
+
+    use super::super::blockdev::{blockdev_uuid, create_dbus_blockdevs};
+    use super::super::types::{DbusContext, DbusErrorEnum, DeferredAction, TData};
+    use super::super::util::{STRATIS_BASE_PATH, STRATIS_BASE_SERVICE, default_object_path,
+                             engine_to_dbus_err, test_reply};
+    use super::{add_blockdevs_each, allocation_policy_from_code, cache_mode_from_code, cache_stats,
+                compression_from_code, create_dbus_pool, create_filesystem_objects,
+                filesystem_list, get_pool_name, mark_device_failed_object,
+                replace_device_objects};
+
+    /// A context holding the objects of the pool with this UUID and of its
+    /// blockdevs, and the path of the pool's object.
+    fn pool_objects(engine: Arc<RwLock<SimEngine>>,
+                    uuid: PoolUuid)
+                    -> (DbusContext, dbus::Path<'static>) {
+        let dbus_context = DbusContext::new(engine.clone(), 0);
+        let path = create_dbus_pool(&dbus_context, default_object_path(), uuid);
+        create_dbus_blockdevs(&dbus_context,
+                              &path,
+                              engine.read().unwrap().get_pool(&uuid).unwrap());
+        dbus_context.actions.borrow_mut().drain().count();
+        (dbus_context, path)
+    }
+
+    /// Whether the removal of the object at path was queued. Drains the
+    /// queue of actions.
+    fn removal_queued(dbus_context: &DbusContext, path: &dbus::Path<'static>) -> bool {
+        dbus_context
+            .actions
+            .borrow_mut()
+            .drain()
+            .any(|action| match action {
+                     DeferredAction::Remove(ref p) => p == path,
+                     DeferredAction::Add(_) => false,
+                 })
+    }
+
+    /// Read the Name property of the pool at path, as a client would.
+    fn read_name(tree: &Tree<MTFn<TData>, TData>, path: &dbus::Path<'static>) -> String {
+        let f = Factory::new_fn();
+        let iface = f.interface(format!("{}.{}", STRATIS_BASE_SERVICE, "pool"), ());
+        let prop = f.property::<&str, _>("Name", ());
+        let method = f.method("Get", (), |_| Ok(vec![]));
+        let call = Message::new_method_call(STRATIS_BASE_SERVICE,
+                                            STRATIS_BASE_PATH,
+                                            "org.freedesktop.DBus.Properties",
+                                            "Get")
+                .expect("arguments are valid D-Bus names");
+        let info = PropInfo {
+            msg: &call,
+            method: &method,
+            prop: &prop,
+            iface: &iface,
+            path: tree.get(path).unwrap(),
+            tree: tree,
+        };
+
+        let mut reply = test_reply();
+        get_pool_name(&mut IterAppend::new(&mut reply), &info).unwrap();
+        reply.read1::<&str>().unwrap().to_owned()
+    }
+
+    #[test]
+    /// The Name property reflects the pool's name after a rename
+    fn name_property_follows_rename() {
+        let engine = Arc::new(RwLock::new(SimEngine::default()));
+        let (uuid, _) = engine
+            .write()
+            .unwrap()
+            .create_pool("old_name", &[], &[], None, false)
+            .unwrap();
+        let dbus_context = DbusContext::new(engine.clone(), 0);
+        let path = create_dbus_pool(&dbus_context, default_object_path(), uuid);
+        let mut tree = Factory::new_fn().tree(dbus_context.clone());
+        for action in dbus_context.actions.borrow_mut().drain() {
+            if let DeferredAction::Add(object_path) = action {
+                tree = tree.add(object_path);
+            }
+        }
+
+        assert_eq!(read_name(&tree, &path), "old_name");
+        engine.write().unwrap().rename_pool(&uuid, "new_name").unwrap();
+        assert_eq!(read_name(&tree, &path), "new_name");
+    }
+
+    #[test]
+    /// A failed device which a spare takes the place of leaves the pool,
+    /// and its object goes with it
+    fn mark_device_failed_removes_object() {
+        let engine = Arc::new(RwLock::new(SimEngine::default()));
+        let (uuid, _) = engine
+            .write()
+            .unwrap()
+            .create_pool("name", &[Path::new("/s/a"), Path::new("/s/b")], &[], None, false)
+            .unwrap();
+        engine
+            .write()
+            .unwrap()
+            .get_mut_pool(&uuid)
+            .unwrap()
+            .add_spares(&[Path::new("/s/s")], false)
+            .unwrap();
+        let (dbus_context, _) = pool_objects(engine.clone(), uuid);
+        let failed = blockdev_uuid(engine.read().unwrap().get_pool(&uuid).unwrap(),
+                                   Path::new("/s/a"))
+                .unwrap();
+        let failed_path = dbus_context.object_path_for(&failed).unwrap();
+
+        let mut engine = engine.write().unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        assert!(mark_device_failed_object(&dbus_context, pool, Path::new("/s/a")).unwrap());
+        assert!(dbus_context.object_path_for(&failed).is_none());
+        assert!(removal_queued(&dbus_context, &failed_path));
+        assert!(dbus_context.object_paths.borrow().len() == 3);
+    }
+
+    #[test]
+    /// A replaced device leaves the pool, and its object goes with it;
+    /// the device which replaces it gets an object
+    fn replace_device_removes_object() {
+        let engine = Arc::new(RwLock::new(SimEngine::default()));
+        let (uuid, _) = engine
+            .write()
+            .unwrap()
+            .create_pool("name", &[Path::new("/s/a"), Path::new("/s/b")], &[], None, false)
+            .unwrap();
+        engine
+            .write()
+            .unwrap()
+            .get_mut_pool(&uuid)
+            .unwrap()
+            .mark_blockdev_failed(Path::new("/s/a"))
+            .unwrap();
+        let (dbus_context, path) = pool_objects(engine.clone(), uuid);
+        let old = blockdev_uuid(engine.read().unwrap().get_pool(&uuid).unwrap(),
+                                Path::new("/s/a"))
+                .unwrap();
+        let old_path = dbus_context.object_path_for(&old).unwrap();
+
+        let mut engine = engine.write().unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        let devnode = replace_device_objects(&dbus_context,
+                                             pool,
+                                             &path,
+                                             Path::new("/s/a"),
+                                             Path::new("/s/c"),
+                                             false)
+                .unwrap();
+        assert_eq!(devnode, PathBuf::from("/s/c"));
+        assert!(dbus_context.object_path_for(&old).is_none());
+        assert!(removal_queued(&dbus_context, &old_path));
+
+        let new = blockdev_uuid(pool, Path::new("/s/c")).unwrap();
+        assert!(dbus_context.object_path_for(&new).is_some());
+    }
+
+    #[test]
+    /// Filesystems for which there is no room outside the space reserved
+    /// free are refused with the NO_SPACE code, and get no objects
+    fn create_filesystems_no_space() {
+        let engine = Arc::new(RwLock::new(SimEngine::default()));
+        let (uuid, _) = engine
+            .write()
+            .unwrap()
+            .create_pool("name", &[Path::new("/s/a")], &[], None, false)
+            .unwrap();
+        {
+            let mut engine = engine.write().unwrap();
+            let pool = engine.get_mut_pool(&uuid).unwrap();
+            let free = pool.total_physical_size() - pool.total_physical_used().unwrap();
+            assert!(pool.set_reserved_free(free - Sectors(1)).unwrap());
+        }
+        let (dbus_context, path) = pool_objects(engine.clone(), uuid);
+        let object_count = dbus_context.object_paths.borrow().len();
+
+        let mut engine = engine.write().unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        let err = create_filesystem_objects(&dbus_context, pool, &path, &["fs"]).unwrap_err();
+        assert_eq!(engine_to_dbus_err(&err).0, DbusErrorEnum::NO_SPACE);
+        assert!(pool.filesystems().is_empty());
+        assert!(dbus_context.object_paths.borrow().len() == object_count);
+    }
+
+    #[test]
+    /// Creating the object for a pool a second time yields the same path
+    fn create_dbus_pool_idempotent() {
+        let engine = Arc::new(RwLock::new(SimEngine::default()));
+        let (uuid, _) = engine
+            .write()
+            .unwrap()
+            .create_pool("name", &[], &[], None, false)
+            .unwrap();
+        let dbus_context = DbusContext::new(engine, 0);
+        let path = create_dbus_pool(&dbus_context, default_object_path(), uuid);
+        let next_index = dbus_context.next_index.get();
+
+        assert!(create_dbus_pool(&dbus_context, default_object_path(), uuid) == path);
+        assert!(dbus_context.next_index.get() == next_index);
+        assert!(dbus_context.object_paths.borrow().len() == 1);
+        assert!(dbus_context.actions.borrow_mut().drain().count() == 1);
+    }
+
+    #[test]
+    /// Disabling the cache keeps the cache devices in the pool, but the
+    /// cache stats report that I/O passes them by until it is re-enabled
+    fn cache_disabled_passthrough() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("name", &[Path::new("/s/a")], &[Path::new("/s/c")], None, false)
+            .unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        let (devices, size, mode) = cache_stats(pool);
+        assert!(pool.cache_enabled());
+        assert!(devices == 1 && size > 0);
+        assert!(mode == "WriteThrough");
+
+        assert!(pool.set_cache_enabled(false).unwrap());
+        assert!(!pool.set_cache_enabled(false).unwrap());
+        assert!(!pool.cache_enabled());
+        assert!(cache_stats(pool) == (devices, size, "Passthrough".into()));
+
+        assert!(pool.set_cache_enabled(true).unwrap());
+        assert!(pool.cache_enabled());
+        assert!(cache_stats(pool) == (devices, size, mode));
+    }
+
+    #[test]
+    /// Filesystems are listed in order of name by default, or of size or
+    /// amount used, either way up; an unknown sort key is refused
+    fn filesystems_sorted() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine.create_pool("name", &[], &[], None, false).unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        let extent_size = pool.extent_size();
+        pool.create_filesystems(&[("b", Some(extent_size * 3usize)),
+                                  ("a", Some(extent_size * 2usize)),
+                                  ("c", Some(extent_size * 4usize))])
+            .unwrap();
+
+        let names = |sort_by: &str, descending: bool| {
+            filesystem_list(pool, sort_by, descending)
+                .unwrap()
+                .into_iter()
+                .map(|(_, name, _, _)| name)
+                .collect::<Vec<_>>()
+        };
+        assert!(names("", false) == vec!["a", "b", "c"]);
+        assert!(names("name", true) == vec!["c", "b", "a"]);
+        assert!(names("size", false) == vec!["a", "b", "c"]);
+        assert!(names("size", true) == vec!["c", "b", "a"]);
+        // All are empty, so they are in order of name
+        assert!(names("used", false) == vec!["a", "b", "c"]);
+        assert!(filesystem_list(pool, "age", false).is_err());
+    }
+
+    #[test]
+    /// Each cache mode code is accepted and any other code is rejected
+    fn cache_mode_codes() {
+        assert!(cache_mode_from_code(CacheMode::WriteThrough.into()) ==
+                Some(CacheMode::WriteThrough));
+        assert!(cache_mode_from_code(CacheMode::WriteBack.into()) == Some(CacheMode::WriteBack));
+        assert!(cache_mode_from_code(2).is_none());
+    }
+
+    #[test]
+    /// Each compression code is accepted and any other code is rejected
+    fn compression_codes() {
+        assert!(Compression::iter_variants().all(|c| compression_from_code(c.into()) == Some(c)));
+        assert!(compression_from_code(3).is_none());
+    }
+
+    #[test]
+    /// Each allocation policy code is accepted and any other code is
+    /// rejected
+    fn allocation_policy_codes() {
+        assert!(AllocationPolicy::iter_variants()
+                    .all(|p| allocation_policy_from_code(p.into()) == Some(p)));
+        assert!(allocation_policy_from_code(3).is_none());
+    }
+
+    #[test]
+    /// Adding devices one at a time adds the good ones and reports each
+    /// of the others separately
+    fn add_blockdevs_each_reported() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("name", &[Path::new("/s/a")], &[], None, false)
+            .unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+
+        let results = add_blockdevs_each(pool,
+                                         &[Path::new("/s/b"), Path::new("/s/a"), Path::new("c")],
+                                         false);
+        assert!(results.iter().map(|&(ref p, _)| p.clone()).collect::<Vec<_>>() ==
+                vec![PathBuf::from("/s/b"), PathBuf::from("/s/a"), PathBuf::from("c")]);
+        assert!(results[0].1.is_ok());
+        assert!(match results[1].1 {
+                    Err(EngineError::Engine(ErrorEnum::AlreadyExists, _)) => true,
+                    _ => false,
+                });
+        assert!(match results[2].1 {
+                    Err(EngineError::Engine(ErrorEnum::Invalid, _)) => true,
+                    _ => false,
+                });
+        assert!(pool.blockdevs().len() == 2);
+    }
+}
@@ -3,19 +3,40 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::collections::vec_deque::{Drain, VecDeque};
 use std::convert::From;
+use std::error::Error;
 use std::rc::Rc;
+use std::sync::{Arc, RwLock};
 
-use dbus::Path;
+use dbus::{Message, MessageItem, Path};
 use dbus::tree::{DataType, MTFn, ObjectPath};
 
+use log::{LogLevelFilter, MaxLogLevelFilter, max_log_level};
+
+use time;
+
 use uuid::Uuid;
 
-use engine::Engine;
+use engine::{Engine, EngineError, EngineResult, Redundancy};
+
+use super::util::{STRATIS_BASE_PATH, STRATIS_BASE_SERVICE};
+
+/// The number of entries retained by the audit log if not otherwise
+/// configured.
+pub const DEFAULT_AUDIT_LOG_SIZE: usize = 256;
+
+/// The number of entries retained by the error log if not otherwise
+/// configured.
+pub const DEFAULT_ERROR_LOG_SIZE: usize = 64;
+
+/// The number of cancellations remembered for operations which have not
+/// yet been called; the oldest is forgotten to make room for a new one.
+pub const MAX_CANCELLED: usize = 64;
 
 custom_derive! {
-    #[derive(Copy, Clone, EnumDisplay,
+    #[derive(Copy, Clone, Debug, Eq, PartialEq, EnumDisplay,
              IterVariants(StratisDBusErrorVariants),
              IterVariantNames(StratisDBusErrorVariantNames))]
     #[allow(non_camel_case_types)]
@@ -29,6 +50,9 @@ custom_derive! {
         INTERNAL_ERROR,
         NIX_ERROR,
         NOTFOUND,
+        PERMISSION_DENIED,
+        TIMED_OUT,
+        NO_SPACE,
     }
 }
 
@@ -50,6 +74,9 @@ impl DbusErrorEnum {
             DbusErrorEnum::IO_ERROR => "IO error during operation",
             DbusErrorEnum::NIX_ERROR => "System error during operation",
             DbusErrorEnum::NOTFOUND => "Not found",
+            DbusErrorEnum::PERMISSION_DENIED => "Permission denied",
+            DbusErrorEnum::TIMED_OUT => "Operation timed out",
+            DbusErrorEnum::NO_SPACE => "Not enough space in pool",
         }
     }
 }
@@ -81,16 +108,38 @@ impl OPContext {
 #[derive(Debug, Clone)]
 pub struct DbusContext {
     pub next_index: Rc<Cell<u64>>,
-    pub engine: Rc<RefCell<Engine>>,
+    /// Handlers that only read engine state take the read lock, so that
+    /// any number of them may hold the engine at once; handlers that
+    /// change it take the write lock, which excludes all others.
+    pub engine: Arc<RwLock<Engine>>,
     pub actions: Rc<RefCell<ActionQueue>>,
+    pub audit_log: Rc<RefCell<AuditLog>>,
+    pub error_log: Rc<RefCell<ErrorLog>>,
+    pub default_redundancy: Rc<Cell<u16>>,
+    pub pending: Rc<RefCell<Vec<PendingOperation>>>,
+    pub object_paths: Rc<RefCell<HashMap<Path<'static>, Uuid>>>,
+    pub signals: Rc<RefCell<SignalQueue>>,
+    pub cancelled: Rc<RefCell<VecDeque<String>>>,
+    pub log_level: Rc<LogLevel>,
+    /// The bus name under which stratisd is registered.
+    pub service_name: Rc<String>,
 }
 
 impl DbusContext {
-    pub fn new(engine: Rc<RefCell<Engine>>) -> DbusContext {
+    pub fn new(engine: Arc<RwLock<Engine>>, audit_log_size: usize) -> DbusContext {
         DbusContext {
             actions: Rc::new(RefCell::new(ActionQueue::default())),
+            audit_log: Rc::new(RefCell::new(AuditLog::new(audit_log_size))),
+            error_log: Rc::new(RefCell::new(ErrorLog::new(DEFAULT_ERROR_LOG_SIZE))),
+            pending: Rc::new(RefCell::new(Vec::new())),
+            default_redundancy: Rc::new(Cell::new(Redundancy::NONE.into())),
             engine: engine,
             next_index: Rc::new(Cell::new(0)),
+            object_paths: Rc::new(RefCell::new(HashMap::new())),
+            signals: Rc::new(RefCell::new(SignalQueue::default())),
+            cancelled: Rc::new(RefCell::new(VecDeque::with_capacity(MAX_CANCELLED))),
+            log_level: Rc::new(LogLevel::new(None)),
+            service_name: Rc::new(STRATIS_BASE_SERVICE.into()),
         }
     }
 
@@ -102,6 +151,144 @@ impl DbusContext {
         self.next_index.set(self.next_index.get() + 1);
         self.next_index.get()
     }
+
+    /// Queue object_path to be added to the tree, and remember the UUID of
+    /// the object it represents.
+    pub fn push_add(&self, object_path: ObjectPath<MTFn<TData>, TData>) {
+        if let Some(ref op_context) = *object_path.get_data() {
+            self.object_paths
+                .borrow_mut()
+                .insert(object_path.get_name().clone(), op_context.uuid);
+        }
+        self.signals
+            .borrow_mut()
+            .object_added(object_path.get_name().clone());
+        self.actions.borrow_mut().push_add(object_path)
+    }
+
+    /// The object path of the object with this UUID, if it has one.
+    pub fn object_path_for(&self, uuid: &Uuid) -> Option<Path<'static>> {
+        self.object_paths
+            .borrow()
+            .iter()
+            .find(|&(_, u)| u == uuid)
+            .map(|(path, _)| path.clone())
+    }
+
+    /// Set the redundancy with which pools are created when the client
+    /// does not give one to that whose code is code.
+    /// Returns an error if code does not correspond to any redundancy.
+    pub fn set_default_redundancy(&self, code: u16) -> Result<(), String> {
+        if Redundancy::iter_variants().nth(code as usize).is_none() {
+            return Err(format!("code {} does not correspond to any redundancy", code));
+        }
+        self.default_redundancy.set(code);
+        Ok(())
+    }
+
+    /// Queue object_path to be removed from the tree, and forget it.
+    pub fn push_remove(&self, object_path: Path<'static>) {
+        self.object_paths.borrow_mut().remove(&object_path);
+        self.signals.borrow_mut().object_removed(object_path.clone());
+        self.actions.borrow_mut().push_remove(object_path)
+    }
+
+    /// Cancel the operation identified by op_id, the correlation ID which
+    /// the client will supply when it calls the operation.
+    /// At most MAX_CANCELLED cancellations are remembered.
+    pub fn cancel(&self, op_id: &str) {
+        let mut cancelled = self.cancelled.borrow_mut();
+        if cancelled.iter().any(|id| id == op_id) {
+            return;
+        }
+        if cancelled.len() == MAX_CANCELLED {
+            cancelled.pop_front();
+        }
+        cancelled.push_back(op_id.to_owned());
+    }
+
+    /// Whether the operation identified by op_id has been cancelled.
+    /// A cancellation applies to one operation only, and is forgotten
+    /// once it has been checked for.
+    pub fn take_cancelled(&self, op_id: &str) -> bool {
+        let mut cancelled = self.cancelled.borrow_mut();
+        match cancelled.iter().position(|id| id == op_id) {
+            Some(index) => cancelled.remove(index).is_some(),
+            None => false,
+        }
+    }
+
+    /// Record a mutating operation and its outcome in the audit log and,
+    /// if it failed, in the error log, together with the object on which
+    /// the operation in progress was invoked.
+    pub fn record_operation<T>(&self, operation: String, result: &EngineResult<T>) {
+        if let Err(ref err) = *result {
+            let target = self.pending
+                .borrow()
+                .last()
+                .map_or_else(String::new, |p| p.target.clone());
+            self.error_log
+                .borrow_mut()
+                .record(operation.clone(), target, err);
+        }
+        self.audit_log.borrow_mut().record(operation, result);
+    }
+
+    /// Mark the engine busy with the mutating operation, invoked on the
+    /// object target, until the returned guard is dropped.
+    /// Returns None if a mutating operation is already in progress.
+    pub fn begin_operation(&self, operation: &str, target: &str) -> Option<OperationGuard> {
+        if !self.pending.borrow().is_empty() {
+            return None;
+        }
+        self.pending
+            .borrow_mut()
+            .push(PendingOperation {
+                      operation: operation.to_owned(),
+                      target: target.to_owned(),
+                      started_at: time::now().to_timespec().sec as u64,
+                  });
+        Some(OperationGuard { pending: Rc::clone(&self.pending) })
+    }
+
+    /// Begin the mutating operation, as begin_operation() does, unless the
+    /// client has cancelled it by means of its correlation ID, op_id.
+    /// Returns the code and message with which to refuse the operation if
+    /// it was cancelled or if another is in progress.
+    pub fn begin_requested_operation(&self,
+                                     op_id: Option<&str>,
+                                     operation: &str,
+                                     target: &str)
+                                     -> Result<OperationGuard, (DbusErrorEnum, String)> {
+        if let Some(op_id) = op_id {
+            if self.take_cancelled(op_id) {
+                return Err((DbusErrorEnum::ERROR, format!("operation {} cancelled", op_id)));
+            }
+        }
+        self.begin_operation(operation, target)
+            .ok_or_else(|| (DbusErrorEnum::BUSY, "operation in progress".into()))
+    }
+}
+
+/// A mutating operation which is in progress.
+#[derive(Debug, Clone)]
+pub struct PendingOperation {
+    pub operation: String,
+    pub target: String,
+    /// Seconds since the Unix epoch
+    pub started_at: u64,
+}
+
+/// Keeps the engine marked busy for as long as it is alive.
+#[derive(Debug)]
+pub struct OperationGuard {
+    pending: Rc<RefCell<Vec<PendingOperation>>>,
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        self.pending.borrow_mut().pop();
+    }
 }
 
 #[derive(Default, Debug)]
@@ -139,3 +326,440 @@ impl ActionQueue {
         self.queue.drain(..)
     }
 }
+
+/// The signals announcing that objects have been added or removed, waiting
+/// to be sent.
+/// While the queue is paused, additions and removals are only remembered;
+/// when it is resumed, they are announced together by a single BulkChange
+/// signal.
+#[derive(Debug, Default)]
+pub struct SignalQueue {
+    paused: bool,
+    added: Vec<Path<'static>>,
+    removed: Vec<Path<'static>>,
+    queue: VecDeque<Message>,
+}
+
+impl SignalQueue {
+    fn signal(member: &str) -> Message {
+        Message::new_signal(STRATIS_BASE_PATH,
+                            format!("{}.{}", STRATIS_BASE_SERVICE, "Manager.r1"),
+                            member)
+                .expect("arguments are valid D-Bus names")
+    }
+
+    /// Announce that object_path has been added.
+    pub fn object_added(&mut self, object_path: Path<'static>) {
+        if self.paused {
+            self.added.push(object_path);
+        } else {
+            let signal = SignalQueue::signal("ObjectAdded").append1(object_path);
+            self.queue.push_back(signal);
+        }
+    }
+
+    /// Announce that object_path has been removed.
+    pub fn object_removed(&mut self, object_path: Path<'static>) {
+        if self.paused {
+            self.removed.push(object_path);
+        } else {
+            let signal = SignalQueue::signal("ObjectRemoved").append1(object_path);
+            self.queue.push_back(signal);
+        }
+    }
+
+    /// Stop announcing additions and removals as they happen.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Announce all additions and removals since the queue was paused, if
+    /// there were any, and announce subsequent ones as they happen.
+    pub fn resume(&mut self) {
+        self.paused = false;
+        if self.added.is_empty() && self.removed.is_empty() {
+            return;
+        }
+        let added = self.added.drain(..).map(MessageItem::ObjectPath).collect();
+        let removed = self.removed
+            .drain(..)
+            .map(MessageItem::ObjectPath)
+            .collect();
+        let signal = SignalQueue::signal("BulkChange")
+            .append2(MessageItem::Array(added, "o".into()),
+                     MessageItem::Array(removed, "o".into()));
+        self.queue.push_back(signal);
+    }
+
+    /// Announce the full current inventory of objects, each given by its
+    /// object path, kind, and name. The inventory is announced even while
+    /// the queue is paused, since it reflects any pending changes.
+    pub fn state_sync(&mut self, objects: Vec<(Path<'static>, String, String)>) {
+        let objects = objects
+            .into_iter()
+            .map(|(object_path, kind, name)| {
+                     MessageItem::Struct(vec![MessageItem::ObjectPath(object_path),
+                                              MessageItem::Str(kind),
+                                              MessageItem::Str(name)])
+                 })
+            .collect();
+        let signal = SignalQueue::signal("StateSync")
+            .append1(MessageItem::Array(objects, "(oss)".into()));
+        self.queue.push_back(signal);
+    }
+
+    /// Drain the signals ready to be sent.
+    pub fn drain(&mut self) -> Drain<Message> {
+        self.queue.drain(..)
+    }
+}
+
+/// A single entry in the audit log.
+#[derive(Debug)]
+pub struct AuditEntry {
+    pub timestamp: u64,
+    pub operation: String,
+    pub result: String,
+}
+
+/// A bounded log of the mutating operations which have failed, for
+/// diagnosing failures which are intermittent. Once the log holds capacity
+/// entries, recording a new entry discards the oldest one.
+#[derive(Debug)]
+pub struct ErrorLog {
+    capacity: usize,
+    entries: VecDeque<ErrorEntry>,
+}
+
+impl ErrorLog {
+    pub fn new(capacity: usize) -> ErrorLog {
+        ErrorLog {
+            capacity: capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Record the failure of an operation invoked on target, stamped with
+    /// the current time.
+    pub fn record(&mut self, operation: String, target: String, err: &EngineError) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries
+            .push_back(ErrorEntry {
+                           timestamp: time::now().to_timespec().sec as u64,
+                           operation: operation,
+                           target: target,
+                           error: err.description().to_owned(),
+                       })
+    }
+
+    /// The most recent count entries, oldest first.
+    pub fn last(&self, count: usize) -> Vec<&ErrorEntry> {
+        let skip = self.entries.len().saturating_sub(count);
+        self.entries.iter().skip(skip).collect()
+    }
+}
+
+/// A single entry in the error log.
+#[derive(Debug)]
+pub struct ErrorEntry {
+    pub timestamp: u64,
+    pub operation: String,
+    pub target: String,
+    pub error: String,
+}
+
+/// The most verbose level of message which stratisd logs. If it holds the
+/// logger's handle on the maximum level, setting the level changes which
+/// messages are logged while stratisd runs.
+#[derive(Debug)]
+pub struct LogLevel {
+    filter: Option<MaxLogLevelFilter>,
+    level: Cell<LogLevelFilter>,
+}
+
+impl LogLevel {
+    pub fn new(filter: Option<MaxLogLevelFilter>) -> LogLevel {
+        let level = filter.as_ref().map_or_else(max_log_level, |f| f.get());
+        LogLevel {
+            filter: filter,
+            level: Cell::new(level),
+        }
+    }
+
+    /// The name of the level, e.g., "debug".
+    pub fn get(&self) -> String {
+        self.level.get().to_string().to_lowercase()
+    }
+
+    /// Set the level by name: one of "error", "warn", "info", "debug",
+    /// and "trace". Returns an error message if name is none of these.
+    pub fn set(&self, name: &str) -> Result<(), String> {
+        let level = match name {
+            "error" => LogLevelFilter::Error,
+            "warn" => LogLevelFilter::Warn,
+            "info" => LogLevelFilter::Info,
+            "debug" => LogLevelFilter::Debug,
+            "trace" => LogLevelFilter::Trace,
+            _ => return Err(format!("{} is not a log level", name)),
+        };
+        if let Some(ref filter) = self.filter {
+            filter.set(level);
+        }
+        self.level.set(level);
+        Ok(())
+    }
+}
+
+/// A bounded log of the mutating operations performed by the engine.
+/// Once the log holds capacity entries, recording a new entry discards
+/// the oldest one.
+#[derive(Debug)]
+pub struct AuditLog {
+    capacity: usize,
+    entries: VecDeque<AuditEntry>,
+}
+
+impl AuditLog {
+    pub fn new(capacity: usize) -> AuditLog {
+        AuditLog {
+            capacity: capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Record an operation and its outcome, stamped with the current time.
+    pub fn record<T>(&mut self, operation: String, result: &EngineResult<T>) {
+        let result = match *result {
+            Ok(_) => "Ok".into(),
+            Err(ref err) => err.description().to_owned(),
+        };
+        let timestamp = time::now().to_timespec().sec as u64;
+        self.push(AuditEntry {
+                      timestamp: timestamp,
+                      operation: operation,
+                      result: result,
+                  })
+    }
+
+    /// Push an entry onto the back of the log, evicting from the front
+    /// if the log is full.
+    pub fn push(&mut self, entry: AuditEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry)
+    }
+
+    /// The most recent count entries, oldest first.
+    pub fn last(&self, count: usize) -> Vec<&AuditEntry> {
+        let skip = self.entries.len().saturating_sub(count);
+        self.entries.iter().skip(skip).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::{Arc, RwLock};
+
+    use engine::{EngineError, EngineResult, ErrorEnum, SimEngine};
+
+    use super::{AuditLog, DbusContext, DbusErrorEnum, ErrorLog, LogLevel, MAX_CANCELLED};
+
+    #[test]
+    /// Entries are returned in the order they were recorded.
+    fn audit_log_ordered() {
+        let mut log = AuditLog::new(10);
+        log.record("CreatePool p".into(), &Ok(()) as &EngineResult<()>);
+        log.record("CreateFilesystems p".into(), &Ok(()) as &EngineResult<()>);
+        log.record("DestroyPool p".into(),
+                   &Err(EngineError::Engine(ErrorEnum::Busy, "busy".into())) as
+                   &EngineResult<()>);
+
+        let entries = log.last(10);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].operation, "CreatePool p");
+        assert_eq!(entries[1].operation, "CreateFilesystems p");
+        assert_eq!(entries[2].operation, "DestroyPool p");
+        assert_eq!(entries[0].result, "Ok");
+        assert_eq!(entries[2].result, "busy");
+        assert!(entries.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+    }
+
+    #[test]
+    /// Only the most recent entries are retained, and only the most
+    /// recent entries requested are returned.
+    fn audit_log_bounded() {
+        let mut log = AuditLog::new(2);
+        for name in &["a", "b", "c"] {
+            log.record(format!("CreatePool {}", name), &Ok(()) as &EngineResult<()>);
+        }
+
+        let entries = log.last(10);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].operation, "CreatePool b");
+        assert_eq!(entries[1].operation, "CreatePool c");
+
+        let entries = log.last(1);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].operation, "CreatePool c");
+    }
+
+    #[test]
+    /// Only failed operations are entered in the error log, in the order
+    /// in which they failed, with the objects on which they were invoked
+    fn error_log_ordered() {
+        let dbus_context = DbusContext::new(Arc::new(RwLock::new(SimEngine::default())), 10);
+        let failures = [("DestroyPool p", "/pool", ErrorEnum::Busy),
+                        ("SetName fs", "/fs", ErrorEnum::AlreadyExists),
+                        ("AddDevs p", "/pool", ErrorEnum::Invalid)];
+        for &(operation, target, ref kind) in &failures {
+            let _guard = dbus_context.begin_operation(operation, target).unwrap();
+            dbus_context.record_operation("CreatePool q".into(), &Ok(()) as &EngineResult<()>);
+            let err = EngineError::Engine(kind.clone(), operation.into());
+            dbus_context.record_operation(operation.into(), &Err(err) as &EngineResult<()>);
+        }
+
+        let entries = dbus_context.error_log.borrow();
+        let entries = entries.last(10);
+        assert_eq!(entries.len(), 3);
+        for (entry, &(operation, target, _)) in entries.iter().zip(failures.iter()) {
+            assert_eq!(entry.operation, operation);
+            assert_eq!(entry.target, target);
+            assert_eq!(entry.error, operation);
+        }
+        assert_eq!(dbus_context.audit_log.borrow().last(10).len(), 6);
+
+        let mut log = ErrorLog::new(1);
+        log.record("a".into(), "/a".into(), &EngineError::Engine(ErrorEnum::Busy, "a".into()));
+        log.record("b".into(), "/b".into(), &EngineError::Engine(ErrorEnum::Busy, "b".into()));
+        assert_eq!(log.last(10).len(), 1);
+        assert_eq!(log.last(10)[0].operation, "b");
+    }
+
+    #[test]
+    /// A level which is set is read back, and an unknown level is rejected
+    /// without changing the level.
+    fn log_level_set() {
+        let log_level = LogLevel::new(None);
+        assert!(log_level.set("trace").is_ok());
+        assert_eq!(log_level.get(), "trace");
+        assert!(log_level.set("warn").is_ok());
+        assert_eq!(log_level.get(), "warn");
+        assert!(log_level.set("off").is_err());
+        assert!(log_level.set("loud").is_err());
+        assert_eq!(log_level.get(), "warn");
+    }
+
+    #[test]
+    /// Readers of the engine do not exclude one another, but do exclude
+    /// a writer.
+    fn engine_shared_reads() {
+        let dbus_context = DbusContext::new(Arc::new(RwLock::new(SimEngine::default())), 0);
+        {
+            let first = dbus_context.engine.read().unwrap();
+            let second = dbus_context.engine.try_read();
+            assert!(second.is_ok());
+            assert!(dbus_context.engine.try_write().is_err());
+            assert_eq!(first.pools().len(), second.unwrap().pools().len());
+        }
+        assert!(dbus_context.engine.try_write().is_ok());
+    }
+
+    #[test]
+    /// A cancelled operation is found to be cancelled exactly once.
+    fn cancelled_operation() {
+        let dbus_context = DbusContext::new(Arc::new(RwLock::new(SimEngine::default())), 0);
+        assert!(!dbus_context.take_cancelled("op-1"));
+        dbus_context.cancel("op-1");
+        assert!(!dbus_context.take_cancelled("op-2"));
+        assert!(dbus_context.take_cancelled("op-1"));
+        assert!(!dbus_context.take_cancelled("op-1"));
+    }
+
+    #[test]
+    /// Only the most recent cancellations are remembered.
+    fn cancelled_bounded() {
+        let dbus_context = DbusContext::new(Arc::new(RwLock::new(SimEngine::default())), 0);
+        for i in 0..MAX_CANCELLED + 1 {
+            dbus_context.cancel(&format!("op-{}", i));
+        }
+        dbus_context.cancel(&format!("op-{}", MAX_CANCELLED));
+        assert_eq!(dbus_context.cancelled.borrow().len(), MAX_CANCELLED);
+        assert!(!dbus_context.take_cancelled("op-0"));
+        assert!(dbus_context.take_cancelled("op-1"));
+        assert!(dbus_context.take_cancelled(&format!("op-{}", MAX_CANCELLED)));
+    }
+
+    #[test]
+    /// A cancelled operation is refused; a second request of it is not
+    /// cancelled.
+    fn cancelled_operation_refused() {
+        let dbus_context = DbusContext::new(Arc::new(RwLock::new(SimEngine::default())), 0);
+        dbus_context.cancel("op-1");
+
+        match dbus_context.begin_requested_operation(Some("op-1"), "CreatePool", "/") {
+            Err((code, message)) => {
+                assert_eq!(code, DbusErrorEnum::ERROR);
+                assert_eq!(message, "operation op-1 cancelled");
+            }
+            Ok(_) => panic!("a cancelled operation began"),
+        }
+        assert!(dbus_context.pending.borrow().is_empty());
+
+        assert!(dbus_context
+                    .begin_requested_operation(Some("op-1"), "CreatePool", "/")
+                    .is_ok());
+    }
+
+    #[test]
+    /// A mutating operation can not begin while another is in progress,
+    /// and is refused as busy.
+    fn busy_while_operation_in_progress() {
+        let dbus_context = DbusContext::new(Arc::new(RwLock::new(SimEngine::default())), 0);
+
+        let guard = dbus_context.begin_operation("CreatePool", "/");
+        assert!(guard.is_some());
+        assert!(dbus_context.begin_operation("CreatePool", "/").is_none());
+
+        match dbus_context.begin_requested_operation(Some("op-1"), "CreatePool", "/") {
+            Err((code, message)) => {
+                assert_eq!(code, DbusErrorEnum::BUSY);
+                assert_eq!(message, "operation in progress");
+            }
+            Ok(_) => panic!("an operation began while another was in progress"),
+        }
+        assert_eq!(dbus_context.pending.borrow().len(), 1);
+
+        drop(guard);
+        assert!(dbus_context
+                    .begin_requested_operation(None, "CreatePool", "/")
+                    .is_ok());
+    }
+
+    #[test]
+    /// An operation is pending exactly while it is in progress.
+    fn pending_while_operation_in_progress() {
+        let dbus_context = DbusContext::new(Arc::new(RwLock::new(SimEngine::default())), 0);
+        assert!(dbus_context.pending.borrow().is_empty());
+
+        let guard = dbus_context.begin_operation("DestroyFilesystems", "/pool");
+        {
+            let pending = dbus_context.pending.borrow();
+            assert_eq!(pending.len(), 1);
+            assert_eq!(pending[0].operation, "DestroyFilesystems");
+            assert_eq!(pending[0].target, "/pool");
+            assert!(pending[0].started_at > 0);
+        }
+        drop(guard);
+        assert!(dbus_context.pending.borrow().is_empty());
+    }
+}
@@ -3,13 +3,18 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use std::error::Error;
+use std::io;
+use std::sync::LockResult;
 
 use dbus;
+use dbus::Message;
 use dbus::MessageItem;
 use dbus::arg::{ArgType, Iter, IterAppend};
 use dbus::tree::{MethodErr, MTFn, PropInfo};
 
-use engine::{EngineError, ErrorEnum};
+use uuid::Uuid;
+
+use engine::{EngineError, EngineResult, ErrorEnum};
 
 use super::types::{DbusErrorEnum, TData};
 
@@ -33,6 +38,50 @@ pub fn get_next_arg<'a, T>(iter: &mut Iter<'a>, loc: u16) -> Result<T, MethodErr
     Ok(value)
 }
 
+/// Get an iterator over the arguments of a mutating method that takes
+/// nargs arguments. A client may pass a correlation ID as an additional
+/// leading string argument; if so, it is returned along with an iterator
+/// positioned at the method's first argument.
+pub fn get_correlation_id<'a>(message: &'a Message,
+                              nargs: usize)
+                              -> Result<(Option<String>, Iter<'a>), MethodErr> {
+    let mut count = 0;
+    let mut counter = message.iter_init();
+    while counter.arg_type() != ArgType::Invalid {
+        count += 1;
+        counter.next();
+    }
+
+    let mut iter = message.iter_init();
+    if count == nargs + 1 {
+        let correlation_id: &str = try!(get_next_arg(&mut iter, 0));
+        Ok((Some(correlation_id.into()), iter))
+    } else {
+        Ok((None, iter))
+    }
+}
+
+/// Append the correlation ID, if the client supplied one, to a method return.
+pub fn echo_correlation_id(message: Message, correlation_id: Option<String>) -> Message {
+    match correlation_id {
+        Some(id) => message.append1(id),
+        None => message,
+    }
+}
+
+/// Log the outcome of a mutating operation, prefixed with the client's
+/// correlation ID if one was supplied.
+pub fn log_operation<T>(correlation_id: &Option<String>,
+                        operation: &str,
+                        result: &EngineResult<T>) {
+    let prefix = correlation_id
+        .as_ref()
+        .map_or_else(String::new, |id| format!("[{}] ", id));
+    match *result {
+        Ok(_) => info!("{}{}: Ok", prefix, operation),
+        Err(ref err) => info!("{}{}: {}", prefix, operation, err.description()),
+    }
+}
 
 /// Translates an engine error to a dbus error.
 pub fn engine_to_dbus_err(err: &EngineError) -> (DbusErrorEnum, String) {
@@ -44,9 +93,19 @@ pub fn engine_to_dbus_err(err: &EngineError) -> (DbusErrorEnum, String) {
                 ErrorEnum::Busy => DbusErrorEnum::BUSY,
                 ErrorEnum::Invalid => DbusErrorEnum::ERROR,
                 ErrorEnum::NotFound => DbusErrorEnum::NOTFOUND,
+                ErrorEnum::NoSpace => DbusErrorEnum::NO_SPACE,
+            }
+        }
+        EngineError::Io(ref e) => {
+            match e.kind() {
+                io::ErrorKind::NotFound => DbusErrorEnum::NOTFOUND,
+                io::ErrorKind::AlreadyExists => DbusErrorEnum::ALREADY_EXISTS,
+                io::ErrorKind::PermissionDenied => DbusErrorEnum::PERMISSION_DENIED,
+                io::ErrorKind::TimedOut => DbusErrorEnum::TIMED_OUT,
+                io::ErrorKind::InvalidInput => DbusErrorEnum::ERROR,
+                _ => DbusErrorEnum::IO_ERROR,
             }
         }
-        EngineError::Io(_) => DbusErrorEnum::IO_ERROR,
         EngineError::Nix(_) => DbusErrorEnum::NIX_ERROR,
         EngineError::Uuid(_) => DbusErrorEnum::INTERNAL_ERROR,
         EngineError::Utf8(_) => DbusErrorEnum::INTERNAL_ERROR,
@@ -68,10 +127,85 @@ pub fn ok_message_items() -> (MessageItem, MessageItem) {
     code_to_message_items(code, code.get_error_string().into())
 }
 
+/// The guard of lock, a lock on the engine, or, if a handler panicked
+/// while it held the write lock, the return code and string to reply with.
+pub fn engine_guard<G>(lock: LockResult<G>) -> Result<G, (MessageItem, MessageItem)> {
+    lock.map_err(|_| {
+                     code_to_message_items(DbusErrorEnum::INTERNAL_ERROR,
+                                           "engine lock poisoned".into())
+                 })
+}
+
+/// The largest array, in bytes, that D-Bus permits in a message.
+pub const MAX_ARRAY_SIZE: usize = 1 << 26;
+
+/// An upper bound on the number of bytes that item occupies when
+/// serialized, allowing for the worst case alignment padding.
+pub fn serialized_size(item: &MessageItem) -> usize {
+    match *item {
+        MessageItem::Array(ref items, _) => {
+            4 + 7 + items.iter().map(serialized_size).sum::<usize>()
+        }
+        MessageItem::Struct(ref items) => 7 + items.iter().map(serialized_size).sum::<usize>(),
+        MessageItem::Variant(ref item) => {
+            let signature = item.type_sig();
+            2 + signature.len() + serialized_size(item)
+        }
+        MessageItem::DictEntry(ref key, ref value) => {
+            7 + serialized_size(key) + serialized_size(value)
+        }
+        MessageItem::ObjectPath(ref path) => 4 + path.len() + 1 + 3,
+        MessageItem::Str(ref s) => 4 + s.len() + 1 + 3,
+        _ => 8,
+    }
+}
+
+/// Make an array of items with element signature sig, unless the array
+/// would exceed limit bytes when serialized, in which case return a
+/// message for the client explaining that the result was too large.
+pub fn bounded_array(items: Vec<MessageItem>,
+                     sig: &str,
+                     limit: usize)
+                     -> Result<MessageItem, String> {
+    let array = MessageItem::Array(items, sig.to_owned().into());
+    let size = serialized_size(&array);
+    if size > limit {
+        return Err(format!("result of about {} bytes exceeds the limit of {} bytes; \
+                            narrow the request to retrieve the result in smaller pages",
+                           size,
+                           limit));
+    }
+    Ok(array)
+}
+
+/// A reply to message carrying the array of items with element signature
+/// sig, or, if the array is too large to send, an empty array and an error.
+pub fn list_return(message: &Message, items: Vec<MessageItem>, sig: &str) -> Message {
+    let (array, rc, rs) = match bounded_array(items, sig, MAX_ARRAY_SIZE) {
+        Ok(array) => {
+            let (rc, rs) = ok_message_items();
+            (array, rc, rs)
+        }
+        Err(msg) => {
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::ERROR, msg);
+            (MessageItem::Array(vec![], sig.to_owned().into()), rc, rs)
+        }
+    };
+    message.method_return().append3(array, rc, rs)
+}
+
 pub fn default_object_path<'a>() -> dbus::Path<'a> {
     dbus::Path::new("/").expect("'/' is guaranteed to be a valid Path.")
 }
 
+/// The last element of the object path of the object with this UUID.
+/// Unlike an id from get_next_id(), it is the same every time the daemon
+/// starts. The UUID's simple form contains only hex digits, all of which
+/// are legal in an object path element.
+pub fn uuid_to_path_element(uuid: &Uuid) -> String {
+    uuid.simple().to_string()
+}
+
 /// Get the UUID for an object path.
 pub fn get_uuid(i: &mut IterAppend, p: &PropInfo<MTFn<TData>, TData>) -> Result<(), MethodErr> {
     let object_path = p.path.get_name();
@@ -108,3 +242,116 @@ pub fn get_parent(i: &mut IterAppend, p: &PropInfo<MTFn<TData>, TData>) -> Resul
     i.append(MessageItem::ObjectPath(data.parent.clone()));
     Ok(())
 }
+
+/// A message to stand in for a method return in tests; a real one can only
+/// be made from a call that has been assigned a serial by the bus.
+#[cfg(test)]
+pub fn test_reply() -> Message {
+    Message::new_signal(STRATIS_BASE_PATH, STRATIS_BASE_SERVICE, "Reply")
+        .expect("arguments are valid D-Bus names")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use dbus::Message;
+    use dbus::arg::Array;
+
+    use engine::EngineError;
+
+    use super::*;
+
+    fn add_devs_call() -> Message {
+        Message::new_method_call(STRATIS_BASE_SERVICE,
+                                 STRATIS_BASE_PATH,
+                                 "org.storage.stratis1.pool",
+                                 "AddDevs")
+                .expect("arguments are valid D-Bus names")
+    }
+
+    #[test]
+    /// The path element for a UUID forms a legal object path.
+    fn uuid_path_element_legal() {
+        let uuid = Uuid::new_v4();
+        let element = uuid_to_path_element(&uuid);
+        assert!(dbus::Path::new(format!("{}/{}", STRATIS_BASE_PATH, element)).is_ok());
+        assert!(Uuid::parse_str(&element).unwrap() == uuid);
+    }
+
+    #[test]
+    /// A leading correlation ID is stripped from the arguments and echoed
+    /// back at the end of the reply.
+    fn correlation_id_echoed() {
+        let message = add_devs_call().append3("trace-42", false, vec!["/dev/a"]);
+        let (correlation_id, mut iter) = get_correlation_id(&message, 2).unwrap();
+        assert_eq!(correlation_id, Some("trace-42".into()));
+
+        let force: bool = get_next_arg(&mut iter, 0).unwrap();
+        let devs: Array<&str, _> = get_next_arg(&mut iter, 1).unwrap();
+        assert!(!force);
+        assert_eq!(devs.collect::<Vec<&str>>(), vec!["/dev/a"]);
+
+        let reply = echo_correlation_id(test_reply().append1(true),
+                                        correlation_id);
+        let items = reply.get_items();
+        assert_eq!(items.last(), Some(&MessageItem::Str("trace-42".into())));
+    }
+
+    #[test]
+    /// Without a correlation ID, arguments and reply are unchanged.
+    fn no_correlation_id() {
+        let message = add_devs_call().append2(false, vec!["/dev/a"]);
+        let (correlation_id, mut iter) = get_correlation_id(&message, 2).unwrap();
+        assert_eq!(correlation_id, None);
+
+        let force: bool = get_next_arg(&mut iter, 0).unwrap();
+        assert!(!force);
+
+        let reply = echo_correlation_id(test_reply().append1(true),
+                                        correlation_id);
+        assert_eq!(reply.get_items(), vec![MessageItem::Bool(true)]);
+    }
+
+    #[test]
+    /// An array too large to send is refused with advice to narrow the
+    /// request, while a small one is returned unchanged.
+    fn bounded_array_limit() {
+        let items = (0..1000)
+            .map(|i| MessageItem::Str(format!("pool-{}", i)))
+            .collect::<Vec<_>>();
+        let size = serialized_size(&MessageItem::Array(items.clone(), "s".into()));
+        assert!(size > 1000 * "pool-0".len());
+
+        let err = bounded_array(items.clone(), "s", size - 1).unwrap_err();
+        assert!(err.contains("smaller pages"));
+
+        match bounded_array(items, "s", MAX_ARRAY_SIZE).unwrap() {
+            MessageItem::Array(items, _) => assert_eq!(items.len(), 1000),
+            _ => panic!("expected an array"),
+        }
+    }
+
+    #[test]
+    /// Each interesting io::ErrorKind maps to its own D-Bus error code.
+    fn io_error_kinds() {
+        let io_code = |kind| engine_to_dbus_err(&EngineError::Io(io::Error::new(kind, "x"))).0;
+        assert_eq!(io_code(io::ErrorKind::NotFound), DbusErrorEnum::NOTFOUND);
+        assert_eq!(io_code(io::ErrorKind::AlreadyExists),
+                   DbusErrorEnum::ALREADY_EXISTS);
+        assert_eq!(io_code(io::ErrorKind::PermissionDenied),
+                   DbusErrorEnum::PERMISSION_DENIED);
+        assert_eq!(io_code(io::ErrorKind::TimedOut), DbusErrorEnum::TIMED_OUT);
+        assert_eq!(io_code(io::ErrorKind::InvalidInput), DbusErrorEnum::ERROR);
+        assert_eq!(io_code(io::ErrorKind::BrokenPipe), DbusErrorEnum::IO_ERROR);
+    }
+
+    #[test]
+    /// A lack of space has its own D-Bus error code.
+    fn no_space_error() {
+        let err = EngineError::Engine(ErrorEnum::NoSpace, "x".into());
+        assert_eq!(engine_to_dbus_err(&err).0, DbusErrorEnum::NO_SPACE);
+        assert_eq!(engine_to_dbus_err(&EngineError::Engine(ErrorEnum::Error, "x".into())).0,
+                   DbusErrorEnum::ERROR);
+    }
+}
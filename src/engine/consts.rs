@@ -2,6 +2,25 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use devicemapper::{Bytes, Sectors};
+use devicemapper::consts::SECTOR_SIZE;
+
+/// The number of filesystems a pool may hold if not otherwise configured.
+pub const DEFAULT_MAX_FILESYSTEMS: u32 = 1000;
+
+/// The number of snapshots of any one filesystem which a pool may hold if
+/// not otherwise configured.
+pub const DEFAULT_MAX_SNAPSHOTS: u32 = 100;
+
+/// The extent size of a newly created pool.
+pub const DEFAULT_EXTENT_SIZE: Sectors = Sectors(IEC::Mi / (SECTOR_SIZE as u64)); // = 1 MiB
+
+/// The smallest device which may be made a blockdev.
+pub const MIN_DEV_SIZE: Bytes = Bytes(IEC::Gi);
+
+/// The greatest number of characters in a pool's description.
+pub const MAX_DESCRIPTION_LEN: usize = 1024;
+
 #[allow(non_upper_case_globals)]
 #[allow(non_snake_case)]
 pub mod IEC {
@@ -0,0 +1,310 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// A comparison of a pool with a specification of the pool as it is
+// desired to be, so that a configuration management tool can tell what
+// must change for the pool to converge on the specification. A field
+// omitted from the specification is not compared. Like the *Topology
+// structs, the *Diff structs contain only serde-friendly data types and
+// are constructed by means of the Pool trait.
+
+use std::path::PathBuf;
+
+use devicemapper::Sectors;
+
+use serde_json;
+
+use super::engine::Pool;
+use super::errors::{EngineError, EngineResult, ErrorEnum};
+use super::types::BlockDevTier;
+
+/// The version of the format of a diff. It changes whenever a field is
+/// added, removed, or changes its meaning.
+pub const DIFF_VERSION: u32 = 1;
+
+/// The desired state of a pool. The names of enumerated properties, e.g.,
+/// the compression, are compared ignoring case.
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PoolSpec {
+    pub devices: Option<Vec<PathBuf>>,
+    pub cache_devices: Option<Vec<PathBuf>>,
+    pub spares: Option<Vec<PathBuf>>,
+    pub filesystems: Option<Vec<FilesystemSpec>>,
+    pub redundancy: Option<String>,
+    pub cache_mode: Option<String>,
+    pub compression: Option<String>,
+    pub allocation_policy: Option<String>,
+    pub description: Option<String>,
+    pub max_filesystems: Option<u32>,
+    pub max_snapshots: Option<u32>,
+    pub reserved_free: Option<Sectors>,
+    pub usage_alarm: Option<u16>,
+}
+
+/// A desired filesystem; if size is omitted, its size is not compared.
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FilesystemSpec {
+    pub name: String,
+    pub size: Option<Sectors>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolDiff {
+    pub version: u32,
+    pub name: String,
+    pub devices: DeviceDiff,
+    pub cache_devices: DeviceDiff,
+    pub spares: DeviceDiff,
+    pub filesystems: FilesystemDiff,
+    pub size_deltas: Vec<SizeDelta>,
+    pub properties: Vec<PropertyDiff>,
+}
+
+/// The devices of one tier to add to and remove from the pool, in order.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceDiff {
+    pub to_add: Vec<PathBuf>,
+    pub to_remove: Vec<PathBuf>,
+}
+
+/// The names of the filesystems to create and destroy, in order.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilesystemDiff {
+    pub to_create: Vec<String>,
+    pub to_destroy: Vec<String>,
+}
+
+/// A filesystem whose size is not the size desired.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SizeDelta {
+    pub filesystem: String,
+    pub actual: Sectors,
+    pub desired: Sectors,
+}
+
+/// A property whose value is not the value desired.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PropertyDiff {
+    pub property: String,
+    pub actual: String,
+    pub desired: String,
+}
+
+impl PoolSpec {
+    /// The specification in the JSON string json.
+    pub fn from_json(json: &str) -> EngineResult<PoolSpec> {
+        serde_json::from_str(json).map_err(|e| {
+            EngineError::Engine(ErrorEnum::Invalid, format!("malformed pool spec: {}", e))
+        })
+    }
+}
+
+/// The devices to add to and remove from actual, the devices of the pool
+/// in some tier, for it to hold just the devices desired, if specified.
+fn device_diff(mut actual: Vec<PathBuf>, desired: &Option<Vec<PathBuf>>) -> DeviceDiff {
+    let desired = match *desired {
+        Some(ref desired) => desired,
+        None => return DeviceDiff::default(),
+    };
+    actual.sort();
+    let mut to_add = desired
+        .iter()
+        .filter(|d| !actual.contains(d))
+        .cloned()
+        .collect::<Vec<_>>();
+    to_add.sort();
+    to_add.dedup();
+    DeviceDiff {
+        to_add: to_add,
+        to_remove: actual.into_iter().filter(|d| !desired.contains(d)).collect(),
+    }
+}
+
+/// Add to properties the difference, if any, between the actual value of
+/// property and the desired value, if specified.
+fn property_diff(properties: &mut Vec<PropertyDiff>,
+                 property: &str,
+                 actual: String,
+                 desired: Option<String>,
+                 ignore_case: bool) {
+    if let Some(desired) = desired {
+        let same = if ignore_case {
+            actual.eq_ignore_ascii_case(&desired)
+        } else {
+            actual == desired
+        };
+        if !same {
+            properties.push(PropertyDiff {
+                                property: property.to_owned(),
+                                actual: actual,
+                                desired: desired,
+                            });
+        }
+    }
+}
+
+impl PoolDiff {
+    /// The differences between pool and spec. Properties are listed in the
+    /// order of the fields of PoolSpec, filesystems in order of name.
+    pub fn new(pool: &Pool, spec: &PoolSpec) -> PoolDiff {
+        let blockdevs = pool.blockdevs();
+        let tier_devices = |tier| {
+            blockdevs
+                .iter()
+                .filter(|&&(t, _)| t == tier)
+                .map(|&(_, bd)| bd.devnode())
+                .collect::<Vec<_>>()
+        };
+
+        let mut filesystems = FilesystemDiff::default();
+        let mut size_deltas = Vec::new();
+        if let Some(ref desired) = spec.filesystems {
+            let actual = pool.filesystems();
+            for fs_spec in desired {
+                match actual.iter().find(|f| f.name() == fs_spec.name) {
+                    Some(filesystem) => {
+                        match fs_spec.size {
+                            Some(size) if size != filesystem.size() => {
+                                size_deltas.push(SizeDelta {
+                                                     filesystem: fs_spec.name.clone(),
+                                                     actual: filesystem.size(),
+                                                     desired: size,
+                                                 })
+                            }
+                            _ => {}
+                        }
+                    }
+                    None => filesystems.to_create.push(fs_spec.name.clone()),
+                }
+            }
+            filesystems.to_destroy = actual
+                .iter()
+                .filter(|f| !desired.iter().any(|d| d.name == f.name()))
+                .map(|f| f.name().to_owned())
+                .collect();
+            filesystems.to_create.sort();
+            filesystems.to_destroy.sort();
+            size_deltas.sort_by(|a, b| a.filesystem.cmp(&b.filesystem));
+        }
+
+        let mut properties = Vec::new();
+        property_diff(&mut properties,
+                      "redundancy",
+                      pool.redundancy().to_string(),
+                      spec.redundancy.clone(),
+                      true);
+        property_diff(&mut properties,
+                      "cache_mode",
+                      pool.cache_mode().to_string(),
+                      spec.cache_mode.clone(),
+                      true);
+        property_diff(&mut properties,
+                      "compression",
+                      pool.compression().to_string(),
+                      spec.compression.clone(),
+                      true);
+        property_diff(&mut properties,
+                      "allocation_policy",
+                      pool.allocation_policy().to_string(),
+                      spec.allocation_policy.clone(),
+                      true);
+        property_diff(&mut properties,
+                      "description",
+                      pool.description().to_owned(),
+                      spec.description.clone(),
+                      false);
+        property_diff(&mut properties,
+                      "max_filesystems",
+                      pool.max_filesystems().to_string(),
+                      spec.max_filesystems.map(|m| m.to_string()),
+                      false);
+        property_diff(&mut properties,
+                      "max_snapshots",
+                      pool.max_snapshots().to_string(),
+                      spec.max_snapshots.map(|m| m.to_string()),
+                      false);
+        property_diff(&mut properties,
+                      "reserved_free",
+                      (*pool.reserved_free()).to_string(),
+                      spec.reserved_free.map(|r| (*r).to_string()),
+                      false);
+        property_diff(&mut properties,
+                      "usage_alarm",
+                      pool.usage_alarm().to_string(),
+                      spec.usage_alarm.map(|u| u.to_string()),
+                      false);
+
+        PoolDiff {
+            version: DIFF_VERSION,
+            name: pool.name().to_owned(),
+            devices: device_diff(tier_devices(BlockDevTier::Data), &spec.devices),
+            cache_devices: device_diff(tier_devices(BlockDevTier::Cache), &spec.cache_devices),
+            spares: device_diff(tier_devices(BlockDevTier::Spare), &spec.spares),
+            filesystems: filesystems,
+            size_deltas: size_deltas,
+            properties: properties,
+        }
+    }
+
+    /// The diff as a JSON string.
+    pub fn to_json(&self) -> EngineResult<String> {
+        Ok(try!(serde_json::to_string(self)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::super::{Engine, SimEngine};
+    use super::{PoolDiff, PoolSpec, PropertyDiff};
+
+    #[test]
+    /// Devices in the spec which the pool lacks are to be added, those
+    /// which the pool has but the spec omits are to be removed, and
+    /// unspecified fields are not compared
+    fn diff_devices_to_add() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("pool", &[Path::new("/s/a"), Path::new("/s/b")], &[], None, false)
+            .unwrap();
+        engine
+            .get_mut_pool(&uuid)
+            .unwrap()
+            .create_filesystems(&[("fs", None)])
+            .unwrap();
+        let pool = engine.get_pool(&uuid).unwrap();
+
+        let spec = PoolSpec::from_json(r#"{
+            "devices": ["/s/d", "/s/a", "/s/c"],
+            "filesystems": [{"name": "fs"}, {"name": "new"}],
+            "compression": "zstd",
+            "redundancy": "none"
+        }"#)
+                .unwrap();
+        let diff = PoolDiff::new(pool, &spec);
+        assert!(diff.name == "pool");
+        assert!(diff.devices.to_add == vec![PathBuf::from("/s/c"), PathBuf::from("/s/d")]);
+        assert!(diff.devices.to_remove == vec![PathBuf::from("/s/b")]);
+        assert!(diff.cache_devices.to_add.is_empty() && diff.spares.to_remove.is_empty());
+        assert!(diff.filesystems.to_create == vec!["new".to_owned()]);
+        assert!(diff.filesystems.to_destroy.is_empty());
+        assert!(diff.size_deltas.is_empty());
+        assert!(diff.properties ==
+                vec![PropertyDiff {
+                         property: "compression".into(),
+                         actual: "None".into(),
+                         desired: "zstd".into(),
+                     }]);
+
+        let diff = PoolDiff::new(pool, &PoolSpec::from_json("{}").unwrap());
+        assert!(diff.devices.to_add.is_empty() && diff.devices.to_remove.is_empty());
+        assert!(diff.filesystems.to_destroy.is_empty());
+        assert!(diff.properties.is_empty());
+
+        assert!(PoolSpec::from_json(r#"{"disks": []}"#).is_err());
+    }
+}
@@ -0,0 +1,250 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// A description of everything the engine knows about, for diagnostic use.
+// Like the *Save structs used for on-disk metadata, the *Dump structs
+// contain only simple, serde-friendly data types, and are constructed
+// from the engine by means of the Engine, Pool, and Filesystem traits, so
+// that any engine may be dumped.
+
+use std::path::PathBuf;
+
+use devicemapper::Sectors;
+
+use serde_json;
+
+use super::engine::{Engine, Filesystem, Pool};
+use super::errors::{EngineError, EngineResult, ErrorEnum};
+use super::types::{DevUuid, FilesystemUuid, PoolUuid};
+
+/// The value which replaces a redacted field.
+pub const REDACTED: &'static str = "<redacted>";
+
+/// The version of the format of a dump. A dump of any other version can
+/// not be loaded.
+pub const DUMP_VERSION: u32 = 1;
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EngineDump {
+    pub version: u32,
+    pub pools: Vec<PoolDump>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolDump {
+    pub name: String,
+    pub uuid: PoolUuid,
+    pub state: u16,
+    pub redundancy: u16,
+    pub created_at: u64,
+    pub cache_mode: u16,
+    pub cache_enabled: bool,
+    pub compression: u16,
+    pub allocation_policy: u16,
+    pub read_only: bool,
+    pub imported: bool,
+    pub encrypted: bool,
+    pub key_description: Option<String>,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub total_physical_size: Sectors,
+    pub metadata_size: Sectors,
+    pub extent_size: Sectors,
+    pub filesystems: Vec<FilesystemDump>,
+    pub blockdevs: Vec<BlockDevDump>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockDevDump {
+    pub devnode: PathBuf,
+    pub uuid: DevUuid,
+    pub tier: u16,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilesystemDump {
+    pub name: String,
+    pub uuid: FilesystemUuid,
+    pub fs_type: String,
+    pub snapshot_schedule: Option<String>,
+    pub mountpoint: Option<PathBuf>,
+    pub io_limits: (u64, u64),
+    pub size: Sectors,
+    pub origin: Option<(PoolUuid, FilesystemUuid)>,
+}
+
+impl EngineDump {
+    /// Describe every pool known to engine, in order of UUID.
+    /// If redact is true, the names of pools and filesystems, which may
+    /// reveal something about the data stored in them, are omitted, as are
+    /// the descriptions of pools and of the keys of encrypted pools, and
+    /// the mount points of filesystems.
+    pub fn new(engine: &Engine, redact: bool) -> EngineDump {
+        let mut pools = engine
+            .pools()
+            .into_iter()
+            .map(|p| PoolDump::new(p, redact))
+            .collect::<Vec<_>>();
+        pools.sort_by_key(|p| p.uuid);
+        EngineDump {
+            version: DUMP_VERSION,
+            pools: pools,
+        }
+    }
+
+    /// The dump as a JSON string.
+    pub fn to_json(&self) -> EngineResult<String> {
+        Ok(try!(serde_json::to_string(self)))
+    }
+
+    /// The dump in the JSON string json, which must be of DUMP_VERSION.
+    pub fn from_json(json: &str) -> EngineResult<EngineDump> {
+        let dump: EngineDump = try!(serde_json::from_str(json).map_err(|e| {
+            EngineError::Engine(ErrorEnum::Invalid, format!("malformed dump: {}", e))
+        }));
+        if dump.version != DUMP_VERSION {
+            let message = format!("dump is of version {}, not {}", dump.version, DUMP_VERSION);
+            return Err(EngineError::Engine(ErrorEnum::Invalid, message));
+        }
+        Ok(dump)
+    }
+}
+
+impl PoolDump {
+    fn new(pool: &Pool, redact: bool) -> PoolDump {
+        let mut filesystems = pool.filesystems()
+            .into_iter()
+            .map(|f| FilesystemDump::new(f, redact))
+            .collect::<Vec<_>>();
+        filesystems.sort_by_key(|f| f.uuid);
+        let mut blockdevs = pool.blockdevs()
+            .into_iter()
+            .map(|(tier, bd)| {
+                     BlockDevDump {
+                         devnode: bd.devnode(),
+                         uuid: *bd.uuid(),
+                         tier: tier.into(),
+                     }
+                 })
+            .collect::<Vec<_>>();
+        blockdevs.sort_by_key(|bd| bd.uuid);
+        PoolDump {
+            name: if redact {
+                REDACTED.into()
+            } else {
+                pool.name().to_owned()
+            },
+            uuid: *pool.uuid(),
+            state: pool.state().into(),
+            redundancy: pool.redundancy().into(),
+            created_at: pool.created_at(),
+            cache_mode: pool.cache_mode().into(),
+            cache_enabled: pool.cache_enabled(),
+            compression: pool.compression().into(),
+            allocation_policy: pool.allocation_policy().into(),
+            read_only: pool.read_only(),
+            imported: pool.imported(),
+            encrypted: pool.key_description().is_some(),
+            key_description: pool.key_description()
+                .map(|k| if redact { REDACTED.into() } else { k.to_owned() }),
+            description: if redact && !pool.description().is_empty() {
+                REDACTED.into()
+            } else {
+                pool.description().to_owned()
+            },
+            tags: pool.tags().into_iter().map(|t| t.to_owned()).collect(),
+            total_physical_size: pool.total_physical_size(),
+            metadata_size: pool.metadata_size(),
+            extent_size: pool.extent_size(),
+            filesystems: filesystems,
+            blockdevs: blockdevs,
+        }
+    }
+}
+
+impl FilesystemDump {
+    fn new(filesystem: &Filesystem, redact: bool) -> FilesystemDump {
+        FilesystemDump {
+            name: if redact {
+                REDACTED.into()
+            } else {
+                filesystem.name().to_owned()
+            },
+            uuid: *filesystem.uuid(),
+            fs_type: filesystem.fs_type().to_owned(),
+            snapshot_schedule: filesystem.snapshot_schedule().map(|s| s.to_owned()),
+            mountpoint: if redact {
+                None
+            } else {
+                filesystem.mountpoint().map(|p| p.to_path_buf())
+            },
+            io_limits: filesystem.io_limits(),
+            size: filesystem.size(),
+            origin: filesystem.origin(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use super::super::{Engine, SimEngine};
+    use super::{DUMP_VERSION, EngineDump, REDACTED};
+
+    #[test]
+    /// A dump read back from its JSON is the same as the original
+    fn dump_round_trip() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine.create_pool("pool", &[], &[], None, false).unwrap();
+        engine
+            .get_mut_pool(&uuid)
+            .unwrap()
+            .create_filesystems(&[("fs", None)])
+            .unwrap();
+        engine.create_pool("other", &[], &[], None, false).unwrap();
+
+        let dump = EngineDump::new(&engine, false);
+        let json = dump.to_json().unwrap();
+        let loaded: EngineDump = serde_json::from_str(&json).unwrap();
+        assert!(loaded == dump);
+        assert!(loaded.pools.len() == 2);
+        assert!(loaded
+                    .pools
+                    .iter()
+                    .any(|p| p.name == "pool" && p.filesystems[0].name == "fs"));
+    }
+
+    #[test]
+    /// A dump is read back only if it is well formed and of the current
+    /// version
+    fn dump_from_json_checked() {
+        let mut engine = SimEngine::default();
+        engine.create_pool("pool", &[], &[], None, false).unwrap();
+        let mut dump = EngineDump::new(&engine, false);
+        assert!(EngineDump::from_json(&dump.to_json().unwrap()).unwrap() == dump);
+
+        dump.version = DUMP_VERSION + 1;
+        assert!(EngineDump::from_json(&dump.to_json().unwrap()).is_err());
+        assert!(EngineDump::from_json("{\"pools\": []}").is_err());
+        assert!(EngineDump::from_json("not json").is_err());
+    }
+
+    #[test]
+    /// A redacted dump contains no names
+    fn dump_redacted() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine.create_pool("pool", &[], &[], None, false).unwrap();
+        engine
+            .get_mut_pool(&uuid)
+            .unwrap()
+            .create_filesystems(&[("fs", None)])
+            .unwrap();
+
+        let dump = EngineDump::new(&engine, true);
+        assert!(dump.pools[0].name == REDACTED);
+        assert!(dump.pools[0].filesystems[0].name == REDACTED);
+        assert!(!dump.to_json().unwrap().contains("\"fs\""));
+    }
+}
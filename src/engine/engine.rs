@@ -9,8 +9,11 @@ use uuid::Uuid;
 
 use devicemapper::Sectors;
 
+use super::dump::EngineDump;
 use super::errors::EngineResult;
-use super::types::{FilesystemUuid, PoolUuid, RenameAction};
+use super::types::{Alert, AllocationPolicy, BlockDevCheck, BlockDevHealth, BlockDevState,
+                   BlockDevTier, CacheMode, Compression, DevUuid, FilesystemUuid, PoolIoStats,
+                   PoolState, PoolUuid, Redundancy, RenameAction};
 
 pub trait HasUuid: Debug {
     fn uuid(&self) -> &Uuid;
@@ -20,19 +23,92 @@ pub trait HasName: Debug {
     fn name(&self) -> &str;
 }
 
-pub trait Dev: Debug {}
+pub trait Dev: HasUuid {
+    /// The device node of the blockdev.
+    fn devnode(&self) -> PathBuf;
+
+    /// The state of the blockdev.
+    fn state(&self) -> BlockDevState;
+
+    /// The percentage of its rated endurance which the device has used.
+    fn wear_percent(&self) -> u16;
+
+    /// The health of the device, as the device reports it.
+    fn health(&self) -> BlockDevHealth;
+
+    /// The size of the device.
+    fn size(&self) -> Sectors;
+
+    /// The amount of the device which holds the pool's data.
+    fn used(&self) -> Sectors;
+
+    /// Whether the device has been made read-only.
+    fn read_only(&self) -> bool;
+}
 
 pub trait Filesystem: HasName + HasUuid {
     /// path of the device node
     fn devnode(&self) -> EngineResult<PathBuf>;
+
+    /// The pool and filesystem of which this filesystem is a snapshot,
+    /// or None if it is not a snapshot.
+    fn origin(&self) -> Option<(PoolUuid, FilesystemUuid)>;
+
+    /// The type of the filesystem, e.g., "xfs".
+    fn fs_type(&self) -> &str;
+
+    /// Record the type of the filesystem.
+    fn set_fs_type(&mut self, fs_type: &str) -> EngineResult<()>;
+
+    /// The schedule on which snapshots of the filesystem are to be made,
+    /// e.g., a cron expression, or None if none has been recorded.
+    /// The schedule is recorded for external tools; it is not acted on.
+    fn snapshot_schedule(&self) -> Option<&str>;
+
+    /// Record the schedule on which snapshots of the filesystem are to be
+    /// made.
+    /// Returns true if the schedule was changed.
+    /// Returns an error if schedule is empty.
+    fn set_snapshot_schedule(&mut self, schedule: &str) -> EngineResult<bool>;
+
+    /// The path at which the filesystem is to be mounted, or None if none
+    /// has been recorded. The path is recorded for external tools; the
+    /// filesystem is not mounted there.
+    fn mountpoint(&self) -> Option<&Path>;
+
+    /// Record the path at which the filesystem is to be mounted.
+    /// Returns true if the path was changed.
+    /// Returns an error if mountpoint is not absolute.
+    fn set_mountpoint(&mut self, mountpoint: &Path) -> EngineResult<bool>;
+
+    /// The most read and write operations per second which the filesystem
+    /// may perform, respectively. 0 means no limit.
+    /// The limits are recorded for a backend to enforce.
+    fn io_limits(&self) -> (u64, u64);
+
+    /// Record the most read and write operations per second which the
+    /// filesystem may perform. 0 removes a limit.
+    /// Returns true if either limit was changed.
+    fn set_io_limits(&mut self, read_iops: u64, write_iops: u64) -> EngineResult<bool>;
+
+    /// The size of the filesystem.
+    fn size(&self) -> Sectors;
+
+    /// The amount of data stored in the filesystem.
+    fn used(&self) -> EngineResult<Sectors>;
 }
 
 pub trait Pool: HasName + HasUuid {
     /// Creates the filesystems specified by specs.
     /// Returns a list of the names of filesystems actually created.
     /// Returns an error if any of the specified names are already in use
-    /// for filesystems in this pool. If the same name is passed multiple
-    /// times, the size associated with the last item is used.
+    /// for filesystems in this pool, or if the pool is read-only.
+    /// Returns an error if the pool would then hold more filesystems than
+    /// its limit.
+    /// If the same name is passed multiple times, the size associated with
+    /// the last item is used. Sizes are rounded up to a whole number of the
+    /// pool's extents; the size of the filesystem actually created is
+    /// reported by Filesystem::size().
     fn create_filesystems<'a, 'b>(&'a mut self,
                                   specs: &[(&'b str, Option<Sectors>)])
                                   -> EngineResult<Vec<(&'b str, FilesystemUuid)>>;
@@ -41,8 +117,40 @@ pub trait Pool: HasName + HasUuid {
     /// Returns a list of device nodes corresponding to devices actually added.
     /// Returns an error if a blockdev can not be added because it is owned
     /// or there was an error while reading or writing a blockdev.
+    /// Returns an error if any path is not absolute.
     fn add_blockdevs(&mut self, paths: &[&Path], force: bool) -> EngineResult<Vec<PathBuf>>;
 
+    /// Mark the blockdev specified by path as failed.
+    /// If the pool has a spare, the spare replaces the failed blockdev.
+    /// Returns true if the blockdev was not already marked failed.
+    /// Returns an error if the blockdev does not belong to the pool.
+    fn mark_blockdev_failed(&mut self, path: &Path) -> EngineResult<bool>;
+
+    /// Make the blockdev specified by path read-only, or writable again.
+    /// Returns true if the setting was changed.
+    /// Returns an error if the blockdev does not belong to the pool.
+    fn set_blockdev_read_only(&mut self, path: &Path, read_only: bool) -> EngineResult<bool>;
+
+    /// Adds the devices specified by paths to the pool as spares. A spare
+    /// holds no data until it replaces a blockdev which has failed.
+    /// Returns a list of device nodes corresponding to the spares added.
+    /// Returns an error if any device already belongs to the pool.
+    fn add_spares(&mut self, paths: &[&Path], force: bool) -> EngineResult<Vec<PathBuf>>;
+
+    /// The device nodes of the pool's spares.
+    fn spares(&self) -> Vec<PathBuf>;
+
+    /// Replace the failed blockdev specified by old_path with the blockdev
+    /// specified by new_path, removing the failed blockdev from the pool.
+    /// Returns the device node of the blockdev added.
+    /// Returns an error if old_path is not a failed blockdev belonging to
+    /// the pool or if new_path already belongs to the pool.
+    fn replace_blockdev(&mut self,
+                        old_path: &Path,
+                        new_path: &Path,
+                        force: bool)
+                        -> EngineResult<PathBuf>;
+
     /// Destroy the pool.
     /// Precondition: All filesystems belonging to this pool must be
     /// unmounted.
@@ -66,6 +174,30 @@ pub trait Pool: HasName + HasUuid {
                          new_name: &str)
                          -> EngineResult<RenameAction>;
 
+    /// Make the contents of the filesystem with this UUID the same as the
+    /// contents of snapshot_uuid, a snapshot of it in this pool.
+    /// The filesystem keeps its name and UUID, so that existing references
+    /// to it remain valid.
+    /// Returns an error if either filesystem does not exist, if the
+    /// snapshot is not a snapshot of the filesystem, or if the pool is
+    /// read-only.
+    fn restore_filesystem(&mut self,
+                          uuid: &FilesystemUuid,
+                          snapshot_uuid: &FilesystemUuid)
+                          -> EngineResult<()>;
+
+    /// Create a new filesystem, named new_name, which is an independent copy
+    /// of the filesystem with this UUID. Unlike a snapshot, the copy has no
+    /// origin, and occupies as much space in the pool as the original.
+    /// Returns the UUID of the copy.
+    /// Returns an error if the filesystem does not exist, if new_name is
+    /// already in use, if the pool does not have enough free space for the
+    /// copy, or if the pool is read-only.
+    fn clone_filesystem(&mut self,
+                        uuid: &FilesystemUuid,
+                        new_name: &str)
+                        -> EngineResult<FilesystemUuid>;
+
     /// Rename this pool.
     fn rename(&mut self, name: &str) -> ();
 
@@ -88,8 +220,221 @@ pub trait Pool: HasName + HasUuid {
     /// or to reserve for some other purpose.
     fn total_physical_used(&self) -> EngineResult<Sectors>;
 
+    /// The amount by which the data stored in the pool's filesystems,
+    /// together with the pool's metadata, exceeds the pool's physical size;
+    /// the amount of space which must be added to the pool to hold it all.
+    /// Zero if the pool is large enough.
+    fn space_shortfall(&self) -> EngineResult<Sectors>;
+
+    /// The number of the pool's filesystems which are snapshots, and the
+    /// amount of data stored in them.
+    fn snapshot_usage(&self) -> EngineResult<(usize, Sectors)>;
+
+    /// The number of Sectors reserved for pool metadata. The reservation
+    /// is included in the pool's total physical used.
+    fn metadata_size(&self) -> Sectors;
+
+    /// Set the number of Sectors reserved for pool metadata.
+    /// Returns true if the reservation was changed.
+    /// Returns an error if size is less than the amount of metadata
+    /// currently in use, or if growing the reservation would leave less
+    /// free than the pool's free-space reservation.
+    fn set_metadata_size(&mut self, size: Sectors) -> EngineResult<bool>;
+
     /// Get all the filesystems belonging to this pool.
     fn filesystems(&self) -> Vec<&Filesystem>;
+
+    /// All the blockdevs belonging to this pool, with their tiers.
+    fn blockdevs(&self) -> Vec<(BlockDevTier, &Dev)>;
+
+    /// Remove the blockdev with this UUID from the pool. The data on a
+    /// data blockdev is first moved to the pool's other data blockdevs.
+    /// Returns false if the pool has no such blockdev.
+    /// Returns an error if the pool would be left with fewer good data
+    /// blockdevs than its redundancy requires.
+    fn remove_blockdev(&mut self, uuid: &DevUuid) -> EngineResult<bool>;
+
+    /// The filesystems which have data on the blockdev with this UUID.
+    /// Returns an error if the pool has no such blockdev.
+    fn blockdev_filesystems(&self, uuid: &DevUuid) -> EngineResult<Vec<FilesystemUuid>>;
+
+    /// Whether the blockdev with this UUID could be removed from the pool.
+    /// A data blockdev can be removed only if the pool would be left with
+    /// enough good data blockdevs and the others have room for its data.
+    /// Returns an error if the pool has no such blockdev.
+    fn can_remove_blockdev(&self, uuid: &DevUuid) -> EngineResult<bool>;
+
+    /// Get the device node of the blockdev in this pool with this UUID,
+    /// whatever its tier.
+    fn get_blockdev_path(&self, uuid: &DevUuid) -> Option<PathBuf>;
+
+    /// The current state of the pool.
+    fn state(&self) -> PoolState;
+
+    /// The redundancy with which the pool was created.
+    fn redundancy(&self) -> Redundancy;
+
+    /// Change the redundancy of the pool to redundancy.
+    /// Returns true if the redundancy was changed.
+    /// Returns an error if the pool's redundancy can not be changed to
+    /// redundancy with the blockdevs the pool has.
+    fn reshape(&mut self, redundancy: Redundancy) -> EngineResult<bool>;
+
+    /// The time at which the pool was created, in seconds since the Unix
+    /// epoch. It is unaffected by renaming the pool.
+    fn created_at(&self) -> u64;
+
+    /// The granularity with which the pool allocates space to its
+    /// filesystems.
+    fn extent_size(&self) -> Sectors;
+
+    /// The I/O the pool has performed since it was created.
+    fn io_stats(&self) -> PoolIoStats;
+
+    /// Whether the pool is read-only. Nothing in a read-only pool may be
+    /// changed, except whether it is read-only or imported. An imported
+    /// pool is always read-only.
+    fn read_only(&self) -> bool;
+
+    /// Make the pool read-only, or writable again.
+    /// Returns true if the setting was changed.
+    /// Returns an error if the pool is imported and is to be made writable.
+    fn set_read_only(&mut self, read_only: bool) -> EngineResult<bool>;
+
+    /// Whether the pool was created by another host, and has not yet been
+    /// adopted by this one.
+    fn imported(&self) -> bool;
+
+    /// Mark the pool as imported from another host, or as adopted by this
+    /// host, after which it is no longer read-only on that account.
+    /// Returns true if the setting was changed.
+    fn set_imported(&mut self, imported: bool) -> EngineResult<bool>;
+
+    /// The cache mode of the pool. A pool is WriteThrough unless it has
+    /// been set otherwise.
+    fn cache_mode(&self) -> CacheMode;
+
+    /// Set the cache mode of the pool.
+    /// Returns true if the cache mode was changed.
+    fn set_cache_mode(&mut self, mode: CacheMode) -> EngineResult<bool>;
+
+    /// Whether the pool's cache is in use. While it is disabled, the cache
+    /// devices remain in the pool, but I/O passes them by.
+    fn cache_enabled(&self) -> bool;
+
+    /// Enable or disable the pool's cache.
+    /// Returns true if the setting was changed.
+    fn set_cache_enabled(&mut self, enabled: bool) -> EngineResult<bool>;
+
+    /// The compression of data written to the pool. A pool is not
+    /// compressed unless it has been set otherwise.
+    fn compression(&self) -> Compression;
+
+    /// Set the compression of data written to the pool.
+    /// Returns true if the compression was changed.
+    fn set_compression(&mut self, compression: Compression) -> EngineResult<bool>;
+
+    /// How new allocations are distributed among the pool's blockdevs.
+    /// A pool is Linear unless it has been set otherwise.
+    fn allocation_policy(&self) -> AllocationPolicy;
+
+    /// Set how new allocations are distributed among the pool's blockdevs.
+    /// Allocations already made stay where they are.
+    /// Returns true if the policy was changed.
+    fn set_allocation_policy(&mut self, policy: AllocationPolicy) -> EngineResult<bool>;
+
+    /// The largest number of filesystems the pool may hold, including
+    /// snapshots and copies.
+    fn max_filesystems(&self) -> u32;
+
+    /// Set the largest number of filesystems the pool may hold.
+    /// Returns true if the limit was changed.
+    /// Returns an error if the pool already holds more filesystems.
+    fn set_max_filesystems(&mut self, max: u32) -> EngineResult<bool>;
+
+    /// The largest number of snapshots of any one filesystem which the
+    /// pool may hold.
+    fn max_snapshots(&self) -> u32;
+
+    /// Set the largest number of snapshots of any one filesystem which the
+    /// pool may hold.
+    /// Returns true if the limit was changed.
+    /// Returns an error if the pool already holds more snapshots of some
+    /// filesystem.
+    fn set_max_snapshots(&mut self, max: u32) -> EngineResult<bool>;
+
+    /// The number of Sectors kept free in the pool. Allocations which would
+    /// leave less than this amount free are refused, so that the pool is
+    /// never filled completely.
+    fn reserved_free(&self) -> Sectors;
+
+    /// Set the number of Sectors kept free in the pool.
+    /// Returns true if the reservation was changed.
+    /// Returns an error if less than size is free now.
+    fn set_reserved_free(&mut self, size: Sectors) -> EngineResult<bool>;
+
+    /// The number of Sectors which may yet be allocated from the pool
+    /// without dipping into its free-space reservation: the pool's free
+    /// space less its reserved free. No space is held back for operations
+    /// in progress, since no operation can be in progress when this is
+    /// asked.
+    fn allocatable_space(&self) -> EngineResult<Sectors>;
+
+    /// The percentage of the pool's physical size which, once used, raises
+    /// an alert; 0 if there is no alarm.
+    fn usage_alarm(&self) -> u16;
+
+    /// Set the percentage of the pool's physical size which, once used,
+    /// raises an alert; 0 disables the alarm.
+    /// Returns true if the threshold was changed.
+    /// Returns an error if percent is greater than 100.
+    fn set_usage_alarm(&mut self, percent: u16) -> EngineResult<bool>;
+
+    /// The description of the key by which the pool's data is encrypted,
+    /// or None if the pool is not encrypted. The description names the key;
+    /// it is not the passphrase from which the key is derived.
+    fn key_description(&self) -> Option<&str>;
+
+    /// Mark the pool as encrypted by the key with this description.
+    /// Returns true if the pool was not previously encrypted.
+    /// Returns an error if the pool is already encrypted by another key.
+    fn set_key_description(&mut self, key_description: &str) -> EngineResult<bool>;
+
+    /// The free-text description of the pool; empty if it has none.
+    fn description(&self) -> &str;
+
+    /// Set the description of the pool; an empty description clears it.
+    /// Returns true if the description was changed.
+    /// Returns an error if description is longer than MAX_DESCRIPTION_LEN
+    /// characters.
+    fn set_description(&mut self, description: &str) -> EngineResult<bool>;
+
+    /// The tags by which the pool is grouped with other pools, in order.
+    fn tags(&self) -> Vec<&str>;
+
+    /// Tag the pool with tag.
+    /// Returns true if the pool was not already tagged with it.
+    fn add_tag(&mut self, tag: &str) -> EngineResult<bool>;
+
+    /// Remove tag from the pool.
+    /// Returns true if the pool was tagged with it.
+    fn remove_tag(&mut self, tag: &str) -> EngineResult<bool>;
+
+    /// Tidy the pool's internal bookkeeping. This may be done at any time
+    /// and does not disturb the pool's filesystems.
+    /// Returns true if anything was changed.
+    fn compact(&mut self) -> EngineResult<bool>;
+
+    /// An estimate of how fragmented the pool's free space is, as a
+    /// percentage: 0 if it is all contiguous. Compacting the pool may
+    /// reduce it.
+    fn fragmentation(&self) -> EngineResult<u16>;
+
+    /// Redistribute the pool's data evenly among its data blockdevs, as
+    /// after blockdevs have been added. The pool's filesystems are not
+    /// otherwise disturbed.
+    /// Returns true if any data was moved.
+    fn rebalance(&mut self) -> EngineResult<bool>;
 }
 
 pub trait Engine: Debug {
@@ -98,18 +443,95 @@ pub trait Engine: Debug {
     /// pool contains.
     /// Returns an error if the redundancy code does not correspond to a
     /// supported redundancy.
+    /// Returns an error if any device is specified both as a blockdev and
+    /// as a cache device. If the cache can not be set up, no pool is
+    /// created.
+    /// Returns an error if any device path is not absolute.
     fn create_pool(&mut self,
                    name: &str,
                    blockdev_paths: &[&Path],
+                   cachedev_paths: &[&Path],
                    redundancy: Option<u16>,
                    force: bool)
                    -> EngineResult<(PoolUuid, Vec<PathBuf>)>;
 
+    /// Create a Stratis pool, as create_pool() does, but identified by uuid
+    /// rather than by a UUID generated for it, so that a pool may keep its
+    /// identity when it is recreated elsewhere.
+    /// Returns the blockdevs the pool contains.
+    /// Returns an error if a pool with uuid already exists.
+    fn create_pool_with_uuid(&mut self,
+                             uuid: PoolUuid,
+                             name: &str,
+                             blockdev_paths: &[&Path],
+                             cachedev_paths: &[&Path],
+                             redundancy: Option<u16>,
+                             force: bool)
+                             -> EngineResult<Vec<PathBuf>>;
+
+    /// Check, without changing anything, whether a pool with the given
+    /// redundancy could be created from the devices at blockdev_paths.
+    /// Returns whether a pool could be created, and for each path, in
+    /// order, the result of checking it and the reason for that result.
+    /// Returns an error if the redundancy code does not correspond to a
+    /// supported redundancy.
+    fn check_blockdevs(&self,
+                       blockdev_paths: &[&Path],
+                       redundancy: Option<u16>)
+                       -> EngineResult<(bool, Vec<(PathBuf, BlockDevCheck, String)>)>;
+
     /// Destroy a pool.
     /// Ensures that the pool of the given UUID is absent on completion.
     /// Returns true if some action was necessary, otherwise false.
+    /// Returns an error if the pool still contains filesystems or if a
+    /// filesystem in some other pool is a snapshot of one of its filesystems.
     fn destroy_pool(&mut self, uuid: &PoolUuid) -> EngineResult<bool>;
 
+    /// Destroy a pool together with its filesystems.
+    /// Returns true if some action was necessary, otherwise false.
+    /// Returns an error, having destroyed nothing, if a filesystem in some
+    /// other pool is a snapshot of one of its filesystems.
+    fn force_destroy_pool(&mut self, uuid: &PoolUuid) -> EngineResult<bool>;
+
+    /// Mark a pool deleted, without destroying it, so that it may yet be
+    /// restored. A deleted pool is not among the engine's pools, and can
+    /// not be got, and so not changed, but it keeps its name and devices
+    /// until it is destroyed by destroy_pool().
+    /// Returns true if the pool was marked deleted, false if there is no
+    /// such pool or it is already marked deleted.
+    /// Returns an error if the pool could not be destroyed, or if the engine
+    /// can not remember which pools are marked deleted.
+    fn soft_destroy_pool(&mut self, uuid: &PoolUuid) -> EngineResult<bool>;
+
+    /// Restore a pool which was marked deleted.
+    /// Returns true if the pool was restored, false if it was not deleted.
+    /// Returns an error if there is no pool with uuid.
+    fn restore_pool(&mut self, uuid: &PoolUuid) -> EngineResult<bool>;
+
+    /// Create a snapshot, named snapshot_name, of the filesystem origin_uuid
+    /// belonging to the pool origin_pool_uuid. The snapshot is placed in the
+    /// pool pool_uuid, which need not be the pool of the origin; in that case
+    /// the origin's pool can not be destroyed while the snapshot exists.
+    /// Returns the UUID of the snapshot.
+    /// Returns an error if the origin or either pool does not exist, if
+    /// snapshot_name is already in use in the pool, if the pool is
+    /// read-only, or if the pool already holds as many snapshots of the
+    /// origin as it may.
+    fn create_snapshot(&mut self,
+                       origin_pool_uuid: &PoolUuid,
+                       origin_uuid: &FilesystemUuid,
+                       pool_uuid: &PoolUuid,
+                       snapshot_name: &str)
+                       -> EngineResult<FilesystemUuid>;
+
+    /// Add the pools described by dump, or, if replace is true, replace all
+    /// the engine's pools with them.
+    /// Returns the UUIDs of the pools added.
+    /// Returns an error, having changed nothing, if the dump is
+    /// inconsistent, or if a pool to be added has the name or UUID of, or
+    /// shares a device with, a pool which is to remain.
+    fn load_state(&mut self, dump: &EngineDump, replace: bool) -> EngineResult<Vec<PoolUuid>>;
+
     /// Rename pool with uuid to new_name.
     /// Raises an error if the mapping can't be applied because
     /// new_name is already in use.
@@ -129,6 +551,44 @@ pub trait Engine: Debug {
     /// Check pools' current state and take appropriate actions
     fn check(&mut self) -> ();
 
-    /// Get all pools belonging to this engine.
+    /// Examine the devices present again, and set up any pool which was
+    /// incomplete before, but whose devices are now all present.
+    /// Returns the UUIDs of the pools set up.
+    fn rescan(&mut self) -> EngineResult<Vec<PoolUuid>>;
+
+    /// Check the engine's internal consistency, without changing anything.
+    /// Returns a description of each invariant violation detected; an empty
+    /// list means that the engine is consistent.
+    fn check_consistency(&self) -> Vec<String>;
+
+    /// Get all pools belonging to this engine, except those marked deleted.
     fn pools(&self) -> Vec<&Pool>;
+
+    /// The pools which are marked deleted.
+    fn deleted_pools(&self) -> Vec<&Pool>;
+
+    /// The alerts the engine has raised which have not been acknowledged,
+    /// oldest first.
+    fn alerts(&self) -> Vec<Alert>;
+
+    /// Acknowledge the alert with id, so that it is no longer listed.
+    /// Returns an error if there is no unacknowledged alert with id.
+    fn acknowledge_alert(&mut self, id: u64) -> EngineResult<()>;
+
+    /// Remember the state of the engine's pools, so that it can later be
+    /// restored by rollback(). A checkpoint replaces any earlier one.
+    /// Returns an error if the engine can not remember its state.
+    fn checkpoint(&mut self) -> EngineResult<()>;
+
+    /// Restore the state remembered by the last checkpoint, and forget it.
+    /// Returns an error if there is no checkpoint.
+    fn rollback(&mut self) -> EngineResult<()>;
+
+    /// Forget the state remembered by the last checkpoint, if any.
+    fn release_checkpoint(&mut self);
+
+    /// Whether the engine supports the optional capability named feature,
+    /// e.g., "cache", "compression", "encryption", or "snapshots".
+    /// An unknown feature is not supported.
+    fn supports(&self, feature: &str) -> bool;
 }
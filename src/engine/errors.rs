@@ -21,6 +21,7 @@ pub enum ErrorEnum {
     Busy,
     Invalid,
     NotFound,
+    NoSpace,
 }
 
 #[derive(Debug)]
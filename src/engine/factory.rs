@@ -0,0 +1,42 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::sync::{Arc, RwLock};
+
+use super::engine::Engine;
+use super::errors::{EngineError, EngineResult, ErrorEnum};
+use super::sim_engine::SimEngine;
+use super::strat_engine::StratEngine;
+
+/// The kind of the simulator engine.
+pub const SIM_ENGINE_KIND: &'static str = "simulator";
+
+/// The kind of the real engine, which manages actual devices.
+pub const STRAT_ENGINE_KIND: &'static str = "strat";
+
+/// Construct an engine of the given kind.
+/// Returns an error if the kind is unknown or the engine could not be
+/// initialized.
+pub fn create_engine(kind: &str) -> EngineResult<Arc<RwLock<Engine>>> {
+    match kind {
+        SIM_ENGINE_KIND => Ok(Arc::new(RwLock::new(SimEngine::default()))),
+        STRAT_ENGINE_KIND => Ok(Arc::new(RwLock::new(try!(StratEngine::initialize())))),
+        _ => {
+            Err(EngineError::Engine(ErrorEnum::Invalid, format!("unknown engine kind {}", kind)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SIM_ENGINE_KIND, create_engine};
+
+    #[test]
+    /// The simulator is built by name, an unknown kind is an error.
+    fn create_by_kind() {
+        let engine = create_engine(SIM_ENGINE_KIND).unwrap();
+        assert!(engine.read().unwrap().pools().is_empty());
+        assert!(create_engine("bogus").is_err());
+    }
+}
@@ -19,7 +19,100 @@ macro_rules! calculate_redundancy {
     }
 }
 
-macro_rules! destroy_pool {
+macro_rules! check_absolute_paths {
+    ( $paths:expr ) => {
+        if let Some(path) = $paths.iter().find(|p| !p.is_absolute()) {
+            let message = format!("device path {} is not absolute", path.display());
+            return Err(EngineError::Engine(ErrorEnum::Invalid, message));
+        }
+    }
+}
+
+macro_rules! check_filesystem_limit {
+    ( $s:ident; $count:expr ) => {
+        if $s.filesystems().len() + $count > $s.max_filesystems() as usize {
+            let message = format!("pool {} may hold at most {} filesystems",
+                                  $s.name(),
+                                  $s.max_filesystems());
+            return Err(EngineError::Engine(ErrorEnum::Error, message));
+        }
+    }
+}
+
+macro_rules! check_writable {
+    ( $s:ident ) => {
+        if $s.read_only() {
+            let message = format!("pool {} is read-only", $s.name());
+            return Err(EngineError::Engine(ErrorEnum::Error, message));
+        }
+    }
+}
+
+macro_rules! check_blockdevs {
+    ( $paths:ident; $redundancy:ident; $check:expr ) => {
+        {
+            let redundancy = calculate_redundancy!($redundancy);
+            let mut checked: Vec<(PathBuf, BlockDevCheck, String)> = Vec::new();
+            for path in $paths {
+                let result = if checked.iter().any(|c| c.0 == *path) {
+                    (BlockDevCheck::Duplicate, "device specified more than once".into())
+                } else {
+                    $check(*path)
+                };
+                checked.push((path.to_path_buf(), result.0, result.1));
+            }
+            let ok = checked.len() >= redundancy.min_blockdevs() &&
+                     checked.iter().all(|c| c.1 == BlockDevCheck::Ok);
+            Ok((ok, checked))
+        }
+    }
+}
+
+macro_rules! space_shortfall {
+    ( $s:ident ) => {
+        {
+            let mut used = $s.metadata_size();
+            for filesystem in $s.filesystems() {
+                used = used + try!(filesystem.used());
+            }
+            let size = $s.total_physical_size();
+            Ok(if used > size { used - size } else { Sectors(0) })
+        }
+    }
+}
+
+macro_rules! snapshot_usage {
+    ( $s:ident ) => {
+        {
+            let mut count = 0;
+            let mut used = Sectors(0);
+            for filesystem in $s.filesystems() {
+                if filesystem.origin().is_some() {
+                    count += 1;
+                    used = used + try!(filesystem.used());
+                }
+            }
+            Ok((count, used))
+        }
+    }
+}
+
+macro_rules! check_dependents {
+    ( $s:ident; $uuid: ident) => {
+        let dependent = $s.pools
+            .into_iter()
+            .filter(|p| p.uuid() != $uuid)
+            .flat_map(|p| p.filesystems().into_iter().map(move |fs| (p.name(), fs)))
+            .find(|&(_, fs)| fs.origin().map_or(false, |(origin_pool, _)| origin_pool == *$uuid))
+            .map(|(pool_name, fs)| format!("filesystem {} in pool {}", fs.name(), pool_name));
+        if let Some(dependent) = dependent {
+            return Err(EngineError::Engine(
+                ErrorEnum::Busy, format!("{} depends on pool", dependent)));
+        }
+    }
+}
+
+macro_rules! destroy_pool_pre {
     ( $s:ident; $uuid: ident) => {
         if let Some(ref pool) = $s.pools.get_by_uuid($uuid) {
             if pool.has_filesystems() {
@@ -29,22 +122,83 @@ macro_rules! destroy_pool {
         } else {
             return Ok(false);
         }
+        check_dependents!($s; $uuid);
+    }
+}
+
+macro_rules! destroy_pool {
+    ( $s:ident; $uuid: ident) => {
+        destroy_pool_pre!($s; $uuid);
         try!($s.pools.remove_by_uuid($uuid)
              .expect("Must succeed since $s.pool.get_by_uuid() returned a value.")
              .destroy());
+        $s.deleted.remove($uuid);
         Ok(true)
     }
 }
 
+macro_rules! force_destroy_pool {
+    ( $s:ident; $uuid: ident) => {
+        let filesystems = match $s.pools.get_by_uuid($uuid) {
+            Some(pool) => pool.filesystems().iter().map(|f| *f.uuid()).collect::<Vec<_>>(),
+            None => return Ok(false),
+        };
+        check_dependents!($s; $uuid);
+        try!($s.pools
+             .get_mut_by_uuid($uuid)
+             .expect("Must succeed since $s.pool.get_by_uuid() returned a value.")
+             .destroy_filesystems(&filesystems.iter().collect::<Vec<_>>()));
+        destroy_pool!($s; $uuid)
+    }
+}
+
+macro_rules! soft_destroy_pool {
+    ( $s:ident; $uuid: ident) => {
+        if $s.deleted.contains($uuid) {
+            return Ok(false);
+        }
+        destroy_pool_pre!($s; $uuid);
+        $s.deleted.insert(*$uuid);
+        Ok(true)
+    }
+}
+
+macro_rules! restore_pool {
+    ( $s:ident; $uuid: ident) => {
+        if !$s.pools.contains_uuid($uuid) {
+            return Err(EngineError::Engine(ErrorEnum::NotFound, $uuid.to_string()));
+        }
+        Ok($s.deleted.remove($uuid))
+    }
+}
+
 macro_rules! get_pool {
     ( $s:ident; $uuid:ident ) => {
-        $s.pools.get_by_uuid($uuid).map(|p| p as &Pool)
+        if $s.deleted.contains($uuid) {
+            None
+        } else {
+            $s.pools.get_by_uuid($uuid).map(|p| p as &Pool)
+        }
     }
 }
 
 macro_rules! get_mut_pool {
     ( $s:ident; $uuid:ident ) => {
-        $s.pools.get_mut_by_uuid($uuid).map(|p| p as &mut Pool)
+        if $s.deleted.contains($uuid) {
+            None
+        } else {
+            $s.pools.get_mut_by_uuid($uuid).map(|p| p as &mut Pool)
+        }
+    }
+}
+
+macro_rules! pools {
+    ( $s:ident; $deleted:expr ) => {
+        $s.pools
+            .into_iter()
+            .filter(|p| $s.deleted.contains(p.uuid()) == $deleted)
+            .map(|p| p as &Pool)
+            .collect()
     }
 }
 
@@ -71,8 +225,11 @@ macro_rules! rename_filesystem_pre {
 macro_rules! rename_pool_pre {
     ( $s:ident; $uuid:ident; $new_name:ident ) => {
         {
-            let old_name = match $s.pools.get_by_uuid($uuid) {
-                Some(pool) => pool.name().to_owned(),
+            let old_name = match get_pool!($s; $uuid) {
+                Some(pool) => {
+                    check_writable!(pool);
+                    pool.name().to_owned()
+                }
                 None => return Ok(RenameAction::NoSource),
             };
 
@@ -88,6 +245,67 @@ macro_rules! rename_pool_pre {
     }
 }
 
+macro_rules! check_consistency {
+    ( $s:ident ) => {
+        {
+            let mut issues = Vec::new();
+            // Pools marked deleted still hold their devices
+            let pools = $s.pools.into_iter().map(|p| p as &Pool).collect::<Vec<_>>();
+            let mut owners: HashMap<PathBuf, &str> = HashMap::new();
+            let mut claimants = HashMap::new();
+            for pool in &pools {
+                for (_, bd) in pool.blockdevs() {
+                    let devnode = bd.devnode();
+                    if let Some((other, other_pool)) =
+                        claimants.insert(*bd.uuid(), (devnode.clone(), pool.name())) {
+                        if other != devnode {
+                            issues.push(format!("device UUID {} is claimed by both device {} \
+                                                 in pool {} and device {} in pool {}",
+                                                bd.uuid(),
+                                                other.display(),
+                                                other_pool,
+                                                devnode.display(),
+                                                pool.name()));
+                        }
+                    }
+                    match owners.insert(devnode.clone(), pool.name()) {
+                        Some(owner) if owner == pool.name() => {
+                            issues.push(format!("device {} is recorded more than once in pool {}",
+                                                devnode.display(),
+                                                owner));
+                        }
+                        Some(owner) => {
+                            issues.push(format!("device {} belongs to both pool {} and pool {}",
+                                                devnode.display(),
+                                                owner,
+                                                pool.name()));
+                        }
+                        None => {}
+                    }
+                }
+                for fs in pool.filesystems() {
+                    if let Some((origin_pool, _)) = fs.origin() {
+                        if $s.get_pool(&origin_pool).is_none() {
+                            issues.push(format!("snapshot {} in pool {} refers to missing \
+                                                 origin pool {}",
+                                                fs.name(),
+                                                pool.name(),
+                                                origin_pool));
+                        }
+                    }
+                }
+                if let Ok(used) = pool.total_physical_used() {
+                    if used > pool.total_physical_size() {
+                        issues.push(format!("pool {} uses more space than its size",
+                                            pool.name()));
+                    }
+                }
+            }
+            issues
+        }
+    }
+}
+
 macro_rules! check_engine {
     ( $s:ident ) => {
         for pool in &mut $s.pools {
@@ -2,25 +2,50 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-pub use self::consts::IEC;
+pub use self::consts::{DEFAULT_EXTENT_SIZE, DEFAULT_MAX_FILESYSTEMS, DEFAULT_MAX_SNAPSHOTS, IEC,
+                       MIN_DEV_SIZE};
+
+pub use self::diff::{PoolDiff, PoolSpec};
+
+pub use self::dump::EngineDump;
 
 pub use self::engine::Dev;
 pub use self::engine::Engine;
 pub use self::engine::Filesystem;
 pub use self::engine::Pool;
 
+pub use self::factory::{SIM_ENGINE_KIND, STRAT_ENGINE_KIND, create_engine};
+
 pub use self::errors::EngineError;
 pub use self::errors::EngineResult;
 pub use self::errors::ErrorEnum;
 
+pub use self::schedule::schedule_due;
+
 pub use self::sim_engine::SimEngine;
 pub use self::strat_engine::StratEngine;
 
+pub use self::topology::PoolTopology;
+
+pub use self::transaction::Transaction;
+
+pub use self::types::Alert;
+pub use self::types::AlertSeverity;
+pub use self::types::AllocationPolicy;
+pub use self::types::BlockDevCheck;
+pub use self::types::BlockDevHealth;
+pub use self::types::BlockDevState;
+pub use self::types::BlockDevTier;
+pub use self::types::CacheMode;
+pub use self::types::Compression;
 pub use self::types::DevUuid;
 pub use self::types::FilesystemUuid;
+pub use self::types::PoolIoStats;
+pub use self::types::PoolState;
 pub use self::types::PoolUuid;
 pub use self::types::Redundancy;
 pub use self::types::RenameAction;
+pub use self::types::format_bytes;
 
 #[macro_use]
 mod macros;
@@ -29,9 +54,15 @@ mod macros;
 pub mod strat_engine;
 
 mod consts;
+mod diff;
+mod dump;
 #[allow(module_inception)]
 pub mod engine;
 mod errors;
+mod factory;
+mod schedule;
 mod sim_engine;
 mod structures;
+mod topology;
+mod transaction;
 pub mod types;
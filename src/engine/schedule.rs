@@ -0,0 +1,104 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Evaluation of the cron-like snapshot schedules recorded for filesystems.
+// A schedule has five fields, "minute hour day-of-month month day-of-week",
+// each of which is "*" or a comma-separated list of values and ranges,
+// optionally followed by "/step". Times are in UTC.
+
+use time;
+
+use super::errors::{EngineError, EngineResult, ErrorEnum};
+
+/// Whether value is one of the values described by field, which may
+/// describe values from min to max, inclusive.
+fn field_matches(field: &str, value: u32, min: u32, max: u32) -> EngineResult<bool> {
+    let invalid = || {
+        EngineError::Engine(ErrorEnum::Invalid,
+                            format!("invalid schedule field \"{}\"", field))
+    };
+    let parse = |s: &str| s.parse::<u32>().map_err(|_| invalid());
+
+    let mut matched = false;
+    for item in field.split(',') {
+        let (range, step) = match item.find('/') {
+            Some(i) => (&item[..i], try!(parse(&item[i + 1..]))),
+            None => (item, 1),
+        };
+        let (low, high) = if range == "*" {
+            (min, max)
+        } else {
+            match range.find('-') {
+                Some(i) => (try!(parse(&range[..i])), try!(parse(&range[i + 1..]))),
+                None => {
+                    let low = try!(parse(range));
+                    (low, if step > 1 { max } else { low })
+                }
+            }
+        };
+        if step == 0 || low < min || high > max || low > high {
+            return Err(invalid());
+        }
+        matched |= value >= low && value <= high && (value - low) % step == 0;
+    }
+    Ok(matched)
+}
+
+/// Whether schedule is due at the minute containing the UNIX time now.
+/// As in cron, if both the day of the month and the day of the week are
+/// restricted, a day which matches either is due. Sunday is day 0 or 7.
+/// Returns an error if schedule is malformed.
+pub fn schedule_due(schedule: &str, now: u64) -> EngineResult<bool> {
+    let fields = schedule.split_whitespace().collect::<Vec<_>>();
+    if fields.len() != 5 {
+        let message = format!("schedule \"{}\" does not have 5 fields", schedule);
+        return Err(EngineError::Engine(ErrorEnum::Invalid, message));
+    }
+    let tm = time::at_utc(time::Timespec::new(now as i64, 0));
+
+    let minute = try!(field_matches(fields[0], tm.tm_min as u32, 0, 59));
+    let hour = try!(field_matches(fields[1], tm.tm_hour as u32, 0, 23));
+    let mday = try!(field_matches(fields[2], tm.tm_mday as u32, 1, 31));
+    let month = try!(field_matches(fields[3], tm.tm_mon as u32 + 1, 1, 12));
+    let wday = try!(field_matches(fields[4], tm.tm_wday as u32, 0, 7)) ||
+               (tm.tm_wday == 0 && try!(field_matches(fields[4], 7, 0, 7)));
+
+    let day = match (fields[2] == "*", fields[4] == "*") {
+        (false, false) => mday || wday,
+        _ => mday && wday,
+    };
+    Ok(minute && hour && month && day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::schedule_due;
+
+    // Thursday, 2017-06-01 12:30:00 UTC
+    const NOW: u64 = 1_496_320_200;
+
+    #[test]
+    /// Each field is matched against the corresponding part of the time
+    fn schedule_fields_matched() {
+        assert!(schedule_due("* * * * *", NOW).unwrap());
+        assert!(schedule_due("30 12 1 6 4", NOW).unwrap());
+        assert!(schedule_due("*/15 10-14 * * *", NOW).unwrap());
+        assert!(schedule_due("0,30 */6 * * 1-5", NOW).unwrap());
+        assert!(!schedule_due("0 * * * *", NOW).unwrap());
+        assert!(!schedule_due("30 12 * * 0,7", NOW).unwrap());
+        assert!(!schedule_due("30 12 2 6 *", NOW).unwrap());
+        // Either the day of the month or the day of the week may match
+        assert!(schedule_due("30 12 15 * 4", NOW).unwrap());
+    }
+
+    #[test]
+    /// A malformed schedule is an error
+    fn schedule_malformed() {
+        assert!(schedule_due("* * * *", NOW).is_err());
+        assert!(schedule_due("60 * * * *", NOW).is_err());
+        assert!(schedule_due("*/0 * * * *", NOW).is_err());
+        assert!(schedule_due("5-1 * * * *", NOW).is_err());
+        assert!(schedule_due("daily * * * *", NOW).is_err());
+    }
+}
@@ -7,24 +7,79 @@ use std::path::Path;
 use std::path::PathBuf;
 use std::rc::Rc;
 
-use super::super::engine::Dev;
+use uuid::Uuid;
+
+use devicemapper::Sectors;
+
+use super::super::consts::IEC;
+use super::super::engine::{Dev, HasUuid};
+use super::super::types::{BlockDevHealth, BlockDevState, DevUuid};
 
 use super::randomization::Randomizer;
 
-#[derive(Debug)]
+/// The size of every simulated device.
+const SIM_DEV_SIZE: Sectors = Sectors(2 * IEC::Gi); // = 1 TiB
+
+#[derive(Clone, Debug)]
 /// A simulated device.
 pub struct SimDev {
     pub devnode: PathBuf,
+    pub uuid: DevUuid,
+    pub state: BlockDevState,
+    pub wear_percent: u16,
+    pub health: BlockDevHealth,
+    pub used: Sectors,
+    pub read_only: bool,
     rdm: Rc<RefCell<Randomizer>>,
 }
 
-impl Dev for SimDev {}
+impl HasUuid for SimDev {
+    fn uuid(&self) -> &DevUuid {
+        &self.uuid
+    }
+}
+
+impl Dev for SimDev {
+    fn devnode(&self) -> PathBuf {
+        self.devnode.clone()
+    }
+
+    fn state(&self) -> BlockDevState {
+        self.state
+    }
+
+    fn wear_percent(&self) -> u16 {
+        self.wear_percent
+    }
+
+    fn health(&self) -> BlockDevHealth {
+        self.health
+    }
+
+    fn size(&self) -> Sectors {
+        SIM_DEV_SIZE
+    }
+
+    fn used(&self) -> Sectors {
+        self.used
+    }
+
+    fn read_only(&self) -> bool {
+        self.read_only
+    }
+}
 
 impl SimDev {
     /// Generates a new device from any devnode.
     pub fn new(rdm: Rc<RefCell<Randomizer>>, devnode: &Path) -> SimDev {
         SimDev {
             devnode: devnode.to_owned(),
+            uuid: Uuid::new_v4(),
+            state: BlockDevState::Good,
+            wear_percent: 0,
+            health: BlockDevHealth::Good,
+            used: Sectors(0),
+            read_only: false,
             rdm: rdm,
         }
     }
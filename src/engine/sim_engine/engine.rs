@@ -3,18 +3,23 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use std::cell::RefCell;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::collections::hash_map::RandomState;
 use std::iter::FromIterator;
 use std::path::Path;
 use std::path::PathBuf;
 use std::rc::Rc;
 
-use super::super::engine::{Engine, HasName, HasUuid, Pool};
+use uuid::Uuid;
+
+use super::super::dump::EngineDump;
+use super::super::engine::{Engine, Filesystem, HasName, HasUuid, Pool};
 use super::super::errors::{EngineError, EngineResult, ErrorEnum};
-use super::super::structures::Table;
-use super::super::types::{PoolUuid, Redundancy, RenameAction};
+use super::super::structures::{AlertLog, Table};
+use super::super::types::{Alert, BlockDevCheck, FilesystemUuid, PoolUuid, Redundancy,
+                          RenameAction};
 
+use super::filesystem::SimFilesystem;
 use super::pool::SimPool;
 use super::randomization::Randomizer;
 
@@ -22,7 +27,13 @@ use super::randomization::Randomizer;
 #[derive(Debug, Default)]
 pub struct SimEngine {
     pools: Table<SimPool>,
+    /// The UUIDs of the pools marked deleted.
+    deleted: HashSet<PoolUuid>,
     rdm: Rc<RefCell<Randomizer>>,
+    alerts: Rc<RefCell<AlertLog>>,
+    /// The pools, and which of them were marked deleted, at the last
+    /// checkpoint.
+    checkpoint: Option<(Table<SimPool>, HashSet<PoolUuid>)>,
 }
 
 impl SimEngine {}
@@ -31,23 +42,62 @@ impl Engine for SimEngine {
     fn create_pool(&mut self,
                    name: &str,
                    blockdev_paths: &[&Path],
+                   cachedev_paths: &[&Path],
                    redundancy: Option<u16>,
-                   _force: bool)
+                   force: bool)
                    -> EngineResult<(PoolUuid, Vec<PathBuf>)> {
+        let uuid = Uuid::new_v4();
+        self.create_pool_with_uuid(uuid, name, blockdev_paths, cachedev_paths, redundancy, force)
+            .map(|devnodes| (uuid, devnodes))
+    }
+
+    fn create_pool_with_uuid(&mut self,
+                             uuid: PoolUuid,
+                             name: &str,
+                             blockdev_paths: &[&Path],
+                             cachedev_paths: &[&Path],
+                             redundancy: Option<u16>,
+                             _force: bool)
+                             -> EngineResult<Vec<PathBuf>> {
 
         let redundancy = calculate_redundancy!(redundancy);
 
+        check_absolute_paths!(blockdev_paths);
+        check_absolute_paths!(cachedev_paths);
+
         if self.pools.contains_name(name) {
             return Err(EngineError::Engine(ErrorEnum::AlreadyExists, name.into()));
         }
 
+        if self.pools.contains_uuid(&uuid) {
+            return Err(EngineError::Engine(ErrorEnum::AlreadyExists, uuid.to_string()));
+        }
+
         let device_set: HashSet<_, RandomState> = HashSet::from_iter(blockdev_paths);
+        let cache_device_set: HashSet<_, RandomState> = HashSet::from_iter(cachedev_paths);
+
+        if let Some(path) = device_set.intersection(&cache_device_set).next() {
+            let message = format!("device {} specified as both blockdev and cache device",
+                                  path.display());
+            return Err(EngineError::Engine(ErrorEnum::Invalid, message));
+        }
+
         let devices = device_set
             .into_iter()
             .map(|x| *x)
             .collect::<Vec<&Path>>();
+        let cache_devices = cache_device_set
+            .into_iter()
+            .map(|x| *x)
+            .collect::<Vec<&Path>>();
 
-        let pool = SimPool::new(self.rdm.clone(), name, &devices, redundancy);
+        let pool = SimPool::new(self.rdm.clone(),
+                                self.alerts.clone(),
+                                uuid,
+                                name,
+                                &devices,
+                                &cache_devices,
+                                redundancy);
 
         if self.rdm.borrow_mut().throw_die() {
             return Err(EngineError::Engine(ErrorEnum::Error, "X".into()));
@@ -57,16 +107,145 @@ impl Engine for SimEngine {
             .values()
             .map(|p| p.devnode.clone())
             .collect();
-        let uuid = pool.uuid().clone();
         self.pools.insert(pool);
 
-        Ok((uuid, bdev_paths))
+        Ok(bdev_paths)
+    }
+
+    fn check_blockdevs(&self,
+                       blockdev_paths: &[&Path],
+                       redundancy: Option<u16>)
+                       -> EngineResult<(bool, Vec<(PathBuf, BlockDevCheck, String)>)> {
+        check_blockdevs!(blockdev_paths; redundancy; |path: &Path| {
+            match self.pools
+                      .into_iter()
+                      .find(|p| p.has_device(path)) {
+                Some(pool) => {
+                    (BlockDevCheck::InUse, format!("device belongs to pool {}", pool.name()))
+                }
+                None => (BlockDevCheck::Ok, String::new()),
+            }
+        })
     }
 
     fn destroy_pool(&mut self, uuid: &PoolUuid) -> EngineResult<bool> {
         destroy_pool!{self; uuid}
     }
 
+    fn force_destroy_pool(&mut self, uuid: &PoolUuid) -> EngineResult<bool> {
+        force_destroy_pool!{self; uuid}
+    }
+
+    fn soft_destroy_pool(&mut self, uuid: &PoolUuid) -> EngineResult<bool> {
+        soft_destroy_pool!{self; uuid}
+    }
+
+    fn restore_pool(&mut self, uuid: &PoolUuid) -> EngineResult<bool> {
+        restore_pool!{self; uuid}
+    }
+
+    fn create_snapshot(&mut self,
+                       origin_pool_uuid: &PoolUuid,
+                       origin_uuid: &FilesystemUuid,
+                       pool_uuid: &PoolUuid,
+                       snapshot_name: &str)
+                       -> EngineResult<FilesystemUuid> {
+        if self.deleted.contains(origin_pool_uuid) {
+            return Err(EngineError::Engine(ErrorEnum::NotFound, origin_uuid.to_string()));
+        }
+        let origin_size = try!(self.pools
+                                   .get_by_uuid(origin_pool_uuid)
+                                   .and_then(|p| p.filesystems.get_by_uuid(origin_uuid))
+                                   .map(|f| f.size())
+                                   .ok_or_else(|| {
+                                                   EngineError::Engine(ErrorEnum::NotFound,
+                                                                       origin_uuid.to_string())
+                                               }));
+
+        if self.deleted.contains(pool_uuid) {
+            return Err(EngineError::Engine(ErrorEnum::NotFound, pool_uuid.to_string()));
+        }
+        let pool = try!(self.pools
+                            .get_mut_by_uuid(pool_uuid)
+                            .ok_or_else(|| {
+                                            EngineError::Engine(ErrorEnum::NotFound,
+                                                                pool_uuid.to_string())
+                                        }));
+        if pool.read_only() {
+            return Err(EngineError::Engine(ErrorEnum::Error,
+                                           format!("pool {} is read-only", pool.name())));
+        }
+        if pool.filesystems.contains_name(snapshot_name) {
+            return Err(EngineError::Engine(ErrorEnum::AlreadyExists, snapshot_name.into()));
+        }
+        check_filesystem_limit!(pool; 1);
+        let snapshots = pool.filesystems
+            .into_iter()
+            .filter(|f| f.origin() == Some((*origin_pool_uuid, *origin_uuid)))
+            .count();
+        if snapshots >= pool.max_snapshots() as usize {
+            let message = format!("pool {} may hold at most {} snapshots of filesystem {}",
+                                  pool.name(),
+                                  pool.max_snapshots(),
+                                  origin_uuid);
+            return Err(EngineError::Engine(ErrorEnum::Error, message));
+        }
+
+        let uuid = Uuid::new_v4();
+        pool.filesystems
+            .insert(SimFilesystem::new_snapshot(uuid,
+                                                snapshot_name,
+                                                (*origin_pool_uuid, *origin_uuid),
+                                                origin_size));
+        Ok(uuid)
+    }
+
+    fn load_state(&mut self, dump: &EngineDump, replace: bool) -> EngineResult<Vec<PoolUuid>> {
+        let mut pools = Vec::new();
+        for pool in &dump.pools {
+            pools.push(try!(SimPool::from_dump(self.rdm.clone(), self.alerts.clone(), pool)));
+        }
+
+        let remaining = if replace {
+            vec![]
+        } else {
+            self.pools.into_iter().collect::<Vec<_>>()
+        };
+        for (index, pool) in pools.iter().enumerate() {
+            for other in remaining.iter().cloned().chain(&pools[..index]) {
+                if other.name() == pool.name() || other.uuid() == pool.uuid() {
+                    let message = format!("pool {} ({}) is already present",
+                                          pool.name(),
+                                          pool.uuid());
+                    return Err(EngineError::Engine(ErrorEnum::AlreadyExists, message));
+                }
+                if let Some((_, bd)) = pool.blockdevs()
+                       .into_iter()
+                       .find(|&(_, bd)| other.has_device(&bd.devnode())) {
+                    let message = format!("device {} already belongs to pool {}",
+                                          bd.devnode().display(),
+                                          other.name());
+                    return Err(EngineError::Engine(ErrorEnum::AlreadyExists, message));
+                }
+            }
+        }
+
+        if replace {
+            self.pools = Table::default();
+            self.deleted.clear();
+        }
+        let uuids = pools.iter().map(|p| *p.uuid()).collect();
+        for pool in pools {
+            self.pools.insert(pool);
+        }
+        // A device may have been copied, so that two devices claim its UUID;
+        // the state is loaded, but the conflict is reported.
+        for issue in self.check_consistency() {
+            warn!("inconsistency after loading state: {}", issue);
+        }
+        Ok(uuids)
+    }
+
     fn rename_pool(&mut self, uuid: &PoolUuid, new_name: &str) -> EngineResult<RenameAction> {
         rename_pool_pre!(self; uuid; new_name);
 
@@ -97,8 +276,58 @@ impl Engine for SimEngine {
         check_engine!(self)
     }
 
+    /// Simulated devices do not appear of their own accord, so there is
+    /// never an incomplete pool to set up.
+    fn rescan(&mut self) -> EngineResult<Vec<PoolUuid>> {
+        Ok(vec![])
+    }
+
+    fn check_consistency(&self) -> Vec<String> {
+        check_consistency!(self)
+    }
+
     fn pools(&self) -> Vec<&Pool> {
-        self.pools.into_iter().map(|x| x as &Pool).collect()
+        pools!(self; false)
+    }
+
+    fn deleted_pools(&self) -> Vec<&Pool> {
+        pools!(self; true)
+    }
+
+    fn alerts(&self) -> Vec<Alert> {
+        self.alerts.borrow().unacknowledged()
+    }
+
+    fn acknowledge_alert(&mut self, id: u64) -> EngineResult<()> {
+        if !self.alerts.borrow_mut().acknowledge(id) {
+            return Err(EngineError::Engine(ErrorEnum::NotFound, format!("alert {}", id)));
+        }
+        Ok(())
+    }
+
+    fn checkpoint(&mut self) -> EngineResult<()> {
+        self.checkpoint = Some((self.pools.clone(), self.deleted.clone()));
+        Ok(())
+    }
+
+    fn rollback(&mut self) -> EngineResult<()> {
+        match self.checkpoint.take() {
+            Some((pools, deleted)) => {
+                self.pools = pools;
+                self.deleted = deleted;
+                Ok(())
+            }
+            None => Err(EngineError::Engine(ErrorEnum::NotFound, "no checkpoint".into())),
+        }
+    }
+
+    fn release_checkpoint(&mut self) {
+        self.checkpoint = None;
+    }
+
+    fn supports(&self, feature: &str) -> bool {
+        ["cache", "compression", "encryption", "snapshots", "spares", "transactions"]
+            .contains(&feature)
     }
 }
 
@@ -106,17 +335,30 @@ impl Engine for SimEngine {
 mod tests {
 
     use std;
-    use std::path::Path;
+    use std::path::{Path, PathBuf};
 
+    use serde_json;
     use uuid::Uuid;
 
+    use devicemapper::Sectors;
+
     use quickcheck::QuickCheck;
 
     use super::SimEngine;
+    use super::super::blockdev::SimDev;
 
+    use engine::AllocationPolicy;
+    use engine::BlockDevCheck;
+    use engine::BlockDevHealth;
+    use engine::Compression;
+    use engine::Dev;
     use engine::Engine;
+    use engine::EngineDump;
     use engine::EngineError;
     use engine::ErrorEnum;
+    use engine::Pool;
+    use engine::PoolIoStats;
+    use engine::PoolState;
     use engine::RenameAction;
 
     #[test]
@@ -152,7 +394,7 @@ mod tests {
     /// Destroying an empty pool should succeed.
     fn destroy_empty_pool() {
         let mut engine = SimEngine::default();
-        let (uuid, _) = engine.create_pool("name", &[], None, false).unwrap();
+        let (uuid, _) = engine.create_pool("name", &[], &[], None, false).unwrap();
         assert!(engine.destroy_pool(&uuid).is_ok());
     }
 
@@ -161,7 +403,7 @@ mod tests {
     fn destroy_pool_w_devices() {
         let mut engine = SimEngine::default();
         let (uuid, _) = engine
-            .create_pool("name", &[Path::new("/s/d")], None, false)
+            .create_pool("name", &[Path::new("/s/d")], &[], None, false)
             .unwrap();
         assert!(engine.destroy_pool(&uuid).is_ok());
     }
@@ -171,7 +413,7 @@ mod tests {
     fn destroy_pool_w_filesystem() {
         let mut engine = SimEngine::default();
         let (uuid, _) = engine
-            .create_pool("name", &[Path::new("/s/d")], None, false)
+            .create_pool("name", &[Path::new("/s/d")], &[], None, false)
             .unwrap();
         {
             let pool = engine.get_mut_pool(&uuid).unwrap();
@@ -180,14 +422,120 @@ mod tests {
         assert!(engine.destroy_pool(&uuid).is_err());
     }
 
+    #[test]
+    /// Destroying a pool on which a snapshot in another pool depends
+    /// should fail until the snapshot is destroyed
+    fn destroy_pool_w_snapshot_dependent() {
+        let mut engine = SimEngine::default();
+        let (origin_pool_uuid, _) = engine.create_pool("origin", &[], &[], None, false).unwrap();
+        let (pool_uuid, _) = engine.create_pool("snapshots", &[], &[], None, false).unwrap();
+        let origin_uuid = engine
+            .get_mut_pool(&origin_pool_uuid)
+            .unwrap()
+            .create_filesystems(&[("fs", None)])
+            .unwrap()[0]
+            .1;
+        let snapshot_uuid = engine
+            .create_snapshot(&origin_pool_uuid, &origin_uuid, &pool_uuid, "snap")
+            .unwrap();
+        engine
+            .get_mut_pool(&origin_pool_uuid)
+            .unwrap()
+            .destroy_filesystems(&[&origin_uuid])
+            .unwrap();
+
+        assert!(match engine.destroy_pool(&origin_pool_uuid) {
+                    Err(EngineError::Engine(ErrorEnum::Busy, msg)) => msg.contains("snap"),
+                    _ => false,
+                });
+
+        engine
+            .get_mut_pool(&pool_uuid)
+            .unwrap()
+            .destroy_filesystems(&[&snapshot_uuid])
+            .unwrap();
+        assert!(engine.destroy_pool(&origin_pool_uuid).unwrap());
+    }
+
+    #[test]
+    /// Destroying a pool by force destroys its filesystems with it, unless
+    /// a snapshot in another pool depends on it, in which case nothing is
+    /// destroyed
+    fn force_destroy_pool_w_snapshot_dependent() {
+        let mut engine = SimEngine::default();
+        let (origin_pool_uuid, _) = engine.create_pool("origin", &[], &[], None, false).unwrap();
+        let (pool_uuid, _) = engine.create_pool("snapshots", &[], &[], None, false).unwrap();
+        let origin_uuid = engine
+            .get_mut_pool(&origin_pool_uuid)
+            .unwrap()
+            .create_filesystems(&[("fs", None)])
+            .unwrap()[0]
+            .1;
+        let snapshot_uuid = engine
+            .create_snapshot(&origin_pool_uuid, &origin_uuid, &pool_uuid, "snap")
+            .unwrap();
+
+        assert!(match engine.force_destroy_pool(&origin_pool_uuid) {
+                    Err(EngineError::Engine(ErrorEnum::Busy, msg)) => msg.contains("snap"),
+                    _ => false,
+                });
+        assert!(engine
+                    .get_pool(&origin_pool_uuid)
+                    .unwrap()
+                    .get_filesystem(&origin_uuid)
+                    .is_some());
+
+        engine
+            .get_mut_pool(&pool_uuid)
+            .unwrap()
+            .destroy_filesystems(&[&snapshot_uuid])
+            .unwrap();
+        assert!(engine.force_destroy_pool(&origin_pool_uuid).unwrap());
+        assert!(engine.get_pool(&origin_pool_uuid).is_none());
+    }
+
+    #[test]
+    /// Snapshots of a filesystem may be created up to the pool's limit,
+    /// which does not count other filesystems or snapshots of others
+    fn max_snapshots_enforced() {
+        let mut engine = SimEngine::default();
+        let (pool_uuid, _) = engine.create_pool("pool", &[], &[], None, false).unwrap();
+        let (origin_uuid, other_uuid) = {
+            let pool = engine.get_mut_pool(&pool_uuid).unwrap();
+            assert!(pool.set_max_snapshots(2).unwrap());
+            assert!(!pool.set_max_snapshots(2).unwrap());
+            assert!(pool.max_snapshots() == 2);
+            let filesystems = pool.create_filesystems(&[("fs", None), ("other", None)])
+                .unwrap();
+            (filesystems[0].1, filesystems[1].1)
+        };
+
+        for name in &["snap1", "snap2"] {
+            engine
+                .create_snapshot(&pool_uuid, &origin_uuid, &pool_uuid, name)
+                .unwrap();
+        }
+        engine
+            .create_snapshot(&pool_uuid, &other_uuid, &pool_uuid, "other_snap")
+            .unwrap();
+        assert!(match engine.create_snapshot(&pool_uuid, &origin_uuid, &pool_uuid, "snap3") {
+                    Err(EngineError::Engine(ErrorEnum::Error, msg)) => msg.contains("2"),
+                    _ => false,
+                });
+
+        let pool = engine.get_mut_pool(&pool_uuid).unwrap();
+        assert!(pool.filesystems().len() == 5);
+        assert!(pool.set_max_snapshots(1).is_err());
+    }
+
     #[test]
     #[ignore]
     /// Creating a new pool identical to the previous should succeed
     fn create_new_pool_twice() {
         let name = "name";
         let mut engine = SimEngine::default();
-        engine.create_pool(name, &[], None, false).unwrap();
-        assert!(match engine.create_pool(name, &[], None, false) {
+        engine.create_pool(name, &[], &[], None, false).unwrap();
+        assert!(match engine.create_pool(name, &[], &[], None, false) {
                     Ok((_, devs)) => devs.is_empty(),
                     Err(_) => false,
                 });
@@ -199,12 +547,115 @@ mod tests {
         let name = "name";
         let mut engine = SimEngine::default();
         engine
-            .create_pool(name, &[Path::new("/s/d")], None, false)
+            .create_pool(name, &[Path::new("/s/d")], &[], None, false)
+            .unwrap();
+        assert!(match engine.create_pool(name, &[], &[], None, false) {
+                    Err(EngineError::Engine(ErrorEnum::AlreadyExists, _)) => true,
+                    _ => false,
+                });
+    }
+
+    #[test]
+    /// The UUID returned by create_pool identifies the pool just created
+    fn create_pool_returns_uuid() {
+        let name = "name";
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine.create_pool(name, &[], &[], None, false).unwrap();
+        let pool = engine.get_pool(&uuid).unwrap();
+        assert!(*pool.uuid() == uuid);
+        assert!(pool.name() == name);
+    }
+
+    #[test]
+    /// A pool created with a given UUID is identified by it, and another
+    /// pool may not be created with the same UUID
+    fn create_pool_with_uuid_kept() {
+        let mut engine = SimEngine::default();
+        let uuid = Uuid::new_v4();
+        engine
+            .create_pool_with_uuid(uuid, "name", &[Path::new("/s/a")], &[], None, false)
             .unwrap();
-        assert!(match engine.create_pool(name, &[], None, false) {
+        let pool = engine.get_pool(&uuid).unwrap();
+        assert!(*pool.uuid() == uuid);
+        assert!(pool.name() == "name");
+
+        assert!(match engine.create_pool_with_uuid(uuid, "other", &[], &[], None, false) {
                     Err(EngineError::Engine(ErrorEnum::AlreadyExists, _)) => true,
                     _ => false,
                 });
+        assert!(engine.pools().len() == 1);
+    }
+
+    #[test]
+    /// A pool marked deleted is hidden and can not be changed, but keeps
+    /// its name until it is restored or destroyed
+    fn soft_destroy_restore_purge() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("name", &[Path::new("/s/a")], &[], None, false)
+            .unwrap();
+        assert!(engine.soft_destroy_pool(&uuid).unwrap());
+        assert!(!engine.soft_destroy_pool(&uuid).unwrap());
+        assert!(engine.pools().is_empty());
+        assert!(engine.get_mut_pool(&uuid).is_none());
+        assert!(match engine.rename_pool(&uuid, "other") {
+                    Ok(RenameAction::NoSource) => true,
+                    _ => false,
+                });
+        assert!(engine.deleted_pools().len() == 1);
+        assert!(match engine.create_pool("name", &[], &[], None, false) {
+                    Err(EngineError::Engine(ErrorEnum::AlreadyExists, _)) => true,
+                    _ => false,
+                });
+
+        assert!(engine.restore_pool(&uuid).unwrap());
+        assert!(!engine.restore_pool(&uuid).unwrap());
+        assert!(engine.get_pool(&uuid).unwrap().name() == "name");
+        assert!(engine.deleted_pools().is_empty());
+
+        assert!(engine.soft_destroy_pool(&uuid).unwrap());
+        assert!(engine.destroy_pool(&uuid).unwrap());
+        assert!(engine.deleted_pools().is_empty());
+        assert!(match engine.restore_pool(&uuid) {
+                    Err(EngineError::Engine(ErrorEnum::NotFound, _)) => true,
+                    _ => false,
+                });
+    }
+
+    #[test]
+    /// Rolling back to a checkpoint restores which pools were marked deleted
+    fn rollback_soft_destroy() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine.create_pool("name", &[], &[], None, false).unwrap();
+        engine.checkpoint().unwrap();
+        assert!(engine.soft_destroy_pool(&uuid).unwrap());
+        engine.rollback().unwrap();
+        assert!(engine.get_pool(&uuid).is_some());
+        assert!(engine.deleted_pools().is_empty());
+    }
+
+    #[test]
+    /// A snapshot can not be placed in a pool marked deleted
+    fn snapshot_soft_destroyed_pool() {
+        let mut engine = SimEngine::default();
+        let (origin_pool_uuid, _) = engine.create_pool("origin", &[], &[], None, false).unwrap();
+        let (pool_uuid, _) = engine.create_pool("snapshots", &[], &[], None, false).unwrap();
+        let origin_uuid = engine
+            .get_mut_pool(&origin_pool_uuid)
+            .unwrap()
+            .create_filesystems(&[("fs", None)])
+            .unwrap()[0]
+            .1;
+
+        assert!(engine.soft_destroy_pool(&pool_uuid).unwrap());
+        assert!(match engine.create_snapshot(&origin_pool_uuid, &origin_uuid, &pool_uuid, "snap") {
+                    Err(EngineError::Engine(ErrorEnum::NotFound, _)) => true,
+                    _ => false,
+                });
+        assert!(engine.restore_pool(&pool_uuid).unwrap());
+        assert!(engine
+                    .create_snapshot(&origin_pool_uuid, &origin_uuid, &pool_uuid, "snap")
+                    .is_ok());
     }
 
     #[test]
@@ -213,18 +664,52 @@ mod tests {
         let path = "/s/d";
         let mut engine = SimEngine::default();
         let devices = vec![Path::new(path), Path::new(path)];
-        assert!(match engine.create_pool("name", &devices, None, false) {
+        assert!(match engine.create_pool("name", &devices, &[], None, false) {
                     Ok((_, devs)) => devs.len() == 1,
                     _ => false,
                 });
     }
 
+    #[test]
+    /// Creating a pool with cache devices should populate both collections
+    fn create_pool_w_cache_devices() {
+        let mut engine = SimEngine::default();
+        let (uuid, devs) = engine
+            .create_pool("name",
+                         &[Path::new("/s/a"), Path::new("/s/b")],
+                         &[Path::new("/s/c")],
+                         None,
+                         false)
+            .unwrap();
+        assert!(devs.len() == 2);
+        let pool = engine.pools.get_by_uuid(&uuid).unwrap();
+        assert!(pool.block_devs.len() == 2);
+        assert!(pool.cache_devs.len() == 1);
+        assert!(pool.cache_devs.contains_key(Path::new("/s/c")));
+    }
+
+    #[test]
+    /// Creating a pool with a device that is both a blockdev and a cache
+    /// device should fail and leave no pool behind
+    fn create_pool_cache_overlap() {
+        let mut engine = SimEngine::default();
+        assert!(match engine.create_pool("name",
+                                         &[Path::new("/s/a")],
+                                         &[Path::new("/s/a")],
+                                         None,
+                                         false) {
+                    Err(EngineError::Engine(ErrorEnum::Invalid, _)) => true,
+                    _ => false,
+                });
+        assert!(engine.pools().is_empty());
+    }
+
     #[test]
     /// Creating a pool with an impossible raid level should fail
     fn create_pool_max_u16_raid() {
         let mut engine = SimEngine::default();
         assert!(engine
-                    .create_pool("name", &[], Some(std::u16::MAX), false)
+                    .create_pool("name", &[], &[], Some(std::u16::MAX), false)
                     .is_err());
     }
 
@@ -243,7 +728,7 @@ mod tests {
     fn rename_identity() {
         let name = "name";
         let mut engine = SimEngine::default();
-        let (uuid, _) = engine.create_pool(name, &[], None, false).unwrap();
+        let (uuid, _) = engine.create_pool(name, &[], &[], None, false).unwrap();
         assert!(match engine.rename_pool(&uuid, name) {
                     Ok(RenameAction::Identity) => true,
                     _ => false,
@@ -254,20 +739,49 @@ mod tests {
     /// Renaming a pool to another pool should work if new name not taken
     fn rename_happens() {
         let mut engine = SimEngine::default();
-        let (uuid, _) = engine.create_pool("old_name", &[], None, false).unwrap();
+        let (uuid, _) = engine.create_pool("old_name", &[], &[], None, false).unwrap();
         assert!(match engine.rename_pool(&uuid, "new_name") {
                     Ok(RenameAction::Renamed) => true,
                     _ => false,
                 });
     }
 
+    #[test]
+    /// A relative device path is refused, naming the path
+    fn create_pool_relative_path() {
+        let mut engine = SimEngine::default();
+        assert!(match engine.create_pool("name", &[Path::new("sda")], &[], None, false) {
+                    Err(EngineError::Engine(ErrorEnum::Invalid, msg)) => msg.contains("sda"),
+                    _ => false,
+                });
+        assert!(engine
+                    .create_pool("name", &[Path::new("/dev/sda")], &[], None, false)
+                    .is_ok());
+    }
+
+    #[test]
+    /// A pool's creation time survives a rename and a save/load cycle
+    fn created_at_stable() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine.create_pool("old_name", &[], &[], None, false).unwrap();
+        let created_at = engine.get_pool(&uuid).unwrap().created_at();
+        assert!(created_at > 0);
+
+        engine.rename_pool(&uuid, "new_name").unwrap();
+        assert!(engine.get_pool(&uuid).unwrap().created_at() == created_at);
+
+        let json = EngineDump::new(&engine, false).to_json().unwrap();
+        let loaded: EngineDump = serde_json::from_str(&json).unwrap();
+        assert!(loaded.pools[0].created_at == created_at);
+    }
+
     #[test]
     /// Renaming a pool to another pool should fail if new name taken
     fn rename_fails() {
         let new_name = "new_name";
         let mut engine = SimEngine::default();
-        let (uuid, _) = engine.create_pool("old_name", &[], None, false).unwrap();
-        engine.create_pool(new_name, &[], None, false).unwrap();
+        let (uuid, _) = engine.create_pool("old_name", &[], &[], None, false).unwrap();
+        engine.create_pool(new_name, &[], &[], None, false).unwrap();
         assert!(match engine.rename_pool(&uuid, new_name) {
                     Err(EngineError::Engine(ErrorEnum::AlreadyExists, _)) => true,
                     _ => false,
@@ -279,11 +793,578 @@ mod tests {
     fn rename_no_op() {
         let new_name = "new_name";
         let mut engine = SimEngine::default();
-        engine.create_pool(new_name, &[], None, false).unwrap();
+        engine.create_pool(new_name, &[], &[], None, false).unwrap();
         assert!(match engine.rename_pool(&Uuid::new_v4(), new_name) {
                     Ok(RenameAction::NoSource) => true,
                     _ => false,
                 });
     }
 
+
+    #[test]
+    /// A blockdev's UUID resolves to its device node, whether it is a data,
+    /// cache, or spare device
+    fn blockdev_path_by_uuid() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("pool_name", &[Path::new("/s/a")], &[Path::new("/s/c")], None, false)
+            .unwrap();
+        engine
+            .get_mut_pool(&uuid)
+            .unwrap()
+            .add_spares(&[Path::new("/s/s")], false)
+            .unwrap();
+        let (dev_uuid, cache_uuid, spare_uuid) = {
+            let pool = engine.pools.get_by_uuid(&uuid).unwrap();
+            (pool.block_devs[Path::new("/s/a")].uuid,
+             pool.cache_devs[Path::new("/s/c")].uuid,
+             pool.spare_devs[Path::new("/s/s")].uuid)
+        };
+        let pool = engine.get_pool(&uuid).unwrap();
+        assert!(pool.get_blockdev_path(&dev_uuid) == Some(PathBuf::from("/s/a")));
+        assert!(pool.get_blockdev_path(&cache_uuid) == Some(PathBuf::from("/s/c")));
+        assert!(pool.get_blockdev_path(&spare_uuid) == Some(PathBuf::from("/s/s")));
+        assert!(pool.get_blockdev_path(&Uuid::new_v4()).is_none());
+    }
+
+    #[test]
+    /// I/O statistics set through the simulator's hook are reported by the pool
+    fn pool_io_stats() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine.create_pool("name", &[], &[], None, false).unwrap();
+        assert!(engine.get_pool(&uuid).unwrap().io_stats() == PoolIoStats::default());
+        let stats = PoolIoStats {
+            bytes_read: 4096,
+            bytes_written: 8192,
+            io_ops: 3,
+        };
+        engine.pools.get_mut_by_uuid(&uuid).unwrap().set_io_stats(stats);
+        assert!(engine.get_pool(&uuid).unwrap().io_stats() == stats);
+    }
+
+    #[test]
+    /// Restoring a filesystem from its snapshot keeps its name and UUID
+    fn restore_filesystem_from_snapshot() {
+        let mut engine = SimEngine::default();
+        let (pool_uuid, _) = engine.create_pool("pool", &[], &[], None, false).unwrap();
+        let fs_uuid = engine
+            .get_mut_pool(&pool_uuid)
+            .unwrap()
+            .create_filesystems(&[("fs", None)])
+            .unwrap()[0]
+            .1;
+        let snapshot_uuid = engine
+            .create_snapshot(&pool_uuid, &fs_uuid, &pool_uuid, "snap")
+            .unwrap();
+
+        let pool = engine.get_mut_pool(&pool_uuid).unwrap();
+        pool.get_mut_filesystem(&fs_uuid)
+            .unwrap()
+            .set_fs_type("ext4")
+            .unwrap();
+        assert!(match pool.restore_filesystem(&snapshot_uuid, &fs_uuid) {
+                    Err(EngineError::Engine(ErrorEnum::Invalid, _)) => true,
+                    _ => false,
+                });
+        assert!(match pool.restore_filesystem(&fs_uuid, &Uuid::new_v4()) {
+                    Err(EngineError::Engine(ErrorEnum::NotFound, _)) => true,
+                    _ => false,
+                });
+
+        pool.restore_filesystem(&fs_uuid, &snapshot_uuid).unwrap();
+        let filesystem = pool.get_filesystem(&fs_uuid).unwrap();
+        assert!(*filesystem.uuid() == fs_uuid);
+        assert!(filesystem.name() == "fs");
+        assert!(filesystem.fs_type() == "xfs");
+        assert!(pool.filesystems().len() == 2);
+    }
+
+    #[test]
+    /// Each device is checked, and no pool could be made from a bad set
+    fn check_blockdevs_mixed() {
+        let mut engine = SimEngine::default();
+        engine
+            .create_pool("name", &[Path::new("/s/used")], &[], None, false)
+            .unwrap();
+
+        let (ok, checks) = engine
+            .check_blockdevs(&[Path::new("/s/good"), Path::new("/s/used"), Path::new("/s/good")],
+                             None)
+            .unwrap();
+        assert!(!ok);
+        let results = checks.iter().map(|c| (c.0.as_path(), c.1)).collect::<Vec<_>>();
+        assert!(results ==
+                vec![(Path::new("/s/good"), BlockDevCheck::Ok),
+                     (Path::new("/s/used"), BlockDevCheck::InUse),
+                     (Path::new("/s/good"), BlockDevCheck::Duplicate)]);
+
+        assert!(engine
+                    .check_blockdevs(&[Path::new("/s/good")], None)
+                    .unwrap()
+                    .0);
+        assert!(!engine.check_blockdevs(&[], None).unwrap().0);
+        assert!(engine
+                    .check_blockdevs(&[Path::new("/s/good")], Some(255))
+                    .is_err());
+    }
+
+    #[test]
+    /// A clone has no origin, and occupies as much space as its source
+    fn clone_filesystem() {
+        let mut engine = SimEngine::default();
+        let (pool_uuid, _) = engine.create_pool("pool", &[], &[], None, false).unwrap();
+        let fs_uuid = engine
+            .get_mut_pool(&pool_uuid)
+            .unwrap()
+            .create_filesystems(&[("fs", None)])
+            .unwrap()[0]
+            .1;
+        engine
+            .pools
+            .get_mut_by_uuid(&pool_uuid)
+            .unwrap()
+            .filesystems
+            .get_mut_by_uuid(&fs_uuid)
+            .unwrap()
+            .set_used(Sectors(2048));
+
+        let pool = engine.get_mut_pool(&pool_uuid).unwrap();
+        let used = pool.total_physical_used().unwrap();
+        let clone_uuid = pool.clone_filesystem(&fs_uuid, "clone").unwrap();
+        let clone = pool.get_filesystem(&clone_uuid).unwrap();
+        assert!(clone.origin().is_none());
+        assert!(clone.used().unwrap() == Sectors(2048));
+        assert!(pool.total_physical_used().unwrap() == used + Sectors(2048));
+
+        assert!(match pool.clone_filesystem(&fs_uuid, "clone") {
+                    Err(EngineError::Engine(ErrorEnum::AlreadyExists, _)) => true,
+                    _ => false,
+                });
+        assert!(match pool.clone_filesystem(&Uuid::new_v4(), "other") {
+                    Err(EngineError::Engine(ErrorEnum::NotFound, _)) => true,
+                    _ => false,
+                });
+    }
+
+    #[test]
+    /// A copy which would not fit in the pool is refused for lack of space
+    fn clone_filesystem_no_space() {
+        let mut engine = SimEngine::default();
+        let (pool_uuid, _) = engine.create_pool("pool", &[], &[], None, false).unwrap();
+        let pool = engine.pools.get_mut_by_uuid(&pool_uuid).unwrap();
+        let fs_uuid = pool.create_filesystems(&[("fs", None)]).unwrap()[0].1;
+        pool.filesystems
+            .get_mut_by_uuid(&fs_uuid)
+            .unwrap()
+            .set_used(Sectors(2048));
+        let used = pool.total_physical_used().unwrap();
+        pool.set_physical_size(used + Sectors(1024));
+
+        assert!(match pool.clone_filesystem(&fs_uuid, "clone") {
+                    Err(EngineError::Engine(ErrorEnum::NoSpace, _)) => true,
+                    _ => false,
+                });
+        assert!(pool.filesystems().len() == 1);
+    }
+
+    #[test]
+    /// A pool whose filesystems hold more than it can has a shortfall
+    fn space_shortfall() {
+        let mut engine = SimEngine::default();
+        let (pool_uuid, _) = engine.create_pool("pool", &[], &[], None, false).unwrap();
+        let fs_uuids = engine
+            .get_mut_pool(&pool_uuid)
+            .unwrap()
+            .create_filesystems(&[("fs1", None), ("fs2", None)])
+            .unwrap()
+            .iter()
+            .map(|x| x.1)
+            .collect::<Vec<_>>();
+        assert!(engine
+                    .get_pool(&pool_uuid)
+                    .unwrap()
+                    .space_shortfall()
+                    .unwrap() == Sectors(0));
+
+        let pool = engine.pools.get_mut_by_uuid(&pool_uuid).unwrap();
+        let metadata_size = pool.metadata_size();
+        pool.set_physical_size(metadata_size + Sectors(4096));
+        for fs_uuid in &fs_uuids {
+            pool.filesystems
+                .get_mut_by_uuid(fs_uuid)
+                .unwrap()
+                .set_used(Sectors(3072));
+        }
+        assert!(pool.space_shortfall().unwrap() == Sectors(2048));
+    }
+
+    #[test]
+    /// A pool which uses more than its physical size has no space to
+    /// reserve
+    fn reserved_free_overcommitted() {
+        let mut engine = SimEngine::default();
+        let (pool_uuid, _) = engine.create_pool("pool", &[], &[], None, false).unwrap();
+        let pool = engine.pools.get_mut_by_uuid(&pool_uuid).unwrap();
+        let used = pool.total_physical_used().unwrap();
+        pool.set_physical_size(used - Sectors(1));
+
+        assert!(match pool.set_reserved_free(Sectors(1)) {
+                    Err(EngineError::Engine(ErrorEnum::NoSpace, _)) => true,
+                    _ => false,
+                });
+        assert!(!pool.set_reserved_free(Sectors(0)).unwrap());
+    }
+
+    #[test]
+    /// Compacting a pool leaves a device recorded twice in only one tier
+    fn compact_duplicate_device() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("pool", &[Path::new("/s/a")], &[], None, false)
+            .unwrap();
+        let rdm = engine.rdm.clone();
+        let pool = engine.pools.get_mut_by_uuid(&uuid).unwrap();
+        pool.spare_devs
+            .insert(PathBuf::from("/s/a"), SimDev::new(rdm, Path::new("/s/a")));
+
+        assert!(pool.compact().unwrap());
+        assert!(pool.block_devs.contains_key(Path::new("/s/a")));
+        assert!(pool.spare_devs.is_empty());
+        assert!(!pool.compact().unwrap());
+    }
+
+    #[test]
+    /// A device set to critical health reports it, and degrades its pool
+    fn critical_device_health() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("pool", &[Path::new("/s/a"), Path::new("/s/b")], &[], None, false)
+            .unwrap();
+        engine
+            .pools
+            .get_mut_by_uuid(&uuid)
+            .unwrap()
+            .set_blockdev_health(Path::new("/s/a"), 97, BlockDevHealth::Critical)
+            .unwrap();
+
+        let pool = engine.get_pool(&uuid).unwrap();
+        let (_, bd) = pool.blockdevs()
+            .into_iter()
+            .find(|&(_, bd)| bd.devnode() == Path::new("/s/a"))
+            .unwrap();
+        assert!(bd.wear_percent() == 97);
+        assert!(bd.health() == BlockDevHealth::Critical);
+        assert!(pool.state() == PoolState::Degraded);
+    }
+
+    #[test]
+    /// After a blockdev is added to a pool whose blockdev is full,
+    /// rebalancing spreads the data evenly, and a second rebalance does
+    /// nothing
+    fn rebalance_after_add() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("pool", &[Path::new("/s/a")], &[], None, false)
+            .unwrap();
+        let fs_uuid = engine
+            .get_mut_pool(&uuid)
+            .unwrap()
+            .create_filesystems(&[("fs", None)])
+            .unwrap()[0]
+            .1;
+        let size = engine.get_pool(&uuid).unwrap().blockdevs()[0].1.size();
+        engine
+            .pools
+            .get_mut_by_uuid(&uuid)
+            .unwrap()
+            .set_blockdev_used(Path::new("/s/a"), size)
+            .unwrap();
+
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        pool.add_blockdevs(&[Path::new("/s/b")], false).unwrap();
+        assert!(pool.rebalance().unwrap());
+        assert!(pool.blockdevs()
+                    .iter()
+                    .all(|&(_, bd)| bd.used() == Sectors(*size / 2)));
+        assert!(!pool.rebalance().unwrap());
+        assert!(pool.get_filesystem(&fs_uuid).is_some());
+    }
+
+    #[test]
+    /// The data in a compressed pool is modeled as using a fixed fraction
+    /// of its size, while the metadata is not compressed
+    fn compression_reduces_used() {
+        let mut engine = SimEngine::default();
+        let (pool_uuid, _) = engine.create_pool("pool", &[], &[], None, false).unwrap();
+        let pool = engine.pools.get_mut_by_uuid(&pool_uuid).unwrap();
+        let fs_uuid = pool.create_filesystems(&[("fs", None)]).unwrap()[0].1;
+        let metadata = pool.total_physical_used().unwrap();
+        pool.filesystems
+            .get_mut_by_uuid(&fs_uuid)
+            .unwrap()
+            .set_used(Sectors(3072));
+        assert!(pool.total_physical_used().unwrap() == metadata + Sectors(3072));
+
+        assert!(pool.set_compression(Compression::Zstd).unwrap());
+        assert!(!pool.set_compression(Compression::Zstd).unwrap());
+        assert!(pool.compression() == Compression::Zstd);
+        assert!(pool.total_physical_used().unwrap() == metadata + Sectors(1024));
+    }
+
+    #[test]
+    /// An alert is raised once usage reaches the usage alarm threshold, and
+    /// not again until usage has fallen below it and risen again
+    fn usage_alarm_raised() {
+        let mut engine = SimEngine::default();
+        let (pool_uuid, _) = engine.create_pool("pool", &[], &[], None, false).unwrap();
+        let fs_uuid = {
+            let pool = engine.pools.get_mut_by_uuid(&pool_uuid).unwrap();
+            assert!(pool.set_usage_alarm(101).is_err());
+            assert!(pool.set_usage_alarm(80).unwrap());
+            assert!(!pool.set_usage_alarm(80).unwrap());
+            assert!(pool.usage_alarm() == 80);
+            pool.create_filesystems(&[("fs", None)]).unwrap()[0].1
+        };
+        engine.check();
+        assert!(engine.alerts().is_empty());
+
+        let set_used = |engine: &mut SimEngine, percent: u64| {
+            let pool = engine.pools.get_mut_by_uuid(&pool_uuid).unwrap();
+            let used = Sectors(*pool.total_physical_size() / 100 * percent);
+            pool.filesystems
+                .get_mut_by_uuid(&fs_uuid)
+                .unwrap()
+                .set_used(used);
+        };
+        set_used(&mut engine, 85);
+        engine.check();
+        engine.check();
+        let alerts = engine.alerts();
+        assert!(alerts.len() == 1);
+        assert!(alerts[0].message.contains("80%"));
+
+        set_used(&mut engine, 50);
+        engine.check();
+        set_used(&mut engine, 90);
+        engine.check();
+        assert!(engine.alerts().len() == 2);
+
+        engine
+            .pools
+            .get_mut_by_uuid(&pool_uuid)
+            .unwrap()
+            .set_usage_alarm(0)
+            .unwrap();
+        set_used(&mut engine, 50);
+        engine.check();
+        set_used(&mut engine, 90);
+        engine.check();
+        assert!(engine.alerts().len() == 2);
+    }
+
+    #[test]
+    /// The snapshot usage of a pool counts its snapshots and sums the data
+    /// stored in them, but not in the filesystems of which they are
+    /// snapshots
+    fn snapshot_usage_summed() {
+        let mut engine = SimEngine::default();
+        let (pool_uuid, _) = engine.create_pool("pool", &[], &[], None, false).unwrap();
+        let origin_uuid = engine
+            .pools
+            .get_mut_by_uuid(&pool_uuid)
+            .unwrap()
+            .create_filesystems(&[("origin", None)])
+            .unwrap()[0]
+            .1;
+        assert!(engine.get_pool(&pool_uuid).unwrap().snapshot_usage().unwrap() ==
+                (0, Sectors(0)));
+
+        let snapshots = ["hourly", "daily"]
+            .iter()
+            .map(|name| {
+                     engine
+                         .create_snapshot(&pool_uuid, &origin_uuid, &pool_uuid, name)
+                         .unwrap()
+                 })
+            .collect::<Vec<_>>();
+        let pool = engine.pools.get_mut_by_uuid(&pool_uuid).unwrap();
+        for (&uuid, &used) in [origin_uuid, snapshots[0], snapshots[1]]
+                .iter()
+                .zip(&[Sectors(4096), Sectors(512), Sectors(1024)]) {
+            pool.filesystems.get_mut_by_uuid(&uuid).unwrap().set_used(used);
+        }
+        assert!(pool.snapshot_usage().unwrap() == (2, Sectors(1536)));
+    }
+
+    #[test]
+    /// New filesystems in a Striped pool are spread over every blockdev,
+    /// keeping any imbalance, while in a MostFree pool they go to the
+    /// blockdev with the most free space, evening it out
+    fn allocation_policy_distributes() {
+        let distribution = |policy: AllocationPolicy| {
+            let mut engine = SimEngine::default();
+            let (uuid, _) = engine
+                .create_pool("pool", &[Path::new("/s/a"), Path::new("/s/b")], &[], None, false)
+                .unwrap();
+            let pool = engine.pools.get_mut_by_uuid(&uuid).unwrap();
+            assert!(pool.set_allocation_policy(policy).unwrap());
+            assert!(pool.allocation_policy() == policy);
+            let extent_size = pool.extent_size();
+            pool.set_blockdev_used(Path::new("/s/a"), extent_size * 2u64)
+                .unwrap();
+            pool.create_filesystems(&[("fs1", None), ("fs2", None)])
+                .unwrap();
+            let mut used = pool.blockdevs()
+                .into_iter()
+                .map(|(_, bd)| (bd.devnode(), bd.used() / extent_size))
+                .collect::<Vec<_>>();
+            used.sort();
+            used
+        };
+
+        assert!(distribution(AllocationPolicy::Striped) ==
+                vec![(PathBuf::from("/s/a"), 3), (PathBuf::from("/s/b"), 1)]);
+        assert!(distribution(AllocationPolicy::MostFree) ==
+                vec![(PathBuf::from("/s/a"), 2), (PathBuf::from("/s/b"), 2)]);
+    }
+
+    #[test]
+    /// A consistent engine reports no issues, a device in two pools is
+    /// reported
+    fn device_in_two_pools_reported() {
+        let mut engine = SimEngine::default();
+        engine
+            .create_pool("pool_a", &[Path::new("/s/a")], &[], None, false)
+            .unwrap();
+        let (uuid, _) = engine
+            .create_pool("pool_b", &[Path::new("/s/b")], &[], None, false)
+            .unwrap();
+        assert!(engine.check_consistency().is_empty());
+
+        let rdm = engine.rdm.clone();
+        engine
+            .pools
+            .get_mut_by_uuid(&uuid)
+            .unwrap()
+            .spare_devs
+            .insert(PathBuf::from("/s/a"), SimDev::new(rdm, Path::new("/s/a")));
+        let issues = engine.check_consistency();
+        assert!(issues.len() == 1);
+        assert!(issues[0].contains("/s/a"));
+    }
+
+    #[test]
+    /// A state in which two devices claim the same UUID is loaded, but the
+    /// conflict is reported
+    fn duplicate_device_uuid_reported() {
+        let mut engine = SimEngine::default();
+        engine
+            .create_pool("pool_a", &[Path::new("/s/a")], &[], None, false)
+            .unwrap();
+        engine
+            .create_pool("pool_b", &[Path::new("/s/b")], &[], None, false)
+            .unwrap();
+        let mut dump = EngineDump::new(&engine, false);
+        dump.pools[1].blockdevs[0].uuid = dump.pools[0].blockdevs[0].uuid;
+
+        let mut engine = SimEngine::default();
+        engine.load_state(&dump, false).unwrap();
+        let issues = engine.check_consistency();
+        assert!(issues.len() == 1);
+        assert!(issues[0].contains(&dump.pools[0].blockdevs[0].uuid.to_string()));
+        assert!(issues[0].contains("/s/a") && issues[0].contains("/s/b"));
+    }
+
+    #[test]
+    /// Rescanning the simulator succeeds, and sets up no pools
+    fn rescan_no_op() {
+        let mut engine = SimEngine::default();
+        engine
+            .create_pool("pool", &[Path::new("/s/a")], &[], None, false)
+            .unwrap();
+        assert!(engine.rescan().unwrap().is_empty());
+        assert!(engine.pools().len() == 1);
+    }
+
+    #[test]
+    /// The simulator supports snapshots, but not an unknown feature
+    fn supports_features() {
+        let engine = SimEngine::default();
+        assert!(engine.supports("snapshots"));
+        assert!(!engine.supports("bogus"));
+    }
+
+    #[test]
+    /// The space which may be allocated excludes both the data already
+    /// stored in the pool's filesystems and the free-space reservation
+    fn allocatable_space_excludes_used_and_reserved() {
+        let mut engine = SimEngine::default();
+        let (pool_uuid, _) = engine.create_pool("pool", &[], &[], None, false).unwrap();
+        let fs_uuid = engine
+            .get_mut_pool(&pool_uuid)
+            .unwrap()
+            .create_filesystems(&[("fs", None)])
+            .unwrap()[0]
+            .1;
+        let free = {
+            let pool = engine.get_pool(&pool_uuid).unwrap();
+            pool.total_physical_size() - pool.total_physical_used().unwrap()
+        };
+        assert!(engine
+                    .get_pool(&pool_uuid)
+                    .unwrap()
+                    .allocatable_space()
+                    .unwrap() == free);
+
+        engine
+            .pools
+            .get_mut_by_uuid(&pool_uuid)
+            .unwrap()
+            .filesystems
+            .get_mut_by_uuid(&fs_uuid)
+            .unwrap()
+            .set_used(Sectors(2048));
+        let pool = engine.get_mut_pool(&pool_uuid).unwrap();
+        pool.set_reserved_free(Sectors(1024)).unwrap();
+        assert!(pool.allocatable_space().unwrap() == free - Sectors(2048) - Sectors(1024));
+    }
+
+    #[test]
+    /// A data blockdev whose data does not fit on the others can not be
+    /// removed; once it fits, removal moves its filesystems' data to them
+    fn blockdev_removal_needs_room() {
+        let mut engine = SimEngine::default();
+        let (pool_uuid, _) = engine
+            .create_pool("pool", &[Path::new("/s/a"), Path::new("/s/b")], &[], None, false)
+            .unwrap();
+        let fs_uuid = engine
+            .get_mut_pool(&pool_uuid)
+            .unwrap()
+            .create_filesystems(&[("fs", None)])
+            .unwrap()[0]
+            .1;
+        let (uuid_a, uuid_b) = {
+            let pool = engine.pools.get_mut_by_uuid(&pool_uuid).unwrap();
+            let size = pool.block_devs[Path::new("/s/b")].size();
+            pool.block_devs.get_mut(Path::new("/s/b")).unwrap().used = size;
+            (pool.block_devs[Path::new("/s/a")].uuid, pool.block_devs[Path::new("/s/b")].uuid)
+        };
+
+        let pool = engine.get_mut_pool(&pool_uuid).unwrap();
+        assert!(pool.blockdev_filesystems(&uuid_a).unwrap() == vec![fs_uuid]);
+        assert!(pool.blockdev_filesystems(&uuid_b).unwrap().is_empty());
+        assert!(!pool.can_remove_blockdev(&uuid_a).unwrap());
+        assert!(match pool.remove_blockdev(&uuid_a) {
+                    Err(EngineError::Engine(ErrorEnum::NoSpace, _)) => true,
+                    _ => false,
+                });
+        assert!(pool.can_remove_blockdev(&Uuid::new_v4()).is_err());
+
+        let pool = engine.pools.get_mut_by_uuid(&pool_uuid).unwrap();
+        pool.block_devs.get_mut(Path::new("/s/b")).unwrap().used = Sectors(0);
+        assert!(pool.can_remove_blockdev(&uuid_a).unwrap());
+        assert!(pool.remove_blockdev(&uuid_a).unwrap());
+        assert!(pool.blockdev_filesystems(&uuid_b).unwrap() == vec![fs_uuid]);
+        assert!(!pool.can_remove_blockdev(&uuid_b).unwrap());
+    }
 }
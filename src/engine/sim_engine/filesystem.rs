@@ -2,23 +2,75 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use devicemapper::Sectors;
+
+use super::super::dump::FilesystemDump;
 use super::super::engine::{HasName, HasUuid, Filesystem};
-use super::super::errors::EngineResult;
-use super::super::types::FilesystemUuid;
+use super::super::errors::{EngineError, EngineResult, ErrorEnum};
+use super::super::types::{FilesystemUuid, PoolUuid};
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct SimFilesystem {
     fs_id: FilesystemUuid,
     name: String,
+    origin: Option<(PoolUuid, FilesystemUuid)>,
+    fs_type: String,
+    snapshot_schedule: Option<String>,
+    mountpoint: Option<PathBuf>,
+    io_limits: (u64, u64),
+    size: Sectors,
+    used: Sectors,
 }
 
 impl SimFilesystem {
-    pub fn new(fs_id: FilesystemUuid, name: &str) -> SimFilesystem {
+    pub fn new(fs_id: FilesystemUuid, name: &str, size: Sectors) -> SimFilesystem {
+        SimFilesystem {
+            fs_id: fs_id,
+            name: name.to_owned(),
+            origin: None,
+            fs_type: "xfs".into(),
+            snapshot_schedule: None,
+            mountpoint: None,
+            io_limits: (0, 0),
+            size: size,
+            used: Sectors(0),
+        }
+    }
+
+    /// A new filesystem which is a snapshot of the filesystem origin.
+    pub fn new_snapshot(fs_id: FilesystemUuid,
+                        name: &str,
+                        origin: (PoolUuid, FilesystemUuid),
+                        size: Sectors)
+                        -> SimFilesystem {
         SimFilesystem {
             fs_id: fs_id,
             name: name.to_owned(),
+            origin: Some(origin),
+            fs_type: "xfs".into(),
+            snapshot_schedule: None,
+            mountpoint: None,
+            io_limits: (0, 0),
+            size: size,
+            used: Sectors(0),
+        }
+    }
+
+    /// A filesystem as described by dump. The data stored in a filesystem
+    /// is not dumped, so it holds none.
+    pub fn from_dump(dump: &FilesystemDump) -> SimFilesystem {
+        SimFilesystem {
+            fs_id: dump.uuid,
+            name: dump.name.clone(),
+            origin: dump.origin,
+            fs_type: dump.fs_type.clone(),
+            snapshot_schedule: dump.snapshot_schedule.clone(),
+            mountpoint: dump.mountpoint.clone(),
+            io_limits: dump.io_limits,
+            size: dump.size,
+            used: Sectors(0),
         }
     }
 
@@ -26,12 +78,106 @@ impl SimFilesystem {
     pub fn rename(&mut self, name: &str) {
         self.name = name.to_owned();
     }
+
+    /// A new filesystem with the same contents as source.
+    pub fn new_clone(fs_id: FilesystemUuid, name: &str, source: &SimFilesystem) -> SimFilesystem {
+        SimFilesystem {
+            fs_id: fs_id,
+            name: name.to_owned(),
+            origin: None,
+            fs_type: source.fs_type.clone(),
+            snapshot_schedule: None,
+            mountpoint: None,
+            io_limits: (0, 0),
+            size: source.size,
+            used: source.used,
+        }
+    }
+
+    /// Make the contents of this filesystem the same as those of snapshot.
+    pub fn restore(&mut self, snapshot: &SimFilesystem) {
+        self.fs_type = snapshot.fs_type.clone();
+        self.size = snapshot.size;
+        self.used = snapshot.used;
+    }
+
+    /// Set the amount of data stored in the filesystem, which the
+    /// simulator does not otherwise change.
+    #[cfg(test)]
+    pub fn set_used(&mut self, used: Sectors) {
+        self.used = used;
+    }
 }
 
 impl Filesystem for SimFilesystem {
     fn devnode(&self) -> EngineResult<PathBuf> {
         Ok(PathBuf::from(format!("/dev/stratis/{}", self.name)))
     }
+
+    fn origin(&self) -> Option<(PoolUuid, FilesystemUuid)> {
+        self.origin
+    }
+
+    fn fs_type(&self) -> &str {
+        &self.fs_type
+    }
+
+    fn set_fs_type(&mut self, fs_type: &str) -> EngineResult<()> {
+        self.fs_type = fs_type.to_owned();
+        Ok(())
+    }
+
+    fn snapshot_schedule(&self) -> Option<&str> {
+        self.snapshot_schedule.as_ref().map(|s| s.as_str())
+    }
+
+    fn set_snapshot_schedule(&mut self, schedule: &str) -> EngineResult<bool> {
+        if schedule.is_empty() {
+            return Err(EngineError::Engine(ErrorEnum::Invalid,
+                                           "snapshot schedule may not be empty".into()));
+        }
+        if self.snapshot_schedule() == Some(schedule) {
+            return Ok(false);
+        }
+        self.snapshot_schedule = Some(schedule.to_owned());
+        Ok(true)
+    }
+
+    fn mountpoint(&self) -> Option<&Path> {
+        self.mountpoint.as_ref().map(|p| p.as_path())
+    }
+
+    fn set_mountpoint(&mut self, mountpoint: &Path) -> EngineResult<bool> {
+        if !mountpoint.is_absolute() {
+            let message = format!("mount point {} is not absolute", mountpoint.display());
+            return Err(EngineError::Engine(ErrorEnum::Invalid, message));
+        }
+        if self.mountpoint() == Some(mountpoint) {
+            return Ok(false);
+        }
+        self.mountpoint = Some(mountpoint.to_path_buf());
+        Ok(true)
+    }
+
+    fn io_limits(&self) -> (u64, u64) {
+        self.io_limits
+    }
+
+    fn set_io_limits(&mut self, read_iops: u64, write_iops: u64) -> EngineResult<bool> {
+        if self.io_limits == (read_iops, write_iops) {
+            return Ok(false);
+        }
+        self.io_limits = (read_iops, write_iops);
+        Ok(true)
+    }
+
+    fn size(&self) -> Sectors {
+        self.size
+    }
+
+    fn used(&self) -> EngineResult<Sectors> {
+        Ok(self.used)
+    }
 }
 
 impl HasName for SimFilesystem {
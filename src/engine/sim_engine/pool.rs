@@ -9,36 +9,107 @@ use std::iter::FromIterator;
 use std::path::Path;
 use std::path::PathBuf;
 use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::vec::Vec;
 
 use uuid::Uuid;
 
 use devicemapper::Sectors;
+use devicemapper::consts::SECTOR_SIZE;
 
-use super::super::consts::IEC;
-use super::super::engine::{Filesystem, HasName, HasUuid, Pool};
+use super::super::consts::{DEFAULT_EXTENT_SIZE, DEFAULT_MAX_FILESYSTEMS, DEFAULT_MAX_SNAPSHOTS,
+                           IEC, MAX_DESCRIPTION_LEN};
+use super::super::dump::PoolDump;
+use super::super::engine::{Dev, Filesystem, HasName, HasUuid, Pool};
 use super::super::errors::{EngineError, EngineResult, ErrorEnum};
-use super::super::structures::Table;
-use super::super::types::{FilesystemUuid, PoolUuid, RenameAction, Redundancy};
+use super::super::structures::{AlertLog, Table};
+use super::super::types::{AlertSeverity, AllocationPolicy, BlockDevHealth, BlockDevState,
+                          BlockDevTier, CacheMode, Compression, DevUuid, FilesystemUuid,
+                          PoolIoStats, PoolState, PoolUuid, RenameAction, Redundancy,
+                          round_to_extent};
 
 use super::blockdev::SimDev;
 use super::filesystem::SimFilesystem;
 use super::randomization::Randomizer;
 
-#[derive(Debug)]
+/// The metadata reservation of a newly created pool.
+const DEFAULT_METADATA_SIZE: Sectors = Sectors(16 * IEC::Mi / (SECTOR_SIZE as u64)); // = 16 MiB
+
+/// The metadata used on behalf of each filesystem in the pool.
+const FILESYSTEM_METADATA_SIZE: Sectors = Sectors(IEC::Mi / (SECTOR_SIZE as u64)); // = 1 MiB
+
+/// The size of a filesystem for which no size is specified.
+const DEFAULT_FILESYSTEM_SIZE: Sectors = Sectors(2 * IEC::Gi); // = 1 TiB
+
+/// The fraction of its size which filesystem data compressed with
+/// compression is modeled as occupying, as a numerator and a denominator.
+fn compressed_fraction(compression: Compression) -> (u64, u64) {
+    match compression {
+        Compression::None => (1, 1),
+        Compression::Lz4 => (1, 2),
+        Compression::Zstd => (1, 3),
+    }
+}
+
+/// The variant of some enum, given by variants, which corresponds to code.
+/// Returns an error naming kind, the kind of the enum, if none does.
+fn from_code<T, I>(mut variants: I, code: u16, kind: &str) -> EngineResult<T>
+    where I: Iterator<Item = T>
+{
+    variants
+        .nth(code as usize)
+        .ok_or_else(|| {
+                        let message = format!("code {} does not correspond to any {}", code, kind);
+                        EngineError::Engine(ErrorEnum::Invalid, message)
+                    })
+}
+
+#[derive(Clone, Debug)]
 pub struct SimPool {
     name: String,
     pool_uuid: PoolUuid,
     pub block_devs: HashMap<PathBuf, SimDev>,
+    pub cache_devs: HashMap<PathBuf, SimDev>,
+    pub spare_devs: HashMap<PathBuf, SimDev>,
     pub filesystems: Table<SimFilesystem>,
     redundancy: Redundancy,
+    created_at: u64,
+    physical_size: Sectors,
+    metadata_size: Sectors,
+    extent_size: Sectors,
+    io_stats: PoolIoStats,
+    read_only: bool,
+    imported: bool,
+    cache_mode: CacheMode,
+    cache_enabled: bool,
+    compression: Compression,
+    allocation_policy: AllocationPolicy,
+    max_filesystems: u32,
+    max_snapshots: u32,
+    reserved_free: Sectors,
+    usage_alarm: u16,
+    /// Whether the usage alarm has been raised since usage last fell below
+    /// its threshold, so that it is raised only once for each crossing.
+    usage_alarm_raised: bool,
+    key_description: Option<String>,
+    description: String,
+    tags: HashSet<String>,
+    /// The filesystems in the order of their first extents. A destroyed
+    /// filesystem leaves a hole, which the next filesystem created fills.
+    extents: Vec<Option<FilesystemUuid>>,
+    /// The data blockdevs on which each filesystem's data is modeled.
+    placement: HashMap<FilesystemUuid, Vec<PathBuf>>,
     rdm: Rc<RefCell<Randomizer>>,
+    alerts: Rc<RefCell<AlertLog>>,
 }
 
 impl SimPool {
     pub fn new(rdm: Rc<RefCell<Randomizer>>,
+               alerts: Rc<RefCell<AlertLog>>,
+               pool_uuid: PoolUuid,
                name: &str,
                paths: &[&Path],
+               cache_paths: &[&Path],
                redundancy: Redundancy)
                -> SimPool {
 
@@ -46,25 +117,340 @@ impl SimPool {
         let device_pairs = devices
             .iter()
             .map(|p| (p.to_path_buf(), SimDev::new(rdm.clone(), p)));
+        let cache_devices: HashSet<_, RandomState> = HashSet::from_iter(cache_paths);
+        let cache_device_pairs = cache_devices
+            .iter()
+            .map(|p| (p.to_path_buf(), SimDev::new(rdm.clone(), p)));
         SimPool {
             name: name.to_owned(),
-            pool_uuid: Uuid::new_v4(),
+            pool_uuid: pool_uuid,
             block_devs: HashMap::from_iter(device_pairs),
+            cache_devs: HashMap::from_iter(cache_device_pairs),
+            spare_devs: HashMap::new(),
             filesystems: Table::default(),
             redundancy: redundancy,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("the present is later than the Unix epoch")
+                .as_secs(),
+            // We choose to make our pools very big, and we can change that
+            // if it is inconvenient.
+            physical_size: Sectors(IEC::Ei),
+            metadata_size: DEFAULT_METADATA_SIZE,
+            extent_size: DEFAULT_EXTENT_SIZE,
+            io_stats: PoolIoStats::default(),
+            read_only: false,
+            imported: false,
+            cache_mode: CacheMode::WriteThrough,
+            cache_enabled: true,
+            compression: Compression::None,
+            allocation_policy: AllocationPolicy::Linear,
+            max_filesystems: DEFAULT_MAX_FILESYSTEMS,
+            max_snapshots: DEFAULT_MAX_SNAPSHOTS,
+            reserved_free: Sectors(0),
+            usage_alarm: 0,
+            usage_alarm_raised: false,
+            key_description: None,
+            description: String::new(),
+            tags: HashSet::new(),
+            extents: Vec::new(),
+            placement: HashMap::new(),
             rdm: rdm.clone(),
+            alerts: alerts,
         }
     }
 
-    pub fn check(&mut self) -> () {}
+    /// A pool as described by dump. The state and health of devices are
+    /// not dumped, so every device is good.
+    /// Returns an error if dump contains a code which stands for nothing.
+    pub fn from_dump(rdm: Rc<RefCell<Randomizer>>,
+                     alerts: Rc<RefCell<AlertLog>>,
+                     dump: &PoolDump)
+                     -> EngineResult<SimPool> {
+        let redundancy =
+            try!(from_code(Redundancy::iter_variants(), dump.redundancy, "redundancy"));
+        let mut pool = SimPool::new(rdm.clone(),
+                                    alerts,
+                                    dump.uuid,
+                                    &dump.name,
+                                    &[],
+                                    &[],
+                                    redundancy);
+        pool.created_at = dump.created_at;
+        pool.physical_size = dump.total_physical_size;
+        pool.metadata_size = dump.metadata_size;
+        pool.extent_size = dump.extent_size;
+        pool.read_only = dump.read_only && !dump.imported;
+        pool.imported = dump.imported;
+        pool.cache_mode =
+            try!(from_code(CacheMode::iter_variants(), dump.cache_mode, "cache mode"));
+        pool.cache_enabled = dump.cache_enabled;
+        pool.compression =
+            try!(from_code(Compression::iter_variants(), dump.compression, "compression"));
+        pool.allocation_policy = try!(from_code(AllocationPolicy::iter_variants(),
+                                                dump.allocation_policy,
+                                                "allocation policy"));
+        pool.key_description = dump.key_description.clone();
+        pool.description = dump.description.clone();
+        pool.tags = dump.tags.iter().cloned().collect();
+
+        for blockdev in &dump.blockdevs {
+            let mut dev = SimDev::new(rdm.clone(), &blockdev.devnode);
+            dev.uuid = blockdev.uuid;
+            let tier = try!(from_code(BlockDevTier::iter_variants(), blockdev.tier, "tier"));
+            let devs = match tier {
+                BlockDevTier::Data => &mut pool.block_devs,
+                BlockDevTier::Cache => &mut pool.cache_devs,
+                BlockDevTier::Spare => &mut pool.spare_devs,
+            };
+            devs.insert(blockdev.devnode.clone(), dev);
+        }
+        for filesystem in &dump.filesystems {
+            pool.filesystems.insert(SimFilesystem::from_dump(filesystem));
+        }
+        Ok(pool)
+    }
+
+    pub fn check(&mut self) -> () {
+        self.check_usage_alarm();
+    }
 
     pub fn has_filesystems(&self) -> bool {
         !self.filesystems.is_empty()
     }
+
+    /// Whether the device at path belongs to the pool in any role.
+    pub fn has_device(&self, path: &Path) -> bool {
+        self.block_devs.contains_key(path) || self.cache_devs.contains_key(path) ||
+        self.spare_devs.contains_key(path)
+    }
+
+    /// Model the allocation of amount to the pool's working blockdevs as
+    /// its allocation policy directs. Blockdevs are considered in order of
+    /// device node, and the first is preferred where the policy leaves a
+    /// choice.
+    /// Returns the blockdevs to which some of amount was allocated.
+    fn allocate(&mut self, amount: Sectors) -> Vec<PathBuf> {
+        let mut paths = self.block_devs
+            .iter()
+            .filter(|&(_, bd)| bd.state != BlockDevState::Failed)
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+        if paths.is_empty() {
+            return vec![];
+        }
+        paths.sort();
+
+        let free = |path: &PathBuf| {
+            let bd = &self.block_devs[path];
+            if bd.used < bd.size() {
+                bd.size() - bd.used
+            } else {
+                Sectors(0)
+            }
+        };
+        let shares = match self.allocation_policy {
+            AllocationPolicy::Linear => {
+                let path = paths
+                    .iter()
+                    .find(|path| free(path) >= amount)
+                    .unwrap_or(&paths[0]);
+                vec![(path.clone(), amount)]
+            }
+            AllocationPolicy::Striped => {
+                let count = paths.len() as u64;
+                paths
+                    .iter()
+                    .enumerate()
+                    .map(|(index, path)| {
+                             let extra = if (index as u64) < *amount % count { 1 } else { 0 };
+                             (path.clone(), Sectors(*amount / count + extra))
+                         })
+                    .collect()
+            }
+            AllocationPolicy::MostFree => {
+                let mut path = &paths[0];
+                for candidate in &paths {
+                    if free(candidate) > free(path) {
+                        path = candidate;
+                    }
+                }
+                vec![(path.clone(), amount)]
+            }
+        };
+
+        let mut allocated = Vec::new();
+        for (path, share) in shares {
+            if share == Sectors(0) {
+                continue;
+            }
+            {
+                let bd = self.block_devs
+                    .get_mut(&path)
+                    .expect("path is a key of self.block_devs");
+                bd.used = bd.used + share;
+            }
+            allocated.push(path);
+        }
+        allocated
+    }
+
+    /// The amount of free space on the working data blockdevs other than
+    /// the one at path.
+    fn free_elsewhere(&self, path: &Path) -> Sectors {
+        self.block_devs
+            .values()
+            .filter(|bd| bd.devnode != path && bd.state != BlockDevState::Failed)
+            .map(|bd| if bd.used < bd.size() {
+                     bd.size() - bd.used
+                 } else {
+                     Sectors(0)
+                 })
+            .fold(Sectors(0), |total, free| total + free)
+    }
+
+    /// The device node of the blockdev with this UUID.
+    /// Returns an error if the pool has no such blockdev.
+    fn blockdev_devnode(&self, uuid: &DevUuid) -> EngineResult<PathBuf> {
+        self.blockdevs()
+            .iter()
+            .find(|&&(_, bd)| bd.uuid() == uuid)
+            .map(|&(_, bd)| bd.devnode())
+            .ok_or_else(|| EngineError::Engine(ErrorEnum::NotFound, format!("blockdev {}", uuid)))
+    }
+
+    /// Check that the data blockdev at path may be removed: the pool must
+    /// be left with as many good data blockdevs as its redundancy requires,
+    /// and the others must have room for the data on it.
+    fn check_data_removal(&self, path: &Path) -> EngineResult<()> {
+        let remaining = self.block_devs
+            .values()
+            .filter(|other| other.devnode != path && other.state == BlockDevState::Good)
+            .count();
+        if remaining < self.redundancy.min_blockdevs() {
+            let message = format!("removing {} would leave too few data devices",
+                                  path.display());
+            return Err(EngineError::Engine(ErrorEnum::Busy, message));
+        }
+        if self.block_devs[path].used > self.free_elsewhere(path) {
+            let message = format!("the other data devices have no room for the data on {}",
+                                  path.display());
+            return Err(EngineError::Engine(ErrorEnum::NoSpace, message));
+        }
+        Ok(())
+    }
+
+    /// Raise an alert if the pool, which was in state previous, has
+    /// become degraded or gone offline.
+    fn alert_state_change(&self, previous: PoolState) {
+        let state = self.state();
+        if state == previous {
+            return;
+        }
+        let severity = match state {
+            PoolState::Degraded => AlertSeverity::Warning,
+            PoolState::Offline => AlertSeverity::Critical,
+            _ => return,
+        };
+        let message = format!("pool {} is {}", self.name, state.to_string().to_lowercase());
+        self.alerts.borrow_mut().raise(severity, message);
+    }
+
+    /// Raise an alert if the pool's usage has risen to its usage alarm
+    /// threshold since the last check.
+    fn check_usage_alarm(&mut self) {
+        let used = match self.total_physical_used() {
+            Ok(used) => used,
+            Err(_) => return,
+        };
+        let percent = *used as f64 * 100.0 / *self.physical_size as f64;
+        if self.usage_alarm == 0 || percent < f64::from(self.usage_alarm) {
+            self.usage_alarm_raised = false;
+            return;
+        }
+        if self.usage_alarm_raised {
+            return;
+        }
+        self.usage_alarm_raised = true;
+        let message = format!("pool {} is {:.0}% full, reaching its usage alarm of {}%",
+                              self.name,
+                              percent,
+                              self.usage_alarm);
+        self.alerts
+            .borrow_mut()
+            .raise(AlertSeverity::Warning, message);
+    }
+
+    /// Set the pool's I/O statistics, which the simulator does not
+    /// otherwise change.
+    #[cfg(test)]
+    pub fn set_io_stats(&mut self, io_stats: PoolIoStats) {
+        self.io_stats = io_stats;
+    }
+
+    /// Set the pool's physical size, which the simulator does not
+    /// otherwise change.
+    #[cfg(test)]
+    pub fn set_physical_size(&mut self, size: Sectors) {
+        self.physical_size = size;
+    }
+
+    /// Set the wear and health of the device at path, which a real engine
+    /// would obtain from the device's SMART data.
+    #[cfg(test)]
+    pub fn set_blockdev_health(&mut self,
+                               path: &Path,
+                               wear_percent: u16,
+                               health: BlockDevHealth)
+                               -> EngineResult<()> {
+        let (block_devs, cache_devs, spare_devs) =
+            (&mut self.block_devs, &mut self.cache_devs, &mut self.spare_devs);
+        let bd = try!(block_devs
+                          .get_mut(path)
+                          .or_else(|| cache_devs.get_mut(path))
+                          .or_else(|| spare_devs.get_mut(path))
+                          .ok_or_else(|| {
+                                          EngineError::Engine(ErrorEnum::NotFound,
+                                                              format!("{}", path.display()))
+                                      }));
+        bd.wear_percent = wear_percent;
+        bd.health = health;
+        Ok(())
+    }
+
+    /// Set the amount of the data device at path which holds the pool's
+    /// data, as if data had been written to it.
+    #[cfg(test)]
+    pub fn set_blockdev_used(&mut self, path: &Path, used: Sectors) -> EngineResult<()> {
+        let bd = try!(self.block_devs
+                          .get_mut(path)
+                          .ok_or_else(|| {
+                                          EngineError::Engine(ErrorEnum::NotFound,
+                                                              format!("{}", path.display()))
+                                      }));
+        bd.used = used;
+        Ok(())
+    }
+
+    /// The amount of the metadata reservation currently in use.
+    fn metadata_used(&self) -> Sectors {
+        FILESYSTEM_METADATA_SIZE * self.filesystems.len()
+    }
+
+    /// The amount of free space which may be allocated without dipping
+    /// into the free-space reservation.
+    fn available(&self) -> EngineResult<Sectors> {
+        let unavailable = try!(self.total_physical_used()) + self.reserved_free;
+        let size = self.total_physical_size();
+        Ok(if unavailable < size { size - unavailable } else { Sectors(0) })
+    }
 }
 
 impl Pool for SimPool {
     fn add_blockdevs(&mut self, paths: &[&Path], _force: bool) -> EngineResult<Vec<PathBuf>> {
+        check_writable!(self);
+
+        check_absolute_paths!(paths);
         let rdm = self.rdm.clone();
         let devices: HashSet<_, RandomState> = HashSet::from_iter(paths);
         let device_pairs = devices
@@ -74,15 +460,128 @@ impl Pool for SimPool {
         Ok(devices.iter().map(|d| d.to_path_buf()).collect())
     }
 
+    fn mark_blockdev_failed(&mut self, path: &Path) -> EngineResult<bool> {
+        check_writable!(self);
+
+        let state = self.state();
+        let bd = try!(self.block_devs
+                          .get_mut(path)
+                          .ok_or_else(|| {
+                                          EngineError::Engine(ErrorEnum::NotFound,
+                                                              format!("{}", path.display()))
+                                      }));
+        if bd.state == BlockDevState::Failed {
+            return Ok(false);
+        }
+        bd.state = BlockDevState::Failed;
+        self.alerts
+            .borrow_mut()
+            .raise(AlertSeverity::Critical,
+                   format!("device {} of pool {} failed", path.display(), self.name));
+
+        let spare_path = self.spare_devs.keys().min().cloned();
+        if let Some(spare_path) = spare_path {
+            let spare = self.spare_devs
+                .remove(&spare_path)
+                .expect("spare_path is a key of self.spare_devs");
+            self.block_devs.remove(path);
+            self.block_devs.insert(spare_path, spare);
+        }
+        self.alert_state_change(state);
+        Ok(true)
+    }
+
+    fn set_blockdev_read_only(&mut self, path: &Path, read_only: bool) -> EngineResult<bool> {
+        check_writable!(self);
+
+        let cache_devs = &mut self.cache_devs;
+        let spare_devs = &mut self.spare_devs;
+        let bd = try!(self.block_devs
+                          .get_mut(path)
+                          .or_else(|| cache_devs.get_mut(path))
+                          .or_else(|| spare_devs.get_mut(path))
+                          .ok_or_else(|| {
+                                          EngineError::Engine(ErrorEnum::NotFound,
+                                                              format!("{}", path.display()))
+                                      }));
+        if bd.read_only == read_only {
+            return Ok(false);
+        }
+        bd.read_only = read_only;
+        Ok(true)
+    }
+
+    fn add_spares(&mut self, paths: &[&Path], _force: bool) -> EngineResult<Vec<PathBuf>> {
+        check_writable!(self);
+
+        check_absolute_paths!(paths);
+        if let Some(path) = paths.iter().find(|p| self.has_device(p)) {
+            return Err(EngineError::Engine(ErrorEnum::AlreadyExists,
+                                           format!("{}", path.display())));
+        }
+        let rdm = self.rdm.clone();
+        let devices: HashSet<_, RandomState> = HashSet::from_iter(paths);
+        let device_pairs = devices
+            .iter()
+            .map(|p| (p.to_path_buf(), SimDev::new(rdm.clone(), p)));
+        self.spare_devs.extend(device_pairs);
+        Ok(devices.iter().map(|d| d.to_path_buf()).collect())
+    }
+
+    fn spares(&self) -> Vec<PathBuf> {
+        self.spare_devs.keys().cloned().collect()
+    }
+
+    fn replace_blockdev(&mut self,
+                        old_path: &Path,
+                        new_path: &Path,
+                        _force: bool)
+                        -> EngineResult<PathBuf> {
+        check_writable!(self);
+
+        match self.block_devs.get(old_path) {
+            Some(bd) => {
+                if bd.state != BlockDevState::Failed {
+                    let message = format!("blockdev {} has not failed", old_path.display());
+                    return Err(EngineError::Engine(ErrorEnum::Invalid, message));
+                }
+            }
+            None => {
+                return Err(EngineError::Engine(ErrorEnum::NotFound,
+                                               format!("{}", old_path.display())))
+            }
+        }
+
+        if self.has_device(new_path) {
+            return Err(EngineError::Engine(ErrorEnum::AlreadyExists,
+                                           format!("{}", new_path.display())));
+        }
+
+        self.block_devs.remove(old_path);
+        self.block_devs
+            .insert(new_path.to_path_buf(),
+                    SimDev::new(self.rdm.clone(), new_path));
+        Ok(new_path.to_path_buf())
+    }
+
     fn destroy_filesystems<'a, 'b>(&'a mut self,
                                    fs_uuids: &[&'b FilesystemUuid])
                                    -> EngineResult<Vec<&'b FilesystemUuid>> {
+        check_writable!(self);
+
         let mut removed = Vec::new();
         for uuid in fs_uuids {
             if self.filesystems.remove_by_uuid(uuid).is_some() {
+                self.placement.remove(uuid);
+                if let Some(extent) = self.extents.iter_mut().find(|e| **e == Some(**uuid)) {
+                    *extent = None;
+                }
                 removed.push(*uuid);
             }
         }
+        while self.extents.last() == Some(&None) {
+            self.extents.pop();
+        }
         Ok(removed)
     }
 
@@ -94,19 +593,48 @@ impl Pool for SimPool {
     fn create_filesystems<'a, 'b>(&'a mut self,
                                   specs: &[(&'b str, Option<Sectors>)])
                                   -> EngineResult<Vec<(&'b str, FilesystemUuid)>> {
+        check_writable!(self);
+
         let names: HashMap<_, _> = HashMap::from_iter(specs.iter().map(|&tup| (tup.0, tup.1)));
         for name in names.keys() {
             if self.filesystems.contains_name(name) {
                 return Err(EngineError::Engine(ErrorEnum::AlreadyExists, name.to_string()));
             }
         }
+        check_filesystem_limit!(self; names.len());
+
+        // Each new filesystem is given an extent of its own
+        let needed = self.extent_size * names.len() as u64;
+        let free = try!(self.available());
+        if needed > free {
+            let message = format!("{} new filesystems need {} sectors but only {} are free",
+                                  names.len(),
+                                  *needed,
+                                  *free);
+            return Err(EngineError::Engine(ErrorEnum::NoSpace, message));
+        }
+
+        let read_only_devs = self.block_devs.values().filter(|bd| bd.read_only).count();
+        if read_only_devs > 0 {
+            warn!("writing to pool {} with {} read-only data devices",
+                  self.name,
+                  read_only_devs);
+        }
 
         let mut result = Vec::new();
-        for name in names.keys() {
+        for (name, size) in names {
             let uuid = Uuid::new_v4();
-            let new_filesystem = SimFilesystem::new(uuid, name);
+            let size = round_to_extent(size.unwrap_or(DEFAULT_FILESYSTEM_SIZE), self.extent_size);
+            let new_filesystem = SimFilesystem::new(uuid, name, size);
             self.filesystems.insert(new_filesystem);
-            result.push((*name, uuid));
+            let extent_size = self.extent_size;
+            let paths = self.allocate(extent_size);
+            self.placement.insert(uuid, paths);
+            match self.extents.iter().position(|e| e.is_none()) {
+                Some(hole) => self.extents[hole] = Some(uuid),
+                None => self.extents.push(Some(uuid)),
+            }
+            result.push((name, uuid));
         }
 
         Ok(result)
@@ -116,6 +644,8 @@ impl Pool for SimPool {
                          uuid: &FilesystemUuid,
                          new_name: &str)
                          -> EngineResult<RenameAction> {
+        check_writable!(self);
+
         rename_filesystem_pre!(self; uuid; new_name);
 
         let mut filesystem =
@@ -128,6 +658,76 @@ impl Pool for SimPool {
         Ok(RenameAction::Renamed)
     }
 
+    fn restore_filesystem(&mut self,
+                          uuid: &FilesystemUuid,
+                          snapshot_uuid: &FilesystemUuid)
+                          -> EngineResult<()> {
+        check_writable!(self);
+
+        if self.filesystems.get_by_uuid(uuid).is_none() {
+            return Err(EngineError::Engine(ErrorEnum::NotFound, uuid.to_string()));
+        }
+
+        match self.filesystems.get_by_uuid(snapshot_uuid) {
+            Some(snapshot) => {
+                if snapshot.origin() != Some((self.pool_uuid, *uuid)) {
+                    let message = format!("filesystem {} is not a snapshot of filesystem {}",
+                                          snapshot_uuid,
+                                          uuid);
+                    return Err(EngineError::Engine(ErrorEnum::Invalid, message));
+                }
+            }
+            None => {
+                return Err(EngineError::Engine(ErrorEnum::NotFound, snapshot_uuid.to_string()))
+            }
+        }
+
+        let mut filesystem =
+            self.filesystems
+                .remove_by_uuid(uuid)
+                .expect("Must succeed since self.filesystems.get_by_uuid() returned a value");
+        filesystem.restore(self.filesystems
+                               .get_by_uuid(snapshot_uuid)
+                               .expect("snapshot is distinct from filesystem"));
+        self.filesystems.insert(filesystem);
+        Ok(())
+    }
+
+    fn clone_filesystem(&mut self,
+                        uuid: &FilesystemUuid,
+                        new_name: &str)
+                        -> EngineResult<FilesystemUuid> {
+        check_writable!(self);
+
+        if self.filesystems.contains_name(new_name) {
+            return Err(EngineError::Engine(ErrorEnum::AlreadyExists, new_name.into()));
+        }
+        check_filesystem_limit!(self; 1);
+
+        let free = try!(self.available());
+        let clone_uuid = Uuid::new_v4();
+        let clone = {
+            let source = try!(self.filesystems
+                                  .get_by_uuid(uuid)
+                                  .ok_or_else(|| {
+                                                  EngineError::Engine(ErrorEnum::NotFound,
+                                                                      uuid.to_string())
+                                              }));
+            let used = try!(source.used());
+            if used > free {
+                let message = format!("copy of filesystem {} needs {} sectors but only {} are \
+                                       free",
+                                      source.name(),
+                                      *used,
+                                      *free);
+                return Err(EngineError::Engine(ErrorEnum::NoSpace, message));
+            }
+            SimFilesystem::new_clone(clone_uuid, new_name, source)
+        };
+        self.filesystems.insert(clone);
+        Ok(clone_uuid)
+    }
+
     fn rename(&mut self, name: &str) {
         self.name = name.to_owned();
     }
@@ -145,13 +745,53 @@ impl Pool for SimPool {
     }
 
     fn total_physical_size(&self) -> Sectors {
-        // We choose to make our pools very big, and we can change that
-        // if it is inconvenient.
-        Sectors(IEC::Ei)
+        self.physical_size
     }
 
     fn total_physical_used(&self) -> EngineResult<Sectors> {
-        Ok(Sectors(0))
+        let mut data = Sectors(0);
+        for filesystem in &self.filesystems {
+            data = data + try!(filesystem.used());
+        }
+        let (numerator, denominator) = compressed_fraction(self.compression);
+        Ok(self.metadata_size + Sectors((*data * numerator + denominator - 1) / denominator))
+    }
+
+    fn space_shortfall(&self) -> EngineResult<Sectors> {
+        space_shortfall!(self)
+    }
+
+    fn snapshot_usage(&self) -> EngineResult<(usize, Sectors)> {
+        snapshot_usage!(self)
+    }
+
+    fn metadata_size(&self) -> Sectors {
+        self.metadata_size
+    }
+
+    fn set_metadata_size(&mut self, size: Sectors) -> EngineResult<bool> {
+        check_writable!(self);
+
+        if size < self.metadata_used() {
+            let message = format!("metadata reservation of {} sectors is less than the {} sectors \
+                                   in use",
+                                  *size,
+                                  *self.metadata_used());
+            return Err(EngineError::Engine(ErrorEnum::Invalid, message));
+        }
+        if size == self.metadata_size {
+            return Ok(false);
+        }
+        if size > self.metadata_size && size - self.metadata_size > try!(self.available()) {
+            let message = format!("metadata reservation of {} sectors would leave less than the \
+                                   {} sectors reserved free in pool {}",
+                                  *size,
+                                  *self.reserved_free,
+                                  self.name);
+            return Err(EngineError::Engine(ErrorEnum::NoSpace, message));
+        }
+        self.metadata_size = size;
+        Ok(true)
     }
 
     fn filesystems(&self) -> Vec<&Filesystem> {
@@ -160,6 +800,420 @@ impl Pool for SimPool {
             .map(|x| x as &Filesystem)
             .collect()
     }
+
+    fn blockdevs(&self) -> Vec<(BlockDevTier, &Dev)> {
+        let mut devs = Vec::new();
+        devs.extend(self.block_devs
+                        .values()
+                        .map(|bd| (BlockDevTier::Data, bd as &Dev)));
+        devs.extend(self.cache_devs
+                        .values()
+                        .map(|bd| (BlockDevTier::Cache, bd as &Dev)));
+        devs.extend(self.spare_devs
+                        .values()
+                        .map(|bd| (BlockDevTier::Spare, bd as &Dev)));
+        devs
+    }
+
+    fn remove_blockdev(&mut self, uuid: &DevUuid) -> EngineResult<bool> {
+        check_writable!(self);
+
+        let (tier, path) = match self.blockdevs()
+                  .iter()
+                  .find(|&&(_, bd)| bd.uuid() == uuid) {
+            Some(&(tier, bd)) => (tier, bd.devnode()),
+            None => return Ok(false),
+        };
+        if tier == BlockDevTier::Data {
+            try!(self.check_data_removal(&path));
+        }
+        self.cache_devs.remove(&path);
+        self.spare_devs.remove(&path);
+        if let Some(bd) = self.block_devs.remove(&path) {
+            let moved = self.allocate(bd.used);
+            for paths in self.placement.values_mut() {
+                if paths.contains(&path) {
+                    paths.retain(|p| *p != path);
+                    for moved_path in &moved {
+                        if !paths.contains(moved_path) {
+                            paths.push(moved_path.clone());
+                        }
+                    }
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    fn blockdev_filesystems(&self, uuid: &DevUuid) -> EngineResult<Vec<FilesystemUuid>> {
+        let path = try!(self.blockdev_devnode(uuid));
+        Ok(self.placement
+               .iter()
+               .filter(|&(_, paths)| paths.contains(&path))
+               .map(|(fs_uuid, _)| *fs_uuid)
+               .collect())
+    }
+
+    fn can_remove_blockdev(&self, uuid: &DevUuid) -> EngineResult<bool> {
+        let path = try!(self.blockdev_devnode(uuid));
+        Ok(!self.block_devs.contains_key(&path) || self.check_data_removal(&path).is_ok())
+    }
+
+    fn get_blockdev_path(&self, uuid: &DevUuid) -> Option<PathBuf> {
+        self.blockdev_devnode(uuid).ok()
+    }
+
+    fn state(&self) -> PoolState {
+        let failed = self.block_devs
+            .values()
+            .filter(|bd| bd.state == BlockDevState::Failed)
+            .count();
+        let critical = self.block_devs
+            .values()
+            .any(|bd| bd.health == BlockDevHealth::Critical);
+        if failed == 0 && !critical {
+            PoolState::Good
+        } else if failed < self.block_devs.len() {
+            PoolState::Degraded
+        } else {
+            PoolState::Offline
+        }
+    }
+
+    fn redundancy(&self) -> Redundancy {
+        self.redundancy
+    }
+
+    fn reshape(&mut self, redundancy: Redundancy) -> EngineResult<bool> {
+        check_writable!(self);
+
+        if !self.redundancy.can_reshape(redundancy, self.block_devs.len()) {
+            let message = format!("pool {} with {} blockdevs can not be reshaped from {} to {}",
+                                  self.name,
+                                  self.block_devs.len(),
+                                  self.redundancy,
+                                  redundancy);
+            return Err(EngineError::Engine(ErrorEnum::Error, message));
+        }
+        if self.redundancy == redundancy {
+            return Ok(false);
+        }
+        self.redundancy = redundancy;
+        Ok(true)
+    }
+
+    fn created_at(&self) -> u64 {
+        self.created_at
+    }
+
+    fn extent_size(&self) -> Sectors {
+        self.extent_size
+    }
+
+    fn io_stats(&self) -> PoolIoStats {
+        self.io_stats
+    }
+
+    fn read_only(&self) -> bool {
+        self.read_only || self.imported
+    }
+
+    fn set_read_only(&mut self, read_only: bool) -> EngineResult<bool> {
+        if !read_only && self.imported {
+            let message = format!("pool {} is imported and must be adopted first", self.name);
+            return Err(EngineError::Engine(ErrorEnum::Error, message));
+        }
+        if self.read_only == read_only {
+            return Ok(false);
+        }
+        self.read_only = read_only;
+        Ok(true)
+    }
+
+    fn imported(&self) -> bool {
+        self.imported
+    }
+
+    fn set_imported(&mut self, imported: bool) -> EngineResult<bool> {
+        if self.imported == imported {
+            return Ok(false);
+        }
+        self.imported = imported;
+        Ok(true)
+    }
+
+    fn cache_mode(&self) -> CacheMode {
+        self.cache_mode
+    }
+
+    fn set_cache_mode(&mut self, mode: CacheMode) -> EngineResult<bool> {
+        check_writable!(self);
+
+        if self.cache_mode == mode {
+            return Ok(false);
+        }
+        self.cache_mode = mode;
+        Ok(true)
+    }
+
+    fn cache_enabled(&self) -> bool {
+        self.cache_enabled
+    }
+
+    fn set_cache_enabled(&mut self, enabled: bool) -> EngineResult<bool> {
+        check_writable!(self);
+
+        if self.cache_enabled == enabled {
+            return Ok(false);
+        }
+        self.cache_enabled = enabled;
+        Ok(true)
+    }
+
+    fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    fn set_compression(&mut self, compression: Compression) -> EngineResult<bool> {
+        check_writable!(self);
+
+        if self.compression == compression {
+            return Ok(false);
+        }
+        self.compression = compression;
+        Ok(true)
+    }
+
+    fn allocation_policy(&self) -> AllocationPolicy {
+        self.allocation_policy
+    }
+
+    fn set_allocation_policy(&mut self, policy: AllocationPolicy) -> EngineResult<bool> {
+        check_writable!(self);
+
+        if self.allocation_policy == policy {
+            return Ok(false);
+        }
+        self.allocation_policy = policy;
+        Ok(true)
+    }
+
+    fn max_filesystems(&self) -> u32 {
+        self.max_filesystems
+    }
+
+    fn set_max_filesystems(&mut self, max: u32) -> EngineResult<bool> {
+        check_writable!(self);
+
+        if (max as usize) < self.filesystems.len() {
+            let message = format!("pool {} already holds {} filesystems",
+                                  self.name,
+                                  self.filesystems.len());
+            return Err(EngineError::Engine(ErrorEnum::Invalid, message));
+        }
+        if self.max_filesystems == max {
+            return Ok(false);
+        }
+        self.max_filesystems = max;
+        Ok(true)
+    }
+
+    fn max_snapshots(&self) -> u32 {
+        self.max_snapshots
+    }
+
+    fn set_max_snapshots(&mut self, max: u32) -> EngineResult<bool> {
+        check_writable!(self);
+
+        let mut origins = HashMap::new();
+        for origin in self.filesystems.into_iter().filter_map(|f| f.origin()) {
+            *origins.entry(origin).or_insert(0) += 1;
+        }
+        if let Some(most) = origins.values().max() {
+            if *most > max {
+                let message = format!("pool {} already holds {} snapshots of one filesystem",
+                                      self.name,
+                                      most);
+                return Err(EngineError::Engine(ErrorEnum::Invalid, message));
+            }
+        }
+        if self.max_snapshots == max {
+            return Ok(false);
+        }
+        self.max_snapshots = max;
+        Ok(true)
+    }
+
+    fn reserved_free(&self) -> Sectors {
+        self.reserved_free
+    }
+
+    fn set_reserved_free(&mut self, size: Sectors) -> EngineResult<bool> {
+        check_writable!(self);
+
+        let used = try!(self.total_physical_used());
+        let total = self.total_physical_size();
+        let free = if used < total { total - used } else { Sectors(0) };
+        if size > free {
+            let message = format!("pool {} has only {} sectors free", self.name, *free);
+            return Err(EngineError::Engine(ErrorEnum::NoSpace, message));
+        }
+        if self.reserved_free == size {
+            return Ok(false);
+        }
+        self.reserved_free = size;
+        Ok(true)
+    }
+
+    fn allocatable_space(&self) -> EngineResult<Sectors> {
+        self.available()
+    }
+
+    fn usage_alarm(&self) -> u16 {
+        self.usage_alarm
+    }
+
+    fn set_usage_alarm(&mut self, percent: u16) -> EngineResult<bool> {
+        check_writable!(self);
+
+        if percent > 100 {
+            let message = format!("usage alarm of {}% is greater than 100%", percent);
+            return Err(EngineError::Engine(ErrorEnum::Invalid, message));
+        }
+        if self.usage_alarm == percent {
+            return Ok(false);
+        }
+        self.usage_alarm = percent;
+        self.usage_alarm_raised = false;
+        Ok(true)
+    }
+
+    /// No encryption is done; the key description is only recorded.
+    fn key_description(&self) -> Option<&str> {
+        self.key_description.as_ref().map(|s| s.as_str())
+    }
+
+    fn set_key_description(&mut self, key_description: &str) -> EngineResult<bool> {
+        check_writable!(self);
+
+        if key_description.is_empty() {
+            return Err(EngineError::Engine(ErrorEnum::Invalid,
+                                           "key description may not be empty".into()));
+        }
+        match self.key_description() {
+            Some(current) if current == key_description => return Ok(false),
+            Some(current) => {
+                let message = format!("pool {} is already encrypted by key {}",
+                                      self.name,
+                                      current);
+                return Err(EngineError::Engine(ErrorEnum::Error, message));
+            }
+            None => {}
+        }
+        self.key_description = Some(key_description.to_owned());
+        Ok(true)
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn set_description(&mut self, description: &str) -> EngineResult<bool> {
+        check_writable!(self);
+
+        if description.chars().count() > MAX_DESCRIPTION_LEN {
+            let message = format!("description is longer than {} characters",
+                                  MAX_DESCRIPTION_LEN);
+            return Err(EngineError::Engine(ErrorEnum::Invalid, message));
+        }
+        if self.description == description {
+            return Ok(false);
+        }
+        self.description = description.to_owned();
+        Ok(true)
+    }
+
+    fn tags(&self) -> Vec<&str> {
+        let mut tags = self.tags.iter().map(|t| t.as_str()).collect::<Vec<_>>();
+        tags.sort();
+        tags
+    }
+
+    fn add_tag(&mut self, tag: &str) -> EngineResult<bool> {
+        check_writable!(self);
+
+        if tag.is_empty() {
+            return Err(EngineError::Engine(ErrorEnum::Invalid, "tag may not be empty".into()));
+        }
+        Ok(self.tags.insert(tag.to_owned()))
+    }
+
+    fn remove_tag(&mut self, tag: &str) -> EngineResult<bool> {
+        check_writable!(self);
+
+        Ok(self.tags.remove(tag))
+    }
+
+    /// The data is modeled only as the amount held by each blockdev, which
+    /// is made as even as whole sectors allow, the blockdevs first in order
+    /// of device node holding one sector more than the rest.
+    fn rebalance(&mut self) -> EngineResult<bool> {
+        check_writable!(self);
+
+        let count = self.block_devs.len() as u64;
+        if count == 0 {
+            return Ok(false);
+        }
+        let total = self.block_devs
+            .values()
+            .fold(Sectors(0), |acc, bd| acc + bd.used);
+        let mut paths = self.block_devs.keys().cloned().collect::<Vec<_>>();
+        paths.sort();
+
+        let mut moved = false;
+        for (index, path) in paths.iter().enumerate() {
+            let extra = if (index as u64) < *total % count { 1 } else { 0 };
+            let share = Sectors(*total / count + extra);
+            let bd = self.block_devs
+                .get_mut(path)
+                .expect("path is a key of self.block_devs");
+            if bd.used != share {
+                bd.used = share;
+                moved = true;
+            }
+        }
+        Ok(moved)
+    }
+
+    /// A device which is recorded in more than one tier is kept only in
+    /// the first of data, cache, and spare. The holes among the
+    /// filesystems' extents are closed up.
+    fn compact(&mut self) -> EngineResult<bool> {
+        check_writable!(self);
+
+        let mut changed = false;
+        for path in self.block_devs.keys() {
+            changed |= self.cache_devs.remove(path).is_some();
+            changed |= self.spare_devs.remove(path).is_some();
+        }
+        for path in self.cache_devs.keys() {
+            changed |= self.spare_devs.remove(path).is_some();
+        }
+        let extents = self.extents.len();
+        self.extents.retain(|e| e.is_some());
+        changed |= self.extents.len() != extents;
+        Ok(changed)
+    }
+
+    /// The fragmentation is modeled as the proportion of the filesystems'
+    /// extents which are holes left by destroyed filesystems.
+    fn fragmentation(&self) -> EngineResult<u16> {
+        if self.extents.is_empty() {
+            return Ok(0);
+        }
+        let holes = self.extents.iter().filter(|e| e.is_none()).count();
+        Ok((holes * 100 / self.extents.len()) as u16)
+    }
 }
 
 impl HasUuid for SimPool {
@@ -178,22 +1232,32 @@ impl HasName for SimPool {
 #[cfg(test)]
 mod tests {
 
-    use std::path::Path;
+    use std::path::{Path, PathBuf};
 
     use uuid::Uuid;
 
+    use devicemapper::Sectors;
+    use devicemapper::consts::SECTOR_SIZE;
+
+    use engine::CacheMode;
     use engine::Engine;
     use engine::ErrorEnum;
     use engine::EngineError;
+    use engine::Pool;
+    use engine::PoolState;
+    use engine::Redundancy;
     use engine::RenameAction;
 
+    use super::super::super::consts::IEC;
     use super::super::SimEngine;
+    use super::DEFAULT_EXTENT_SIZE;
+    use super::MAX_DESCRIPTION_LEN;
 
     #[test]
     /// Renaming a filesystem on an empty pool always works
     fn rename_empty() {
         let mut engine = SimEngine::default();
-        let (uuid, _) = engine.create_pool("name", &[], None, false).unwrap();
+        let (uuid, _) = engine.create_pool("name", &[], &[], None, false).unwrap();
         let pool = engine.get_mut_pool(&uuid).unwrap();
         assert!(match pool.rename_filesystem(&Uuid::new_v4(), "new_name") {
                     Ok(RenameAction::NoSource) => true,
@@ -205,7 +1269,7 @@ mod tests {
     /// Renaming a filesystem to another filesystem should work if new name not taken
     fn rename_happens() {
         let mut engine = SimEngine::default();
-        let (uuid, _) = engine.create_pool("name", &[], None, false).unwrap();
+        let (uuid, _) = engine.create_pool("name", &[], &[], None, false).unwrap();
         let pool = engine.get_mut_pool(&uuid).unwrap();
         let infos = pool.create_filesystems(&[("old_name", None)]).unwrap();
         assert!(match pool.rename_filesystem(&infos[0].1, "new_name") {
@@ -220,7 +1284,7 @@ mod tests {
         let old_name = "old_name";
         let new_name = "new_name";
         let mut engine = SimEngine::default();
-        let (uuid, _) = engine.create_pool("name", &[], None, false).unwrap();
+        let (uuid, _) = engine.create_pool("name", &[], &[], None, false).unwrap();
         let pool = engine.get_mut_pool(&uuid).unwrap();
         let results = pool.create_filesystems(&[(old_name, None), (new_name, None)])
             .unwrap();
@@ -236,7 +1300,7 @@ mod tests {
     fn rename_no_op() {
         let new_name = "new_name";
         let mut engine = SimEngine::default();
-        let (uuid, _) = engine.create_pool("name", &[], None, false).unwrap();
+        let (uuid, _) = engine.create_pool("name", &[], &[], None, false).unwrap();
         let pool = engine.get_mut_pool(&uuid).unwrap();
         assert!(match pool.rename_filesystem(&Uuid::new_v4(), new_name) {
                     Ok(RenameAction::NoSource) => true,
@@ -248,7 +1312,7 @@ mod tests {
     /// Removing an empty list of filesystems should always succeed
     fn destroy_fs_empty() {
         let mut engine = SimEngine::default();
-        let (uuid, _) = engine.create_pool("name", &[], None, false).unwrap();
+        let (uuid, _) = engine.create_pool("name", &[], &[], None, false).unwrap();
         let pool = engine.get_mut_pool(&uuid).unwrap();
         assert!(match pool.destroy_filesystems(&[]) {
                     Ok(names) => names.is_empty(),
@@ -260,7 +1324,7 @@ mod tests {
     /// Removing a non-empty list of filesystems should succeed on empty pool
     fn destroy_fs_some() {
         let mut engine = SimEngine::default();
-        let (uuid, _) = engine.create_pool("name", &[], None, false).unwrap();
+        let (uuid, _) = engine.create_pool("name", &[], &[], None, false).unwrap();
         let pool = engine.get_mut_pool(&uuid).unwrap();
         assert!(pool.destroy_filesystems(&[&Uuid::new_v4()]).is_ok());
     }
@@ -269,7 +1333,7 @@ mod tests {
     /// Removing a non-empty list of filesystems should succeed on any pool
     fn destroy_fs_any() {
         let mut engine = SimEngine::default();
-        let (uuid, _) = engine.create_pool("name", &[], None, false).unwrap();
+        let (uuid, _) = engine.create_pool("name", &[], &[], None, false).unwrap();
         let pool = engine.get_mut_pool(&uuid).unwrap();
         let fs_results = pool.create_filesystems(&[("fs_name", None)]).unwrap();
         let fs_uuid = fs_results[0].1;
@@ -284,7 +1348,7 @@ mod tests {
     fn create_fs_none() {
         let mut engine = SimEngine::default();
         let (uuid, _) = engine
-            .create_pool("pool_name", &[], None, false)
+            .create_pool("pool_name", &[], &[], None, false)
             .unwrap();
         let pool = engine.get_mut_pool(&uuid).unwrap();
         assert!(match pool.create_filesystems(&[]) {
@@ -298,7 +1362,7 @@ mod tests {
     fn create_fs_some() {
         let mut engine = SimEngine::default();
         let (uuid, _) = engine
-            .create_pool("pool_name", &[], None, false)
+            .create_pool("pool_name", &[], &[], None, false)
             .unwrap();
         let pool = engine.get_mut_pool(&uuid).unwrap();
         assert!(match pool.create_filesystems(&[("name", None)]) {
@@ -313,7 +1377,7 @@ mod tests {
         let fs_name = "fs_name";
         let mut engine = SimEngine::default();
         let (uuid, _) = engine
-            .create_pool("pool_name", &[], None, false)
+            .create_pool("pool_name", &[], &[], None, false)
             .unwrap();
         let pool = engine.get_mut_pool(&uuid).unwrap();
         pool.create_filesystems(&[(fs_name, None)]).unwrap();
@@ -329,7 +1393,7 @@ mod tests {
         let fs_name = "fs_name";
         let mut engine = SimEngine::default();
         let (uuid, _) = engine
-            .create_pool("pool_name", &[], None, false)
+            .create_pool("pool_name", &[], &[], None, false)
             .unwrap();
         let pool = engine.get_mut_pool(&uuid).unwrap();
         assert!(match pool.create_filesystems(&[(fs_name, None), (fs_name, None)]) {
@@ -343,7 +1407,7 @@ mod tests {
     fn add_device_empty() {
         let mut engine = SimEngine::default();
         let (uuid, _) = engine
-            .create_pool("pool_name", &[], None, false)
+            .create_pool("pool_name", &[], &[], None, false)
             .unwrap();
         let pool = engine.get_mut_pool(&uuid).unwrap();
         let devices = [Path::new("/s/a"), Path::new("/s/b")];
@@ -352,4 +1416,460 @@ mod tests {
                     _ => false,
                 });
     }
+
+    #[test]
+    /// Failing a blockdev degrades the pool, replacing it restores it.
+    fn fail_and_replace_device() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("pool_name",
+                         &[Path::new("/s/a"), Path::new("/s/b")],
+                         &[],
+                         None,
+                         false)
+            .unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        assert!(pool.state() == PoolState::Good);
+
+        assert!(pool.mark_blockdev_failed(Path::new("/s/a")).unwrap());
+        assert!(pool.state() == PoolState::Degraded);
+        assert!(!pool.mark_blockdev_failed(Path::new("/s/a")).unwrap());
+
+        assert!(pool.replace_blockdev(Path::new("/s/a"), Path::new("/s/c"), false)
+                    .unwrap() == Path::new("/s/c"));
+        assert!(pool.state() == PoolState::Good);
+    }
+
+    #[test]
+    /// Destroying a filesystem among others fragments the pool, until a
+    /// new filesystem fills the hole or the pool is compacted; destroying
+    /// the last filesystem does not.
+    fn fragmentation_from_holes() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("pool_name", &[Path::new("/s/a")], &[], None, false)
+            .unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        let mut uuids = Vec::new();
+        for name in &["a", "b", "c"] {
+            uuids.push(pool.create_filesystems(&[(name, None)]).unwrap()[0].1);
+        }
+        assert!(pool.fragmentation().unwrap() == 0);
+
+        pool.destroy_filesystems(&[&uuids[2]]).unwrap();
+        assert!(pool.fragmentation().unwrap() == 0);
+
+        pool.create_filesystems(&[("c", None)]).unwrap();
+        pool.destroy_filesystems(&[&uuids[1]]).unwrap();
+        assert!(pool.fragmentation().unwrap() == 33);
+
+        pool.create_filesystems(&[("d", None)]).unwrap();
+        assert!(pool.fragmentation().unwrap() == 0);
+
+        pool.destroy_filesystems(&[&uuids[0]]).unwrap();
+        assert!(pool.fragmentation().unwrap() > 0);
+        assert!(pool.compact().unwrap());
+        assert!(pool.fragmentation().unwrap() == 0);
+    }
+
+    #[test]
+    /// Failing every blockdev takes the pool offline.
+    fn fail_all_devices() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("pool_name", &[Path::new("/s/a")], &[], None, false)
+            .unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        pool.mark_blockdev_failed(Path::new("/s/a")).unwrap();
+        assert!(pool.state() == PoolState::Offline);
+    }
+
+    #[test]
+    /// A blockdev can be made read-only and writable again, and a blockdev
+    /// not in the pool can not be made either.
+    fn toggle_device_read_only() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("pool_name", &[Path::new("/s/a")], &[], None, false)
+            .unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        let read_only = |pool: &Pool| {
+            pool.blockdevs()
+                .into_iter()
+                .find(|&(_, bd)| bd.devnode() == Path::new("/s/a"))
+                .map(|(_, bd)| bd.read_only())
+                .unwrap()
+        };
+        assert!(!read_only(pool));
+
+        assert!(pool.set_blockdev_read_only(Path::new("/s/a"), true).unwrap());
+        assert!(read_only(pool));
+        assert!(!pool.set_blockdev_read_only(Path::new("/s/a"), true).unwrap());
+
+        assert!(pool.set_blockdev_read_only(Path::new("/s/a"), false).unwrap());
+        assert!(!read_only(pool));
+
+        assert!(match pool.set_blockdev_read_only(Path::new("/s/b"), true) {
+                    Err(EngineError::Engine(ErrorEnum::NotFound, _)) => true,
+                    _ => false,
+                });
+    }
+
+    #[test]
+    /// Failing or replacing a blockdev not in the pool, or replacing a
+    /// blockdev which has not failed, is an error.
+    fn fail_and_replace_errors() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("pool_name",
+                         &[Path::new("/s/a"), Path::new("/s/b")],
+                         &[],
+                         None,
+                         false)
+            .unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        assert!(match pool.mark_blockdev_failed(Path::new("/s/z")) {
+                    Err(EngineError::Engine(ErrorEnum::NotFound, _)) => true,
+                    _ => false,
+                });
+        assert!(match pool.replace_blockdev(Path::new("/s/a"), Path::new("/s/c"), false) {
+                    Err(EngineError::Engine(ErrorEnum::Invalid, _)) => true,
+                    _ => false,
+                });
+        pool.mark_blockdev_failed(Path::new("/s/a")).unwrap();
+        assert!(match pool.replace_blockdev(Path::new("/s/a"), Path::new("/s/b"), false) {
+                    Err(EngineError::Engine(ErrorEnum::AlreadyExists, _)) => true,
+                    _ => false,
+                });
+    }
+
+    #[test]
+    /// Growing the metadata reservation reduces the space left for data
+    fn grow_metadata_size() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("pool_name", &[], &[], None, false)
+            .unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        let size = pool.metadata_size();
+        let free = pool.total_physical_size() - pool.total_physical_used().unwrap();
+        assert!(pool.set_metadata_size(size + size).unwrap());
+        assert!(pool.metadata_size() == size + size);
+        assert!(pool.total_physical_size() - pool.total_physical_used().unwrap() == free - size);
+        assert!(!pool.set_metadata_size(size + size).unwrap());
+    }
+
+    #[test]
+    /// Shrinking the metadata reservation below the metadata in use fails
+    fn shrink_metadata_size_in_use() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("pool_name", &[], &[], None, false)
+            .unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        let size = pool.metadata_size();
+        pool.create_filesystems(&[("name", None)]).unwrap();
+        assert!(match pool.set_metadata_size(Sectors(0)) {
+                    Err(EngineError::Engine(ErrorEnum::Invalid, _)) => true,
+                    _ => false,
+                });
+        assert!(pool.metadata_size() == size);
+    }
+
+    #[test]
+    /// Filesystems can not be created in a read-only pool
+    fn create_fs_read_only() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("pool_name", &[], &[], None, false)
+            .unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        assert!(pool.set_read_only(true).unwrap());
+        assert!(pool.read_only());
+        assert!(match pool.create_filesystems(&[("name", None)]) {
+                    Err(EngineError::Engine(ErrorEnum::Error, _)) => true,
+                    _ => false,
+                });
+        assert!(pool.filesystems().is_empty());
+
+        assert!(pool.set_read_only(false).unwrap());
+        assert!(!pool.set_read_only(false).unwrap());
+        assert!(pool.create_filesystems(&[("name", None)]).is_ok());
+    }
+
+    #[test]
+    /// No more filesystems may be created than the pool's limit allows
+    fn max_filesystems_enforced() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine.create_pool("pool_name", &[], &[], None, false).unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        assert!(pool.set_max_filesystems(2).unwrap());
+        assert!(pool.max_filesystems() == 2);
+
+        pool.create_filesystems(&[("fs1", None), ("fs2", None)]).unwrap();
+        assert!(match pool.create_filesystems(&[("fs3", None)]) {
+                    Err(EngineError::Engine(ErrorEnum::Error, msg)) => msg.contains("2"),
+                    _ => false,
+                });
+        assert!(pool.filesystems().len() == 2);
+        assert!(pool.set_max_filesystems(1).is_err());
+    }
+
+    #[test]
+    /// An imported pool is read-only until it is adopted: nothing in it
+    /// may be changed, except that it may be adopted
+    fn import_then_adopt() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("pool_name", &[Path::new("/s/a")], &[], None, false)
+            .unwrap();
+        let fs_uuid = {
+            let pool = engine.get_mut_pool(&uuid).unwrap();
+            let fs_uuid = pool.create_filesystems(&[("fs", None)]).unwrap()[0].1;
+            assert!(pool.set_imported(true).unwrap());
+            assert!(!pool.set_imported(true).unwrap());
+            assert!(pool.imported() && pool.read_only());
+            assert!(match pool.create_filesystems(&[("name", None)]) {
+                        Err(EngineError::Engine(ErrorEnum::Error, _)) => true,
+                        _ => false,
+                    });
+            assert!(match pool.add_blockdevs(&[Path::new("/s/b")], false) {
+                        Err(EngineError::Engine(ErrorEnum::Error, _)) => true,
+                        _ => false,
+                    });
+            assert!(match pool.destroy_filesystems(&[&fs_uuid]) {
+                        Err(EngineError::Engine(ErrorEnum::Error, _)) => true,
+                        _ => false,
+                    });
+            assert!(pool.set_description("imported").is_err());
+            assert!(pool.set_read_only(false).is_err());
+            assert!(pool.blockdevs().len() == 1 && pool.filesystems().len() == 1);
+            fs_uuid
+        };
+        assert!(engine.rename_pool(&uuid, "new_name").is_err());
+
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        assert!(pool.set_imported(false).unwrap());
+        assert!(!pool.imported() && !pool.read_only());
+        assert!(pool.create_filesystems(&[("name", None)]).is_ok());
+        assert!(pool.add_blockdevs(&[Path::new("/s/b")], false).is_ok());
+        assert!(pool.destroy_filesystems(&[&fs_uuid]).unwrap() == vec![&fs_uuid]);
+    }
+
+    #[test]
+    /// A pool may keep its redundancy if it has enough blockdevs
+    fn reshape_pool() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("pool_name", &[Path::new("/s/a")], &[], None, false)
+            .unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        assert!(!pool.reshape(Redundancy::NONE).unwrap());
+        assert!(pool.redundancy() == Redundancy::NONE);
+
+        let (uuid, _) = engine.create_pool("empty", &[], &[], None, false).unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        assert!(match pool.reshape(Redundancy::NONE) {
+                    Err(EngineError::Engine(ErrorEnum::Error, _)) => true,
+                    _ => false,
+                });
+    }
+
+    #[test]
+    /// A snapshot schedule is recorded and read back, an empty one is refused
+    fn snapshot_schedule_recorded() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine.create_pool("pool_name", &[], &[], None, false).unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        let fs_uuid = pool.create_filesystems(&[("fs", None)]).unwrap()[0].1;
+        let filesystem = pool.get_mut_filesystem(&fs_uuid).unwrap();
+        assert!(filesystem.snapshot_schedule().is_none());
+
+        assert!(filesystem.set_snapshot_schedule("0 * * * *").unwrap());
+        assert!(!filesystem.set_snapshot_schedule("0 * * * *").unwrap());
+        assert!(filesystem.snapshot_schedule() == Some("0 * * * *"));
+        assert!(match filesystem.set_snapshot_schedule("") {
+                    Err(EngineError::Engine(ErrorEnum::Invalid, _)) => true,
+                    _ => false,
+                });
+        assert!(filesystem.snapshot_schedule() == Some("0 * * * *"));
+    }
+
+    #[test]
+    /// An encrypted pool records the description of its key, which may not
+    /// afterwards be replaced by another
+    fn encrypted_pool() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine.create_pool("pool_name", &[], &[], None, false).unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        assert!(pool.key_description().is_none());
+
+        assert!(pool.set_key_description("stratis-key").unwrap());
+        assert!(!pool.set_key_description("stratis-key").unwrap());
+        assert!(pool.key_description() == Some("stratis-key"));
+        assert!(pool.set_key_description("other-key").is_err());
+        assert!(match pool.set_key_description("") {
+                    Err(EngineError::Engine(ErrorEnum::Invalid, _)) => true,
+                    _ => false,
+                });
+        assert!(pool.key_description() == Some("stratis-key"));
+    }
+
+    #[test]
+    /// A description is recorded and read back, cleared by an empty one,
+    /// and refused if it is too long
+    fn description_set_and_cleared() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine.create_pool("pool_name", &[], &[], None, false).unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        assert!(pool.description().is_empty());
+
+        assert!(pool.set_description("database tier, do not shrink").unwrap());
+        assert!(!pool.set_description("database tier, do not shrink").unwrap());
+        assert!(pool.description() == "database tier, do not shrink");
+
+        let too_long = "x".repeat(MAX_DESCRIPTION_LEN + 1);
+        assert!(match pool.set_description(&too_long) {
+                    Err(EngineError::Engine(ErrorEnum::Invalid, _)) => true,
+                    _ => false,
+                });
+        assert!(pool.description() == "database tier, do not shrink");
+
+        assert!(pool.set_description("").unwrap());
+        assert!(pool.description().is_empty());
+    }
+
+    #[test]
+    /// Tags are kept once each, in order, until they are removed
+    fn tag_and_untag() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine.create_pool("pool_name", &[], &[], None, false).unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        assert!(pool.tags().is_empty());
+
+        assert!(pool.add_tag("web").unwrap());
+        assert!(pool.add_tag("db").unwrap());
+        assert!(!pool.add_tag("web").unwrap());
+        assert!(pool.tags() == vec!["db", "web"]);
+        assert!(match pool.add_tag("") {
+                    Err(EngineError::Engine(ErrorEnum::Invalid, _)) => true,
+                    _ => false,
+                });
+
+        assert!(pool.remove_tag("web").unwrap());
+        assert!(!pool.remove_tag("web").unwrap());
+        assert!(pool.tags() == vec!["db"]);
+    }
+
+    #[test]
+    /// I/O limits are recorded and read back, and are cleared by zero
+    fn io_limits_recorded() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine.create_pool("pool_name", &[], &[], None, false).unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        let fs_uuid = pool.create_filesystems(&[("fs", None)]).unwrap()[0].1;
+        let filesystem = pool.get_mut_filesystem(&fs_uuid).unwrap();
+        assert!(filesystem.io_limits() == (0, 0));
+
+        assert!(filesystem.set_io_limits(100, 50).unwrap());
+        assert!(!filesystem.set_io_limits(100, 50).unwrap());
+        assert!(filesystem.io_limits() == (100, 50));
+
+        assert!(filesystem.set_io_limits(0, 0).unwrap());
+        assert!(filesystem.io_limits() == (0, 0));
+    }
+
+    #[test]
+    /// An allocation which would dip into the free-space reservation is
+    /// refused
+    fn reserved_free_enforced() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine.create_pool("pool_name", &[], &[], None, false).unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        let free = pool.total_physical_size() - pool.total_physical_used().unwrap();
+        let reserved = free - Sectors(16);
+        assert!(pool.set_reserved_free(reserved).unwrap());
+        assert!(!pool.set_reserved_free(reserved).unwrap());
+        assert!(pool.reserved_free() == reserved);
+
+        let metadata_size = pool.metadata_size();
+        assert!(match pool.set_metadata_size(metadata_size + Sectors(17)) {
+                    Err(EngineError::Engine(ErrorEnum::NoSpace, _)) => true,
+                    _ => false,
+                });
+        assert!(pool.metadata_size() == metadata_size);
+        assert!(pool.set_metadata_size(metadata_size + Sectors(16)).unwrap());
+        assert!(pool.set_reserved_free(free).is_err());
+    }
+
+    #[test]
+    /// A pool is WriteThrough until its cache mode is set
+    fn set_cache_mode() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("pool_name", &[], &[Path::new("/s/c")], None, false)
+            .unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        assert!(pool.cache_mode() == CacheMode::WriteThrough);
+        assert!(!pool.set_cache_mode(CacheMode::WriteThrough).unwrap());
+        assert!(pool.set_cache_mode(CacheMode::WriteBack).unwrap());
+        assert!(pool.cache_mode() == CacheMode::WriteBack);
+        assert!(pool.set_cache_mode(CacheMode::WriteThrough).unwrap());
+        assert!(pool.cache_mode() == CacheMode::WriteThrough);
+    }
+
+    #[test]
+    /// A new pool allocates in extents of the default size
+    fn default_extent_size() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("pool_name", &[], &[], None, false)
+            .unwrap();
+        let pool = engine.get_pool(&uuid).unwrap();
+        assert!(pool.extent_size() == DEFAULT_EXTENT_SIZE);
+        assert!(*pool.extent_size() * SECTOR_SIZE as u64 == IEC::Mi);
+    }
+
+    #[test]
+    /// A filesystem's size is rounded up to a whole number of extents
+    fn filesystem_size_rounded() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("pool_name", &[], &[], None, false)
+            .unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        let extent_size = pool.extent_size();
+        let results = pool.create_filesystems(&[("aligned", Some(extent_size * 2usize)),
+                                                ("unaligned", Some(extent_size + Sectors(1)))])
+            .unwrap();
+        for &(name, fs_uuid) in &results {
+            assert!(pool.get_filesystem(&fs_uuid).unwrap().size() == extent_size * 2usize,
+                    "{}",
+                    name);
+        }
+    }
+
+    #[test]
+    /// A spare replaces a failed blockdev, and the pool is healthy again
+    fn spare_replaces_failed() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("pool_name",
+                         &[Path::new("/s/a"), Path::new("/s/b")],
+                         &[],
+                         None,
+                         false)
+            .unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        assert!(pool.add_spares(&[Path::new("/s/a")], false).is_err());
+        pool.add_spares(&[Path::new("/s/spare")], false).unwrap();
+        assert!(pool.spares() == vec![PathBuf::from("/s/spare")]);
+
+        assert!(pool.mark_blockdev_failed(Path::new("/s/a")).unwrap());
+        assert!(pool.state() == PoolState::Good);
+        assert!(pool.spares().is_empty());
+
+        assert!(pool.mark_blockdev_failed(Path::new("/s/b")).unwrap());
+        assert!(pool.state() == PoolState::Degraded);
+    }
 }
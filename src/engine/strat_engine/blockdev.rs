@@ -11,8 +11,9 @@ use time::Timespec;
 
 use devicemapper::{Device, Sectors, Segment};
 
+use super::super::engine::{Dev, HasUuid};
 use super::super::errors::EngineResult;
-use super::super::types::{DevUuid, PoolUuid};
+use super::super::types::{BlockDevHealth, BlockDevState, DevUuid, PoolUuid};
 
 use super::metadata::BDA;
 use super::range_alloc::RangeAllocator;
@@ -118,6 +119,43 @@ impl BlockDev {
     }
 }
 
+impl HasUuid for BlockDev {
+    fn uuid(&self) -> &DevUuid {
+        self.bda.dev_uuid()
+    }
+}
+
+impl Dev for BlockDev {
+    fn devnode(&self) -> PathBuf {
+        self.devnode.clone()
+    }
+
+    fn state(&self) -> BlockDevState {
+        BlockDevState::Good
+    }
+
+    fn wear_percent(&self) -> u16 {
+        // SMART data is not yet read.
+        0
+    }
+
+    fn health(&self) -> BlockDevHealth {
+        BlockDevHealth::Good
+    }
+
+    fn size(&self) -> Sectors {
+        self.current_capacity()
+    }
+
+    fn used(&self) -> Sectors {
+        self.current_capacity() - self.available()
+    }
+
+    fn read_only(&self) -> bool {
+        false
+    }
+}
+
 impl Recordable<BlockDevSave> for BlockDev {
     fn record(&self) -> EngineResult<BlockDevSave> {
         Ok(BlockDevSave { devnode: self.devnode.clone() })
@@ -16,9 +16,9 @@ use uuid::Uuid;
 
 use devicemapper::{Bytes, Device, Sectors, Segment};
 
-use super::super::consts::IEC;
+use super::super::consts::MIN_DEV_SIZE;
 use super::super::errors::{EngineError, EngineResult, ErrorEnum};
-use super::super::types::{DevUuid, PoolUuid};
+use super::super::types::{BlockDevCheck, DevUuid, PoolUuid};
 
 use super::cleanup::wipe_blockdevs;
 use super::blockdev::BlockDev;
@@ -28,7 +28,6 @@ use super::metadata::{BDA, MIN_MDA_SECTORS, StaticHeader, validate_mda_size};
 use super::range_alloc::RangeAllocator;
 use super::serde_structs::{BlockDevSave, Recordable};
 
-const MIN_DEV_SIZE: Bytes = Bytes(IEC::Gi);
 const MAX_NUM_TO_WRITE: usize = 10;
 
 /// Resolve a list of Paths of some sort to a set of unique Devices.
@@ -40,6 +39,40 @@ pub fn resolve_devices(paths: &[&Path]) -> io::Result<HashSet<Device>> {
         .collect()
 }
 
+/// Check whether the device at path could be used as a blockdev in a new
+/// pool, without writing to it.
+pub fn check_device(path: &Path) -> (BlockDevCheck, String) {
+    let devnode = match Device::from_str(&path.to_string_lossy())
+              .ok()
+              .and_then(|d| d.devnode()) {
+        Some(devnode) => devnode,
+        None => return (BlockDevCheck::NotFound, "not a block device".into()),
+    };
+
+    let mut f = match OpenOptions::new().read(true).open(&devnode) {
+        Ok(f) => f,
+        Err(err) => return (BlockDevCheck::NotFound, err.to_string()),
+    };
+
+    match blkdev_size(&f) {
+        Ok(size) if size < MIN_DEV_SIZE => {
+            return (BlockDevCheck::TooSmall, format!("minimum {} bytes", MIN_DEV_SIZE))
+        }
+        Ok(_) => {}
+        Err(err) => return (BlockDevCheck::NotFound, err.to_string()),
+    }
+
+    match StaticHeader::determine_ownership(&mut f) {
+        Ok(DevOwnership::Unowned) => (BlockDevCheck::Ok, String::new()),
+        Ok(DevOwnership::Ours(uuid)) => {
+            (BlockDevCheck::InUse, format!("device belongs to Stratis pool {}", uuid))
+        }
+        Ok(DevOwnership::Theirs) => {
+            (BlockDevCheck::InUse, "device appears to belong to another application".into())
+        }
+        Err(err) => (BlockDevCheck::NotFound, err.to_string()),
+    }
+}
 
 #[derive(Debug)]
 pub struct BlockDevMgr {
@@ -70,6 +103,11 @@ impl BlockDevMgr {
         self.block_devs.iter().find(|d| d.device() == &device)
     }
 
+    /// All the BlockDevs.
+    pub fn blockdevs(&self) -> Vec<&BlockDev> {
+        self.block_devs.iter().collect()
+    }
+
     // Obtain a BlockDev by its UUID.
     pub fn get_by_uuid(&self, uuid: &DevUuid) -> Option<&BlockDev> {
         self.block_devs.iter().find(|d| d.uuid() == uuid)
@@ -2,6 +2,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::path::PathBuf;
 
@@ -9,11 +10,14 @@ use uuid::Uuid;
 
 use devicemapper::DM;
 
+use super::super::dump::EngineDump;
 use super::super::engine::{Engine, HasName, HasUuid, Pool};
 use super::super::errors::{EngineError, EngineResult, ErrorEnum};
 use super::super::structures::Table;
-use super::super::types::{PoolUuid, Redundancy, RenameAction};
+use super::super::types::{Alert, BlockDevCheck, FilesystemUuid, PoolUuid, Redundancy,
+                          RenameAction};
 
+use super::blockdevmgr::check_device;
 use super::cleanup::teardown_pools;
 use super::pool::StratPool;
 use super::setup::find_all;
@@ -28,6 +32,8 @@ pub enum DevOwnership {
 #[derive(Debug)]
 pub struct StratEngine {
     pools: Table<StratPool>,
+    /// The UUIDs of the pools marked deleted.
+    deleted: HashSet<PoolUuid>,
 }
 
 impl StratEngine {
@@ -53,7 +59,10 @@ impl StratEngine {
             }
         }
 
-        Ok(StratEngine { pools: table })
+        Ok(StratEngine {
+               pools: table,
+               deleted: HashSet::new(),
+           })
     }
 
     /// Teardown Stratis, preparatory to a shutdown.
@@ -75,29 +84,91 @@ impl Engine for StratEngine {
     fn create_pool(&mut self,
                    name: &str,
                    blockdev_paths: &[&Path],
+                   cachedev_paths: &[&Path],
                    redundancy: Option<u16>,
                    force: bool)
                    -> EngineResult<(PoolUuid, Vec<PathBuf>)> {
+        let uuid = Uuid::new_v4();
+        self.create_pool_with_uuid(uuid, name, blockdev_paths, cachedev_paths, redundancy, force)
+            .map(|devnodes| (uuid, devnodes))
+    }
+
+    fn create_pool_with_uuid(&mut self,
+                             uuid: PoolUuid,
+                             name: &str,
+                             blockdev_paths: &[&Path],
+                             cachedev_paths: &[&Path],
+                             redundancy: Option<u16>,
+                             force: bool)
+                             -> EngineResult<Vec<PathBuf>> {
 
         let redundancy = calculate_redundancy!(redundancy);
 
+        check_absolute_paths!(blockdev_paths);
+        check_absolute_paths!(cachedev_paths);
+
         if self.pools.contains_name(name) {
             return Err(EngineError::Engine(ErrorEnum::AlreadyExists, name.into()));
         }
 
+        if self.pools.contains_uuid(&uuid) {
+            return Err(EngineError::Engine(ErrorEnum::AlreadyExists, uuid.to_string()));
+        }
+
+        if !cachedev_paths.is_empty() {
+            return Err(EngineError::Engine(ErrorEnum::Invalid,
+                                           "cache devices are not yet supported".into()));
+        }
+
         let dm = try!(DM::new());
-        let (pool, devnodes) =
-            try!(StratPool::initialize(name, &dm, blockdev_paths, redundancy, force));
+        let (pool, devnodes) = try!(StratPool::initialize_with_uuid(uuid,
+                                                                    name,
+                                                                    &dm,
+                                                                    blockdev_paths,
+                                                                    redundancy,
+                                                                    force));
 
-        let uuid = pool.uuid().clone();
         self.pools.insert(pool);
-        Ok((uuid, devnodes))
+        Ok(devnodes)
+    }
+
+    fn check_blockdevs(&self,
+                       blockdev_paths: &[&Path],
+                       redundancy: Option<u16>)
+                       -> EngineResult<(bool, Vec<(PathBuf, BlockDevCheck, String)>)> {
+        check_blockdevs!(blockdev_paths; redundancy; check_device)
     }
 
     fn destroy_pool(&mut self, uuid: &PoolUuid) -> EngineResult<bool> {
         destroy_pool!{self; uuid}
     }
 
+    fn force_destroy_pool(&mut self, uuid: &PoolUuid) -> EngineResult<bool> {
+        force_destroy_pool!{self; uuid}
+    }
+
+    fn soft_destroy_pool(&mut self, _uuid: &PoolUuid) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error,
+                                "soft destroying pools is not yet supported".into()))
+    }
+
+    fn restore_pool(&mut self, uuid: &PoolUuid) -> EngineResult<bool> {
+        restore_pool!{self; uuid}
+    }
+
+    fn create_snapshot(&mut self,
+                       _origin_pool_uuid: &PoolUuid,
+                       _origin_uuid: &FilesystemUuid,
+                       _pool_uuid: &PoolUuid,
+                       _snapshot_name: &str)
+                       -> EngineResult<FilesystemUuid> {
+        Err(EngineError::Engine(ErrorEnum::Error, "snapshots are not yet supported".into()))
+    }
+
+    fn load_state(&mut self, _dump: &EngineDump, _replace: bool) -> EngineResult<Vec<PoolUuid>> {
+        Err(EngineError::Engine(ErrorEnum::Error, "loading state is not yet supported".into()))
+    }
+
     fn rename_pool(&mut self, uuid: &PoolUuid, new_name: &str) -> EngineResult<RenameAction> {
         let old_name = rename_pool_pre!(self; uuid; new_name);
 
@@ -128,7 +199,55 @@ impl Engine for StratEngine {
         check_engine!(self);
     }
 
+    fn rescan(&mut self) -> EngineResult<Vec<PoolUuid>> {
+        let mut uuids = Vec::new();
+        for (pool_uuid, devices) in try!(find_all()) {
+            if self.pools.contains_uuid(&pool_uuid) {
+                continue;
+            }
+            let pool = try!(StratPool::setup(pool_uuid, &devices));
+            if self.pools.contains_name(pool.name()) {
+                let err_msg = format!("found two pools with the name {}", pool.name());
+                let _ = teardown_pools(vec![pool]);
+                return Err(EngineError::Engine(ErrorEnum::AlreadyExists, err_msg));
+            }
+            self.pools.insert(pool);
+            uuids.push(pool_uuid);
+        }
+        Ok(uuids)
+    }
+
+    fn check_consistency(&self) -> Vec<String> {
+        check_consistency!(self)
+    }
+
     fn pools(&self) -> Vec<&Pool> {
-        self.pools.into_iter().map(|x| x as &Pool).collect()
+        pools!(self; false)
+    }
+
+    fn deleted_pools(&self) -> Vec<&Pool> {
+        pools!(self; true)
+    }
+
+    fn alerts(&self) -> Vec<Alert> {
+        vec![]
+    }
+
+    fn acknowledge_alert(&mut self, id: u64) -> EngineResult<()> {
+        Err(EngineError::Engine(ErrorEnum::NotFound, format!("alert {}", id)))
+    }
+
+    fn checkpoint(&mut self) -> EngineResult<()> {
+        Err(EngineError::Engine(ErrorEnum::Error, "checkpoints are not yet supported".into()))
+    }
+
+    fn rollback(&mut self) -> EngineResult<()> {
+        Err(EngineError::Engine(ErrorEnum::NotFound, "no checkpoint".into()))
+    }
+
+    fn release_checkpoint(&mut self) {}
+
+    fn supports(&self, _feature: &str) -> bool {
+        false
     }
 }
@@ -13,7 +13,7 @@ use nix::sys::statvfs::vfs::Statvfs;
 
 use super::super::engine::{Filesystem, HasName, HasUuid};
 use super::super::errors::{EngineError, EngineResult, ErrorEnum};
-use super::super::types::FilesystemUuid;
+use super::super::types::{FilesystemUuid, PoolUuid};
 
 use super::serde_structs::{FilesystemSave, Recordable};
 
@@ -142,6 +142,55 @@ impl Filesystem for StratFilesystem {
     fn devnode(&self) -> EngineResult<PathBuf> {
         Ok(try!(self.thin_dev.devnode()))
     }
+
+    fn origin(&self) -> Option<(PoolUuid, FilesystemUuid)> {
+        None
+    }
+
+    fn fs_type(&self) -> &str {
+        "xfs"
+    }
+
+    fn size(&self) -> Sectors {
+        self.thin_dev.size()
+    }
+
+    fn used(&self) -> EngineResult<Sectors> {
+        let mount_point = try!(self.get_mount_point());
+        let (_, used) = try!(fs_usage(&mount_point));
+        Ok(used.sectors())
+    }
+
+    fn set_fs_type(&mut self, _fs_type: &str) -> EngineResult<()> {
+        Err(EngineError::Engine(ErrorEnum::Error,
+                                "filesystem types other than xfs are not yet supported".into()))
+    }
+
+    fn snapshot_schedule(&self) -> Option<&str> {
+        None
+    }
+
+    fn set_snapshot_schedule(&mut self, _schedule: &str) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error,
+                                "recording a snapshot schedule is not yet supported".into()))
+    }
+
+    fn mountpoint(&self) -> Option<&Path> {
+        None
+    }
+
+    fn set_mountpoint(&mut self, _mountpoint: &Path) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error,
+                                "recording a mount point is not yet supported".into()))
+    }
+
+    fn io_limits(&self) -> (u64, u64) {
+        (0, 0)
+    }
+
+    fn set_io_limits(&mut self, _read_iops: u64, _write_iops: u64) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error, "I/O limits are not yet supported".into()))
+    }
 }
 
 impl Recordable<FilesystemSave> for StratFilesystem {
@@ -9,6 +9,7 @@ use std::path::PathBuf;
 use std::vec::Vec;
 
 use serde_json;
+use time;
 use uuid::Uuid;
 
 use devicemapper as dm;
@@ -19,10 +20,13 @@ use devicemapper::{DataBlocks, MetaBlocks, Sectors, Segment};
 use devicemapper::LinearDev;
 use devicemapper::{ThinDevId, ThinPoolWorkingStatus, ThinPoolDev};
 
+use super::super::consts::{DEFAULT_MAX_FILESYSTEMS, DEFAULT_MAX_SNAPSHOTS};
 use super::super::consts::IEC::Mi;
-use super::super::engine::{Filesystem, HasName, HasUuid, Pool};
+use super::super::engine::{Dev, Filesystem, HasName, HasUuid, Pool};
 use super::super::errors::{EngineError, EngineResult, ErrorEnum};
-use super::super::types::{DevUuid, FilesystemUuid, PoolUuid, RenameAction, Redundancy};
+use super::super::types::{AllocationPolicy, BlockDevTier, CacheMode, Compression, DevUuid,
+                          FilesystemUuid, PoolIoStats, PoolState, PoolUuid, RenameAction,
+                          Redundancy};
 
 use super::blockdevmgr::BlockDevMgr;
 use super::device::wipe_sectors;
@@ -46,6 +50,7 @@ pub struct StratPool {
     pool_uuid: PoolUuid,
     block_devs: BlockDevMgr,
     redundancy: Redundancy,
+    created_at: u64,
     thin_pool: ThinPool,
 }
 
@@ -59,8 +64,18 @@ impl StratPool {
                       redundancy: Redundancy,
                       force: bool)
                       -> EngineResult<(StratPool, Vec<PathBuf>)> {
-        let pool_uuid = Uuid::new_v4();
+        StratPool::initialize_with_uuid(Uuid::new_v4(), name, dm, paths, redundancy, force)
+    }
 
+    /// Initialize a Stratis Pool, as initialize() does, but identified by
+    /// pool_uuid rather than by a UUID generated for it.
+    pub fn initialize_with_uuid(pool_uuid: PoolUuid,
+                                name: &str,
+                                dm: &DM,
+                                paths: &[&Path],
+                                redundancy: Redundancy,
+                                force: bool)
+                                -> EngineResult<(StratPool, Vec<PathBuf>)> {
         let mut block_mgr =
             try!(BlockDevMgr::initialize(&pool_uuid, paths, MIN_MDA_SECTORS, force));
 
@@ -134,6 +149,7 @@ impl StratPool {
             pool_uuid: pool_uuid,
             block_devs: block_mgr,
             redundancy: redundancy,
+            created_at: time::now().to_timespec().sec as u64,
             thin_pool: thinpool,
         };
 
@@ -235,6 +251,7 @@ impl StratPool {
                pool_uuid: uuid,
                block_devs: BlockDevMgr::new(blockdevs),
                redundancy: Redundancy::NONE,
+               created_at: metadata.created_at,
                thin_pool: thinpool,
            })
     }
@@ -269,7 +286,7 @@ impl StratPool {
         } else {
             let err_msg = format!("Insufficient space to accomodate request for {} data blocks",
                                   *extend_size);
-            return Err(EngineError::Engine(ErrorEnum::Error, err_msg));
+            return Err(EngineError::Engine(ErrorEnum::NoSpace, err_msg));
         }
         Ok(extend_size)
     }
@@ -371,6 +388,7 @@ impl Pool for StratPool {
                 return Err(EngineError::Engine(ErrorEnum::AlreadyExists, name.to_string()));
             }
         }
+        check_filesystem_limit!(self; names.len());
 
         // TODO: Roll back on filesystem initialization failure.
         let dm = try!(DM::new());
@@ -385,11 +403,39 @@ impl Pool for StratPool {
     }
 
     fn add_blockdevs(&mut self, paths: &[&Path], force: bool) -> EngineResult<Vec<PathBuf>> {
+        check_absolute_paths!(paths);
         let bdev_paths = try!(self.block_devs.add(&self.pool_uuid, paths, force));
         try!(self.write_metadata());
         Ok(bdev_paths)
     }
 
+    fn mark_blockdev_failed(&mut self, _path: &Path) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error,
+                                "marking blockdevs failed is not yet supported".into()))
+    }
+
+    fn set_blockdev_read_only(&mut self, _path: &Path, _read_only: bool) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error,
+                                "making blockdevs read-only is not yet supported".into()))
+    }
+
+    fn add_spares(&mut self, _paths: &[&Path], _force: bool) -> EngineResult<Vec<PathBuf>> {
+        Err(EngineError::Engine(ErrorEnum::Error, "spares are not yet supported".into()))
+    }
+
+    fn spares(&self) -> Vec<PathBuf> {
+        vec![]
+    }
+
+    fn replace_blockdev(&mut self,
+                        _old_path: &Path,
+                        _new_path: &Path,
+                        _force: bool)
+                        -> EngineResult<PathBuf> {
+        Err(EngineError::Engine(ErrorEnum::Error,
+                                "replacing blockdevs is not yet supported".into()))
+    }
+
     fn destroy(self) -> EngineResult<()> {
         let dm = try!(DM::new());
 
@@ -420,6 +466,21 @@ impl Pool for StratPool {
         self.thin_pool.rename_filesystem(uuid, new_name)
     }
 
+    fn restore_filesystem(&mut self,
+                          _uuid: &FilesystemUuid,
+                          _snapshot_uuid: &FilesystemUuid)
+                          -> EngineResult<()> {
+        Err(EngineError::Engine(ErrorEnum::Error, "snapshots are not yet supported".into()))
+    }
+
+    fn clone_filesystem(&mut self,
+                        _uuid: &FilesystemUuid,
+                        _new_name: &str)
+                        -> EngineResult<FilesystemUuid> {
+        Err(EngineError::Engine(ErrorEnum::Error,
+                                "cloning filesystems is not yet supported".into()))
+    }
+
     fn rename(&mut self, name: &str) {
         self.name = name.to_owned();
     }
@@ -446,9 +507,221 @@ impl Pool for StratPool {
             .and_then(|v| Ok(v + self.block_devs.metadata_size()))
     }
 
+    fn space_shortfall(&self) -> EngineResult<Sectors> {
+        space_shortfall!(self)
+    }
+
+    fn snapshot_usage(&self) -> EngineResult<(usize, Sectors)> {
+        snapshot_usage!(self)
+    }
+
+    fn metadata_size(&self) -> Sectors {
+        self.block_devs.metadata_size()
+    }
+
+    fn set_metadata_size(&mut self, _size: Sectors) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error,
+                                "resizing the metadata reservation is not yet supported".into()))
+    }
+
     fn filesystems(&self) -> Vec<&Filesystem> {
         self.thin_pool.filesystems()
     }
+
+    fn blockdevs(&self) -> Vec<(BlockDevTier, &Dev)> {
+        self.block_devs
+            .blockdevs()
+            .into_iter()
+            .map(|bd| (BlockDevTier::Data, bd as &Dev))
+            .collect()
+    }
+
+    fn remove_blockdev(&mut self, _uuid: &DevUuid) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error,
+                                "removing a blockdev is not yet supported".into()))
+    }
+
+    fn blockdev_filesystems(&self, _uuid: &DevUuid) -> EngineResult<Vec<FilesystemUuid>> {
+        Err(EngineError::Engine(ErrorEnum::Error,
+                                "locating filesystem data is not yet supported".into()))
+    }
+
+    fn can_remove_blockdev(&self, uuid: &DevUuid) -> EngineResult<bool> {
+        if self.get_blockdev_path(uuid).is_none() {
+            return Err(EngineError::Engine(ErrorEnum::NotFound, format!("blockdev {}", uuid)));
+        }
+        // Removing a blockdev is not yet supported.
+        Ok(false)
+    }
+
+    fn get_blockdev_path(&self, uuid: &DevUuid) -> Option<PathBuf> {
+        self.block_devs
+            .get_by_uuid(uuid)
+            .map(|bd| bd.devnode.clone())
+    }
+
+    fn state(&self) -> PoolState {
+        PoolState::Good
+    }
+
+    fn redundancy(&self) -> Redundancy {
+        self.redundancy
+    }
+
+    fn reshape(&mut self, _redundancy: Redundancy) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error,
+                                "changing the redundancy is not yet supported".into()))
+    }
+
+    fn created_at(&self) -> u64 {
+        self.created_at
+    }
+
+    fn extent_size(&self) -> Sectors {
+        self.thin_pool.thinpooldev().data_block_size()
+    }
+
+    fn io_stats(&self) -> PoolIoStats {
+        // I/O is not yet tracked.
+        PoolIoStats::default()
+    }
+
+    fn read_only(&self) -> bool {
+        false
+    }
+
+    fn set_read_only(&mut self, _read_only: bool) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error,
+                                "read-only pools are not yet supported".into()))
+    }
+
+    fn imported(&self) -> bool {
+        false
+    }
+
+    fn set_imported(&mut self, _imported: bool) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error, "importing pools is not yet supported".into()))
+    }
+
+    fn cache_mode(&self) -> CacheMode {
+        CacheMode::WriteThrough
+    }
+
+    fn set_cache_mode(&mut self, _mode: CacheMode) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error,
+                                "setting the cache mode is not yet supported".into()))
+    }
+
+    fn cache_enabled(&self) -> bool {
+        true
+    }
+
+    fn set_cache_enabled(&mut self, _enabled: bool) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error,
+                                "disabling the cache is not yet supported".into()))
+    }
+
+    fn compression(&self) -> Compression {
+        Compression::None
+    }
+
+    fn set_compression(&mut self, _compression: Compression) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error, "compression is not yet supported".into()))
+    }
+
+    fn allocation_policy(&self) -> AllocationPolicy {
+        AllocationPolicy::Linear
+    }
+
+    fn set_allocation_policy(&mut self, _policy: AllocationPolicy) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error,
+                                "setting the allocation policy is not yet supported".into()))
+    }
+
+    fn max_filesystems(&self) -> u32 {
+        DEFAULT_MAX_FILESYSTEMS
+    }
+
+    fn set_max_filesystems(&mut self, _max: u32) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error,
+                                "setting the filesystem limit is not yet supported".into()))
+    }
+
+    fn max_snapshots(&self) -> u32 {
+        DEFAULT_MAX_SNAPSHOTS
+    }
+
+    fn set_max_snapshots(&mut self, _max: u32) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error,
+                                "setting the snapshot limit is not yet supported".into()))
+    }
+
+    fn reserved_free(&self) -> Sectors {
+        Sectors(0)
+    }
+
+    fn set_reserved_free(&mut self, _size: Sectors) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error,
+                                "reserving free space is not yet supported".into()))
+    }
+
+    fn allocatable_space(&self) -> EngineResult<Sectors> {
+        let used = try!(self.total_physical_used());
+        let size = self.total_physical_size();
+        Ok(if used < size { size - used } else { Sectors(0) })
+    }
+
+    fn usage_alarm(&self) -> u16 {
+        0
+    }
+
+    fn set_usage_alarm(&mut self, _percent: u16) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error, "usage alarms are not yet supported".into()))
+    }
+
+    fn key_description(&self) -> Option<&str> {
+        None
+    }
+
+    fn set_key_description(&mut self, _key_description: &str) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error, "encryption is not yet supported".into()))
+    }
+
+    fn description(&self) -> &str {
+        ""
+    }
+
+    fn set_description(&mut self, _description: &str) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error,
+                                "pool descriptions are not yet supported".into()))
+    }
+
+    fn tags(&self) -> Vec<&str> {
+        vec![]
+    }
+
+    fn add_tag(&mut self, _tag: &str) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error, "pool tags are not yet supported".into()))
+    }
+
+    fn remove_tag(&mut self, _tag: &str) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error, "pool tags are not yet supported".into()))
+    }
+
+    fn fragmentation(&self) -> EngineResult<u16> {
+        Err(EngineError::Engine(ErrorEnum::Error,
+                                "estimating fragmentation is not yet supported".into()))
+    }
+
+    fn compact(&mut self) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error,
+                                "compacting a pool is not yet supported".into()))
+    }
+
+    fn rebalance(&mut self) -> EngineResult<bool> {
+        Err(EngineError::Engine(ErrorEnum::Error,
+                                "rebalancing a pool is not yet supported".into()))
+    }
 }
 
 impl HasUuid for StratPool {
@@ -501,6 +774,7 @@ impl Recordable<PoolSave> for StratPool {
 
         Ok(PoolSave {
                name: self.name.clone(),
+               created_at: self.created_at,
                block_devs: try!(self.block_devs.record()),
                flex_devs: FlexDevsSave {
                    meta_dev: meta_dev,
@@ -32,6 +32,9 @@ pub trait Recordable<T> {
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PoolSave {
     pub name: String,
+    // Metadata written before creation times were recorded has none.
+    #[serde(default)]
+    pub created_at: u64,
     pub block_devs: HashMap<DevUuid, BlockDevSave>,
     pub flex_devs: FlexDevsSave,
     pub thinpool_dev: ThinPoolDevSave,
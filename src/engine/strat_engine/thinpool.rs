@@ -18,7 +18,7 @@ use super::super::consts::IEC;
 use super::super::engine::{Filesystem, HasName};
 use super::super::errors::{EngineError, EngineResult, ErrorEnum};
 use super::super::structures::Table;
-use super::super::types::{PoolUuid, FilesystemUuid, RenameAction};
+use super::super::types::{PoolUuid, FilesystemUuid, RenameAction, round_to_extent};
 
 use super::dmdevice::{FlexRole, ThinDevIdPool, ThinPoolRole, ThinRole, format_flex_name,
                       format_thinpool_name, format_thin_name};
@@ -298,7 +298,8 @@ impl ThinPool {
                                          dm,
                                          &self.thin_pool,
                                          try!(self.id_gen.new_id()),
-                                         size.unwrap_or(DEFAULT_THIN_DEV_SIZE)));
+                                         round_to_extent(size.unwrap_or(DEFAULT_THIN_DEV_SIZE),
+                                                         self.thin_pool.data_block_size())));
 
         let new_filesystem = try!(StratFilesystem::initialize(fs_uuid, name, thin_dev));
         try!(self.mdv.save_fs(&new_filesystem));
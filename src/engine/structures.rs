@@ -5,14 +5,16 @@
 use std::collections::HashMap;
 use std::iter::IntoIterator;
 use std::slice::{Iter, IterMut};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use uuid::Uuid;
 
 use super::engine::{HasName, HasUuid};
+use super::types::{Alert, AlertSeverity};
 
 
 /// Map UUID and name to T items.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Table<T: HasName + HasUuid> {
     items: Vec<T>,
     name_map: HashMap<String, usize>,
@@ -180,6 +182,43 @@ impl<T: HasName + HasUuid> Table<T> {
     }
 }
 
+/// The alerts which have been raised and not yet acknowledged, in the
+/// order in which they were raised. Every alert gets a new id.
+#[derive(Debug, Default)]
+pub struct AlertLog {
+    next_id: u64,
+    alerts: Vec<Alert>,
+}
+
+impl AlertLog {
+    /// Raise an alert, stamped with the current time.
+    pub fn raise(&mut self, severity: AlertSeverity, message: String) {
+        self.next_id += 1;
+        self.alerts.push(Alert {
+                             id: self.next_id,
+                             severity: severity,
+                             message: message,
+                             timestamp: SystemTime::now()
+                                 .duration_since(UNIX_EPOCH)
+                                 .expect("the present is later than the Unix epoch")
+                                 .as_secs(),
+                         });
+    }
+
+    /// The alerts which have not been acknowledged.
+    pub fn unacknowledged(&self) -> Vec<Alert> {
+        self.alerts.clone()
+    }
+
+    /// Acknowledge the alert with id, forgetting it.
+    /// Returns false if there is no unacknowledged alert with id.
+    pub fn acknowledge(&mut self, id: u64) -> bool {
+        let len = self.alerts.len();
+        self.alerts.retain(|alert| alert.id != id);
+        self.alerts.len() != len
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -0,0 +1,146 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// The hierarchy of a pool's devices, for display as a tree: the pool,
+// the groups into which its devices are organized, and the devices in
+// each group. Like the *Dump structs, the *Topology structs contain only
+// serde-friendly data types and are constructed by means of the Pool
+// trait, so that the topology of any engine's pools may be described.
+
+use std::path::PathBuf;
+
+use serde_json;
+
+use super::engine::Pool;
+use super::errors::EngineResult;
+use super::types::{BlockDevTier, DevUuid, PoolUuid};
+
+/// The version of the format of a topology. It changes whenever a field
+/// is added, removed, or changes its meaning.
+pub const TOPOLOGY_VERSION: u32 = 1;
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolTopology {
+    pub version: u32,
+    pub name: String,
+    pub uuid: PoolUuid,
+    pub state: String,
+    pub groups: Vec<GroupTopology>,
+}
+
+/// A group of devices which share a role in the pool. The data devices
+/// form a single RAID group, which has the pool's redundancy.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupTopology {
+    pub role: String,
+    pub redundancy: Option<String>,
+    pub devices: Vec<DeviceTopology>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceTopology {
+    pub devnode: PathBuf,
+    pub uuid: DevUuid,
+    pub state: String,
+    pub health: String,
+}
+
+impl PoolTopology {
+    /// The topology of pool. The group of data devices is always present,
+    /// the groups of cache devices and spares only if the pool has any.
+    /// Within a group, devices are in order of device node.
+    pub fn new(pool: &Pool) -> PoolTopology {
+        let blockdevs = pool.blockdevs();
+        let mut groups = Vec::new();
+        for tier in BlockDevTier::iter_variants() {
+            let mut devices = blockdevs
+                .iter()
+                .filter(|&&(t, _)| t == tier)
+                .map(|&(_, bd)| {
+                         DeviceTopology {
+                             devnode: bd.devnode(),
+                             uuid: *bd.uuid(),
+                             state: format!("{:?}", bd.state()),
+                             health: bd.health().to_string(),
+                         }
+                     })
+                .collect::<Vec<_>>();
+            if devices.is_empty() && tier != BlockDevTier::Data {
+                continue;
+            }
+            devices.sort_by(|a, b| a.devnode.cmp(&b.devnode));
+            groups.push(GroupTopology {
+                            role: tier.to_string(),
+                            redundancy: if tier == BlockDevTier::Data {
+                                Some(pool.redundancy().to_string())
+                            } else {
+                                None
+                            },
+                            devices: devices,
+                        });
+        }
+        PoolTopology {
+            version: TOPOLOGY_VERSION,
+            name: pool.name().to_owned(),
+            uuid: *pool.uuid(),
+            state: pool.state().to_string(),
+            groups: groups,
+        }
+    }
+
+    /// The topology as a JSON string.
+    pub fn to_json(&self) -> EngineResult<String> {
+        Ok(try!(serde_json::to_string(self)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use serde_json;
+    use serde_json::Value;
+
+    use super::super::{Engine, SimEngine};
+    use super::{PoolTopology, TOPOLOGY_VERSION};
+
+    #[test]
+    /// The topology of a pool with data and cache devices has a RAID
+    /// group holding the data devices and a group holding the cache
+    /// device, and no group of spares
+    fn topology_data_and_cache() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("pool",
+                         &[Path::new("/s/b"), Path::new("/s/a")],
+                         &[Path::new("/s/c")],
+                         None,
+                         false)
+            .unwrap();
+        let json = PoolTopology::new(engine.get_pool(&uuid).unwrap())
+            .to_json()
+            .unwrap();
+
+        let topology: Value = serde_json::from_str(&json).unwrap();
+        assert!(topology["version"] == TOPOLOGY_VERSION);
+        assert!(topology["name"] == "pool");
+        assert!(topology["state"] == "Good");
+
+        let groups = topology["groups"].as_array().unwrap();
+        assert!(groups.len() == 2);
+        assert!(groups[0]["role"] == "Data");
+        assert!(groups[0]["redundancy"] == "NONE");
+        let devnodes = groups[0]["devices"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|d| d["devnode"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert!(devnodes == vec!["/s/a", "/s/b"]);
+        assert!(groups[1]["role"] == "Cache");
+        assert!(groups[1]["redundancy"].is_null());
+        assert!(groups[1]["devices"][0]["devnode"] == "/s/c");
+        assert!(groups[1]["devices"][0]["state"] == "Good");
+    }
+}
@@ -0,0 +1,168 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// A sequence of operations which is applied to the engine as a whole or
+// not at all. Each step names its pools and filesystems, rather than
+// giving their UUIDs, so that a step may refer to a pool or filesystem
+// created by an earlier step of the same transaction.
+
+use std::path::{Path, PathBuf};
+
+use serde_json;
+
+use super::engine::Engine;
+use super::errors::{EngineError, EngineResult, ErrorEnum};
+use super::types::PoolUuid;
+
+/// A single operation of a transaction. In JSON, the operation is given
+/// by the "op" field, e.g., {"op": "DestroyPool", "pool": "name"}.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op")]
+pub enum TransactionStep {
+    CreatePool { name: String, devices: Vec<PathBuf> },
+    AddDevices { pool: String, devices: Vec<PathBuf> },
+    DestroyPool { pool: String },
+    CreateFilesystem { pool: String, name: String },
+    DestroyFilesystem { pool: String, name: String },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Transaction {
+    pub steps: Vec<TransactionStep>,
+}
+
+/// The UUID of the pool named name.
+fn pool_uuid(engine: &Engine, name: &str) -> EngineResult<PoolUuid> {
+    engine
+        .pools()
+        .into_iter()
+        .find(|p| p.name() == name)
+        .map(|p| *p.uuid())
+        .ok_or_else(|| {
+                        EngineError::Engine(ErrorEnum::NotFound, format!("no pool named {}", name))
+                    })
+}
+
+impl TransactionStep {
+    /// Apply this step to engine.
+    fn apply(&self, engine: &mut Engine) -> EngineResult<()> {
+        match *self {
+            TransactionStep::CreatePool {
+                ref name,
+                ref devices,
+            } => {
+                let devices = devices.iter().map(|d| d.as_path()).collect::<Vec<&Path>>();
+                try!(engine.create_pool(name, &devices, &[], None, false));
+            }
+            TransactionStep::AddDevices {
+                ref pool,
+                ref devices,
+            } => {
+                let uuid = try!(pool_uuid(engine, pool));
+                let devices = devices.iter().map(|d| d.as_path()).collect::<Vec<&Path>>();
+                try!(engine
+                         .get_mut_pool(&uuid)
+                         .expect("pool was just found")
+                         .add_blockdevs(&devices, false));
+            }
+            TransactionStep::DestroyPool { ref pool } => {
+                let uuid = try!(pool_uuid(engine, pool));
+                try!(engine.destroy_pool(&uuid));
+            }
+            TransactionStep::CreateFilesystem { ref pool, ref name } => {
+                let uuid = try!(pool_uuid(engine, pool));
+                try!(engine
+                         .get_mut_pool(&uuid)
+                         .expect("pool was just found")
+                         .create_filesystems(&[(name, None)]));
+            }
+            TransactionStep::DestroyFilesystem { ref pool, ref name } => {
+                let uuid = try!(pool_uuid(engine, pool));
+                let pool = engine.get_mut_pool(&uuid).expect("pool was just found");
+                let fs_uuid = try!(pool.filesystems()
+                                       .into_iter()
+                                       .find(|f| f.name() == name)
+                                       .map(|f| *f.uuid())
+                                       .ok_or_else(|| {
+                                                       EngineError::Engine(ErrorEnum::NotFound,
+                                                                           name.to_owned())
+                                                   }));
+                try!(pool.destroy_filesystems(&[&fs_uuid]));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Transaction {
+    /// The transaction in the JSON string json, an array of steps.
+    pub fn from_json(json: &str) -> EngineResult<Transaction> {
+        let steps = try!(serde_json::from_str(json).map_err(|e| {
+            EngineError::Engine(ErrorEnum::Invalid, format!("malformed transaction: {}", e))
+        }));
+        Ok(Transaction { steps: steps })
+    }
+
+    /// Apply the steps of the transaction to engine, in order. If a step
+    /// fails, the engine is restored to its state before the first step,
+    /// and the index of the failing step is returned with its error.
+    /// Returns an error if the engine can not checkpoint its state.
+    pub fn execute(&self, engine: &mut Engine) -> EngineResult<Option<(usize, EngineError)>> {
+        try!(engine.checkpoint());
+        for (index, step) in self.steps.iter().enumerate() {
+            if let Err(err) = step.apply(engine) {
+                try!(engine.rollback());
+                return Ok(Some((index, err)));
+            }
+        }
+        engine.release_checkpoint();
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::super::{Engine, EngineDump, SimEngine};
+    use super::{Transaction, TransactionStep};
+
+    #[test]
+    /// A transaction whose second step fails leaves the engine as it was
+    /// before the transaction, and identifies the failing step
+    fn transaction_rolled_back() {
+        let mut engine = SimEngine::default();
+        engine
+            .create_pool("pool", &[Path::new("/s/a")], &[], None, false)
+            .unwrap();
+        let before = EngineDump::new(&engine, false);
+
+        let transaction = Transaction::from_json(r#"[
+            {"op": "CreateFilesystem", "pool": "pool", "name": "fs"},
+            {"op": "DestroyPool", "pool": "nonexistent"}
+        ]"#)
+                .unwrap();
+        assert!(transaction.steps[0] ==
+                TransactionStep::CreateFilesystem {
+                    pool: "pool".into(),
+                    name: "fs".into(),
+                });
+
+        let (index, _) = transaction.execute(&mut engine).unwrap().unwrap();
+        assert!(index == 1);
+        assert!(EngineDump::new(&engine, false) == before);
+        assert!(engine.rollback().is_err());
+
+        let transaction = Transaction::from_json(r#"[
+            {"op": "CreateFilesystem", "pool": "pool", "name": "fs"},
+            {"op": "CreatePool", "name": "other", "devices": ["/s/b"]}
+        ]"#)
+                .unwrap();
+        assert!(transaction.execute(&mut engine).unwrap().is_none());
+        assert!(engine.pools().len() == 2);
+        assert!(engine.rollback().is_err());
+
+        assert!(Transaction::from_json(r#"[{"op": "Reboot"}]"#).is_err());
+    }
+}
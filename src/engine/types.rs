@@ -4,6 +4,10 @@
 
 use uuid::Uuid;
 
+use devicemapper::Sectors;
+
+use super::consts::IEC;
+
 pub type DevUuid = Uuid;
 pub type FilesystemUuid = Uuid;
 pub type PoolUuid = Uuid;
@@ -17,7 +21,7 @@ pub enum RenameAction {
 
 /// Redundancy classifications which the engine allows for pools.
 custom_derive! {
-    #[derive(Debug, Eq, PartialEq, EnumDisplay,
+    #[derive(Debug, Clone, Copy, Eq, PartialEq, EnumDisplay,
              IterVariants(RedundancyVariants))]
     #[allow(non_camel_case_types)]
     /// Redundancy specification for a pool.
@@ -32,3 +36,293 @@ impl From<Redundancy> for u16 {
         r as u16
     }
 }
+
+impl Redundancy {
+    /// The fewest blockdevs a pool with this redundancy can be created with.
+    pub fn min_blockdevs(&self) -> usize {
+        match *self {
+            Redundancy::NONE => 1,
+        }
+    }
+
+    /// The number of blockdevs a pool with this redundancy may lose
+    /// without losing data.
+    pub fn tolerance(&self) -> usize {
+        match *self {
+            Redundancy::NONE => 0,
+        }
+    }
+
+    /// The redundancy to suggest for a pool of device_count blockdevs:
+    /// the most protective one that can be created with that many.
+    /// Redundancies are ordered from least to most protective.
+    /// Returns None if no redundancy can be created with that many.
+    pub fn suggest(device_count: usize) -> Option<Redundancy> {
+        Redundancy::iter_variants()
+            .filter(|r| r.min_blockdevs() <= device_count)
+            .last()
+    }
+
+    /// Whether a pool of device_count blockdevs with this redundancy may be
+    /// reshaped to have redundancy target.
+    pub fn can_reshape(&self, target: Redundancy, device_count: usize) -> bool {
+        match (*self, target) {
+            (Redundancy::NONE, Redundancy::NONE) => device_count >= target.min_blockdevs(),
+        }
+    }
+}
+
+custom_derive! {
+    #[derive(Debug, Clone, Copy, Eq, PartialEq, EnumDisplay,
+             IterVariants(BlockDevTierVariants))]
+    /// The role of a device in a pool: a data device, a cache device, or
+    /// a spare which replaces a data device if it fails.
+    pub enum BlockDevTier {
+        Data,
+        Cache,
+        Spare,
+    }
+}
+
+/// Get the u16 value of this BlockDevTier constructor.
+impl From<BlockDevTier> for u16 {
+    fn from(t: BlockDevTier) -> u16 {
+        t as u16
+    }
+}
+
+custom_derive! {
+    #[derive(Debug, Clone, Copy, Eq, PartialEq, EnumDisplay,
+             IterVariants(BlockDevCheckVariants))]
+    /// Whether a device could be used as a blockdev in a new pool, and if
+    /// not, why not.
+    pub enum BlockDevCheck {
+        Ok,
+        Duplicate,
+        InUse,
+        NotFound,
+        TooSmall,
+    }
+}
+
+/// Get the u16 value of this BlockDevCheck constructor.
+impl From<BlockDevCheck> for u16 {
+    fn from(c: BlockDevCheck) -> u16 {
+        c as u16
+    }
+}
+
+custom_derive! {
+    #[derive(Debug, Clone, Copy, Eq, PartialEq, EnumDisplay,
+             IterVariants(CacheModeVariants))]
+    /// How writes to a pool are handled by its cache devices.
+    pub enum CacheMode {
+        WriteThrough,
+        WriteBack,
+    }
+}
+
+/// Get the u16 value of this CacheMode constructor.
+impl From<CacheMode> for u16 {
+    fn from(m: CacheMode) -> u16 {
+        m as u16
+    }
+}
+
+custom_derive! {
+    #[derive(Debug, Clone, Copy, Eq, PartialEq, EnumDisplay,
+             IterVariants(CompressionVariants))]
+    /// How data written to a pool is compressed, if at all.
+    pub enum Compression {
+        None,
+        Lz4,
+        Zstd,
+    }
+}
+
+/// Get the u16 value of this Compression constructor.
+impl From<Compression> for u16 {
+    fn from(c: Compression) -> u16 {
+        c as u16
+    }
+}
+
+custom_derive! {
+    #[derive(Debug, Clone, Copy, Eq, PartialEq, EnumDisplay,
+             IterVariants(AlertSeverityVariants))]
+    /// How urgently an alert calls for an operator's attention.
+    pub enum AlertSeverity {
+        Warning,
+        Critical,
+    }
+}
+
+/// Get the u16 value of this AlertSeverity constructor.
+impl From<AlertSeverity> for u16 {
+    fn from(s: AlertSeverity) -> u16 {
+        s as u16
+    }
+}
+
+/// A change in the state of a pool or device which the engine has noted
+/// for an operator, and which remains until the operator acknowledges it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Alert {
+    pub id: u64,
+    pub severity: AlertSeverity,
+    pub message: String,
+    /// Seconds since the Unix epoch at which the alert was raised.
+    pub timestamp: u64,
+}
+
+custom_derive! {
+    #[derive(Debug, Clone, Copy, Eq, PartialEq, EnumDisplay,
+             IterVariants(AllocationPolicyVariants))]
+    /// How new allocations in a pool are distributed among its blockdevs:
+    /// all to the first blockdev with room, evenly over every blockdev, or
+    /// all to the blockdev with the most free space.
+    pub enum AllocationPolicy {
+        Linear,
+        Striped,
+        MostFree,
+    }
+}
+
+/// Get the u16 value of this AllocationPolicy constructor.
+impl From<AllocationPolicy> for u16 {
+    fn from(p: AllocationPolicy) -> u16 {
+        p as u16
+    }
+}
+
+/// Round size up to a whole number of extents of extent_size.
+/// A size which is already a whole number of extents is unchanged.
+pub fn round_to_extent(size: Sectors, extent_size: Sectors) -> Sectors {
+    Sectors((*size + *extent_size - 1) / *extent_size * *extent_size)
+}
+
+/// Format a number of bytes for people to read, in the largest binary unit
+/// of which there is at least one, to one decimal place, e.g., "1.5 TiB".
+/// Fewer than 1 KiB are given exactly, e.g., "512 B".
+/// A value which would round up to 1024 of a unit is given in the next.
+pub fn format_bytes(bytes: u64) -> String {
+    let units = [("KiB", IEC::Ki),
+                 ("MiB", IEC::Mi),
+                 ("GiB", IEC::Gi),
+                 ("TiB", IEC::Ti),
+                 ("PiB", IEC::Pi),
+                 ("EiB", IEC::Ei)];
+    let index = match units.iter().rposition(|&(_, size)| bytes >= size) {
+        Some(index) => index,
+        None => return format!("{} B", bytes),
+    };
+    let index = if index + 1 < units.len() &&
+                   (bytes as f64 / units[index].1 as f64 * 10.0).round() >= 10240.0 {
+        index + 1
+    } else {
+        index
+    };
+    let (name, size) = units[index];
+    format!("{:.1} {}", bytes as f64 / size as f64, name)
+}
+
+/// Cumulative I/O statistics for a pool since it was created.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct PoolIoStats {
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+    pub io_ops: u64,
+}
+
+/// The state of a single blockdev.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BlockDevState {
+    Good,
+    Failed,
+}
+
+/// Get the u16 value of this BlockDevState constructor.
+impl From<BlockDevState> for u16 {
+    fn from(s: BlockDevState) -> u16 {
+        s as u16
+    }
+}
+
+custom_derive! {
+    #[derive(Debug, Clone, Copy, Eq, PartialEq, EnumDisplay,
+             IterVariants(BlockDevHealthVariants))]
+    /// The health of a single blockdev, as reported by the device itself,
+    /// e.g., in its SMART data.
+    pub enum BlockDevHealth {
+        Good,
+        Warning,
+        Critical,
+    }
+}
+
+/// Get the u16 value of this BlockDevHealth constructor.
+impl From<BlockDevHealth> for u16 {
+    fn from(h: BlockDevHealth) -> u16 {
+        h as u16
+    }
+}
+
+custom_derive! {
+    #[derive(Debug, Clone, Copy, Eq, PartialEq, EnumDisplay,
+             IterVariants(PoolStateVariants))]
+    /// The state of a pool, derived from the states of its blockdevs.
+    /// A pool is Degraded if some of its blockdevs have failed or report
+    /// critical health, and Offline if all of them have failed.
+    pub enum PoolState {
+        Good,
+        Degraded,
+        Offline,
+    }
+}
+
+/// Get the u16 value of this PoolState constructor.
+impl From<PoolState> for u16 {
+    fn from(s: PoolState) -> u16 {
+        s as u16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::consts::IEC;
+
+    use super::{Redundancy, format_bytes};
+
+    #[test]
+    /// Bytes are given in the largest unit of which there is at least one,
+    /// and values which round up to a whole unit are given in that unit
+    fn format_bytes_boundaries() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(IEC::Ki - 1), "1023 B");
+        assert_eq!(format_bytes(IEC::Ki), "1.0 KiB");
+        assert_eq!(format_bytes(IEC::Ki + IEC::Ki / 2), "1.5 KiB");
+        assert_eq!(format_bytes(IEC::Mi - 1), "1.0 MiB");
+        assert_eq!(format_bytes(IEC::Mi), "1.0 MiB");
+        assert_eq!(format_bytes(IEC::Gi - IEC::Mi), "1023.0 MiB");
+        assert_eq!(format_bytes(IEC::Gi), "1.0 GiB");
+        assert_eq!(format_bytes(IEC::Ti + IEC::Ti / 2), "1.5 TiB");
+        assert_eq!(format_bytes(IEC::Ti - 1), "1.0 TiB");
+        assert_eq!(format_bytes(u64::max_value()), "16.0 EiB");
+    }
+
+    #[test]
+    /// A redundancy is suggested for any count of devices but none
+    fn suggest_redundancy() {
+        assert!(Redundancy::suggest(0).is_none());
+        assert!(Redundancy::suggest(1) == Some(Redundancy::NONE));
+        assert!(Redundancy::suggest(2) == Some(Redundancy::NONE));
+        assert!(Redundancy::suggest(4) == Some(Redundancy::NONE));
+    }
+
+    #[test]
+    /// A reshape is allowed only if the pool has enough blockdevs
+    fn can_reshape() {
+        assert!(Redundancy::NONE.can_reshape(Redundancy::NONE, 1));
+        assert!(!Redundancy::NONE.can_reshape(Redundancy::NONE, 0));
+    }
+}
@@ -2,7 +2,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-pub use self::stratis::VERSION;
+pub use self::stratis::{VERSION, build_profile, features, git_hash};
 pub use self::errors::{StratisError, StratisResult};
 
 mod errors;
@@ -4,3 +4,28 @@
 
 
 pub const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+/// The git commit from which stratisd was built, if the build set
+/// STRATIS_GIT_HASH, otherwise "unknown".
+pub fn git_hash() -> &'static str {
+    option_env!("STRATIS_GIT_HASH").unwrap_or("unknown")
+}
+
+/// "debug" or "release", according to how stratisd was built.
+pub fn build_profile() -> &'static str {
+    if cfg!(debug_assertions) {
+        "debug"
+    } else {
+        "release"
+    }
+}
+
+/// The cargo features with which stratisd was built.
+pub fn features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "clippy") {
+        features.push("clippy");
+    }
+    features
+}
+
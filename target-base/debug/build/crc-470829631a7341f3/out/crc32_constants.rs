@@ -0,0 +1,777 @@
+pub const CASTAGNOLI: u32 = 2197175160;
+pub const CASTAGNOLI_TABLE: [u32; 256] = [
+    0,
+    4067132163,
+    3778769143,
+    324072436,
+    3348797215,
+    904991772,
+    648144872,
+    3570033899,
+    2329499855,
+    2024987596,
+    1809983544,
+    2575936315,
+    1296289744,
+    3207089363,
+    2893594407,
+    1578318884,
+    274646895,
+    3795141740,
+    4049975192,
+    51262619,
+    3619967088,
+    632279923,
+    922689671,
+    3298075524,
+    2592579488,
+    1760304291,
+    2075979607,
+    2312596564,
+    1562183871,
+    2943781820,
+    3156637768,
+    1313733451,
+    549293790,
+    3537243613,
+    3246849577,
+    871202090,
+    3878099393,
+    357341890,
+    102525238,
+    4101499445,
+    2858735121,
+    1477399826,
+    1264559846,
+    3107202533,
+    1845379342,
+    2677391885,
+    2361733625,
+    2125378298,
+    820201905,
+    3263744690,
+    3520608582,
+    598981189,
+    4151959214,
+    85089709,
+    373468761,
+    3827903834,
+    3124367742,
+    1213305469,
+    1526817161,
+    2842354314,
+    2107672161,
+    2412447074,
+    2627466902,
+    1861252501,
+    1098587580,
+    3004210879,
+    2688576843,
+    1378610760,
+    2262928035,
+    1955203488,
+    1742404180,
+    2511436119,
+    3416409459,
+    969524848,
+    714683780,
+    3639785095,
+    205050476,
+    4266873199,
+    3976438427,
+    526918040,
+    1361435347,
+    2739821008,
+    2954799652,
+    1114974503,
+    2529119692,
+    1691668175,
+    2005155131,
+    2247081528,
+    3690758684,
+    697762079,
+    986182379,
+    3366744552,
+    476452099,
+    3993867776,
+    4250756596,
+    255256311,
+    1640403810,
+    2477592673,
+    2164122517,
+    1922457750,
+    2791048317,
+    1412925310,
+    1197962378,
+    3037525897,
+    3944729517,
+    427051182,
+    170179418,
+    4165941337,
+    746937522,
+    3740196785,
+    3451792453,
+    1070968646,
+    1905808397,
+    2213795598,
+    2426610938,
+    1657317369,
+    3053634322,
+    1147748369,
+    1463399397,
+    2773627110,
+    4215344322,
+    153784257,
+    444234805,
+    3893493558,
+    1021025245,
+    3467647198,
+    3722505002,
+    797665321,
+    2197175160,
+    1889384571,
+    1674398607,
+    2443626636,
+    1164749927,
+    3070701412,
+    2757221520,
+    1446797203,
+    137323447,
+    4198817972,
+    3910406976,
+    461344835,
+    3484808360,
+    1037989803,
+    781091935,
+    3705997148,
+    2460548119,
+    1623424788,
+    1939049696,
+    2180517859,
+    1429367560,
+    2807687179,
+    3020495871,
+    1180866812,
+    410100952,
+    3927582683,
+    4182430767,
+    186734380,
+    3756733383,
+    763408580,
+    1053836080,
+    3434856499,
+    2722870694,
+    1344288421,
+    1131464017,
+    2971354706,
+    1708204729,
+    2545590714,
+    2229949006,
+    1988219213,
+    680717673,
+    3673779818,
+    3383336350,
+    1002577565,
+    4010310262,
+    493091189,
+    238226049,
+    4233660802,
+    2987750089,
+    1082061258,
+    1395524158,
+    2705686845,
+    1972364758,
+    2279892693,
+    2494862625,
+    1725896226,
+    952904198,
+    3399985413,
+    3656866545,
+    731699698,
+    4283874585,
+    222117402,
+    510512622,
+    3959836397,
+    3280807620,
+    837199303,
+    582374963,
+    3504198960,
+    68661723,
+    4135334616,
+    3844915500,
+    390545967,
+    1230274059,
+    3141532936,
+    2825850620,
+    1510247935,
+    2395924756,
+    2091215383,
+    1878366691,
+    2644384480,
+    3553878443,
+    565732008,
+    854102364,
+    3229815391,
+    340358836,
+    3861050807,
+    4117890627,
+    119113024,
+    1493875044,
+    2875275879,
+    3090270611,
+    1247431312,
+    2660249211,
+    1828433272,
+    2141937292,
+    2378227087,
+    3811616794,
+    291187481,
+    34330861,
+    4032846830,
+    615137029,
+    3603020806,
+    3314634738,
+    939183345,
+    1776939221,
+    2609017814,
+    2295496738,
+    2058945313,
+    2926798794,
+    1545135305,
+    1330124605,
+    3173225534,
+    4084100981,
+    17165430,
+    307568514,
+    3762199681,
+    888469610,
+    3332340585,
+    3587147933,
+    665062302,
+    2042050490,
+    2346497209,
+    2559330125,
+    1793573966,
+    3190661285,
+    1279665062,
+    1595330642,
+    2910671697,
+];
+pub const IEEE: u32 = 3988292384;
+pub const IEEE_TABLE: [u32; 256] = [
+    0,
+    1996959894,
+    3993919788,
+    2567524794,
+    124634137,
+    1886057615,
+    3915621685,
+    2657392035,
+    249268274,
+    2044508324,
+    3772115230,
+    2547177864,
+    162941995,
+    2125561021,
+    3887607047,
+    2428444049,
+    498536548,
+    1789927666,
+    4089016648,
+    2227061214,
+    450548861,
+    1843258603,
+    4107580753,
+    2211677639,
+    325883990,
+    1684777152,
+    4251122042,
+    2321926636,
+    335633487,
+    1661365465,
+    4195302755,
+    2366115317,
+    997073096,
+    1281953886,
+    3579855332,
+    2724688242,
+    1006888145,
+    1258607687,
+    3524101629,
+    2768942443,
+    901097722,
+    1119000684,
+    3686517206,
+    2898065728,
+    853044451,
+    1172266101,
+    3705015759,
+    2882616665,
+    651767980,
+    1373503546,
+    3369554304,
+    3218104598,
+    565507253,
+    1454621731,
+    3485111705,
+    3099436303,
+    671266974,
+    1594198024,
+    3322730930,
+    2970347812,
+    795835527,
+    1483230225,
+    3244367275,
+    3060149565,
+    1994146192,
+    31158534,
+    2563907772,
+    4023717930,
+    1907459465,
+    112637215,
+    2680153253,
+    3904427059,
+    2013776290,
+    251722036,
+    2517215374,
+    3775830040,
+    2137656763,
+    141376813,
+    2439277719,
+    3865271297,
+    1802195444,
+    476864866,
+    2238001368,
+    4066508878,
+    1812370925,
+    453092731,
+    2181625025,
+    4111451223,
+    1706088902,
+    314042704,
+    2344532202,
+    4240017532,
+    1658658271,
+    366619977,
+    2362670323,
+    4224994405,
+    1303535960,
+    984961486,
+    2747007092,
+    3569037538,
+    1256170817,
+    1037604311,
+    2765210733,
+    3554079995,
+    1131014506,
+    879679996,
+    2909243462,
+    3663771856,
+    1141124467,
+    855842277,
+    2852801631,
+    3708648649,
+    1342533948,
+    654459306,
+    3188396048,
+    3373015174,
+    1466479909,
+    544179635,
+    3110523913,
+    3462522015,
+    1591671054,
+    702138776,
+    2966460450,
+    3352799412,
+    1504918807,
+    783551873,
+    3082640443,
+    3233442989,
+    3988292384,
+    2596254646,
+    62317068,
+    1957810842,
+    3939845945,
+    2647816111,
+    81470997,
+    1943803523,
+    3814918930,
+    2489596804,
+    225274430,
+    2053790376,
+    3826175755,
+    2466906013,
+    167816743,
+    2097651377,
+    4027552580,
+    2265490386,
+    503444072,
+    1762050814,
+    4150417245,
+    2154129355,
+    426522225,
+    1852507879,
+    4275313526,
+    2312317920,
+    282753626,
+    1742555852,
+    4189708143,
+    2394877945,
+    397917763,
+    1622183637,
+    3604390888,
+    2714866558,
+    953729732,
+    1340076626,
+    3518719985,
+    2797360999,
+    1068828381,
+    1219638859,
+    3624741850,
+    2936675148,
+    906185462,
+    1090812512,
+    3747672003,
+    2825379669,
+    829329135,
+    1181335161,
+    3412177804,
+    3160834842,
+    628085408,
+    1382605366,
+    3423369109,
+    3138078467,
+    570562233,
+    1426400815,
+    3317316542,
+    2998733608,
+    733239954,
+    1555261956,
+    3268935591,
+    3050360625,
+    752459403,
+    1541320221,
+    2607071920,
+    3965973030,
+    1969922972,
+    40735498,
+    2617837225,
+    3943577151,
+    1913087877,
+    83908371,
+    2512341634,
+    3803740692,
+    2075208622,
+    213261112,
+    2463272603,
+    3855990285,
+    2094854071,
+    198958881,
+    2262029012,
+    4057260610,
+    1759359992,
+    534414190,
+    2176718541,
+    4139329115,
+    1873836001,
+    414664567,
+    2282248934,
+    4279200368,
+    1711684554,
+    285281116,
+    2405801727,
+    4167216745,
+    1634467795,
+    376229701,
+    2685067896,
+    3608007406,
+    1308918612,
+    956543938,
+    2808555105,
+    3495958263,
+    1231636301,
+    1047427035,
+    2932959818,
+    3654703836,
+    1088359270,
+    936918000,
+    2847714899,
+    3736837829,
+    1202900863,
+    817233897,
+    3183342108,
+    3401237130,
+    1404277552,
+    615818150,
+    3134207493,
+    3453421203,
+    1423857449,
+    601450431,
+    3009837614,
+    3294710456,
+    1567103746,
+    711928724,
+    3020668471,
+    3272380065,
+    1510334235,
+    755167117,
+];
+pub const KOOPMAN: u32 = 3945912366;
+pub const KOOPMAN_TABLE: [u32; 256] = [
+    0,
+    2526397642,
+    4215814601,
+    1843264771,
+    552846287,
+    3060139781,
+    3686529542,
+    1294876364,
+    1105692574,
+    3614589780,
+    3132079703,
+    742029981,
+    1628718161,
+    4152459419,
+    2589752728,
+    214546770,
+    2211385148,
+    358271990,
+    2022127349,
+    3994219071,
+    2738670835,
+    900270137,
+    1484059962,
+    3470864880,
+    3257436322,
+    1421692008,
+    962638187,
+    2952099233,
+    3806022509,
+    1951305639,
+    429093540,
+    2399581806,
+    3523063333,
+    1198025455,
+    716543980,
+    3156235046,
+    4044254698,
+    1738254624,
+    172381219,
+    2631110889,
+    2417633723,
+    110095729,
+    1800540274,
+    4257731768,
+    2968119924,
+    645672638,
+    1268896701,
+    3711178615,
+    1379049753,
+    3299304915,
+    2843384016,
+    1072651290,
+    1925276374,
+    3830752796,
+    2307480351,
+    521969621,
+    332736135,
+    2235621965,
+    3902611278,
+    2114509700,
+    858187080,
+    2779980162,
+    3362708609,
+    1593514059,
+    1972956183,
+    3809290461,
+    2396050910,
+    407179540,
+    1433087960,
+    3288263442,
+    2921534993,
+    951505627,
+    880765833,
+    2733289283,
+    3476509248,
+    1503827594,
+    344762438,
+    2182704268,
+    4022636943,
+    2035373381,
+    4132954923,
+    1623336929,
+    220191458,
+    2609520168,
+    3601080548,
+    1077011502,
+    770447661,
+    3145326055,
+    3081790645,
+    556114047,
+    1291345276,
+    3664615862,
+    2537793402,
+    30827440,
+    1812700851,
+    4204681849,
+    2758099506,
+    854623992,
+    1596816379,
+    3384327985,
+    2224456189,
+    302204215,
+    2145302580,
+    3914038526,
+    3850552748,
+    1930887526,
+    516619365,
+    2287941807,
+    3312518755,
+    1407500969,
+    1043939242,
+    2829908832,
+    665472270,
+    2973731268,
+    3705828551,
+    1249357837,
+    123309761,
+    2446084619,
+    4229019400,
+    1787065282,
+    1716374160,
+    4040691290,
+    2634412889,
+    194000787,
+    1186859359,
+    3492531605,
+    3187028118,
+    727970908,
+    3945912366,
+    2107907300,
+    276423143,
+    2263622957,
+    3418495969,
+    1566039851,
+    814359080,
+    2787108578,
+    2866175920,
+    1011063674,
+    1369335417,
+    3339428531,
+    2317720703,
+    481319093,
+    1903011254,
+    3891814780,
+    1761531666,
+    4268430296,
+    2478239451,
+    86190609,
+    1208816861,
+    3734556695,
+    3007655188,
+    634448350,
+    689524876,
+    3213661254,
+    3528550725,
+    1153740175,
+    166368067,
+    2675922825,
+    4070746762,
+    1681354304,
+    986478091,
+    2891559617,
+    3246673858,
+    1460767496,
+    440382916,
+    2359980302,
+    3782708237,
+    2011318471,
+    2066477461,
+    3988665695,
+    2154023004,
+    385223830,
+    1540895322,
+    3444438672,
+    2693794707,
+    906350425,
+    3104012599,
+    798408189,
+    1112228094,
+    3571352628,
+    2582690552,
+    258309682,
+    1656259377,
+    4096608251,
+    4175756969,
+    1852914275,
+    61654880,
+    2503541674,
+    3625401702,
+    1317206444,
+    593429679,
+    3049963621,
+    2661889081,
+    138211571,
+    1709247984,
+    4084517178,
+    3193632758,
+    684667708,
+    1158860351,
+    3548842741,
+    3746476967,
+    1239119725,
+    604408430,
+    2995998372,
+    4290605160,
+    1764275362,
+    83184033,
+    2455801195,
+    493239045,
+    2348023759,
+    3861775052,
+    1891354118,
+    1033238730,
+    2868919296,
+    3336421635,
+    1346897353,
+    1552006299,
+    3390339153,
+    2815001938,
+    828129688,
+    2087878484,
+    3941055390,
+    2268743325,
+    296714839,
+    1330944540,
+    3653328598,
+    3021775829,
+    579430175,
+    1873238483,
+    4180843801,
+    2498715674,
+    41592016,
+    246619522,
+    2552682824,
+    4126876747,
+    1668210817,
+    776003149,
+    3100973703,
+    3574130564,
+    1134371662,
+    3432748320,
+    1510887914,
+    936619241,
+    2705745955,
+    3966260975,
+    2063438373,
+    388001574,
+    2176166892,
+    2373718718,
+    468309620,
+    1983130487,
+    3768709053,
+    2911883633,
+    991565243,
+    1455941816,
+    3226610802,
+];
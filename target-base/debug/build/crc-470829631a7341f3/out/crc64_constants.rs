@@ -0,0 +1,518 @@
+pub const ECMA: u64 = 14514072000185962306;
+pub const ECMA_TABLE: [u64; 256] = [
+    0,
+    12911341560706588527,
+    17619267392293085275,
+    5164075066763771700,
+    8921845837811637811,
+    14483170935171449180,
+    10328150133527543400,
+    4357999468653093127,
+    17843691675623275622,
+    4940391307328217865,
+    226782375002905661,
+    12685511915359257426,
+    10119945210068853333,
+    4566377562367245626,
+    8715998937306186254,
+    14689403211693301089,
+    9051005139383707209,
+    14895072503764629798,
+    9880782614656435730,
+    4193374422961527165,
+    453564750005811322,
+    13070904082541799189,
+    17496296445768931361,
+    4747102235666401102,
+    9960315520700766767,
+    4113029525020509504,
+    9132755124734491252,
+    14812441257301386523,
+    17431997874612372508,
+    4811156168024382323,
+    391483189436228679,
+    13132671735097031464,
+    18102010278767414418,
+    5195199925788447741,
+    1131375642422963401,
+    13591081480414639014,
+    9288535643022529185,
+    3731739485546663374,
+    8386748845923054330,
+    14361410892855143829,
+    907129500011622644,
+    13814943346342178715,
+    17875617253995106479,
+    5421418680781082560,
+    8594564625313771207,
+    14152643483341451688,
+    9494204471332802204,
+    3525329033817543155,
+    9704381199536204507,
+    3855837706121835956,
+    8226059050041019008,
+    13908973417437222383,
+    18265510249468982504,
+    5643692520190618503,
+    718348998302913715,
+    13463047253836762076,
+    8146277531524994749,
+    13989069943491807698,
+    9622312336048764646,
+    3938150108875254153,
+    782966378872457358,
+    13399312233903888353,
+    18327840216347633877,
+    5582173445676054458,
+    7257036000092981153,
+    15535280666427316430,
+    10390399851576895482,
+    2529986302517213333,
+    2262751284845926802,
+    12414353723947190013,
+    16997392145760156105,
+    6398650419759490726,
+    10599130201908394951,
+    2322133910755632296,
+    7463478971093326748,
+    15329644185724306675,
+    16773497691846108660,
+    6622864283287239323,
+    2036569382881248687,
+    12640783567252986560,
+    1814259000023245288,
+    12250853444207230599,
+    17125426475222188467,
+    6811676960462675676,
+    7132938157145702363,
+    15119434731753103540,
+    10842837361562165120,
+    2690676064372932847,
+    17189129250627542414,
+    6747026957542163169,
+    1875814858707893717,
+    12188560364711551674,
+    10762704257491731389,
+    2770420489343360210,
+    7050658067635086310,
+    15201536148867841161,
+    11493583972846619443,
+    3219832958944941148,
+    7711675412243671912,
+    15576564987190227975,
+    16452118100082038016,
+    6305011443818121839,
+    1213047649942025563,
+    11816267669673208372,
+    7503259434831574869,
+    15784731923736995898,
+    11287385040381237006,
+    3425713581329221729,
+    1436697996605827430,
+    11591809733187859977,
+    16677985422973077821,
+    6078267261889762898,
+    16292555063049989498,
+    5851447209550246421,
+    1630020308903038241,
+    11939238787801010766,
+    11081681957373440841,
+    3090674103720225830,
+    7876300217750508306,
+    16023932746787097725,
+    1565932757744914716,
+    12003503911822413427,
+    16230825569204842823,
+    5913566482019610152,
+    7956607163135676207,
+    15944361922680361024,
+    11164346891352108916,
+    3008957496780927003,
+    14514072000185962306,
+    8809633696146542637,
+    4460922918905818905,
+    10287960411460399222,
+    12879331835779764593,
+    113391187501452830,
+    5059972605034426666,
+    17660565739912801861,
+    4525502569691853604,
+    10224187249629523019,
+    14576435430675780479,
+    8748148222884465680,
+    4980157760350383383,
+    17740628527280140920,
+    12797300839518981452,
+    195741594718114339,
+    13040162471224305931,
+    565687821211481700,
+    4644267821511264592,
+    17536326748496696895,
+    14926957942186653496,
+    8937808626997553239,
+    4297282312656885603,
+    9839608450464401420,
+    4852190599768102253,
+    17327666750234135042,
+    13245728566574478646,
+    359174499151456857,
+    4073138765762497374,
+    10063573324157604913,
+    14700457781105076997,
+    9163920108173816938,
+    3628518000046490576,
+    9328460452529085631,
+    14330211790445699979,
+    8498696072880078052,
+    5299565100954197475,
+    18061012165519327884,
+    13623353920925351352,
+    1018284691440624343,
+    14265876314291404726,
+    8562713237611094233,
+    3566469078572851181,
+    9390260331795218562,
+    13702854325316886917,
+    937907429353946858,
+    5381352128745865694,
+    17978417549248290481,
+    5746791986423309721,
+    18225777846762470134,
+    13494053915084326338,
+    606523824971012781,
+    3751629717415787434,
+    9745292510640121029,
+    13876787882151992305,
+    8338992711486538910,
+    13285957365033343487,
+    815010154451519120,
+    5540840978686720420,
+    18431906428167644875,
+    14101316135270172620,
+    8115412784602421411,
+    3978303581567838103,
+    9519354766961195256,
+    12527462061959317731,
+    2230461459452909452,
+    6439665917889882296,
+    16893009583564617687,
+    15423350824487343824,
+    7288217715337890239,
+    2490078880175191691,
+    10493603952060017124,
+    6520081235612152965,
+    16813546994155744234,
+    12610022887636243678,
+    2148641156328442801,
+    2426095299884051126,
+    10557972909709735385,
+    15361512820870335213,
+    7350228890552538498,
+    15006518869663149738,
+    7165105895222849989,
+    2649782550477098737,
+    10947027550912647582,
+    12362696414880903321,
+    1783234539286425590,
+    6851427162658443458,
+    17022309211647725485,
+    2873395993211654860,
+    10722532847870938531,
+    15232418832718623383,
+    6938393941075996152,
+    6642978682516671743,
+    17230443782969840528,
+    12156534523779525796,
+    1989151790783919051,
+    6263731030979658865,
+    16556202624882645790,
+    11702894419100492842,
+    1245039440087595845,
+    3260040617806076482,
+    11390642587947386157,
+    15688795063501830681,
+    7680756410435167606,
+    11622868312827688983,
+    1324891275238549368,
+    6181348207440451660,
+    16638201170595874595,
+    15752600435501016612,
+    7616209416359311691,
+    3321489341258335871,
+    11328242235714328848,
+    3131865515489829432,
+    10977756817953029463,
+    16137146508898304611,
+    7844397531750915340,
+    5811434156413844491,
+    16395372229761246052,
+    11827132964039220304,
+    1660744670629167935,
+    15913214326271352414,
+    8068573254449152305,
+    2905717078206922245,
+    11204220263579804010,
+    12035829987123708013,
+    1452858539103461122,
+    6017914993561854006,
+    16189773752444600153,
+];
+pub const ISO: u64 = 15564440312192434176;
+pub const ISO_TABLE: [u64; 256] = [
+    0,
+    121597189939003392,
+    243194379878006784,
+    202661983231672320,
+    486388759756013568,
+    535928355657089024,
+    405323966463344640,
+    292733975779082240,
+    972777519512027136,
+    878201927337246720,
+    1071856711314178048,
+    1103381908705771520,
+    810647932926689280,
+    788129934789836800,
+    585467951558164480,
+    689050742987685888,
+    1945555039024054272,
+    1923037040887201792,
+    1756403854674493440,
+    1859986646104014848,
+    2143713422628356096,
+    2049137830453575680,
+    2206763817411543040,
+    2238289014803136512,
+    1621295865853378560,
+    1670835461754454016,
+    1576259869579673600,
+    1463669878895411200,
+    1170935903116328960,
+    1292533093055332352,
+    1378101485975371776,
+    1337569089329037312,
+    3891110078048108544,
+    4012707267987111936,
+    3846074081774403584,
+    3805541685128069120,
+    3512807709348986880,
+    3562347305250062336,
+    3719973292208029696,
+    3607383301523767296,
+    4287426845256712192,
+    4192851253081931776,
+    4098275660907151360,
+    4129800858298744832,
+    4413527634823086080,
+    4391009636686233600,
+    4476578029606273024,
+    4580160821035794432,
+    3242591731706757120,
+    3220073733569904640,
+    3341670923508908032,
+    3445253714938429440,
+    3152519739159347200,
+    3057944146984566784,
+    2927339757790822400,
+    2958864955182415872,
+    2341871806232657920,
+    2391411402133733376,
+    2585066186110664704,
+    2472476195426402304,
+    2756202971950743552,
+    2877800161889746944,
+    2675138178658074624,
+    2634605782011740160,
+    7782220156096217088,
+    7903817346035220480,
+    8025414535974223872,
+    7984882139327889408,
+    7692148163548807168,
+    7741687759449882624,
+    7611083370256138240,
+    7498493379571875840,
+    7025615418697973760,
+    6931039826523193344,
+    7124694610500124672,
+    7156219807891718144,
+    7439946584416059392,
+    7417428586279206912,
+    7214766603047534592,
+    7318349394477056000,
+    8574853690513424384,
+    8552335692376571904,
+    8385702506163863552,
+    8489285297593384960,
+    8196551321814302720,
+    8101975729639522304,
+    8259601716597489664,
+    8291126913989083136,
+    8827055269646172160,
+    8876594865547247616,
+    8782019273372467200,
+    8669429282688204800,
+    8953156059212546048,
+    9074753249151549440,
+    9160321642071588864,
+    9119789245425254400,
+    6485183463413514240,
+    6606780653352517632,
+    6440147467139809280,
+    6399615070493474816,
+    6683341847017816064,
+    6732881442918891520,
+    6890507429876858880,
+    6777917439192596480,
+    6305039478318694400,
+    6210463886143913984,
+    6115888293969133568,
+    6147413491360727040,
+    5854679515581644800,
+    5832161517444792320,
+    5917729910364831744,
+    6021312701794353152,
+    4683743612465315840,
+    4661225614328463360,
+    4782822804267466752,
+    4886405595696988160,
+    5170132372221329408,
+    5075556780046548992,
+    4944952390852804608,
+    4976477588244398080,
+    5512405943901487104,
+    5561945539802562560,
+    5755600323779493888,
+    5643010333095231488,
+    5350276357316149248,
+    5471873547255152640,
+    5269211564023480320,
+    5228679167377145856,
+    15564440312192434176,
+    15686037502131437568,
+    15807634692070440960,
+    15767102295424106496,
+    16050829071948447744,
+    16100368667849523200,
+    15969764278655778816,
+    15857174287971516416,
+    15384296327097614336,
+    15289720734922833920,
+    15483375518899765248,
+    15514900716291358720,
+    15222166740512276480,
+    15199648742375424000,
+    14996986759143751680,
+    15100569550573273088,
+    14051230837395947520,
+    14028712839259095040,
+    13862079653046386688,
+    13965662444475908096,
+    14249389221000249344,
+    14154813628825468928,
+    14312439615783436288,
+    14343964813175029760,
+    14879893168832118784,
+    14929432764733194240,
+    14834857172558413824,
+    14722267181874151424,
+    14429533206095069184,
+    14551130396034072576,
+    14636698788954112000,
+    14596166392307777536,
+    17149707381026848768,
+    17271304570965852160,
+    17104671384753143808,
+    17064138988106809344,
+    16771405012327727104,
+    16820944608228802560,
+    16978570595186769920,
+    16865980604502507520,
+    16393102643628605440,
+    16298527051453825024,
+    16203951459279044608,
+    16235476656670638080,
+    16519203433194979328,
+    16496685435058126848,
+    16582253827978166272,
+    16685836619407687680,
+    17654110539292344320,
+    17631592541155491840,
+    17753189731094495232,
+    17856772522524016640,
+    17564038546744934400,
+    17469462954570153984,
+    17338858565376409600,
+    17370383762768003072,
+    17906312118425092096,
+    17955851714326167552,
+    18149506498303098880,
+    18036916507618836480,
+    18320643284143177728,
+    18442240474082181120,
+    18239578490850508800,
+    18199046094204174336,
+    12970366926827028480,
+    13091964116766031872,
+    13213561306705035264,
+    13173028910058700800,
+    12880294934279618560,
+    12929834530180694016,
+    12799230140986949632,
+    12686640150302687232,
+    13366683694035632128,
+    13272108101860851712,
+    13465762885837783040,
+    13497288083229376512,
+    13781014859753717760,
+    13758496861616865280,
+    13555834878385192960,
+    13659417669814714368,
+    12610078956637388800,
+    12587560958500536320,
+    12420927772287827968,
+    12524510563717349376,
+    12231776587938267136,
+    12137200995763486720,
+    12294826982721454080,
+    12326352180113047552,
+    11709359031163289600,
+    11758898627064365056,
+    11664323034889584640,
+    11551733044205322240,
+    11835459820729663488,
+    11957057010668666880,
+    12042625403588706304,
+    12002093006942371840,
+    9367487224930631680,
+    9489084414869635072,
+    9322451228656926720,
+    9281918832010592256,
+    9565645608534933504,
+    9615185204436008960,
+    9772811191393976320,
+    9660221200709713920,
+    10340264744442658816,
+    10245689152267878400,
+    10151113560093097984,
+    10182638757484691456,
+    9889904781705609216,
+    9867386783568756736,
+    9952955176488796160,
+    10056537967918317568,
+    11024811887802974208,
+    11002293889666121728,
+    11123891079605125120,
+    11227473871034646528,
+    11511200647558987776,
+    11416625055384207360,
+    11286020666190462976,
+    11317545863582056448,
+    10700552714632298496,
+    10750092310533373952,
+    10943747094510305280,
+    10831157103826042880,
+    10538423128046960640,
+    10660020317985964032,
+    10457358334754291712,
+    10416825938107957248,
+];
@@ -16,7 +16,7 @@ use libstratis::engine::strat_engine::StratEngine;
 pub fn test_teardown(paths: &[&Path]) -> () {
     let mut engine = StratEngine::initialize().unwrap();
     engine
-        .create_pool("test_pool", paths, None, true)
+        .create_pool("test_pool", paths, &[], None, true)
         .unwrap();
     assert!(engine.teardown().is_ok())
 }
@@ -28,7 +28,7 @@ pub fn test_empty_pool(paths: &[&Path]) -> () {
     assert!(paths.len() == 0);
     let mut engine = StratEngine::initialize().unwrap();
     assert!(match engine
-                      .create_pool("test_pool", paths, None, true)
+                      .create_pool("test_pool", paths, &[], None, true)
                       .unwrap_err() {
                 EngineError::Engine(ErrorEnum::Invalid, _) => true,
                 _ => false,